@@ -0,0 +1,86 @@
+//! Client-side caching of responses to queries declared with `cache`.
+//!
+//! Entries are keyed by method, endpoint and parameters and served until the max age the
+//! server declared for the query has passed. Browser builds leave caching to the HTTP
+//! cache, which honours the server's `Cache-Control` header.
+
+use std::time::Duration;
+
+use serde_json::Value;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{collections::HashMap, sync::Mutex, time::Instant};
+
+/// Responses of cached queries, shared by clones of a client
+#[derive(Debug, Default)]
+pub(crate) struct ResponseCache {
+    #[cfg(not(target_arch = "wasm32"))]
+    entries: Mutex<HashMap<String, (Instant, Value)>>,
+}
+
+/// Key of the response to a call
+pub(crate) fn cache_key(method: &str, endpoint: &str, params: Option<&Value>) -> String {
+    match params {
+        Some(params) => format!("{} {} {}", method, endpoint, params),
+        None => format!("{} {}", method, endpoint),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ResponseCache {
+    /// The response stored under `key`, unless it is older than `max_age`
+    pub(crate) fn get(&self, key: &str, max_age: Duration) -> Option<Value> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match entries.get(key) {
+            Some((stored, value)) if stored.elapsed() < max_age => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, key: String, value: Value) {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries.insert(key, (Instant::now(), value));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl ResponseCache {
+    pub(crate) fn get(&self, _key: &str, _max_age: Duration) -> Option<Value> {
+        None
+    }
+
+    pub(crate) fn insert(&self, _key: String, _value: Value) {}
+
+    pub(crate) fn clear(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_entries_expire_after_max_age() {
+        let cache = ResponseCache::default();
+        let key = cache_key("GET", "/api/trees", Some(&json!({ "page": 1 })));
+        assert_ne!(key, cache_key("GET", "/api/trees", Some(&json!({ "page": 2 }))));
+
+        cache.insert(key.clone(), json!(["oak"]));
+        assert_eq!(cache.get(&key, Duration::from_secs(60)), Some(json!(["oak"])));
+        assert_eq!(cache.get(&key, Duration::ZERO), None);
+        // Expired entries are dropped
+        assert_eq!(cache.get(&key, Duration::from_secs(60)), None);
+
+        cache.insert(key.clone(), json!(["elm"]));
+        cache.clear();
+        assert_eq!(cache.get(&key, Duration::from_secs(60)), None);
+    }
+}
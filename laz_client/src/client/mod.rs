@@ -1,7 +1,33 @@
+mod cache;
+mod pages;
+mod query;
+mod retry;
+mod route;
+mod sse;
+mod stream;
+mod timeout;
+#[cfg(not(target_arch = "wasm32"))]
+mod ws;
+
+pub use pages::paginate;
+pub use query::encode_query;
+pub use retry::{idempotency_key, RetryPolicy, IDEMPOTENCY_KEY_HEADER};
+pub use stream::lines;
+pub use timeout::with_timeout;
+#[cfg(not(target_arch = "wasm32"))]
+pub use ws::WsSession;
+pub use laz_types::EndpointDiscovery;
+
+use futures_util::{Stream, StreamExt};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use laz_types::{
+    AuthScheme, BodyEncoding, FunctionConflict, HttpMethod, MetadataDocument, RateLimit, RpcErrorBody, ServerVersion,
+};
 use thiserror::Error;
 use tracing::{debug, info};
 
@@ -13,10 +39,85 @@ pub enum RpcClientError {
     JsonError(#[from] serde_json::Error),
     #[error("Function not found: {0}")]
     FunctionNotFound(String),
+    /// Several server handlers register the function's name, so which one a call reaches
+    /// is undefined
+    #[error("Function {function} is registered by several handlers: {}", handlers.join(", "))]
+    AmbiguousFunction { function: String, handlers: Vec<String> },
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
     #[error("Server error: {0}")]
     ServerError(String),
+    /// Structured error returned by the server
+    #[error("RPC error: {0}")]
+    Rpc(RpcErrorBody),
+    #[error("API version mismatch: client expects {expected}, server reports {found}")]
+    VersionMismatch { expected: String, found: String },
+    /// The function requires credentials the client wasn't configured with
+    #[error("Missing credentials: {0}")]
+    MissingCredentials(String),
+    /// The function reads a header the client wasn't configured with
+    #[error("Missing header: {0}")]
+    MissingHeader(String),
+    /// The function doesn't exist in the API version the server reports
+    #[error("{function} is not available in API version {server_version}")]
+    NotAvailable { function: String, server_version: String },
+    /// The server answered `429 Too Many Requests`; the call may be retried after
+    /// `retry_after`, when the server said how long to wait
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<std::time::Duration>,
+    },
+    /// The call took longer than its timeout
+    #[error("Call timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    /// A WebSocket session failed after the handshake
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+    /// The server answered with an error status the function declares with `error_status`
+    #[error("Status {status}: {message}")]
+    Status {
+        status: u16,
+        message: String,
+        /// `details` of the error body, holding a declared application error if there is one
+        details: Option<Value>,
+    },
+}
+
+impl RpcClientError {
+    /// Whether repeating the same call later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            RpcClientError::RateLimited { .. } | RpcClientError::Timeout(_) => true,
+            RpcClientError::RequestError(e) => retry::is_transient_error(e),
+            _ => false,
+        }
+    }
+}
+
+/// Error of a call to a function that declares an application error type `E`
+#[derive(Debug, Error)]
+pub enum RpcCallError<E> {
+    /// The server rejected the call with the function's declared error
+    #[error("Application error: {0}")]
+    Application(E),
+    #[error(transparent)]
+    Client(#[from] RpcClientError),
+}
+
+impl<E: DeserializeOwned> RpcCallError<E> {
+    /// Decode the declared error from an application error body; other errors are kept as-is
+    pub fn from_client_error(error: RpcClientError) -> Self {
+        let details = match &error {
+            RpcClientError::Rpc(body) if body.code == RpcErrorBody::APPLICATION_ERROR => body.details.as_ref(),
+            RpcClientError::Status { details, .. } => details.as_ref(),
+            _ => None,
+        };
+        if let Some(Ok(decoded)) = details.cloned().map(serde_json::from_value) {
+            return RpcCallError::Application(decoded);
+        }
+        RpcCallError::Client(error)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +135,8 @@ impl ServerAddr {
 #[derive(Debug, Clone)]
 pub struct RpcFunction {
     pub name: String,
+    /// Name of the Rust handler, when the function has a different public name
+    pub rust_name: Option<String>,
     pub is_mutation: bool,
     pub is_async: bool,
     pub input_type_name: Option<String>,
@@ -41,12 +144,68 @@ pub struct RpcFunction {
     pub params: Vec<Value>, // Store as JSON Value for now
     pub input_schema_json: Option<String>,
     pub output_schema_json: Option<String>,
+    pub description: Option<String>,
+    /// Deprecation note from the server; empty when deprecated without a note
+    pub deprecated: Option<String>,
+    /// Declared application error type, if any
+    pub error_type_name: Option<String>,
+    /// Route path serving the function, as resolved by the server
+    pub path: Option<String>,
+    /// HTTP verb the function is served with
+    pub method: HttpMethod,
+    /// Whether the function streams server-sent events
+    pub is_subscription: bool,
+    /// Served by an `rpc_ws` handler; connect with [`LocoClient::connect_ws`]
+    pub is_websocket: bool,
+    /// How the function's request body is encoded
+    pub body_encoding: BodyEncoding,
+    /// Credentials the function requires, if any
+    pub auth: Option<AuthScheme>,
+    /// Group the function is listed under, if any
+    pub namespace: Option<String>,
+    /// Categories the function is tagged with
+    pub tags: Vec<String>,
+    /// Default timeout of a call, in milliseconds
+    pub timeout_ms: Option<u64>,
+    /// Seconds a response stays fresh, for cached queries
+    pub cache_max_age: Option<u64>,
+    /// Broadcast topic a subscription streams, also served at `/_laz/topics/{topic}`
+    pub topic: Option<String>,
+    /// Declared success status, accepted besides `2xx`
+    pub status: Option<u16>,
+    /// Declared error statuses, reported as [`RpcClientError::Status`]
+    pub error_statuses: Vec<u16>,
+    /// API version that introduced the function
+    pub since: Option<String>,
+    /// API version that drops the function
+    pub removed: Option<String>,
+    /// Requests the server accepts per period, if limited
+    pub rate_limit: Option<RateLimit>,
 }
 
+/// Credentials attached to every request made by a [`LocoClient`]
 #[derive(Debug, Clone)]
-pub struct EndpointDiscovery {
-    pub uri: String,
-    pub methods: Vec<String>,
+pub enum Credentials {
+    /// `Authorization: Bearer <token>`
+    Bearer(String),
+    /// `Authorization: Basic ...`
+    Basic { username: String, password: Option<String> },
+    /// Key sent in a custom header
+    ApiKey { header: String, key: String },
+}
+
+impl Credentials {
+    /// Whether these credentials satisfy `scheme`
+    pub fn satisfies(&self, scheme: &AuthScheme) -> bool {
+        match (self, scheme) {
+            (Credentials::Bearer(_), AuthScheme::Bearer) => true,
+            (Credentials::Basic { .. }, AuthScheme::Basic) => true,
+            (Credentials::ApiKey { header, .. }, AuthScheme::ApiKey { header: required }) => {
+                header.eq_ignore_ascii_case(required)
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -55,14 +214,21 @@ pub struct LocoClient {
     http_client: Client,
     functions: HashMap<String, RpcFunction>,
     endpoints_discovery: Vec<EndpointDiscovery>,
-}
-
-#[derive(Debug, Deserialize)]
-struct MetadataResponse {
-    total_functions: usize,
-    functions: Vec<Value>,
-    endpoints_discovery: Vec<Value>,
-    total_endpoints: usize,
+    schema_digest: Option<String>,
+    /// `ETag` of the metadata last fetched, sent to find out whether it changed
+    metadata_etag: Option<String>,
+    api_version: Option<String>,
+    credentials: Option<Credentials>,
+    retry_policy: RetryPolicy,
+    response_cache: Option<Arc<cache::ResponseCache>>,
+    /// Headers sent with every request
+    headers: Vec<(String, String)>,
+    /// Names the server reports as registered by several handlers
+    conflicts: Vec<FunctionConflict>,
+    /// Send [`call_function`](Self::call_function) calls to the server's dispatch endpoint
+    dispatch: bool,
+    /// Path of the dispatch endpoint advertised by the server's `/_laz` index
+    rpc_path: Option<String>,
 }
 
 impl LocoClient {
@@ -87,6 +253,16 @@ impl LocoClient {
             http_client,
             functions: HashMap::new(),
             endpoints_discovery: Vec::new(),
+            schema_digest: None,
+            metadata_etag: None,
+            api_version: None,
+            credentials: None,
+            retry_policy: RetryPolicy::default(),
+            response_cache: None,
+            headers: Vec::new(),
+            conflicts: Vec::new(),
+            dispatch: false,
+            rpc_path: None,
         };
 
         // Fetch metadata from server
@@ -103,13 +279,69 @@ impl LocoClient {
         Ok(client)
     }
 
-    /// Fetch metadata from the server's _laz/metadata endpoint
-    async fn fetch_metadata(&mut self) -> Result<(), RpcClientError> {
-        let metadata_url = format!("{}/_laz/metadata", self.server_addr.base_url());
+    /// The server's `/_laz` index, listing the paths of laz's own routes
+    async fn index(&self) -> Option<Value> {
+        let index_url = format!("{}/_laz", self.server_addr.base_url());
+        match self.http_client.get(&index_url).send().await {
+            Ok(response) if response.status().is_success() => response.json::<Value>().await.ok(),
+            // Servers from before the index serve the default paths
+            _ => None,
+        }
+    }
+
+    /// The server's laz and API versions, schema digest and function count, fetched without
+    /// downloading the metadata
+    pub async fn server_version(&self) -> Result<ServerVersion, RpcClientError> {
+        let url = format!("{}/_laz/version", self.server_addr.base_url());
+        let response = self.http_client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(RpcClientError::ServerError(format!(
+                "Failed to fetch server version: HTTP {}",
+                response.status()
+            )));
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Whether the server is up and answering its `/_laz/health` check
+    pub async fn is_healthy(&self) -> bool {
+        let url = format!("{}/_laz/health", self.server_addr.base_url());
+        matches!(self.http_client.get(&url).send().await, Ok(response) if response.status().is_success())
+    }
+
+    /// Fetch the server's metadata again, returning whether it changed. The server is asked
+    /// to answer `304 Not Modified` when the client already has its current metadata, so
+    /// polling for schema changes is cheap.
+    pub async fn refresh_metadata(&mut self) -> Result<bool, RpcClientError> {
+        self.fetch_metadata().await
+    }
+
+    /// Fetch metadata from the server's metadata endpoint, returning whether it changed
+    async fn fetch_metadata(&mut self) -> Result<bool, RpcClientError> {
+        let index = self.index().await;
+        let advertised = |route: &str| index.as_ref().and_then(|index| index[route].as_str()).map(String::from);
+        self.rpc_path = advertised("rpc");
+        // `LAZ_METADATA_PATH` overrides the advertised path
+        let metadata_path = std::env::var("LAZ_METADATA_PATH")
+            .ok()
+            .or_else(|| advertised("metadata"))
+            .unwrap_or_else(|| "/_laz/metadata".to_string());
+        let metadata_url = format!("{}{}", self.server_addr.base_url(), metadata_path);
         info!("Fetching RPC metadata from: {}", metadata_url);
 
-        let response = self.http_client.get(&metadata_url).send().await?;
+        let mut request = self.http_client.get(&metadata_url);
+        if let Ok(token) = std::env::var("LAZ_METADATA_TOKEN") {
+            request = request.bearer_auth(token);
+        }
+        if let Some(etag) = &self.metadata_etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
 
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            debug!("RPC metadata unchanged");
+            return Ok(false);
+        }
         if !response.status().is_success() {
             return Err(RpcClientError::ServerError(format!(
                 "Failed to fetch metadata: HTTP {}",
@@ -117,7 +349,28 @@ impl LocoClient {
             )));
         }
 
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let signature = response
+            .headers()
+            .get(laz_types::signature::HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
         let response_text = response.text().await?;
+        // Holders of the server's signing secret only trust metadata signed with it
+        if let Ok(secret) = std::env::var("LAZ_METADATA_SECRET") {
+            let signed = signature.is_some_and(|signature| {
+                laz_types::signature::verify(secret.as_bytes(), response_text.as_bytes(), &signature)
+            });
+            if !signed {
+                return Err(RpcClientError::ServerError(
+                    "Metadata signature is missing or doesn't match LAZ_METADATA_SECRET".to_string(),
+                ));
+            }
+        }
         debug!(
             "Raw metadata response length: {} bytes",
             response_text.len()
@@ -127,91 +380,75 @@ impl LocoClient {
             &response_text[..response_text.len().min(500)]
         );
 
-        let metadata_response: MetadataResponse = serde_json::from_str(&response_text)
-            .map_err(|e| {
-                RpcClientError::ServerError(format!(
-                    "Failed to parse metadata JSON: {}. Response length: {} bytes, first 300 chars: {}",
-                    e,
-                    response_text.len(),
-                    response_text.chars().take(300).collect::<String>()
-                ))
-            })?;
+        let metadata = MetadataDocument::from_json(&response_text).map_err(|e| {
+            RpcClientError::ServerError(format!(
+                "Failed to parse metadata JSON: {}. Response length: {} bytes, first 300 chars: {}",
+                e,
+                response_text.len(),
+                response_text.chars().take(300).collect::<String>()
+            ))
+        })?;
 
         debug!(
             "Received metadata for {} functions and {} endpoints",
-            metadata_response.total_functions, metadata_response.total_endpoints
+            metadata.total_functions, metadata.total_endpoints
         );
 
-        // Parse and store function metadata
-        for func_value in metadata_response.functions {
-            let function_name = func_value["function_name"]
-                .as_str()
-                .ok_or_else(|| {
-                    RpcClientError::InvalidParameter("Missing function_name".to_string())
-                })?
-                .to_string();
-
-            let is_mutation = func_value["is_mutation"].as_bool().unwrap_or(false);
-            let is_async = func_value["is_async"].as_bool().unwrap_or(false);
-            let input_type_name = func_value["input_type_name"].as_str().map(String::from);
-            let output_type_name = func_value["output_type_name"]
-                .as_str()
-                .ok_or_else(|| {
-                    RpcClientError::InvalidParameter("Missing output_type_name".to_string())
-                })?
-                .to_string();
-
-            // Parse input schema if available - store as JSON string for now
-            let input_schema_json = func_value["input_schema_json"].as_str().map(String::from);
-
-            // Parse output schema - store as JSON string for now
-            let output_schema_json = func_value["output_schema_json"].as_str().map(String::from);
-
-            // Parse parameters - store as JSON value for now
-            let params_value = func_value["params"].clone();
+        self.metadata_etag = etag;
+        self.schema_digest = metadata.schema_digest;
+        self.api_version = metadata.api_version;
+        // A refresh replaces what an earlier fetch stored
+        self.functions.clear();
+        for conflict in &metadata.conflicts {
+            tracing::warn!(
+                "RPC function {} is registered by several handlers: {}",
+                conflict.function_name,
+                conflict.handlers.join(", ")
+            );
+        }
+        self.conflicts = metadata.conflicts;
 
+        for function in metadata.functions {
             let rpc_function = RpcFunction {
-                name: function_name.clone(),
-                is_mutation,
-                is_async,
-                input_type_name: input_type_name.clone(),
-                output_type_name: output_type_name.clone(),
-                params: vec![params_value], // Store the JSON value
-                input_schema_json,
-                output_schema_json,
+                name: function.function_name.clone(),
+                rust_name: function.rust_name,
+                is_mutation: function.is_mutation,
+                is_async: function.is_async,
+                input_type_name: function.input_type_name,
+                output_type_name: function.output_type_name,
+                params: vec![serde_json::to_value(&function.params).unwrap_or_default()],
+                input_schema_json: function.input_schema_json,
+                output_schema_json: function.output_schema_json,
+                description: function.description,
+                deprecated: function.deprecated,
+                error_type_name: function.error_type_name,
+                path: function.path,
+                method: function.method.unwrap_or(HttpMethod::default_for(function.is_mutation)),
+                is_subscription: function.is_subscription,
+                is_websocket: function.is_websocket,
+                body_encoding: function.body_encoding,
+                auth: function.auth,
+                namespace: function.namespace,
+                tags: function.tags,
+                timeout_ms: function.timeout_ms,
+                cache_max_age: function.cache_max_age,
+                topic: function.topic,
+                status: function.status,
+                error_statuses: function.error_statuses,
+                since: function.since,
+                removed: function.removed,
+                rate_limit: function.rate_limit,
             };
-
-            self.functions.insert(function_name, rpc_function);
-        }
-
-        // Parse and store endpoints discovery
-        for endpoint_value in metadata_response.endpoints_discovery {
-            let uri = endpoint_value["uri"]
-                .as_str()
-                .ok_or_else(|| {
-                    RpcClientError::InvalidParameter("Missing endpoint uri".to_string())
-                })?
-                .to_string();
-
-            let methods = endpoint_value["methods"]
-                .as_array()
-                .ok_or_else(|| {
-                    RpcClientError::InvalidParameter("Missing endpoint methods".to_string())
-                })?
-                .iter()
-                .filter_map(|v| v.as_str().map(String::from))
-                .collect();
-
-            let endpoint_discovery = EndpointDiscovery { uri, methods };
-            self.endpoints_discovery.push(endpoint_discovery);
+            self.functions.insert(function.function_name, rpc_function);
         }
+        self.endpoints_discovery = metadata.endpoints_discovery;
 
         info!(
             "Successfully loaded {} RPC functions and {} endpoints",
             self.functions.len(),
             self.endpoints_discovery.len()
         );
-        Ok(())
+        Ok(true)
     }
 
     /// Get a list of available function names
@@ -224,6 +461,121 @@ impl LocoClient {
         &self.endpoints_discovery
     }
 
+    /// Digest of the server's API metadata, if the server reported one
+    pub fn schema_digest(&self) -> Option<&str> {
+        self.schema_digest.as_deref()
+    }
+
+    /// API version declared by the server, if any
+    pub fn api_version(&self) -> Option<&str> {
+        self.api_version.as_deref()
+    }
+
+    /// Fail when the server's API version has a different major version than `expected`.
+    ///
+    /// For `0.x` versions the minor version is compared instead, as semver treats it as
+    /// breaking. Servers that don't declare a version pass the check.
+    pub fn check_api_version(&self, expected: &str) -> Result<(), RpcClientError> {
+        match self.api_version() {
+            Some(found) if breaking_version(found) != breaking_version(expected) => {
+                Err(RpcClientError::VersionMismatch {
+                    expected: expected.to_string(),
+                    found: found.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail when the server's API version is outside `since..removed`, the versions that
+    /// have `function`. Servers that don't declare a version pass the check.
+    pub fn check_available(
+        &self,
+        function: &str,
+        since: Option<&str>,
+        removed: Option<&str>,
+    ) -> Result<(), RpcClientError> {
+        match self.api_version() {
+            Some(version) if !laz_types::version_in_range(version, since, removed) => {
+                Err(RpcClientError::NotAvailable {
+                    function: function.to_string(),
+                    server_version: version.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Attach `credentials` to every request
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Set how calls to idempotent mutations are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Send the header `name` with every request, replacing an earlier value
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        self.headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Fail unless the client sends the header `name`, which a function reads
+    pub fn require_header(&self, name: &str) -> Result<(), RpcClientError> {
+        if self.headers.iter().any(|(header, _)| header.eq_ignore_ascii_case(name)) {
+            Ok(())
+        } else {
+            Err(RpcClientError::MissingHeader(name.to_string()))
+        }
+    }
+
+    /// Make [`call_function`](Self::call_function) send calls by name to the server's
+    /// `/_laz/rpc` endpoint instead of locating the function's route. The server has to
+    /// enable it with `LazEndpoint::with_dispatch`.
+    pub fn with_dispatch(mut self) -> Self {
+        self.dispatch = true;
+        self
+    }
+
+    /// Keep responses of queries declared with `cache` for as long as the server allows,
+    /// sharing them between clones of this client
+    pub fn with_response_cache(mut self) -> Self {
+        self.response_cache = Some(Arc::new(cache::ResponseCache::default()));
+        self
+    }
+
+    /// Drop every cached response
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.response_cache {
+            cache.clear();
+        }
+    }
+
+    /// Fail when the server registers `function_name` more than once
+    pub fn check_unambiguous(&self, function_name: &str) -> Result<(), RpcClientError> {
+        match self.conflicts.iter().find(|c| c.function_name == function_name) {
+            Some(conflict) => Err(RpcClientError::AmbiguousFunction {
+                function: conflict.function_name.clone(),
+                handlers: conflict.handlers.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail unless the configured credentials satisfy `scheme`
+    pub fn require_credentials(&self, scheme: &AuthScheme) -> Result<(), RpcClientError> {
+        match &self.credentials {
+            Some(credentials) if credentials.satisfies(scheme) => Ok(()),
+            _ => Err(RpcClientError::MissingCredentials(describe_scheme(scheme))),
+        }
+    }
+
     /// Get metadata for a specific function
     pub fn get_function_metadata(&self, function_name: &str) -> Option<&RpcFunction> {
         self.functions.get(function_name)
@@ -239,16 +591,36 @@ impl LocoClient {
             .functions
             .get(function_name)
             .ok_or_else(|| RpcClientError::FunctionNotFound(function_name.to_string()))?;
+        self.check_unambiguous(function_name)?;
+
+        if let Some(note) = &function.deprecated {
+            tracing::warn!("Calling deprecated RPC function {}: {}", function_name, note);
+        }
+        if let Some(scheme) = &function.auth {
+            self.require_credentials(scheme)?;
+        }
+        self.check_available(function_name, function.since.as_deref(), function.removed.as_deref())?;
 
-        let endpoint = self
-            .find_endpoint_for_function(function_name)
+        if self.dispatch {
+            // laz's own route, served at the root like the metadata
+            let path = self.rpc_path.as_deref().unwrap_or("/_laz/rpc");
+            let url = format!("{}{}", self.server_addr.base_url(), path);
+            let call = serde_json::json!({ "function": function_name, "params": params });
+            let request = self.request_to(&url, HttpMethod::Post).json(&call);
+            let response = self.send(request, path, HttpMethod::Post).await?;
+            return response.json::<Value>().await.map_err(RpcClientError::from);
+        }
+        // The server resolves each function's route, so a missing path means it has none
+        let endpoint = function
+            .path
+            .clone()
             .ok_or_else(|| {
                 RpcClientError::FunctionNotFound(format!(
                     "No endpoint found for function: {}",
                     function_name
                 ))
             })?;
-        self.call_endpoint(&endpoint, function.is_mutation, params)
+        self.call_endpoint(&endpoint, function.method, params)
             .await
     }
 
@@ -256,94 +628,312 @@ impl LocoClient {
     pub async fn call_endpoint(
         &self,
         endpoint: &str,
-        is_mutation: bool,
+        method: HttpMethod,
         params: Option<Value>,
     ) -> Result<Value, RpcClientError> {
-        let temp_endpoint = format!("/api{}", endpoint); // TODO: temporary url until I figure out how to automatically get the url
-        let url = format!("{}{}", self.server_addr.base_url(), temp_endpoint);
-        debug!("Calling RPC endpoint: {} (mutation = {})", url, is_mutation);
-        eprintln!("Calling RPC endpoint: {} (mutation = {})", url, is_mutation);
-
-        let response = if is_mutation {
-            let mut request = self.http_client.post(&url);
-            if let Some(params) = params {
-                request = request.json(&params);
-            }
-            request.send().await?
-        } else {
-            let mut request = self.http_client.get(&url);
-            if let Some(Value::Object(obj)) = params {
-                let query_pairs: Vec<(String, String)> = obj
-                    .into_iter()
-                    .map(|(k, v)| (k, stringify_value(&v)))
-                    .collect();
-                if !query_pairs.is_empty() {
-                    request = request.query(&query_pairs);
-                }
-            }
-            request.send().await?
-        };
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(RpcClientError::ServerError(format!(
-                "Endpoint {} failed with status {}: {}",
-                endpoint, status, error_text
-            )));
-        }
-
+        let response = self.send_request(endpoint, method, params).await?;
         let r = response.json::<Value>().await.map_err(RpcClientError::from);
 
         eprintln!("Response: {:#?}", r);
         r
     }
 
-    /// Find the HTTP endpoint for a function using dynamic endpoint discovery
-    fn find_endpoint_for_function(&self, function_name: &str) -> Option<String> {
-        // Try to find a matching endpoint based on function name
-        for endpoint in &self.endpoints_discovery {
-            let uri = &endpoint.uri;
-            println!("URI {}", uri);
+    /// Call an endpoint of a query declared with `cache`, answering from the client's
+    /// response cache while a stored response is younger than `max_age`. Without
+    /// [`with_response_cache`](Self::with_response_cache) this is a plain call.
+    pub async fn call_endpoint_cached(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+        max_age: Duration,
+    ) -> Result<Value, RpcClientError> {
+        let Some(cache) = &self.response_cache else {
+            return self.call_endpoint(endpoint, method, params).await;
+        };
+        let key = cache::cache_key(method.as_str(), endpoint, params.as_ref());
+        if let Some(value) = cache.get(&key, max_age) {
+            debug!("Serving {} from the response cache", endpoint);
+            return Ok(value);
+        }
+        let value = self.call_endpoint(endpoint, method, params).await?;
+        cache.insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// Call an endpoint whose handler returns `()`, without reading a response body
+    pub async fn call_endpoint_unit(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+    ) -> Result<(), RpcClientError> {
+        self.send_request(endpoint, method, params).await?;
+        Ok(())
+    }
+
+    /// Call an endpoint of an `idempotent` mutation. `key` (or a fresh key) is sent as the
+    /// `Idempotency-Key` header, and failures that may be transient are retried with the
+    /// same key as the client's [`RetryPolicy`] allows.
+    pub async fn call_endpoint_idempotent(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+        key: Option<String>,
+    ) -> Result<Value, RpcClientError> {
+        let response = self.send_idempotent(endpoint, method, params, key).await?;
+        Ok(response.json::<Value>().await?)
+    }
 
-            // Check if function name appears in the URI
-            if uri.contains(function_name)
-                || uri.contains(&function_name.replace('_', "-"))
-                || uri.contains(&function_name.to_lowercase())
-            {
-                return Some(uri.clone());
+    /// Like [`call_endpoint_idempotent`](Self::call_endpoint_idempotent), for handlers
+    /// returning `()`
+    pub async fn call_endpoint_idempotent_unit(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+        key: Option<String>,
+    ) -> Result<(), RpcClientError> {
+        self.send_idempotent(endpoint, method, params, key).await?;
+        Ok(())
+    }
+
+    async fn send_idempotent(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+        key: Option<String>,
+    ) -> Result<reqwest::Response, RpcClientError> {
+        let key = key.unwrap_or_else(idempotency_key);
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .request_with_params(endpoint, method, params.as_ref())
+                .header(IDEMPOTENCY_KEY_HEADER, &key)
+                .send()
+                .await;
+            let transient = match &result {
+                Ok(response) => retry::is_transient_status(response.status()),
+                Err(e) => retry::is_transient_error(e),
+            };
+            if transient && attempt < self.retry_policy.max_attempts {
+                debug!("Retrying idempotent call to {} (attempt {})", endpoint, attempt + 1);
+                // Wait at least as long as a rate-limiting server asks for
+                let retry_after = result.as_ref().ok().and_then(|response| retry::retry_after(response.headers()));
+                retry::sleep(self.retry_policy.delay(attempt).max(retry_after.unwrap_or_default())).await;
+                attempt += 1;
+                continue;
+            }
+            return self.check_response(result?, endpoint, method).await;
+        }
+    }
+
+    /// Subscribe to an endpoint served by an `rpc_subscription` handler, yielding the JSON
+    /// value of each server-sent event
+    pub async fn subscribe_endpoint(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+    ) -> Result<impl Stream<Item = Result<Value, RpcClientError>>, RpcClientError> {
+        let response = self.send_request(endpoint, method, params).await?;
+        Ok(sse::json_events(response.bytes_stream()))
+    }
+
+    /// Call an endpoint declared with `stream_body`, yielding its response body in chunks
+    /// as they arrive instead of buffering it
+    pub async fn stream_endpoint(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+    ) -> Result<impl Stream<Item = Result<bytes::Bytes, RpcClientError>>, RpcClientError> {
+        let response = self.send_request(endpoint, method, params).await?;
+        Ok(response.bytes_stream().map(|chunk| chunk.map_err(RpcClientError::from)))
+    }
+
+    /// Open a session with an endpoint served by an `rpc_ws` handler, sending the client's
+    /// headers and credentials with the handshake
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn connect_ws<In: Serialize, Out: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+    ) -> Result<WsSession<In, Out>, RpcClientError> {
+        let url = format!("{}{}", self.server_addr.base_url(), endpoint).replacen("http", "ws", 1);
+        debug!("Connecting to RPC socket: {}", url);
+        let mut headers = self.headers.clone();
+        headers.extend(match &self.credentials {
+            Some(Credentials::Bearer(token)) => Some(("Authorization".to_string(), format!("Bearer {}", token))),
+            Some(Credentials::Basic { username, password }) => {
+                let pair = format!("{}:{}", username, password.as_deref().unwrap_or_default());
+                Some(("Authorization".to_string(), format!("Basic {}", laz_types::base64::encode(pair.as_bytes()))))
             }
+            Some(Credentials::ApiKey { header, key }) => Some((header.clone(), key.clone())),
+            None => None,
+        });
+        ws::connect(&url, &headers).await
+    }
+
+    /// Call an endpoint whose handler takes a `Multipart` extractor, sending `form` as
+    /// `multipart/form-data`
+    pub async fn call_endpoint_multipart(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        form: reqwest::multipart::Form,
+    ) -> Result<Value, RpcClientError> {
+        let request = self.request(endpoint, method).multipart(form);
+        let response = self.send(request, endpoint, method).await?;
+        response.json::<Value>().await.map_err(RpcClientError::from)
+    }
 
-            // Check common patterns
-            let patterns = vec![
-                format!("/api/{}", function_name.replace('_', "-")),
-                format!("/api/auth/{}", function_name.replace('_', "-")),
-                format!("/{}/{}", "auth", function_name.replace('_', "-")),
-            ];
+    /// Like [`Self::call_endpoint_multipart`] for handlers returning `()`
+    pub async fn call_endpoint_multipart_unit(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        form: reqwest::multipart::Form,
+    ) -> Result<(), RpcClientError> {
+        let request = self.request(endpoint, method).multipart(form);
+        self.send(request, endpoint, method).await?;
+        Ok(())
+    }
+
+    fn request(&self, endpoint: &str, method: HttpMethod) -> reqwest::RequestBuilder {
+        // Endpoints are full paths, as the server resolves them
+        let url = format!("{}{}", self.server_addr.base_url(), endpoint);
+        self.request_to(&url, method)
+    }
+
+    /// A request to `url` carrying the client's headers and credentials
+    fn request_to(&self, url: &str, method: HttpMethod) -> reqwest::RequestBuilder {
+        debug!("Calling RPC endpoint: {} {}", method, url);
+        eprintln!("Calling RPC endpoint: {} {}", method, url);
+        let request = self
+            .headers
+            .iter()
+            .fold(self.http_client.request(reqwest_method(method), url), |request, (name, value)| {
+                request.header(name.as_str(), value)
+            });
+        match &self.credentials {
+            Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+            Some(Credentials::Basic { username, password }) => request.basic_auth(username, password.as_ref()),
+            Some(Credentials::ApiKey { header, key }) => request.header(header.as_str(), key),
+            None => request,
+        }
+    }
+
+    /// Send the request for an endpoint, with `params` as a JSON body or, for GET, the
+    /// query string
+    async fn send_request(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<Value>,
+    ) -> Result<reqwest::Response, RpcClientError> {
+        let request = self.request_with_params(endpoint, method, params.as_ref());
+        self.send(request, endpoint, method).await
+    }
 
-            for pattern in patterns {
-                if uri == &pattern {
-                    return Some(uri.clone());
+    fn request_with_params(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        params: Option<&Value>,
+    ) -> reqwest::RequestBuilder {
+        let request = self.request(endpoint, method);
+        let form = |f: &RpcFunction| f.body_encoding == BodyEncoding::Form;
+        match params {
+            // Functions reading `Form<T>` take their params urlencoded, flattened like a query
+            Some(params) if method.has_body() && self.function_serving(endpoint, method, form).is_some() => {
+                request.form(&query::query_pairs(params))
+            }
+            Some(params) if method.has_body() => request.json(params),
+            Some(params) => {
+                let query_pairs = query::query_pairs(params);
+                if query_pairs.is_empty() {
+                    request
+                } else {
+                    request.query(&query_pairs)
                 }
             }
+            None => request,
         }
+    }
 
-        // Fallback: generate common patterns if no match found
-        let fallback_patterns = vec![
-            format!("/api/{}", function_name.replace('_', "-")),
-            format!("/api/auth/{}", function_name.replace('_', "-")),
-        ];
+    /// Send a request, turning non-success statuses into errors
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        endpoint: &str,
+        method: HttpMethod,
+    ) -> Result<reqwest::Response, RpcClientError> {
+        let response = request.send().await?;
+        self.check_response(response, endpoint, method).await
+    }
 
-        for pattern in fallback_patterns {
-            if self.endpoints_discovery.iter().any(|e| e.uri == pattern) {
-                return Some(pattern);
+    /// Turn a non-success status into an error, unless the function serving `endpoint`
+    /// declares it a success
+    async fn check_response(
+        &self,
+        response: reqwest::Response,
+        endpoint: &str,
+        method: HttpMethod,
+    ) -> Result<reqwest::Response, RpcClientError> {
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry::retry_after(response.headers());
+            let error_text = response.text().await.unwrap_or_default();
+            let message = match serde_json::from_str::<RpcErrorBody>(&error_text) {
+                Ok(body) => body.message,
+                Err(_) => format!("Endpoint {} refused the request", endpoint),
+            };
+            return Err(RpcClientError::RateLimited { message, retry_after });
+        }
+        if !status.is_success() {
+            let declares_statuses = |f: &RpcFunction| f.status.is_some() || !f.error_statuses.is_empty();
+            let function = self.function_serving(endpoint, method, declares_statuses);
+            if function.and_then(|f| f.status) == Some(status.as_u16()) {
+                return Ok(response);
+            }
+            let declared_error = function.is_some_and(|f| f.error_statuses.contains(&status.as_u16()));
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            if declared_error {
+                let (message, details) = match serde_json::from_str::<RpcErrorBody>(&error_text) {
+                    Ok(body) => (body.message, body.details),
+                    Err(_) => (error_text, None),
+                };
+                return Err(RpcClientError::Status { status: status.as_u16(), message, details });
             }
+            if let Ok(body) = serde_json::from_str::<RpcErrorBody>(&error_text) {
+                return Err(RpcClientError::Rpc(body));
+            }
+            return Err(RpcClientError::ServerError(format!(
+                "Endpoint {} failed with status {}: {}",
+                endpoint, status, error_text
+            )));
         }
+        Ok(response)
+    }
 
-        None
+    /// The function matching `filter` that serves `endpoint` with `method`. Filtering first
+    /// keeps the route lookup to the few functions a caller cares about.
+    fn function_serving(
+        &self,
+        endpoint: &str,
+        method: HttpMethod,
+        filter: impl Fn(&RpcFunction) -> bool,
+    ) -> Option<&RpcFunction> {
+        self.functions
+            .iter()
+            .filter(|(_, f)| f.method == method && filter(f))
+            .find(|(_, f)| f.path.as_deref().is_some_and(|route| route::route_matches(route, endpoint)))
+            .map(|(_, f)| f)
     }
 
     /// Helper method to call a function with typed input parameters
@@ -357,6 +947,33 @@ impl LocoClient {
     }
 }
 
+/// The version components whose change signals a breaking change: `1.4.0` -> `1`, `0.3.1` -> `0.3`
+fn breaking_version(version: &str) -> String {
+    let mut parts = version.trim().trim_start_matches('v').split('.');
+    match (parts.next(), parts.next()) {
+        (Some("0"), Some(minor)) => format!("0.{}", minor),
+        (major, _) => major.unwrap_or_default().to_string(),
+    }
+}
+
+fn describe_scheme(scheme: &AuthScheme) -> String {
+    match scheme {
+        AuthScheme::Bearer => "bearer token".to_string(),
+        AuthScheme::Basic => "basic auth".to_string(),
+        AuthScheme::ApiKey { header } => format!("API key in {}", header),
+    }
+}
+
+fn reqwest_method(method: HttpMethod) -> reqwest::Method {
+    match method {
+        HttpMethod::Get => reqwest::Method::GET,
+        HttpMethod::Post => reqwest::Method::POST,
+        HttpMethod::Put => reqwest::Method::PUT,
+        HttpMethod::Patch => reqwest::Method::PATCH,
+        HttpMethod::Delete => reqwest::Method::DELETE,
+    }
+}
+
 fn stringify_value(value: &Value) -> String {
     match value {
         Value::String(s) => s.clone(),
@@ -366,10 +983,74 @@ fn stringify_value(value: &Value) -> String {
     }
 }
 
+/// Text of a path parameter: strings as-is, anything else as its JSON form
+pub fn path_value<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .map(|value| stringify_value(&value))
+        .unwrap_or_default()
+}
+
+/// Fill the placeholders of a route like `/users/{id}` or `/users/:id`.
+///
+/// A placeholder takes the value of the same name, else the next value whose name matches
+/// no placeholder. Values are percent-encoded, keeping `/` in wildcard segments.
+pub fn fill_path_params(template: &str, values: &[(&str, String)]) -> String {
+    fn placeholder(segment: &str) -> Option<(&str, bool)> {
+        let name = segment
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .or_else(|| segment.strip_prefix(':'))
+            .or_else(|| segment.strip_prefix('*').map(|s| s.trim_start_matches('*')))?;
+        match name.strip_prefix('*') {
+            Some(name) => Some((name, true)),
+            None => Some((name, segment.starts_with('*'))),
+        }
+    }
+
+    let names: Vec<&str> = template.split('/').filter_map(placeholder).map(|(name, _)| name).collect();
+    let mut unnamed = values.iter().filter(|(name, _)| !names.contains(name));
+    template
+        .split('/')
+        .map(|segment| match placeholder(segment) {
+            Some((name, wildcard)) => values
+                .iter()
+                .find(|(n, _)| *n == name)
+                .or_else(|| unnamed.next())
+                .map(|(_, value)| percent_encode(value, wildcard))
+                .unwrap_or_else(|| segment.to_string()),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn percent_encode(value: &str, keep_slashes: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slashes => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_fill_path_params() {
+        let values = [("id", "42".to_string()), ("org", "acme corp".to_string())];
+        assert_eq!(fill_path_params("/orgs/{org}/users/:id", &values), "/orgs/acme%20corp/users/42");
+        // Unnamed values fill the remaining placeholders in order
+        let values = [("user_id", path_value(&7)), ("slug", path_value(&"a/b"))];
+        assert_eq!(fill_path_params("/users/{id}/files/{*path}", &values), "/users/7/files/a/b");
+    }
+
     #[test]
     fn test_server_addr_base_url() {
         let addr = ServerAddr {
@@ -383,6 +1064,7 @@ mod tests {
     fn test_function_metadata_storage() {
         let function = RpcFunction {
             name: "test_function".to_string(),
+            rust_name: None,
             is_mutation: true,
             is_async: false,
             input_type_name: Some("TestInput".to_string()),
@@ -390,6 +1072,25 @@ mod tests {
             params: vec![],
             input_schema_json: None,
             output_schema_json: Some(r#"{"kind": "Primitive", "value": "String"}"#.to_string()),
+            description: Some("Test function".to_string()),
+            deprecated: None,
+            error_type_name: None,
+            path: None,
+            method: HttpMethod::Post,
+            is_subscription: false,
+            is_websocket: false,
+            body_encoding: BodyEncoding::Json,
+            auth: Some(AuthScheme::Bearer),
+            namespace: None,
+            tags: vec![],
+            timeout_ms: None,
+            cache_max_age: None,
+            topic: None,
+            status: None,
+            error_statuses: Vec::new(),
+            since: Some("1.2".to_string()),
+            removed: None,
+            rate_limit: None,
         };
 
         let mut functions = HashMap::new();
@@ -403,10 +1104,96 @@ mod tests {
             http_client: Client::new(),
             functions,
             endpoints_discovery: Vec::new(),
+            schema_digest: None,
+            metadata_etag: None,
+            api_version: Some("1.4.0".to_string()),
+            credentials: None,
+            retry_policy: RetryPolicy::default(),
+            response_cache: None,
+            headers: Vec::new(),
+            conflicts: Vec::new(),
+            dispatch: false,
+            rpc_path: None,
         };
 
         assert!(client.get_function_metadata("test_function").is_some());
         assert_eq!(client.get_function_names().len(), 1);
+        assert!(client.check_api_version("1.0.0").is_ok());
+        assert!(matches!(
+            client.check_api_version("2.0.0"),
+            Err(RpcClientError::VersionMismatch { .. })
+        ));
+        assert_eq!(breaking_version("v0.3.1"), "0.3");
+        // The server reports 1.4.0
+        assert!(client.check_available("test_function", Some("1.2"), None).is_ok());
+        assert!(matches!(
+            client.check_available("test_function", Some("1.5"), None),
+            Err(RpcClientError::NotAvailable { .. })
+        ));
+        assert!(client.check_available("test_function", None, Some("1.4")).is_err());
+
+        assert!(matches!(
+            client.require_credentials(&AuthScheme::Bearer),
+            Err(RpcClientError::MissingCredentials(_))
+        ));
+        let client = client.with_credentials(Credentials::Bearer("token".to_string()));
+        assert!(client.require_credentials(&AuthScheme::Bearer).is_ok());
+        assert!(client.require_credentials(&AuthScheme::Basic).is_err());
+
+        assert!(matches!(client.require_header("User-Agent"), Err(RpcClientError::MissingHeader(_))));
+        let client = client.with_header("user-agent", "a").with_header("User-Agent", "b");
+        assert!(client.require_header("User-Agent").is_ok());
+        assert_eq!(client.headers, [("User-Agent".to_string(), "b".to_string())]);
+
+        // Only functions declaring statuses are matched to the endpoints they serve
+        let mut client = client;
+        let plant_tree = RpcFunction {
+            name: "plant_tree".to_string(),
+            path: Some("/trees/{id}".to_string()),
+            status: Some(201),
+            error_statuses: vec![409],
+            ..function
+        };
+        let rename_tree = RpcFunction {
+            name: "rename_tree".to_string(),
+            path: Some("/trees/{id}/name".to_string()),
+            body_encoding: BodyEncoding::Form,
+            ..plant_tree.clone()
+        };
+        client.functions.insert(plant_tree.name.clone(), plant_tree);
+        client.functions.insert(rename_tree.name.clone(), rename_tree);
+        let declares_statuses = |f: &RpcFunction| f.status.is_some();
+        let serving = client.function_serving("/trees/7", HttpMethod::Post, declares_statuses);
+        assert_eq!(serving.map(|f| f.name.as_str()), Some("plant_tree"));
+        assert!(client.function_serving("/trees/7", HttpMethod::Get, declares_statuses).is_none());
+        assert!(client.function_serving("/test_function", HttpMethod::Post, |f| f.status.is_none()).is_none());
+
+        client.conflicts = vec![FunctionConflict {
+            function_name: "plant_tree".to_string(),
+            namespace: None,
+            handlers: vec!["app::a::plant_tree".to_string(), "app::b::plant_tree".to_string()],
+        }];
+        assert!(matches!(
+            client.check_unambiguous("plant_tree"),
+            Err(RpcClientError::AmbiguousFunction { handlers, .. }) if handlers.len() == 2
+        ));
+        assert!(client.check_unambiguous("rename_tree").is_ok());
+
+        // Params of functions reading `Form<T>` are sent urlencoded
+        let params = serde_json::json!({ "name": "old oak", "tags": ["a", "b"] });
+        let request = client.request_with_params("/trees/7/name", HttpMethod::Post, Some(&params)).build().unwrap();
+        assert_eq!(request.headers()["content-type"], "application/x-www-form-urlencoded");
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+        assert_eq!(body, b"name=old+oak&tags=a&tags=b");
+        let request = client.request_with_params("/trees/7", HttpMethod::Post, Some(&params)).build().unwrap();
+        assert_eq!(request.headers()["content-type"], "application/json");
+
+        let error = RpcClientError::Status {
+            status: 409,
+            message: "taken".to_string(),
+            details: Some(Value::from("Taken")),
+        };
+        assert!(matches!(RpcCallError::<String>::from_client_error(error), RpcCallError::Application(e) if e == "Taken"));
     }
 
     #[test]
@@ -430,6 +1217,16 @@ mod tests {
             http_client: Client::new(),
             functions: HashMap::new(),
             endpoints_discovery: endpoints_discovery.clone(),
+            schema_digest: None,
+            metadata_etag: None,
+            api_version: None,
+            credentials: None,
+            retry_policy: RetryPolicy::default(),
+            response_cache: None,
+            headers: Vec::new(),
+            conflicts: Vec::new(),
+            dispatch: false,
+            rpc_path: None,
         };
 
         let discovered_endpoints = client.get_endpoints_discovery();
@@ -467,14 +1264,117 @@ mod tests {
         }
         "#;
 
-        let metadata: MetadataResponse = serde_json::from_str(json_response).unwrap();
+        let metadata = MetadataDocument::from_json(json_response).unwrap();
         assert_eq!(metadata.total_functions, 2);
         assert_eq!(metadata.functions.len(), 1);
         assert_eq!(metadata.total_endpoints, 1);
         assert_eq!(metadata.endpoints_discovery.len(), 1);
 
         let endpoint = &metadata.endpoints_discovery[0];
-        assert_eq!(endpoint["uri"], "/api/test");
-        assert_eq!(endpoint["methods"].as_array().unwrap().len(), 2);
+        assert_eq!(endpoint.uri, "/api/test");
+        assert_eq!(endpoint.methods.len(), 2);
+    }
+
+    /// Answer one request with `{}` on a local port, returning the port and the request line
+    fn serve_once() -> (usize, std::thread::JoinHandle<String>) {
+        use std::io::{BufRead, BufReader, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port().into();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            // Skip the headers; a body, if any, is left unread
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}")
+                .unwrap();
+            request_line.trim_end().to_string()
+        });
+        (port, server)
+    }
+
+    fn local_client(port: usize, functions: HashMap<String, RpcFunction>) -> LocoClient {
+        LocoClient {
+            server_addr: ServerAddr { ip: "127.0.0.1".to_string(), port },
+            http_client: Client::new(),
+            functions,
+            endpoints_discovery: Vec::new(),
+            schema_digest: None,
+            metadata_etag: None,
+            api_version: None,
+            credentials: None,
+            retry_policy: RetryPolicy::default(),
+            response_cache: None,
+            headers: Vec::new(),
+            conflicts: Vec::new(),
+            dispatch: false,
+            rpc_path: None,
+        }
+    }
+
+    fn register_function() -> RpcFunction {
+        RpcFunction {
+            name: "register".to_string(),
+            rust_name: None,
+            is_mutation: true,
+            is_async: true,
+            input_type_name: None,
+            output_type_name: "User".to_string(),
+            params: vec![],
+            input_schema_json: None,
+            output_schema_json: None,
+            description: None,
+            deprecated: None,
+            error_type_name: None,
+            path: Some("/api/auth/register".to_string()),
+            method: HttpMethod::Post,
+            is_subscription: false,
+            is_websocket: false,
+            body_encoding: BodyEncoding::Json,
+            auth: None,
+            namespace: None,
+            tags: vec![],
+            timeout_ms: None,
+            cache_max_age: None,
+            topic: None,
+            status: None,
+            error_statuses: Vec::new(),
+            since: None,
+            removed: None,
+            rate_limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatched_calls_go_to_the_advertised_rpc_route() {
+        let functions = HashMap::from([("register".to_string(), register_function())]);
+        let (port, server) = serve_once();
+        let client = local_client(port, functions.clone()).with_dispatch();
+        client.call_function("register", None).await.unwrap();
+        assert_eq!(server.join().unwrap(), "POST /_laz/rpc HTTP/1.1");
+
+        let (port, server) = serve_once();
+        let mut client = local_client(port, functions).with_dispatch();
+        client.rpc_path = Some("/internal/rpc".to_string());
+        client.call_function("register", None).await.unwrap();
+        assert_eq!(server.join().unwrap(), "POST /internal/rpc HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_calls_go_to_the_resolved_path() {
+        let functions = HashMap::from([("register".to_string(), register_function())]);
+        let (port, server) = serve_once();
+        local_client(port, functions).call_function("register", None).await.unwrap();
+        assert_eq!(server.join().unwrap(), "POST /api/auth/register HTTP/1.1");
+
+        let (port, server) = serve_once();
+        let params = serde_json::json!({ "page": 2 });
+        local_client(port, HashMap::new()).call_endpoint("/api/trees", HttpMethod::Get, Some(params)).await.unwrap();
+        assert_eq!(server.join().unwrap(), "GET /api/trees?page=2 HTTP/1.1");
     }
 }
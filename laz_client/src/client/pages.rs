@@ -0,0 +1,115 @@
+//! Walking the pages of `paginated` queries.
+
+use std::future::Future;
+
+use futures_util::stream::{self, Stream};
+use laz_types::Pagination;
+use serde_json::Value;
+
+use super::RpcClientError;
+
+/// Fetch the pages of a paginated query one after the other, starting from `params`.
+///
+/// `fetch` calls the query with the params of one page. Numbered pages stop at an empty or
+/// short page, cursor pages when the next cursor is missing or `null`; a failed call ends
+/// the stream after yielding its error.
+pub fn paginate<F, Fut>(
+    pagination: Pagination,
+    params: Value,
+    fetch: F,
+) -> impl Stream<Item = Result<Value, RpcClientError>>
+where
+    F: FnMut(Value) -> Fut,
+    Fut: Future<Output = Result<Value, RpcClientError>>,
+{
+    stream::unfold((Some(params), fetch), move |(params, mut fetch)| {
+        let pagination = pagination.clone();
+        async move {
+            let params = params?;
+            match fetch(params.clone()).await {
+                Ok(page) => {
+                    let next = next_params(&pagination, params, &page);
+                    Some((Ok(page), (next, fetch)))
+                }
+                Err(e) => Some((Err(e), (None, fetch))),
+            }
+        }
+    })
+}
+
+/// Params of the page after `page`, or `None` when it was the last one
+fn next_params(pagination: &Pagination, mut params: Value, page: &Value) -> Option<Value> {
+    let item_count = |items_field: &Option<String>| {
+        items_field
+            .as_ref()
+            .and_then(|field| page[field].as_array())
+            .map(Vec::len)
+    };
+    match pagination {
+        Pagination::Page { page_field, limit_field, items_field } => {
+            let count = item_count(items_field);
+            let limit = limit_field.as_ref().and_then(|field| params[field].as_u64());
+            match (count, limit) {
+                (Some(0), _) => return None,
+                (Some(count), Some(limit)) if (count as u64) < limit => return None,
+                // Without an item count there is no way to see the end
+                (None, _) => return None,
+                _ => {}
+            }
+            let current = params[page_field].as_i64().unwrap_or(1);
+            params[page_field] = Value::from(current + 1);
+        }
+        Pagination::Cursor { cursor_field, next_cursor_field, items_field } => {
+            if item_count(items_field) == Some(0) {
+                return None;
+            }
+            let next = page.get(next_cursor_field).filter(|cursor| !cursor.is_null())?;
+            if next.as_str() == Some("") {
+                return None;
+            }
+            params[cursor_field] = next.clone();
+        }
+    }
+    Some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_cursor_pages_follow_next_cursor() {
+        let pagination = Pagination::Cursor {
+            cursor_field: "after".to_string(),
+            next_cursor_field: "nextCursor".to_string(),
+            items_field: Some("items".to_string()),
+        };
+        let pages: Vec<Value> = paginate(pagination, json!({ "q": "ada" }), |params| async move {
+            Ok(match params["after"].as_str() {
+                None => json!({ "items": [1, 2], "nextCursor": "c2" }),
+                Some("c2") => json!({ "items": [3], "nextCursor": null }),
+                Some(other) => panic!("unexpected cursor {}", other),
+            })
+        })
+        .map(|page| page.unwrap())
+        .collect()
+        .await;
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1]["items"], json!([3]));
+    }
+
+    #[test]
+    fn test_numbered_pages_stop_at_short_page() {
+        let pagination = Pagination::Page {
+            page_field: "page".to_string(),
+            limit_field: Some("limit".to_string()),
+            items_field: Some("items".to_string()),
+        };
+        let params = json!({ "limit": 2 });
+        let next = next_params(&pagination, params.clone(), &json!({ "items": [1, 2] }));
+        assert_eq!(next, Some(json!({ "limit": 2, "page": 2 })));
+        assert_eq!(next_params(&pagination, params, &json!({ "items": [1] })), None);
+    }
+}
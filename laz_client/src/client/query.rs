@@ -0,0 +1,88 @@
+//! Query string encoding of structured parameters.
+//!
+//! Arrays of scalars repeat their key (`tags=a&tags=b`) and nested values use bracketed
+//! keys (`filter[owner]=ada`, `sort[0][field]=name`), the layout `serde_qs` and
+//! `axum_extra::extract::Query` read. `null` values are left out.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{percent_encode, stringify_value, RpcClientError};
+
+/// Encode `params` as a query string, without the leading `?`
+pub fn encode_query<T: Serialize>(params: &T) -> Result<String, RpcClientError> {
+    let value = serde_json::to_value(params)?;
+    Ok(query_pairs(&value)
+        .iter()
+        .map(|(key, value)| format!("{}={}", percent_encode(key, false), percent_encode(value, false)))
+        .collect::<Vec<_>>()
+        .join("&"))
+}
+
+/// Flatten an object into key/value pairs
+pub(crate) fn query_pairs(value: &Value) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    if let Value::Object(fields) = value {
+        for (key, value) in fields {
+            push_pairs(key.clone(), value, &mut pairs);
+        }
+    }
+    pairs
+}
+
+fn push_pairs(key: String, value: &Value, pairs: &mut Vec<(String, String)>) {
+    match value {
+        Value::Null => {}
+        Value::Object(fields) => {
+            for (field, value) in fields {
+                push_pairs(format!("{}[{}]", key, field), value, pairs);
+            }
+        }
+        Value::Array(items) if items.iter().all(is_scalar) => {
+            for item in items {
+                push_pairs(key.clone(), item, pairs);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                push_pairs(format!("{}[{}]", key, index), item, pairs);
+            }
+        }
+        scalar => pairs.push((key, stringify_value(scalar))),
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_nested_values_use_bracketed_keys() {
+        let filters = json!({
+            "q": "rust & web",
+            "tags": ["a", "b"],
+            "owner": { "name": "ada", "team": null },
+            "sort": [{ "field": "name" }],
+            "limit": 20
+        });
+        let query = encode_query(&filters).unwrap();
+        let mut pairs: Vec<_> = query.split('&').collect();
+        pairs.sort();
+        assert_eq!(
+            pairs,
+            [
+                "limit=20",
+                "owner%5Bname%5D=ada",
+                "q=rust%20%26%20web",
+                "sort%5B0%5D%5Bfield%5D=name",
+                "tags=a",
+                "tags=b",
+            ]
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Matching of calls to the routes of the functions serving them.
+//!
+//! What a function declares, such as its statuses or body encoding, then applies whichever
+//! way it is called.
+
+/// Whether `endpoint` is served by a route like `/trees/{id}`, `/trees/:id` or
+/// `/files/{*path}`; a query string on the endpoint is ignored
+pub(crate) fn route_matches(route: &str, endpoint: &str) -> bool {
+    let path = endpoint.split('?').next().unwrap_or_default();
+    let mut segments = path.split('/');
+    for pattern in route.split('/') {
+        if pattern.starts_with('*') || pattern.starts_with("{*") {
+            return true;
+        }
+        let Some(segment) = segments.next() else {
+            return false;
+        };
+        let placeholder = pattern.starts_with(':') || (pattern.starts_with('{') && pattern.ends_with('}'));
+        if placeholder && !segment.is_empty() {
+            continue;
+        }
+        if pattern != segment {
+            return false;
+        }
+    }
+    segments.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_match_filled_endpoints() {
+        assert!(route_matches("/trees/{id}", "/trees/42"));
+        assert!(route_matches("/trees/:id/photo", "/trees/42/photo?size=small"));
+        assert!(route_matches("/files/{*path}", "/files/a/b"));
+        assert!(route_matches("/api/plant", "/api/plant"));
+        assert!(!route_matches("/trees/{id}", "/trees/"));
+        assert!(!route_matches("/trees/{id}", "/trees/42/photo"));
+        assert!(!route_matches("/trees/{id}/photo", "/trees/42"));
+        assert!(!route_matches("/api/plant", "/api/plants"));
+    }
+}
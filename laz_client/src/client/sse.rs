@@ -0,0 +1,106 @@
+//! Decoding of server-sent event streams produced by `rpc_subscription` handlers.
+//!
+//! Each event's `data` lines are joined and parsed as one JSON value; `event`, `id` and
+//! `retry` fields and comment lines are ignored.
+
+use futures_util::stream::{self, Stream, StreamExt};
+use serde_json::Value;
+
+use super::RpcClientError;
+
+/// Decode a response body into the JSON values of its events
+pub(crate) fn json_events<S, B, E>(body: S) -> impl Stream<Item = Result<Value, RpcClientError>>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    RpcClientError: From<E>,
+{
+    let state = EventReader {
+        body: Box::pin(body),
+        buffer: Vec::new(),
+        data: Vec::new(),
+        done: false,
+    };
+    stream::unfold(state, |mut reader| async move {
+        let event = reader.next_event().await?;
+        Some((event, reader))
+    })
+}
+
+struct EventReader<S> {
+    body: std::pin::Pin<Box<S>>,
+    /// Bytes received but not yet split into lines
+    buffer: Vec<u8>,
+    /// `data` lines of the event being read
+    data: Vec<String>,
+    done: bool,
+}
+
+impl<S, B, E> EventReader<S>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    RpcClientError: From<E>,
+{
+    async fn next_event(&mut self) -> Option<Result<Value, RpcClientError>> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end_matches(['\n', '\r']);
+                if line.is_empty() {
+                    if let Some(event) = self.dispatch() {
+                        return Some(event);
+                    }
+                } else if let Some(data) = line.strip_prefix("data:") {
+                    self.data.push(data.strip_prefix(' ').unwrap_or(data).to_string());
+                }
+                continue;
+            }
+            if self.done {
+                return self.dispatch();
+            }
+            match self.body.next().await {
+                Some(Ok(chunk)) => self.buffer.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+                None => {
+                    // A final event without its blank line still counts
+                    self.done = true;
+                    self.buffer.push(b'\n');
+                }
+            }
+        }
+    }
+
+    fn dispatch(&mut self) -> Option<Result<Value, RpcClientError>> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        Some(serde_json::from_str(&data).map_err(RpcClientError::JsonError))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_events_split_across_chunks() {
+        let chunks = [
+            ": keep-alive\n\nevent: tick\nda",
+            "ta: {\"n\": 1}\n\n",
+            "data: [1,\ndata: 2]\r\n\r\ndata: \"last\"",
+        ];
+        let body = stream::iter(chunks.map(Ok::<_, RpcClientError>));
+        let events: Vec<Value> = json_events(body)
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        assert_eq!(events, [json!({ "n": 1 }), json!([1, 2]), json!("last")]);
+    }
+}
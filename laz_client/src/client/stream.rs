@@ -0,0 +1,55 @@
+//! Helpers for response bodies streamed by `stream_body` handlers.
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use super::RpcClientError;
+
+/// Split a streamed body into lines, without their `\n` or `\r\n` terminators.
+///
+/// Suits line-oriented exports such as CSV or newline-delimited JSON. A final line without
+/// a terminator is still yielded.
+pub fn lines<S, B>(body: S) -> impl Stream<Item = Result<String, RpcClientError>>
+where
+    S: Stream<Item = Result<B, RpcClientError>>,
+    B: AsRef<[u8]>,
+{
+    let state = (Box::pin(body), Vec::new(), false);
+    stream::unfold(state, |(mut body, mut buffer, mut done)| async move {
+        loop {
+            if let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string();
+                return Some((Ok(line), (body, buffer, done)));
+            }
+            if done {
+                if buffer.is_empty() {
+                    return None;
+                }
+                let line = String::from_utf8_lossy(&std::mem::take(&mut buffer)).into_owned();
+                return Some((Ok(line), (body, buffer, done)));
+            }
+            match body.next().await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => {
+                    done = true;
+                    buffer.clear();
+                    return Some((Err(e), (body, buffer, done)));
+                }
+                None => done = true,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lines_split_across_chunks() {
+        let chunks = ["id,name\r\n1,ada", "\n2,grace\n3,", "linus"];
+        let body = stream::iter(chunks.map(Ok::<_, RpcClientError>));
+        let rows: Vec<String> = lines(body).map(|line| line.unwrap()).collect().await;
+        assert_eq!(rows, ["id,name", "1,ada", "2,grace", "3,linus"]);
+    }
+}
@@ -0,0 +1,43 @@
+//! Per-call timeouts of functions declared with `timeout_ms`.
+
+use std::future::Future;
+use std::time::Duration;
+
+use super::RpcClientError;
+
+/// Run `call`, failing with [`RpcClientError::Timeout`] when it takes longer than `timeout`
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn with_timeout<F, T>(timeout: Duration, call: F) -> Result<T, RpcClientError>
+where
+    F: Future<Output = Result<T, RpcClientError>>,
+{
+    tokio::time::timeout(timeout, call)
+        .await
+        .unwrap_or(Err(RpcClientError::Timeout(timeout)))
+}
+
+// Without a runtime-specific timer the call runs to completion, as the browser's own
+// request timeout applies
+#[cfg(target_arch = "wasm32")]
+pub async fn with_timeout<F, T>(_timeout: Duration, call: F) -> Result<T, RpcClientError>
+where
+    F: Future<Output = Result<T, RpcClientError>>,
+{
+    call.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_slow_calls_time_out() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        };
+        let result = with_timeout(Duration::from_millis(10), slow).await;
+        assert!(matches!(result, Err(RpcClientError::Timeout(t)) if t == Duration::from_millis(10)));
+        assert_eq!(with_timeout(Duration::from_secs(1), async { Ok(7) }).await.unwrap(), 7);
+    }
+}
@@ -0,0 +1,115 @@
+//! Sessions with handlers declared with `rpc_ws`.
+//!
+//! Each message is one JSON value in a text frame. Binary frames are decoded the same way,
+//! and pings are answered by the socket itself.
+
+use std::marker::PhantomData;
+
+use futures_util::{SinkExt, StreamExt};
+use laz_types::RpcErrorBody;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest, http::HeaderValue, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use super::RpcClientError;
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Open session with an `rpc_ws` handler, sending `In` messages and receiving `Out` ones
+pub struct WsSession<In, Out> {
+    socket: Socket,
+    messages: PhantomData<fn(In) -> Out>,
+}
+
+impl<In: Serialize, Out: DeserializeOwned> WsSession<In, Out> {
+    /// Send one message
+    pub async fn send(&mut self, message: &In) -> Result<(), RpcClientError> {
+        let text = serde_json::to_string(message)?;
+        self.socket.send(Message::text(text)).await.map_err(ws_error)
+    }
+
+    /// Next message from the server, or `None` once the session is closed
+    pub async fn recv(&mut self) -> Option<Result<Out, RpcClientError>> {
+        loop {
+            let decoded = match self.socket.next().await? {
+                Ok(Message::Text(text)) => serde_json::from_str(text.as_str()),
+                Ok(Message::Binary(bytes)) => serde_json::from_slice(&bytes),
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => continue,
+                Err(e) => return Some(Err(ws_error(e))),
+            };
+            return Some(decoded.map_err(RpcClientError::from));
+        }
+    }
+
+    /// Close the session, telling the server
+    pub async fn close(mut self) -> Result<(), RpcClientError> {
+        self.socket.close(None).await.map_err(ws_error)
+    }
+}
+
+/// Open a session at `url`, sending `headers` with the handshake
+pub(crate) async fn connect<In, Out>(url: &str, headers: &[(String, String)]) -> Result<WsSession<In, Out>, RpcClientError> {
+    let mut request = url.into_client_request().map_err(ws_error)?;
+    for (name, value) in headers {
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| RpcClientError::InvalidParameter(format!("Invalid value for header {}", name)))?;
+        let name = tungstenite::http::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| RpcClientError::InvalidParameter(format!("Invalid header name {}", name)))?;
+        request.headers_mut().insert(name, value);
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(request).await.map_err(ws_error)?;
+    Ok(WsSession { socket, messages: PhantomData })
+}
+
+/// A handshake the server refused carries its error body, like any other failed call
+fn ws_error(error: tungstenite::Error) -> RpcClientError {
+    match error {
+        tungstenite::Error::Http(response) => {
+            match response.body().as_deref().map(serde_json::from_slice::<RpcErrorBody>) {
+                Some(Ok(body)) => RpcClientError::Rpc(body),
+                _ => RpcClientError::ServerError(format!("WebSocket handshake failed: {}", response.status())),
+            }
+        }
+        other => RpcClientError::WebSocket(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+    #[tokio::test]
+    // The handshake callback's signature is tungstenite's
+    #[allow(clippy::result_large_err)]
+    async fn test_session_exchanges_json_messages() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/api/echo", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut token = None;
+            let mut socket = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+                token = request.headers().get("authorization").cloned();
+                Ok(response)
+            })
+            .await
+            .unwrap();
+            // Echo each number doubled, after a ping the client must skip
+            socket.send(Message::Ping(Vec::new().into())).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = socket.next().await {
+                let n: u32 = serde_json::from_str(text.as_str()).unwrap();
+                socket.send(Message::text((n * 2).to_string())).await.unwrap();
+            }
+            token
+        });
+
+        let headers = [("Authorization".to_string(), "Bearer t0k".to_string())];
+        let mut session: WsSession<u32, u32> = connect(&url, &headers).await.unwrap();
+        session.send(&21).await.unwrap();
+        assert_eq!(session.recv().await.unwrap().unwrap(), 42);
+        session.close().await.unwrap();
+        assert_eq!(server.await.unwrap().unwrap(), "Bearer t0k");
+    }
+}
@@ -6,6 +6,26 @@
 
 pub mod client;
 
-pub use client::{LocoClient, ServerAddr, RpcClientError, RpcFunction};
+pub use client::{
+    encode_query, fill_path_params, idempotency_key, lines, paginate, path_value, with_timeout, Credentials, RetryPolicy, LocoClient, ServerAddr, RpcCallError, RpcClientError, RpcFunction,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::WsSession;
 pub use laz_client_macros::{generate_rpc_client, create_rpc_client};
+pub use laz_types::{
+    base64, int128, validate, AuthScheme, FunctionDocument, HttpMethod, MetadataDocument, Pagination, RateLimit, RpcErrorBody,
+    ServerVersion, ValidationError, METADATA_SCHEMA_VERSION,
+};
+pub use bytes::Bytes;
+pub use futures_util;
 pub use reqwest;
+pub use reqwest::multipart;
+
+#[cfg(feature = "chrono")]
+pub use chrono;
+
+#[cfg(feature = "uuid")]
+pub use uuid;
+
+#[cfg(feature = "decimal")]
+pub use rust_decimal;
@@ -60,6 +60,15 @@ fn write_generated_client(code: &str) {
 fn setup_rerun_triggers() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-env-changed=LAZ_SERVER_URL");
+    println!("cargo:rerun-if-env-changed=LAZ_TARGET_API_VERSION");
+    println!("cargo:rerun-if-env-changed=LAZ_CLIENT_TAGS");
+    println!("cargo:rerun-if-env-changed=LAZ_METADATA_TOKEN");
+    println!("cargo:rerun-if-env-changed=LAZ_METADATA_SECRET");
+    println!("cargo:rerun-if-env-changed=LAZ_METADATA_PATH");
+    println!("cargo:rerun-if-env-changed=LAZ_METADATA_SNAPSHOT");
+    if let Ok(snapshot) = env::var("LAZ_METADATA_SNAPSHOT") {
+        println!("cargo:rerun-if-changed={}", snapshot);
+    }
 
     if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
         let manifest_dir = PathBuf::from(manifest_dir);
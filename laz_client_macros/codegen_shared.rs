@@ -1,53 +1,154 @@
 use reqwest::blocking::Client;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::error::Error;
 
+/// Path of the server's metadata document: `LAZ_METADATA_PATH` when set, else the one
+/// advertised by the server's `/_laz` index, else `/_laz/metadata`
+fn metadata_path(client: &Client, server_url: &str) -> String {
+    if let Ok(path) = std::env::var("LAZ_METADATA_PATH") {
+        return path;
+    }
+    let advertised = client
+        .get(format!("{}/_laz", server_url))
+        .send()
+        .ok()
+        .filter(|response| response.status().is_success())
+        .and_then(|response| response.text().ok())
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok());
+    advertised
+        .and_then(|index| index["metadata"].as_str().map(String::from))
+        .unwrap_or_else(|| "/_laz/metadata".to_string())
+}
+
 pub fn fetch_metadata_json(server_url: &str) -> Result<String, Box<dyn Error>> {
-    let metadata_url = format!("{}/_laz/metadata", server_url.trim_end_matches('/'));
+    let server_url = server_url.trim_end_matches('/');
     let client = Client::new();
-    let response = client.get(&metadata_url).send()?;
+    let metadata_url = format!("{}{}", server_url, metadata_path(&client, server_url));
+    let mut request = client.get(&metadata_url);
+    // Servers built `with_auth` only describe their API to holders of the token
+    if let Ok(token) = std::env::var("LAZ_METADATA_TOKEN") {
+        request = request.bearer_auth(token);
+    }
+    // Servers built `with_signing_secret` sign the document for holders of the secret
+    let secret = std::env::var("LAZ_METADATA_SECRET").ok();
+    // A copy kept with its ETag lets an unchanged server answer `304` instead of resending
+    // it; a copy can't be checked against a signature, so it's only used without a secret
+    let cache_path = std::env::var_os("OUT_DIR").map(|dir| std::path::Path::new(&dir).join("laz_metadata_etag"));
+    let cached = cache_path
+        .as_ref()
+        .filter(|_| secret.is_none())
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|cached| cached.split_once('\n').map(|(etag, json)| (etag.to_string(), json.to_string())));
+    if let Some((etag, _)) = &cached {
+        request = request.header("If-None-Match", etag);
+    }
+    let response = request.send()?;
 
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some((_, json)) = cached {
+            return Ok(json);
+        }
+    }
     if !response.status().is_success() {
         return Err(format!("Failed to fetch metadata: HTTP {}", response.status()).into());
     }
 
-    Ok(response.text()?)
+    let etag = response.headers().get("ETag").and_then(|value| value.to_str().ok()).map(String::from);
+    let signature =
+        response.headers().get(laz_types::signature::HEADER).and_then(|value| value.to_str().ok()).map(String::from);
+    let json = response.text()?;
+    if let Some(secret) = &secret {
+        let signed = signature
+            .is_some_and(|signature| laz_types::signature::verify(secret.as_bytes(), json.as_bytes(), &signature));
+        if !signed {
+            return Err("Metadata signature is missing or doesn't match LAZ_METADATA_SECRET".into());
+        }
+    }
+    if let (Some(path), Some(etag)) = (cache_path, etag) {
+        let _ = std::fs::write(path, format!("{}\n{}", etag, json));
+    }
+    Ok(json)
 }
 
 pub fn generate_client_code_from_metadata_json(
     server_url: &str,
     metadata_json: &str,
 ) -> Result<String, Box<dyn Error>> {
-    let metadata: Value = serde_json::from_str(metadata_json)?;
-    let functions = metadata["functions"]
-        .as_array()
-        .ok_or("No functions found in metadata")?
-        .to_vec();
-    let endpoints = metadata["endpoints_discovery"]
-        .as_array()
-        .cloned()
-        .unwrap_or_else(Vec::new);
+    let metadata = laz_types::MetadataDocument::from_json(metadata_json)?;
+    // Methods of handlers sharing a name would collide, and calls couldn't tell them apart
+    if !metadata.conflicts.is_empty() {
+        let described: Vec<String> = metadata
+            .conflicts
+            .iter()
+            .map(|conflict| format!("{} ({})", conflict.function_name, conflict.handlers.join(", ")))
+            .collect();
+        return Err(format!(
+            "RPC functions registered by several handlers: {}; give them distinct names or namespaces",
+            described.join("; ")
+        )
+        .into());
+    }
+    // The generator reads functions and types as JSON in the shape the document defines
+    let mut functions =
+        metadata.functions.iter().map(serde_json::to_value).collect::<Result<Vec<Value>, _>>()?;
+    // Clients targeting an API version leave out functions that version doesn't have
+    if let Ok(target) = std::env::var("LAZ_TARGET_API_VERSION") {
+        functions.retain(|func| available_in(func, &target));
+    }
+    // A comma-separated list of tags restricts the client to functions with one of them
+    if let Ok(tags) = std::env::var("LAZ_CLIENT_TAGS") {
+        let tags: Vec<&str> = tags.split(',').map(str::trim).filter(|t| !t.is_empty()).collect();
+        if !tags.is_empty() {
+            functions.retain(|func| has_any_tag(func, &tags));
+        }
+    }
+    let type_table = metadata
+        .types
+        .iter()
+        .map(|(name, schema)| Ok((name.clone(), serde_json::to_value(schema)?)))
+        .collect::<Result<serde_json::Map<String, Value>, serde_json::Error>>()?;
+    let schema_digest = metadata.schema_digest.as_deref();
+    let api_version = metadata.api_version.as_deref();
 
-    generate_dynamic_typed_client(&functions, &endpoints, server_url)
+    generate_dynamic_typed_client(
+        &functions,
+        &type_table,
+        server_url,
+        schema_digest,
+        api_version,
+    )
 }
 
 pub fn generate_client_code_from_server(
     server_url: &str,
 ) -> Result<(String, String), Box<dyn Error>> {
-    let metadata_json = fetch_metadata_json(server_url)?;
+    // A committed snapshot lets codegen run without a live server
+    let metadata_json = match std::env::var("LAZ_METADATA_SNAPSHOT") {
+        Ok(path) => std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read metadata snapshot {}: {}", path, e))?,
+        Err(_) => fetch_metadata_json(server_url)?,
+    };
     let generated_code = generate_client_code_from_metadata_json(server_url, &metadata_json)?;
     Ok((generated_code, metadata_json))
 }
 
 fn generate_dynamic_typed_client(
     functions: &[Value],
-    endpoints: &[Value],
+    type_table: &serde_json::Map<String, Value>,
     server_url: &str,
+    schema_digest: Option<&str>,
+    api_version: Option<&str>,
 ) -> Result<String, Box<dyn Error>> {
     let mut code = String::new();
-    let mut types = HashMap::new();
-    let endpoint_map = build_endpoint_map(endpoints);
+    // Sorted so the generated code doesn't change between builds
+    let mut types = BTreeMap::new();
+
+    // Types shared between functions or referenced from other types come from the type table
+    for (type_name, schema) in type_table {
+        let type_def = generate_type_from_schema(type_name, Some(&schema.to_string()));
+        types.insert(type_name.clone(), type_def);
+    }
 
     for func in functions {
         if let (Some(_func_name), Some(output_type)) = (
@@ -62,17 +163,62 @@ fn generate_dynamic_typed_client(
                 }
             }
 
-            if !output_type.is_empty() && !types.contains_key(output_type) {
+            if !is_unit_output(func) && !types.contains_key(output_type) {
                 let type_def =
                     generate_type_from_schema(output_type, func["output_schema_json"].as_str());
                 types.insert(output_type.to_string(), type_def);
             }
+
+            if let Some(error_type) = func["error_type_name"].as_str() {
+                if !types.contains_key(error_type) {
+                    let type_def =
+                        generate_type_from_schema(error_type, func["error_schema_json"].as_str());
+                    types.insert(error_type.to_string(), type_def);
+                }
+            }
+
+            // A query read alongside a body becomes an extra argument of its own type
+            for param in func["params"].as_array().into_iter().flatten() {
+                let schema = &param["inner_type_schema"];
+                if param["role"] != "query" || schema["kind"] != "Struct" {
+                    continue;
+                }
+                if let Some(type_name) = param["type_name"].as_str() {
+                    if !types.contains_key(type_name) {
+                        let type_def = generate_type_from_schema(type_name, Some(&schema.to_string()));
+                        types.insert(type_name.to_string(), type_def);
+                    }
+                }
+            }
+        }
+    }
+
+    // References the server couldn't resolve still need a type to compile against
+    let mut referenced = Vec::new();
+    for schema in type_table.values() {
+        collect_refs(schema, &mut referenced);
+    }
+    for func in functions {
+        for key in ["input_schema_json", "output_schema_json", "error_schema_json"] {
+            if let Some(schema) = func[key].as_str().and_then(|s| serde_json::from_str::<Value>(s).ok()) {
+                collect_refs(&schema, &mut referenced);
+            }
+        }
+    }
+    for name in referenced {
+        if let std::collections::btree_map::Entry::Vacant(entry) = types.entry(name) {
+            let type_def = generate_basic_type(entry.key());
+            entry.insert(type_def);
         }
     }
 
     let mut type_definitions = String::new();
-    for type_def in types.values() {
+    for (type_name, type_def) in &types {
         if !type_def.trim().is_empty() {
+            // Instances of generic types are named like `Page_User`
+            if type_name.contains('_') {
+                type_definitions.push_str("#[allow(non_camel_case_types)]\n");
+            }
             type_definitions.push_str(type_def);
             type_definitions.push_str("\n\n");
         }
@@ -98,6 +244,31 @@ impl GeneratedRpcClient {{
         Ok(Self {{ inner: client }})
     }}
 
+    /// Like `init`, but fails when the server's major API version differs from the
+    /// version this client was generated against
+    pub async fn init_checked(server_addr: ::laz_client::ServerAddr) -> Result<Self, ::laz_client::RpcClientError> {{
+        let client = Self::init(server_addr).await?;
+        if let Some(expected) = Self::API_VERSION {{
+            client.inner.check_api_version(expected)?;
+        }}
+        Ok(client)
+    }}
+
+    /// Attach credentials to every call, required by functions declared with `auth`
+    pub fn with_credentials(self, credentials: ::laz_client::Credentials) -> Self {{
+        Self {{ inner: self.inner.with_credentials(credentials) }}
+    }}
+
+    /// Send a header with every call, required by functions reading it with `TypedHeader`
+    pub fn with_header(self, name: impl Into<String>, value: impl Into<String>) -> Self {{
+        Self {{ inner: self.inner.with_header(name, value) }}
+    }}
+
+    /// Reuse responses of queries declared with `cache` until they expire
+    pub fn with_response_cache(self) -> Self {{
+        Self {{ inner: self.inner.with_response_cache() }}
+    }}
+
     pub fn inner(&self) -> &::laz_client::LocoClient {{
         &self.inner
     }}
@@ -106,13 +277,35 @@ impl GeneratedRpcClient {{
         &self.inner.server_addr
     }}
 
+    /// Digest of the server metadata this client was generated from
+    pub const SCHEMA_DIGEST: Option<&'static str> = {:?};
+
+    /// API version of the server this client was generated from
+    pub const API_VERSION: Option<&'static str> = {:?};
+
+    /// API version the connected server currently declares
+    pub fn api_version(&self) -> Option<&str> {{
+        self.inner.api_version()
+    }}
+
+    /// Whether the server's API has changed since this client was generated
+    pub fn is_stale(&self) -> bool {{
+        match (Self::SCHEMA_DIGEST, self.inner.schema_digest()) {{
+            (Some(generated), Some(current)) => generated != current,
+            _ => false,
+        }}
+    }}
+
 "#,
         server_url,
         functions.len(),
         types.len(),
-        type_definitions
+        type_definitions,
+        schema_digest,
+        api_version
     ));
 
+    let mut namespaces: BTreeMap<String, String> = BTreeMap::new();
     for func in functions {
         if let (Some(func_name), Some(is_mutation), Some(output_type)) = (
             func["function_name"].as_str(),
@@ -120,27 +313,143 @@ impl GeneratedRpcClient {{
             func["output_type_name"].as_str(),
         ) {
             let input_type = func["input_type_name"].as_str();
-            let endpoint_hint = find_endpoint_for_function(func_name, &endpoint_map)
-                .unwrap_or_else(|| format!("/{}", func_name));
-            let func_impl = generate_typed_function_impl(
-                func_name,
-                is_mutation,
-                input_type,
-                output_type,
-                &endpoint_hint,
-            );
+            // The server resolves each route; functions without one are assumed to be served
+            // at their Rust handler's name
+            let handler_name = func["rust_name"].as_str().unwrap_or(func_name);
+            let endpoint_hint = func["path"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| format!("/{}", handler_name));
+            let method_name = rust_ident(&to_snake_case(func_name));
+            // Namespaced functions go on the namespace's sub-client
+            let code = match func["namespace"].as_str().filter(|ns| !ns.is_empty()) {
+                Some(namespace) => namespaces.entry(namespace.to_string()).or_default(),
+                None => &mut code,
+            };
+            if let Some(description) = func["description"].as_str() {
+                code.push_str(&doc_lines(description, "    "));
+                code.push_str("    ///\n");
+            }
+            if let Some(example) = func.get("example").filter(|e| !e.is_null()) {
+                code.push_str(&doc_lines(&format!("Example input: `{}`", example), "    "));
+                code.push_str("    ///\n");
+            }
+            if let Some(since) = func["since"].as_str() {
+                code.push_str(&format!("    /// Available since API version {}.\n    ///\n", since));
+            }
+            if let (Some(requests), Some(secs)) =
+                (func["rate_limit"]["requests"].as_u64(), func["rate_limit"]["period_secs"].as_u64())
+            {
+                code.push_str(&format!(
+                    "    /// Limited to {} requests per {} seconds; calls over the limit fail with\n    /// `RpcClientError::RateLimited`.\n    ///\n",
+                    requests, secs
+                ));
+            }
+            if let Some(max_age) = func["cache_max_age"].as_u64() {
+                code.push_str(&format!(
+                    "    /// Responses stay fresh for {} seconds and are reused by clients built\n    /// `with_response_cache`.\n    ///\n",
+                    max_age
+                ));
+            }
+            if let Some(statuses) = func["error_statuses"].as_array().filter(|s| !s.is_empty()) {
+                let statuses: Vec<String> = statuses.iter().map(|status| status.to_string()).collect();
+                code.push_str(&format!(
+                    "    /// Fails with `RpcClientError::Status` for status {}.\n    ///\n",
+                    statuses.join(", ")
+                ));
+            }
+            match (func["deprecated"].as_str(), func["removed"].as_str()) {
+                (Some(note), _) => code.push_str(&deprecated_attr(note, "    ")),
+                (None, Some(removed)) => code.push_str(&deprecated_attr(
+                    &format!("removed in API version {}", removed),
+                    "    ",
+                )),
+                (None, None) => {}
+            }
+            let output_type = if is_unit_output(func) { "()" } else { output_type };
+            let method = http_method_variant(func["method"].as_str(), is_mutation);
+            let call_args = call_args(func);
+            let func_impl = if func["is_websocket"].as_bool().unwrap_or(false) {
+                generate_websocket_impl(&method_name, input_type, output_type, &endpoint_hint, &call_args)
+            } else if func["stream_body"].as_bool().unwrap_or(false) {
+                generate_stream_body_impl(&method_name, method, input_type, &endpoint_hint, &call_args)
+            } else if func["is_subscription"].as_bool().unwrap_or(false) {
+                generate_subscription_impl(
+                    &method_name,
+                    method,
+                    input_type,
+                    output_type,
+                    &endpoint_hint,
+                    &call_args,
+                )
+            } else {
+                generate_typed_function_impl(
+                    &method_name,
+                    method,
+                    input_type,
+                    output_type,
+                    func["error_type_name"].as_str(),
+                    &endpoint_hint,
+                    &call_args,
+                )
+            };
             code.push_str(&func_impl);
             code.push('\n');
+            if let (Some(pagination), Some(input_type)) = (
+                func.get("pagination").filter(|p| p.is_object()),
+                input_type.filter(|t| !t.is_empty()),
+            ) {
+                code.push_str(&generate_pages_impl(
+                    &method_name,
+                    method,
+                    input_type,
+                    output_type,
+                    pagination,
+                    &endpoint_hint,
+                    &call_args,
+                ));
+                code.push('\n');
+            }
         }
     }
 
+    for namespace in namespaces.keys() {
+        code.push_str(&format!(
+            "    /// Functions in the `{ns}` namespace\n    pub fn {accessor}(&self) -> {client}<'_> {{\n        {client} {{ inner: &self.inner }}\n    }}\n\n",
+            ns = namespace,
+            accessor = rust_ident(&to_snake_case(namespace)),
+            client = namespace_client_name(namespace),
+        ));
+    }
     code.push_str("\n}\n");
+    for (namespace, methods) in &namespaces {
+        let client = namespace_client_name(namespace);
+        code.push_str(&format!(
+            "\n/// Auto-generated client for the `{ns}` namespace\npub struct {client}<'a> {{\n    inner: &'a ::laz_client::LocoClient,\n}}\n\nimpl {client}<'_> {{\n{methods}}}\n",
+            ns = namespace,
+            client = client,
+            methods = methods,
+        ));
+    }
     Ok(code)
 }
 
+/// Name of the sub-client struct for a namespace, e.g. `AuthClient` for `auth`
+fn namespace_client_name(namespace: &str) -> String {
+    let pascal: String = to_snake_case(namespace)
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_ascii_uppercase()).into_iter().chain(chars).collect::<String>()
+        })
+        .collect();
+    format!("{}Client", pascal)
+}
+
 fn generate_type_from_schema(type_name: &str, schema_json: Option<&str>) -> String {
     // Don't generate custom types for primitive types that conflict with Rust built-ins
-    if matches!(type_name, "String" | "i32" | "i64" | "bool" | "f32" | "f64") {
+    if primitive_rust_type(type_name).is_some() {
         return String::new();
     }
 
@@ -149,7 +458,16 @@ fn generate_type_from_schema(type_name: &str, schema_json: Option<&str>) -> Stri
             if let Some(kind) = schema_value.get("kind").and_then(|k| k.as_str()) {
                 match kind {
                     "Struct" => return generate_struct_type_from_schema(type_name, &schema_value),
+                    "Newtype" | "Tuple" => {
+                        return generate_tuple_struct_from_schema(type_name, &schema_value)
+                    }
                     "Enum" => return generate_enum_type_from_schema(type_name, &schema_value),
+                    "Unit" => {
+                        return format!(
+                            "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub struct {};\n",
+                            type_name
+                        )
+                    }
                     "Primitive" => {
                         return generate_primitive_type_from_schema(type_name, &schema_value)
                     }
@@ -163,61 +481,275 @@ fn generate_type_from_schema(type_name: &str, schema_json: Option<&str>) -> Stri
 }
 
 fn generate_struct_type_from_schema(name: &str, schema: &Value) -> String {
-    let mut code = format!(
-        "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub struct {} {{\n",
-        name
-    );
+    let fields = schema
+        .get("value")
+        .and_then(|v| v.get("fields"))
+        .and_then(|f| f.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
 
-    if let Some(value) = schema.get("value") {
-        if let Some(fields) = value.get("fields").and_then(|f| f.as_array()) {
-            for field in fields {
-                if let (Some(field_name), Some(field_type_info)) = (
-                    field.get("field_name").and_then(|n| n.as_str()),
-                    field.get("field_type"),
-                ) {
-                    let field_type = get_rust_type_from_schema(field_type_info);
-                    let optional = field
-                        .get("optional")
-                        .and_then(|o| o.as_bool())
-                        .unwrap_or(false);
-
-                    if optional {
-                        code.push_str(&format!(
-                            "    pub {}: Option<{}>,\n",
-                            field_name, field_type
-                        ));
-                    } else {
-                        code.push_str(&format!("    pub {}: {},\n", field_name, field_type));
-                    }
+    // Structs whose fields can all be omitted get a `Default` matching the server's defaults
+    let defaultable = fields.iter().all(|f| f["optional"] == true || !f["default"].is_null());
+    let has_default_values = fields.iter().any(|f| f["default"].get("value").is_some());
+    let derives = if defaultable && !has_default_values {
+        "Debug, Clone, Default, serde::Serialize, serde::Deserialize"
+    } else {
+        "Debug, Clone, serde::Serialize, serde::Deserialize"
+    };
+
+    let mut code = format!("#[derive({})]\npub struct {} {{\n", derives, name);
+    code.push_str(&generate_field_lines(name, fields, "    ", "pub "));
+    code.push_str("}\n");
+
+    if defaultable && has_default_values {
+        code.push_str(&format!(
+            "\nimpl Default for {} {{\n    fn default() -> Self {{\n        Self {{\n",
+            name
+        ));
+        for field in fields {
+            let Some(field_name) = field["field_name"].as_str() else {
+                continue;
+            };
+            let value = if field["default"].get("value").is_some() {
+                format!("{}()", default_fn_name(name, field_name))
+            } else {
+                "Default::default()".to_string()
+            };
+            code.push_str(&format!("            {}: {},\n", rust_ident(field_name), value));
+        }
+        code.push_str("        }\n    }\n}\n");
+    }
+    code.push_str(&generate_default_fns(name, fields));
+    code
+}
+
+/// Name of the generated function providing a field's default value
+fn default_fn_name(owner: &str, field_name: &str) -> String {
+    format!("__laz_default_{}_{}", owner, field_name)
+}
+
+/// Functions returning the described default values of `fields`, for `#[serde(default = ...)]`
+fn generate_default_fns(owner: &str, fields: &[Value]) -> String {
+    let mut code = String::new();
+    for field in fields {
+        let (Some(field_name), Some(default)) = (
+            field["field_name"].as_str(),
+            field["default"].get("value"),
+        ) else {
+            continue;
+        };
+        let mut field_type = get_rust_type_from_schema(&field["field_type"]);
+        if field["optional"] == true && !field_type.starts_with("Option<") {
+            field_type = format!("Option<{}>", field_type);
+        }
+        code.push_str(&format!(
+            "\n#[allow(non_snake_case)]\nfn {}() -> {} {{\n    serde_json::from_str({:?}).expect(\"invalid default for {}.{}\")\n}}\n",
+            default_fn_name(owner, field_name),
+            field_type,
+            default.to_string(),
+            owner,
+            field_name
+        ));
+    }
+    code
+}
+
+/// Serde helper module for plain or optional fields whose wire encoding differs from
+/// serde's default: base64-encoded bytes and string-encoded 128-bit integers
+fn serde_with_helper(field_type_info: &Value) -> Option<String> {
+    let helper = |schema: &Value| match schema["kind"].as_str() {
+        Some("Bytes") if schema["value"] == "base64" => Some("::laz_client::base64"),
+        Some("Int128") => Some("::laz_client::int128"),
+        Some("NonZero") if matches!(schema["value"].as_str(), Some("i128" | "u128")) => {
+            Some("::laz_client::int128")
+        }
+        _ => None,
+    };
+    if let Some(helper) = helper(field_type_info) {
+        return Some(helper.to_string());
+    }
+    let value = &field_type_info["value"];
+    match value["type_params"].as_array().map(Vec::as_slice) {
+        Some([inner]) if value["container_type"] == "Option" => {
+            helper(inner).map(|helper| format!("{}::option", helper))
+        }
+        _ => None,
+    }
+}
+
+/// Render named fields (of a struct or struct variant) with their docs and serde attributes.
+/// `owner` names the type (or `Enum_Variant`) that default value functions belong to.
+fn generate_field_lines(owner: &str, fields: &[Value], indent: &str, visibility: &str) -> String {
+    let mut code = String::new();
+    for field in fields {
+        if let (Some(field_name), Some(field_type_info)) = (
+            field.get("field_name").and_then(|n| n.as_str()),
+            field.get("field_type"),
+        ) {
+            let field_type = get_rust_type_from_schema(field_type_info);
+            let optional = field
+                .get("optional")
+                .and_then(|o| o.as_bool())
+                .unwrap_or(false);
+
+            if let Some(description) = field.get("description").and_then(|d| d.as_str()) {
+                code.push_str(&doc_lines(description, indent));
+            }
+            if let Some(constraints) = field.get("constraints").and_then(constraints_doc) {
+                code.push_str(&doc_lines(&constraints, indent));
+            }
+            if let Some(example) = field.get("example").filter(|e| !e.is_null()) {
+                code.push_str(&doc_lines(&format!("Example: `{}`", example), indent));
+            }
+            // A `#[deprecated]` field would warn inside serde's own derive output,
+            // so deprecated fields are only flagged in their docs
+            if let Some(note) = field.get("deprecated").and_then(|d| d.as_str()) {
+                let doc = if note.is_empty() {
+                    "**Deprecated**".to_string()
+                } else {
+                    format!("**Deprecated**: {}", note)
+                };
+                code.push_str(&doc_lines(&doc, indent));
+            }
+            if let Some(rename) = field.get("rename").and_then(|r| r.as_str()) {
+                code.push_str(&format!("{}#[serde(rename = {:?})]\n", indent, rename));
+            }
+            let mut serde_args = Vec::new();
+            match field.get("default") {
+                Some(Value::Object(default)) if default.contains_key("value") => serde_args
+                    .push(format!("default = {:?}", default_fn_name(owner, field_name))),
+                Some(Value::String(_)) => serde_args.push("default".to_string()),
+                // Optional fields are wrapped in `Option` below; with a `with` helper they
+                // still need `default` to accept a missing value
+                _ if optional && serde_with_helper(field_type_info).is_some() => {
+                    serde_args.push("default".to_string())
+                }
+                _ => {}
+            }
+            if let Some(helper) = serde_with_helper(field_type_info) {
+                let helper = if optional && !field_type.starts_with("Option<") {
+                    format!("{}::option", helper)
+                } else {
+                    helper
+                };
+                serde_args.push(format!("with = {:?}", helper));
+            }
+            // Fields that may be absent but not null are wrapped in an `Option` that
+            // must be left out rather than sent as `null`
+            let nullable = field.get("nullable").and_then(|n| n.as_bool()).unwrap_or(false)
+                || field_type.starts_with("Option<");
+            if optional && !nullable {
+                if !serde_args.iter().any(|arg| arg.starts_with("default")) {
+                    serde_args.insert(0, "default".to_string());
                 }
+                serde_args.push("skip_serializing_if = \"Option::is_none\"".to_string());
+            }
+            if !serde_args.is_empty() {
+                code.push_str(&format!("{}#[serde({})]\n", indent, serde_args.join(", ")));
+            }
+
+            let field_ident = rust_ident(field_name);
+            if optional && !field_type.starts_with("Option<") {
+                code.push_str(&format!(
+                    "{}{}{}: Option<{}>,\n",
+                    indent, visibility, field_ident, field_type
+                ));
+            } else {
+                code.push_str(&format!(
+                    "{}{}{}: {},\n",
+                    indent, visibility, field_ident, field_type
+                ));
             }
         }
     }
-
-    code.push_str("}\n");
     code
 }
 
 fn generate_enum_type_from_schema(name: &str, schema: &Value) -> String {
-    let mut code = format!(
-        "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub enum {} {{\n",
-        name
-    );
+    let mut code =
+        "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\n".to_string();
+    if let Some(tagging) = schema.get("value").and_then(|v| v.get("tagging")) {
+        code.push_str(&enum_tagging_attr(tagging));
+    }
+    code.push_str(&format!("pub enum {} {{\n", name));
 
+    let mut default_fns = String::new();
     if let Some(value) = schema.get("value") {
         if let Some(variants) = value.get("variants").and_then(|v| v.as_array()) {
             for variant in variants {
                 if let Some(variant_name) = variant.get("variant_name").and_then(|n| n.as_str()) {
-                    code.push_str(&format!("    {},\n", variant_name));
+                    if let Some(rename) = variant.get("rename").and_then(|r| r.as_str()) {
+                        code.push_str(&format!("    #[serde(rename = {:?})]\n", rename));
+                    }
+                    let fields = variant.get("fields").and_then(|f| f.as_array());
+                    let inner = variant.get("inner_schema").filter(|i| !i.is_null());
+                    match (fields, inner) {
+                        (Some(fields), _) if !fields.is_empty() => {
+                            code.push_str(&format!("    {} {{\n", variant_name));
+                            let owner = format!("{}_{}", name, variant_name);
+                            code.push_str(&generate_field_lines(&owner, fields, "        ", ""));
+                            default_fns.push_str(&generate_default_fns(&owner, fields));
+                            code.push_str("    },\n");
+                        }
+                        // Multi-field tuple variants: `Pair(String, i32)`
+                        (_, Some(inner)) if inner["kind"] == "Tuple" => {
+                            let payloads: Vec<String> = inner
+                                .get("value")
+                                .and_then(|v| v.as_array())
+                                .map(|items| items.iter().map(get_rust_type_from_schema).collect())
+                                .unwrap_or_default();
+                            code.push_str(&format!(
+                                "    {}({}),\n",
+                                variant_name,
+                                payloads.join(", ")
+                            ));
+                        }
+                        (_, Some(inner)) => code.push_str(&format!(
+                            "    {}({}),\n",
+                            variant_name,
+                            get_rust_type_from_schema(inner)
+                        )),
+                        _ => code.push_str(&format!("    {},\n", variant_name)),
+                    }
                 }
             }
         }
     }
 
     code.push_str("}\n");
+    code.push_str(&default_fns);
     code
 }
 
+/// Container attribute reproducing the server enum's serde representation
+fn enum_tagging_attr(tagging: &Value) -> String {
+    let tag = tagging.get("tag").and_then(|t| t.as_str());
+    let content = tagging.get("content").and_then(|c| c.as_str());
+    match (tagging.get("style").and_then(|s| s.as_str()), tag, content) {
+        (Some("internal"), Some(tag), _) => format!("#[serde(tag = {:?})]\n", tag),
+        (Some("adjacent"), Some(tag), Some(content)) => {
+            format!("#[serde(tag = {:?}, content = {:?})]\n", tag, content)
+        }
+        (Some("untagged"), _, _) => "#[serde(untagged)]\n".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Newtypes and multi-field tuple structs become tuple structs with public fields
+fn generate_tuple_struct_from_schema(name: &str, schema: &Value) -> String {
+    let field_types: Vec<String> = match schema.get("value") {
+        Some(Value::Array(items)) => items.iter().map(get_rust_type_from_schema).collect(),
+        Some(inner) => vec![get_rust_type_from_schema(inner)],
+        None => vec!["serde_json::Value".to_string()],
+    };
+    let fields: Vec<String> = field_types.iter().map(|ty| format!("pub {}", ty)).collect();
+    format!(
+        "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub struct {}({});\n",
+        name,
+        fields.join(", ")
+    )
+}
+
 fn generate_primitive_type_from_schema(name: &str, schema: &Value) -> String {
     if let Some(value) = schema.get("value").and_then(|v| v.as_str()) {
         match value {
@@ -251,20 +783,52 @@ fn generate_primitive_type_from_schema(name: &str, schema: &Value) -> String {
     }
 }
 
+/// Collect the names of all `Ref` schemas nested anywhere in a schema
+fn collect_refs(schema: &Value, names: &mut Vec<String>) {
+    match schema {
+        Value::Object(map) => {
+            if let (Some("Ref"), Some(name)) = (
+                map.get("kind").and_then(|k| k.as_str()),
+                map.get("value").and_then(|v| v.as_str()),
+            ) {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+            map.values().for_each(|v| collect_refs(v, names));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_refs(v, names)),
+        _ => {}
+    }
+}
+
 fn get_rust_type_from_schema(field_type_info: &Value) -> String {
     match field_type_info.get("kind").and_then(|k| k.as_str()) {
+        Some("Newtype") => field_type_info
+            .get("value")
+            .map(get_rust_type_from_schema)
+            .unwrap_or_else(|| "serde_json::Value".to_string()),
+        Some("Tuple") => {
+            let items: Vec<String> = field_type_info
+                .get("value")
+                .and_then(|v| v.as_array())
+                .map(|items| items.iter().map(get_rust_type_from_schema).collect())
+                .unwrap_or_default();
+            match items.as_slice() {
+                [single] => format!("({},)", single),
+                _ => format!("({})", items.join(", ")),
+            }
+        }
+        Some("Ref") => field_type_info
+            .get("value")
+            .and_then(|v| v.as_str())
+            .map(|name| primitive_rust_type(name).unwrap_or(name))
+            .unwrap_or("serde_json::Value")
+            .to_string(),
         Some("Primitive") => field_type_info
             .get("value")
             .and_then(|v| v.as_str())
-            .map(|s| match s {
-                "String" => "String",
-                "i32" => "i32",
-                "i64" => "i64",
-                "bool" => "bool",
-                "f32" => "f32",
-                "f64" => "f64",
-                _ => "serde_json::Value",
-            })
+            .and_then(primitive_rust_type)
             .unwrap_or("serde_json::Value")
             .to_string(),
         Some("Struct") => field_type_info
@@ -274,28 +838,240 @@ fn get_rust_type_from_schema(field_type_info: &Value) -> String {
             .unwrap_or("serde_json::Value")
             .to_string(),
         Some("Container") => {
-            if let Some(container_type) = field_type_info
-                .get("container_type")
-                .and_then(|c| c.as_str())
-            {
-                if let Some(inner_type) = field_type_info.get("inner_type") {
-                    let inner_type_str = get_rust_type_from_schema(inner_type);
-                    match container_type {
-                        "Vec" => format!("Vec<{}>", inner_type_str),
-                        "Option" => format!("Option<{}>", inner_type_str),
-                        _ => "serde_json::Value".to_string(),
-                    }
-                } else {
-                    "serde_json::Value".to_string()
+            let value = field_type_info.get("value");
+            let container_type = value
+                .and_then(|v| v.get("container_type"))
+                .and_then(|c| c.as_str());
+            let type_params: Vec<String> = value
+                .and_then(|v| v.get("type_params"))
+                .and_then(|p| p.as_array())
+                .map(|params| params.iter().map(get_rust_type_from_schema).collect())
+                .unwrap_or_default();
+
+            match (container_type, type_params.as_slice()) {
+                (Some("Vec"), [inner]) => format!("Vec<{}>", inner),
+                (Some("Option"), [inner]) => format!("Option<{}>", inner),
+                (Some("Result"), [ok, err]) => format!("Result<{}, {}>", ok, err),
+                // Sets need Hash/Ord elements; degrade to Vec when the element type can't provide them
+                (Some("HashSet" | "BTreeSet"), [inner]) if !is_set_element_type(inner) => {
+                    format!("Vec<{}>", inner)
                 }
-            } else {
-                "serde_json::Value".to_string()
+                (Some("HashSet"), [inner]) => format!("::std::collections::HashSet<{}>", inner),
+                (Some("BTreeSet"), [inner]) => format!("::std::collections::BTreeSet<{}>", inner),
+                _ => "serde_json::Value".to_string(),
+            }
+        }
+        Some("Any") => "serde_json::Value".to_string(),
+        Some("Unit") => "()".to_string(),
+        // Base64 encoding is applied by the field's serde helper, see `serde_with_helper`
+        Some("Bytes") => "Vec<u8>".to_string(),
+        // String encoding is applied by the field's serde helper as well
+        Some("Int128") => match field_type_info.get("value").and_then(|v| v.as_str()) {
+            Some(name @ ("i128" | "u128")) => name.to_string(),
+            _ => "serde_json::Value".to_string(),
+        },
+        Some("NonZero") => field_type_info
+            .get("value")
+            .and_then(|v| v.as_str())
+            .and_then(non_zero_rust_type)
+            .unwrap_or_else(|| "serde_json::Value".to_string()),
+        Some("Decimal") if cfg!(feature = "decimal") => {
+            "::laz_client::rust_decimal::Decimal".to_string()
+        }
+        // Keep the exact string representation rather than degrading money values to floats
+        Some("Decimal") => "String".to_string(),
+        Some("DateTime") => {
+            let encoding = field_type_info
+                .get("value")
+                .and_then(|v| v.get("encoding"))
+                .and_then(|e| e.as_str());
+            datetime_rust_type(encoding).to_string()
+        }
+        Some("Array") => {
+            let value = field_type_info.get("value");
+            match (
+                value.and_then(|v| v.get("inner")),
+                value.and_then(|v| v.get("len")).and_then(|l| l.as_u64()),
+            ) {
+                // serde only implements Deserialize for arrays up to 32 elements
+                (Some(inner), Some(len)) if len <= 32 => {
+                    format!("[{}; {}]", get_rust_type_from_schema(inner), len)
+                }
+                (Some(inner), Some(_)) => format!("Vec<{}>", get_rust_type_from_schema(inner)),
+                _ => "serde_json::Value".to_string(),
+            }
+        }
+        Some("Map") => {
+            let value = field_type_info.get("value");
+            match (
+                value.and_then(|v| v.get("key_type")),
+                value.and_then(|v| v.get("value_type")),
+            ) {
+                (Some(key_type), Some(value_type)) => {
+                    // JSON object keys are always strings; serde_json::Value is not Hash
+                    let key = match get_rust_type_from_schema(key_type).as_str() {
+                        "serde_json::Value" => "String".to_string(),
+                        other => other.to_string(),
+                    };
+                    format!(
+                        "::std::collections::HashMap<{}, {}>",
+                        key,
+                        get_rust_type_from_schema(value_type)
+                    )
+                }
+                _ => "serde_json::Value".to_string(),
             }
         }
         _ => "serde_json::Value".to_string(),
     }
 }
 
+/// Summarize validation constraints for a field's doc comment, e.g. `Constraints: min 1, max 100`
+fn constraints_doc(constraints: &Value) -> Option<String> {
+    let mut parts = Vec::new();
+    for key in ["min", "max", "min_length", "max_length"] {
+        if let Some(n) = constraints.get(key).filter(|n| n.is_number()) {
+            parts.push(format!("{} {}", key, n));
+        }
+    }
+    if let Some(pattern) = constraints.get("pattern").and_then(|p| p.as_str()) {
+        parts.push(format!("pattern `{}`", pattern));
+    }
+    if let Some(values) = constraints.get("one_of").and_then(|v| v.as_array()) {
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        parts.push(format!("one of {}", values.join(", ")));
+    }
+    (!parts.is_empty()).then(|| format!("Constraints: {}", parts.join(", ")))
+}
+
+/// Render a `#[deprecated]` attribute, with a note when the server provided one
+fn deprecated_attr(note: &str, indent: &str) -> String {
+    if note.is_empty() {
+        format!("{}#[deprecated]\n", indent)
+    } else {
+        format!("{}#[deprecated(note = {:?})]\n", indent, note)
+    }
+}
+
+/// Render a description as `///` doc comment lines at the given indentation.
+///
+/// Code blocks are marked `text`: server-side examples would otherwise run as doctests
+/// of the client crate, where the server's items don't exist.
+fn doc_lines(description: &str, indent: &str) -> String {
+    let mut in_code_block = false;
+    description
+        .lines()
+        .map(|line| {
+            let line = match line.trim_start().strip_prefix("```") {
+                Some(_) if in_code_block => {
+                    in_code_block = false;
+                    line.to_string()
+                }
+                Some(info) => {
+                    in_code_block = true;
+                    let fence = &line[..line.len() - line.trim_start().len() + 3];
+                    format!("{}{}", fence, if info.trim().is_empty() || info.contains("rust") { "text" } else { info })
+                }
+                None => line.to_string(),
+            };
+            if line.is_empty() {
+                format!("{}///\n", indent)
+            } else {
+                format!("{}/// {}\n", indent, line)
+            }
+        })
+        .collect()
+}
+
+/// Escape names that collide with Rust keywords as raw identifiers
+fn rust_ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe",
+        "use", "where", "while", "yield", "abstract", "become", "do", "final", "macro",
+        "override", "priv", "try", "typeof", "unsized", "virtual",
+    ];
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Rust type for a primitive schema name, if it is one the generated client understands
+fn primitive_rust_type(name: &str) -> Option<&'static str> {
+    match name {
+        "String" => Some("String"),
+        "i32" => Some("i32"),
+        "i64" => Some("i64"),
+        "bool" => Some("bool"),
+        "f32" => Some("f32"),
+        "f64" => Some("f64"),
+        "Uuid" if cfg!(feature = "uuid") => Some("::laz_client::uuid::Uuid"),
+        "Uuid" => Some("String"),
+        "Value" => Some("serde_json::Value"),
+        _ => None,
+    }
+}
+
+/// `std::num` type for a never-zero integer, e.g. `::std::num::NonZeroU32` for `u32`
+fn non_zero_rust_type(primitive: &str) -> Option<String> {
+    let is_integer = matches!(
+        primitive,
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+    );
+    if !is_integer {
+        return None;
+    }
+    let mut chars = primitive.chars();
+    let first = chars.next()?.to_ascii_uppercase();
+    Some(format!("::std::num::NonZero{}{}", first, chars.as_str()))
+}
+
+/// Date/time fields map to chrono types with the `chrono` feature, otherwise to their raw wire type
+fn datetime_rust_type(encoding: Option<&str>) -> &'static str {
+    match encoding {
+        Some("unix") => "i64",
+        Some("rfc3339") if cfg!(feature = "chrono") => {
+            "::laz_client::chrono::DateTime<::laz_client::chrono::Utc>"
+        }
+        Some("naive_date_time") if cfg!(feature = "chrono") => "::laz_client::chrono::NaiveDateTime",
+        Some("date") if cfg!(feature = "chrono") => "::laz_client::chrono::NaiveDate",
+        Some("time") if cfg!(feature = "chrono") => "::laz_client::chrono::NaiveTime",
+        _ => "String",
+    }
+}
+
+fn is_set_element_type(rust_type: &str) -> bool {
+    !matches!(rust_type, "serde_json::Value" | "f32" | "f64")
+}
+
+/// Name of the `HttpMethod` variant for a function's declared verb, defaulting to GET for
+/// queries and POST for mutations
+fn http_method_variant(method: Option<&str>, is_mutation: bool) -> &'static str {
+    match method.map(|m| m.to_ascii_uppercase()).as_deref() {
+        Some("GET") => "Get",
+        Some("PUT") => "Put",
+        Some("PATCH") => "Patch",
+        Some("DELETE") => "Delete",
+        Some("POST") => "Post",
+        _ if is_mutation => "Post",
+        _ => "Get",
+    }
+}
+
+/// Whether a function returns nothing: `()`, or a type whose schema is `Unit`
+fn is_unit_output(func: &Value) -> bool {
+    let output_type = func["output_type_name"].as_str().unwrap_or_default().trim();
+    output_type.is_empty()
+        || output_type == "()"
+        || func["output_schema_json"]
+            .as_str()
+            .and_then(|s| serde_json::from_str::<Value>(s).ok())
+            .is_some_and(|schema| schema["kind"] == "Unit")
+}
+
 fn generate_basic_type(name: &str) -> String {
     format!(
         "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub struct {}(pub serde_json::Value);\n",
@@ -303,117 +1079,1222 @@ fn generate_basic_type(name: &str) -> String {
     )
 }
 
+/// A URL path parameter, taken as its own argument by generated methods
+struct PathArg {
+    /// Placeholder name in the route
+    name: String,
+    rust_type: String,
+}
+
+impl PathArg {
+    fn ident(&self) -> String {
+        rust_ident(&to_snake_case(&self.name))
+    }
+}
+
+/// Where a generated method puts its arguments in the request
+#[derive(Default)]
+struct CallArgs {
+    /// Path params of all `Path<T>` extractors, in declaration order
+    path: Vec<PathArg>,
+    /// The input is read with `Query<T>` and goes in the query string, whatever the method
+    input_in_query: bool,
+    /// Rust type of a `Query<T>` read alongside a body, passed as a separate `query` argument
+    query: Option<String>,
+    /// The handler reads a `Multipart` body, passed to the method as a form
+    multipart: bool,
+    /// `AuthScheme` expression for the credentials the function requires
+    auth: Option<String>,
+    /// Headers read with `TypedHeader<T>`, which the client must be configured to send
+    headers: Vec<String>,
+    /// The mutation takes an optional idempotency key and is retried on transient failures
+    idempotent: bool,
+    /// Statement checking the server's API version has the function
+    availability: Option<String>,
+    /// Default timeout of a call, overridable through a `timeout` argument
+    timeout_ms: Option<u64>,
+    /// Seconds a query's response may be served from the client's response cache
+    cache_max_age: Option<u64>,
+}
+
+fn call_args(func: &Value) -> CallArgs {
+    let params = func["params"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let path = params
+        .iter()
+        .filter_map(|param| param["path_params"].as_array())
+        .flatten()
+        .map(|path_param| PathArg {
+            name: path_param["name"].as_str().unwrap_or_default().to_string(),
+            rust_type: get_rust_type_from_schema(&path_param["schema"]),
+        })
+        .collect();
+    let has_extractor = |name: &str| params.iter().any(|param| param["extractor"] == name);
+    let input_in_query = has_extractor("Query") && !has_extractor("Json") && !has_extractor("Form");
+    let query = params
+        .iter()
+        .filter(|param| param["role"] == "query" && !input_in_query)
+        .find(|param| param["type_name"].as_str() != func["input_type_name"].as_str())
+        .map(|param| get_rust_type_from_schema(&param["inner_type_schema"]));
+    CallArgs {
+        path,
+        input_in_query,
+        query,
+        multipart: func["is_multipart"].as_bool().unwrap_or(false),
+        auth: auth_scheme_expr(&func["auth"]),
+        // `Authorization` comes from the client's credentials
+        headers: params
+            .iter()
+            .filter_map(|param| param["header"].as_str())
+            .filter(|header| !header.eq_ignore_ascii_case("Authorization"))
+            .map(String::from)
+            .collect(),
+        // Forms can't be replayed, so multipart uploads are sent once
+        idempotent: func["idempotent"].as_bool().unwrap_or(false) && !func["is_multipart"].as_bool().unwrap_or(false),
+        availability: match (func["since"].as_str(), func["removed"].as_str()) {
+            (None, None) => None,
+            (since, removed) => Some(format!(
+                "        self.inner.check_available({:?}, {:?}, {:?})?;\n",
+                func["function_name"].as_str().unwrap_or_default(),
+                since,
+                removed
+            )),
+        },
+        timeout_ms: func["timeout_ms"].as_u64(),
+        cache_max_age: func["cache_max_age"].as_u64(),
+    }
+}
+
+fn auth_scheme_expr(auth: &Value) -> Option<String> {
+    match auth["scheme"].as_str()? {
+        "bearer" => Some("::laz_client::AuthScheme::Bearer".to_string()),
+        "basic" => Some("::laz_client::AuthScheme::Basic".to_string()),
+        "api_key" => Some(format!(
+            "::laz_client::AuthScheme::ApiKey {{ header: {:?}.to_string() }}",
+            auth["header"].as_str().unwrap_or("X-Api-Key")
+        )),
+        _ => None,
+    }
+}
+
+/// Statements failing the call early when the server's API version lacks the function or
+/// the function needs credentials the client lacks
+fn preconditions(call_args: &CallArgs) -> String {
+    let mut statements = call_args.availability.clone().unwrap_or_default();
+    if let Some(scheme) = &call_args.auth {
+        statements.push_str(&format!("        self.inner.require_credentials(&{})?;\n", scheme));
+    }
+    for header in &call_args.headers {
+        statements.push_str(&format!("        self.inner.require_header({:?})?;\n", header));
+    }
+    statements
+}
+
+/// Compare dotted version numbers, treating missing components as zero
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let components = |version: &str| -> Vec<u64> {
+        let version = version.trim().trim_start_matches('v');
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        core.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    };
+    let (a, b) = (components(a), components(b));
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Whether a function is tagged with one of `tags`
+fn has_any_tag(func: &Value, tags: &[&str]) -> bool {
+    func["tags"]
+        .as_array()
+        .is_some_and(|own| own.iter().any(|tag| tag.as_str().is_some_and(|tag| tags.contains(&tag))))
+}
+
+/// Whether a function exists in API `version`, per its `since` and `removed` versions
+fn available_in(func: &Value, version: &str) -> bool {
+    func["since"].as_str().is_none_or(|since| compare_versions(version, since).is_ge())
+        && func["removed"].as_str().is_none_or(|removed| compare_versions(version, removed).is_lt())
+}
+
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            // A new word starts after a lowercase letter, or at the last capital of an
+            // acronym followed by lowercase (`getURLPath` -> `get_url_path`)
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let word_start = prev.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(|p| p.is_ascii_uppercase()) && next.is_some_and(|n| n.is_ascii_lowercase()));
+            if word_start {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            snake.push(c);
+        } else {
+            snake.push('_');
+        }
+    }
+    snake
+}
+
+/// Arguments of a generated method: path params, then the input if there is one
+fn method_args(call_args: &CallArgs, input_type_name: Option<&str>) -> String {
+    let mut args: Vec<String> = call_args
+        .path
+        .iter()
+        .map(|arg| format!(", {}: {}", arg.ident(), arg.rust_type))
+        .collect();
+    if let Some(input_type) = input_type_name.filter(|t| !t.is_empty()) {
+        args.push(format!(", params: {}", primitive_rust_type(input_type).unwrap_or(input_type)));
+    }
+    if let Some(query_type) = &call_args.query {
+        args.push(format!(", query: {}", query_type));
+    }
+    if call_args.multipart {
+        args.push(", form: ::laz_client::multipart::Form".to_string());
+    }
+    if call_args.idempotent {
+        args.push(", idempotency_key: Option<String>".to_string());
+    }
+    if call_args.timeout_ms.is_some() {
+        args.push(", timeout: Option<::std::time::Duration>".to_string());
+    }
+    args.concat()
+}
+
+/// A call future, bounded by the function's timeout when it declares one
+fn timed(call_args: &CallArgs, call: String) -> String {
+    match call_args.timeout_ms {
+        Some(ms) => format!(
+            "::laz_client::with_timeout(timeout.unwrap_or(::std::time::Duration::from_millis({})), {})",
+            ms, call
+        ),
+        None => call,
+    }
+}
+
+/// Statements computing the URL of a call when it has path or query params, and the
+/// expression for the endpoint argument
+fn endpoint_expr(endpoint: &str, call_args: &CallArgs, has_input: bool) -> (String, String) {
+    let mut statements = String::new();
+    let mut url = format!("{:?}", endpoint);
+    if !call_args.path.is_empty() {
+        let values: Vec<String> = call_args
+            .path
+            .iter()
+            .map(|arg| format!("({:?}, ::laz_client::path_value(&{}))", arg.name, arg.ident()))
+            .collect();
+        statements.push_str(&format!(
+            "        let endpoint = ::laz_client::fill_path_params({}, &[{}]);\n",
+            url,
+            values.join(", ")
+        ));
+        url = "endpoint".to_string();
+    }
+    let query_arg = match &call_args.query {
+        Some(_) => Some("query"),
+        None if call_args.input_in_query && has_input => Some("params"),
+        None => None,
+    };
+    if let Some(query_arg) = query_arg {
+        statements.push_str(&format!(
+            "        let endpoint = format!(\"{{}}?{{}}\", {}, ::laz_client::encode_query(&{})?);\n",
+            url, query_arg
+        ));
+    }
+    if statements.is_empty() {
+        (statements, url)
+    } else {
+        (statements, "&endpoint".to_string())
+    }
+}
+
+/// Request body of a call
+fn payload_expr(call_args: &CallArgs, input_type_name: Option<&str>) -> &'static str {
+    match input_type_name.filter(|t| !t.is_empty()) {
+        _ if call_args.multipart => "form",
+        Some(_) if !call_args.input_in_query => "Some(serde_json::to_value(&params)?)",
+        _ => "None",
+    }
+}
+
 fn generate_typed_function_impl(
     func_name: &str,
-    is_mutation: bool,
+    method: &str,
     input_type_name: Option<&str>,
     output_type_name: &str,
+    error_type_name: Option<&str>,
     endpoint: &str,
+    call_args: &CallArgs,
 ) -> String {
     let output_type = if output_type_name.trim().is_empty() {
         "()"
     } else {
-        output_type_name
+        primitive_rust_type(output_type_name).unwrap_or(output_type_name)
     };
 
-    let signature = if let Some(input_type) = input_type_name {
-        if !input_type.is_empty() {
-            let input_type_rust = match input_type {
-                "String" => "String",
-                "i32" => "i32",
-                "i64" => "i64",
-                "bool" => "bool",
-                "f32" => "f32",
-                "f64" => "f64",
-                _ => input_type,
-            };
-            format!(
-                "    pub async fn {}(&self, params: {}) -> Result<{}, ::laz_client::RpcClientError>",
-                func_name, input_type_rust, output_type
-            )
-        } else {
-            format!(
-                "    pub async fn {}(&self) -> Result<{}, ::laz_client::RpcClientError>",
-                func_name, output_type
-            )
+    // Functions with a declared error type surface it as `RpcCallError::Application`
+    let error_type = match error_type_name {
+        Some(error_type) => format!("::laz_client::RpcCallError<{}>", error_type),
+        None => "::laz_client::RpcClientError".to_string(),
+    };
+
+    let signature = format!(
+        "    pub async fn {}(&self{}) -> Result<{}, {}>",
+        func_name,
+        method_args(call_args, input_type_name),
+        output_type,
+        error_type
+    );
+
+    let has_input = input_type_name.is_some_and(|t| !t.is_empty());
+    let payload = payload_expr(call_args, input_type_name);
+    let method = format!("::laz_client::HttpMethod::{}", method);
+    let (url, endpoint_arg) = endpoint_expr(endpoint, call_args, has_input);
+    let url = preconditions(call_args) + &url;
+    let cached = call_args.cache_max_age.filter(|_| output_type != "()");
+    let call = if call_args.multipart {
+        "call_endpoint_multipart"
+    } else if call_args.idempotent {
+        "call_endpoint_idempotent"
+    } else if cached.is_some() {
+        "call_endpoint_cached"
+    } else {
+        "call_endpoint"
+    };
+    let payload = match cached {
+        _ if call_args.idempotent => format!("{}, idempotency_key", payload),
+        Some(max_age) if !call_args.multipart => {
+            format!("{}, ::std::time::Duration::from_secs({})", payload, max_age)
         }
+        _ => payload.to_string(),
+    };
+    // Unit handlers may reply with an empty body, so it isn't parsed
+    let body = if output_type == "()" {
+        let call = timed(call_args, format!("self.inner.{}_unit({}, {}, {})", call, endpoint_arg, method, payload));
+        format!("{}        {}.await?;\n        Ok(())", url, call)
     } else {
+        let call = timed(call_args, format!("self.inner.{}({}, {}, {})", call, endpoint_arg, method, payload));
         format!(
-            "    pub async fn {}(&self) -> Result<{}, ::laz_client::RpcClientError>",
-            func_name, output_type
+            "{}        let value = {}.await?;\n        serde_json::from_value(value).map_err(|e| ::laz_client::RpcClientError::JsonError(e))",
+            url, call
         )
     };
-
-    let payload = if let Some(input_type) = input_type_name {
-        if !input_type.is_empty() {
-            "Some(serde_json::to_value(&params)?)"
-        } else {
-            "None"
-        }
+    let body = if error_type_name.is_some() {
+        let indented: Vec<String> = body.lines().map(|line| format!("    {}", line)).collect();
+        format!(
+            "        let result: Result<{}, ::laz_client::RpcClientError> = async {{\n{}\n        }}\n        .await;\n        result.map_err(::laz_client::RpcCallError::from_client_error)",
+            output_type,
+            indented.join("\n")
+        )
     } else {
-        "None"
+        body
     };
 
-    let call_expr = format!(
-        "self.inner.call_endpoint(\"{}\", {}, {}).await?",
-        endpoint, is_mutation, payload
-    );
+    format!(
+        "    /// Auto-generated wrapper for `{}` hitting `{}`\n{}\n    {{\n{}\n    }}\n",
+        func_name, endpoint, signature, body
+    )
+}
 
-    let body = if output_type == "()" {
-        format!("        {};\n        Ok(())", call_expr)
+/// Method for an `rpc_subscription` function: connects, then yields each event decoded as
+/// the output type
+fn generate_subscription_impl(
+    func_name: &str,
+    method: &str,
+    input_type_name: Option<&str>,
+    output_type_name: &str,
+    endpoint: &str,
+    call_args: &CallArgs,
+) -> String {
+    let output_type = primitive_rust_type(output_type_name).unwrap_or(output_type_name);
+    let has_input = input_type_name.is_some_and(|t| !t.is_empty());
+    let payload = payload_expr(call_args, input_type_name);
+    let (url, endpoint_arg) = endpoint_expr(endpoint, call_args, has_input);
+    let url = preconditions(call_args) + &url;
+    format!(
+        r#"    /// Auto-generated subscription to `{name}` streaming from `{endpoint}`
+    pub async fn {name}(&self{params}) -> Result<impl ::laz_client::futures_util::Stream<Item = Result<{output}, ::laz_client::RpcClientError>>, ::laz_client::RpcClientError>
+    {{
+        use ::laz_client::futures_util::StreamExt;
+{url}        let events = {subscribe}.await?;
+        Ok(events.map(|event| event.and_then(|value| serde_json::from_value(value).map_err(::laz_client::RpcClientError::JsonError))))
+    }}
+"#,
+        name = func_name,
+        endpoint = endpoint,
+        params = method_args(call_args, input_type_name),
+        url = url,
+        subscribe = timed(
+            call_args,
+            format!("self.inner.subscribe_endpoint({}, ::laz_client::HttpMethod::{}, {})", endpoint_arg, method, payload)
+        ),
+        output = output_type,
+    )
+}
+
+/// Method for an `rpc_ws` function: opens a session sending the input type and receiving
+/// the output type. Sockets need a native runtime, so the method is left out of wasm builds.
+fn generate_websocket_impl(
+    func_name: &str,
+    input_type_name: Option<&str>,
+    output_type_name: &str,
+    endpoint: &str,
+    call_args: &CallArgs,
+) -> String {
+    let message_type = |name: &str| primitive_rust_type(name).unwrap_or(name).to_string();
+    let input_type = input_type_name.filter(|t| !t.is_empty()).map_or("()".to_string(), message_type);
+    let (url, endpoint_arg) = endpoint_expr(endpoint, call_args, false);
+    let url = preconditions(call_args) + &url;
+    format!(
+        r#"    /// Auto-generated session with `{name}` at `{endpoint}`, sending `{input}` messages and
+    /// receiving `{output}` ones
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn {name}(&self{params}) -> Result<::laz_client::WsSession<{input}, {output}>, ::laz_client::RpcClientError>
+    {{
+{url}        {connect}.await
+    }}
+"#,
+        name = func_name,
+        endpoint = endpoint,
+        params = method_args(call_args, None),
+        url = url,
+        connect = timed(call_args, format!("self.inner.connect_ws({})", endpoint_arg)),
+        input = input_type,
+        output = message_type(output_type_name),
+    )
+}
+
+/// Method for a `stream_body` function: yields the response body in chunks as it arrives
+fn generate_stream_body_impl(
+    func_name: &str,
+    method: &str,
+    input_type_name: Option<&str>,
+    endpoint: &str,
+    call_args: &CallArgs,
+) -> String {
+    let has_input = input_type_name.is_some_and(|t| !t.is_empty());
+    let payload = payload_expr(call_args, input_type_name);
+    let (url, endpoint_arg) = endpoint_expr(endpoint, call_args, has_input);
+    let url = preconditions(call_args) + &url;
+    format!(
+        r#"    /// Auto-generated wrapper for `{name}` streaming its body from `{endpoint}`; see
+    /// `laz_client::lines` for line-oriented bodies
+    pub async fn {name}(&self{params}) -> Result<impl ::laz_client::futures_util::Stream<Item = Result<::laz_client::Bytes, ::laz_client::RpcClientError>>, ::laz_client::RpcClientError>
+    {{
+{url}        {stream}.await
+    }}
+"#,
+        name = func_name,
+        endpoint = endpoint,
+        params = method_args(call_args, input_type_name),
+        url = url,
+        stream = timed(
+            call_args,
+            format!("self.inner.stream_endpoint({}, ::laz_client::HttpMethod::{}, {})", endpoint_arg, method, payload)
+        ),
+    )
+}
+
+/// `<name>_pages` method of a paginated query: yields each page, following the pagination
+/// convention recorded in the metadata
+fn generate_pages_impl(
+    func_name: &str,
+    method: &str,
+    input_type_name: &str,
+    output_type_name: &str,
+    pagination: &Value,
+    endpoint: &str,
+    call_args: &CallArgs,
+) -> String {
+    let output_type = primitive_rust_type(output_type_name).unwrap_or(output_type_name);
+    let (url, endpoint_arg) = endpoint_expr(endpoint, call_args, false);
+    let url = preconditions(call_args) + &url;
+    let fetch = if call_args.input_in_query {
+        format!(
+            "let endpoint = format!(\"{{}}?{{}}\", endpoint, ::laz_client::encode_query(&params)?);\n                {}.await",
+            timed(call_args, format!("inner.call_endpoint(&endpoint, ::laz_client::HttpMethod::{}, None)", method))
+        )
     } else {
         format!(
-            "        let value = {};\n        serde_json::from_value(value).map_err(|e| ::laz_client::RpcClientError::JsonError(e))",
-            call_expr
+            "{}.await",
+            timed(call_args, format!("inner.call_endpoint(&endpoint, ::laz_client::HttpMethod::{}, Some(params))", method))
         )
     };
-
     format!(
-        "    /// Auto-generated wrapper for `{}` hitting `{}`\n{}\n    {{\n{}\n    }}\n",
-        func_name, endpoint, signature, body
+        r#"    /// Pages of `{name}`, fetched one after the other starting from `params`
+    pub fn {name}_pages(&self{params}) -> Result<impl ::laz_client::futures_util::Stream<Item = Result<{output}, ::laz_client::RpcClientError>> + '_, ::laz_client::RpcClientError>
+    {{
+        use ::laz_client::futures_util::StreamExt;
+{url}        let endpoint = ({endpoint_arg}).to_string();
+        let pagination: ::laz_client::Pagination = serde_json::from_str({pagination:?})?;
+        let params = serde_json::to_value(&params)?;
+        let inner = &self.inner;
+        let pages = ::laz_client::paginate(pagination, params, move |params| {{
+            let endpoint = endpoint.clone();
+            async move {{
+                {fetch}
+            }}
+        }});
+        Ok(pages.map(|page| page.and_then(|value| serde_json::from_value(value).map_err(::laz_client::RpcClientError::JsonError))))
+    }}
+"#,
+        name = func_name,
+        params = method_args(call_args, Some(input_type_name)),
+        output = output_type,
+        url = url,
+        endpoint_arg = endpoint_arg,
+        pagination = pagination.to_string(),
+        fetch = fetch,
     )
 }
 
-fn build_endpoint_map(values: &[Value]) -> HashMap<String, Vec<String>> {
-    let mut map = HashMap::new();
-    for entry in values {
-        if let Some(uri) = entry.get("uri").and_then(|v| v.as_str()) {
-            let methods = entry
-                .get("methods")
-                .and_then(|m| m.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default();
-            map.insert(uri.to_string(), methods);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rust_type_for_collection_schemas() {
+        let set = json!({
+            "kind": "Container",
+            "value": {
+                "container_type": "HashSet",
+                "type_params": [{ "kind": "Primitive", "value": "String" }]
+            }
+        });
+        assert_eq!(
+            get_rust_type_from_schema(&set),
+            "::std::collections::HashSet<String>"
+        );
+
+        let float_set = json!({
+            "kind": "Container",
+            "value": {
+                "container_type": "BTreeSet",
+                "type_params": [{ "kind": "Primitive", "value": "f64" }]
+            }
+        });
+        assert_eq!(get_rust_type_from_schema(&float_set), "Vec<f64>");
+
+        let map = json!({
+            "kind": "Map",
+            "value": {
+                "key_type": { "kind": "Primitive", "value": "String" },
+                "value_type": {
+                    "kind": "Array",
+                    "value": { "inner": { "kind": "Primitive", "value": "f32" }, "len": 3 }
+                }
+            }
+        });
+        assert_eq!(
+            get_rust_type_from_schema(&map),
+            "::std::collections::HashMap<String, [f32; 3]>"
+        );
     }
-    map
-}
 
-fn find_endpoint_for_function(
-    function_name: &str,
-    endpoints: &HashMap<String, Vec<String>>,
-) -> Option<String> {
-    for uri in endpoints.keys() {
-        if uri.contains(function_name) || uri.contains(&function_name.replace('_', "-")) {
-            return Some(uri.clone());
-        }
+    #[test]
+    fn test_rust_type_for_datetime_schemas() {
+        let unix = json!({
+            "kind": "DateTime",
+            "value": { "type_name": "DateTime < Utc >", "encoding": "unix" }
+        });
+        assert_eq!(get_rust_type_from_schema(&unix), "i64");
+
+        let rfc3339 = json!({
+            "kind": "DateTime",
+            "value": { "type_name": "DateTime < Utc >", "encoding": "rfc3339" }
+        });
+        let expected = if cfg!(feature = "chrono") {
+            "::laz_client::chrono::DateTime<::laz_client::chrono::Utc>"
+        } else {
+            "String"
+        };
+        assert_eq!(get_rust_type_from_schema(&rfc3339), expected);
     }
 
-    let patterns = [
-        format!("/api/{}", function_name.replace('_', "-")),
-        format!("/api/auth/{}", function_name.replace('_', "-")),
-        format!("/auth/{}", function_name.replace('_', "-")),
-    ];
+    #[test]
+    fn test_uuid_primitive_is_not_wrapped() {
+        let uuid = json!({ "kind": "Primitive", "value": "Uuid" });
+        let expected = if cfg!(feature = "uuid") {
+            "::laz_client::uuid::Uuid"
+        } else {
+            "String"
+        };
+        assert_eq!(get_rust_type_from_schema(&uuid), expected);
+        assert!(generate_type_from_schema("Uuid", None).is_empty());
+    }
 
-    for pattern in patterns {
-        if endpoints.contains_key(&pattern) {
-            return Some(pattern);
-        }
+    #[test]
+    fn test_struct_fields_keep_wire_names() {
+        let schema = json!({
+            "kind": "Struct",
+            "value": {
+                "type_name": "Profile",
+                "fields": [{
+                    "field_name": "display_name",
+                    "field_type": { "kind": "Primitive", "value": "String" },
+                    "optional": false,
+                    "rename": "displayName"
+                }]
+            }
+        });
+        let code = generate_struct_type_from_schema("Profile", &schema);
+        assert!(code.contains("#[serde(rename = \"displayName\")]\n    pub display_name: String,"));
+    }
+
+    #[test]
+    fn test_descriptions_become_doc_comments() {
+        assert_eq!(
+            doc_lines("Registers a new user\n\nReturns the token", "    "),
+            "    /// Registers a new user\n    ///\n    /// Returns the token\n"
+        );
+        assert_eq!(
+            doc_lines("```\nregister(params)\n```\n```json\n{}\n```", ""),
+            "/// ```text\n/// register(params)\n/// ```\n/// ```json\n/// {}\n/// ```\n"
+        );
+    }
+
+    #[test]
+    fn test_deprecated_attr_carries_note() {
+        assert_eq!(
+            deprecated_attr("use login_v2", "    "),
+            "    #[deprecated(note = \"use login_v2\")]\n"
+        );
+        assert_eq!(deprecated_attr("", "    "), "    #[deprecated]\n");
+    }
+
+    #[test]
+    fn test_declared_error_type_is_returned_typed() {
+        let error_schema = json!({
+            "kind": "Enum",
+            "value": {
+                "type_name": "SignupError",
+                "variants": [{ "variant_name": "UsernameTaken", "inner_schema": null }]
+            }
+        });
+        let metadata = json!({
+            "functions": [{
+                "function_name": "signup",
+                "is_mutation": true,
+                "input_type_name": null,
+                "output_type_name": "String",
+                "error_type_name": "SignupError",
+                "error_schema_json": error_schema.to_string()
+            }],
+            "endpoints_discovery": []
+        });
+        let code =
+            generate_client_code_from_metadata_json("http://localhost:5150", &metadata.to_string())
+                .unwrap();
+        assert!(code.contains("pub enum SignupError {"));
+        assert!(code.contains(
+            "pub async fn signup(&self) -> Result<String, ::laz_client::RpcCallError<SignupError>>"
+        ));
+        assert!(code.contains("result.map_err(::laz_client::RpcCallError::from_client_error)"));
+    }
+
+    #[test]
+    fn test_wide_and_non_zero_integers() {
+        let schema = json!({
+            "kind": "Struct",
+            "value": {
+                "type_name": "Account",
+                "fields": [
+                    { "field_name": "balance", "field_type": { "kind": "Int128", "value": "i128" } },
+                    {
+                        "field_name": "limit",
+                        "field_type": {
+                            "kind": "Container",
+                            "value": {
+                                "container_type": "Option",
+                                "type_params": [{ "kind": "Int128", "value": "u128" }]
+                            }
+                        },
+                        "optional": true,
+                        "nullable": true
+                    },
+                    { "field_name": "owner", "field_type": { "kind": "NonZero", "value": "u64" } }
+                ]
+            }
+        });
+        let code = generate_struct_type_from_schema("Account", &schema);
+        assert!(code.contains("    #[serde(with = \"::laz_client::int128\")]\n    pub balance: i128,\n"));
+        assert!(code.contains(
+            "    #[serde(default, with = \"::laz_client::int128::option\")]\n    pub limit: Option<u128>,\n"
+        ));
+        assert!(code.contains("    pub owner: ::std::num::NonZeroU64,\n"));
+    }
+
+    #[test]
+    fn test_base64_bytes_fields_use_serde_helper() {
+        let schema = json!({
+            "kind": "Struct",
+            "value": {
+                "type_name": "Upload",
+                "fields": [
+                    {
+                        "field_name": "content",
+                        "field_type": { "kind": "Bytes", "value": "base64" },
+                        "optional": false
+                    },
+                    {
+                        "field_name": "thumbnail",
+                        "field_type": { "kind": "Bytes", "value": "base64" },
+                        "optional": true
+                    },
+                    {
+                        "field_name": "checksum",
+                        "field_type": { "kind": "Bytes", "value": "array" },
+                        "optional": false
+                    }
+                ]
+            }
+        });
+        let code = generate_struct_type_from_schema("Upload", &schema);
+        assert!(code.contains(
+            "    #[serde(with = \"::laz_client::base64\")]\n    pub content: Vec<u8>,\n"
+        ));
+        assert!(code.contains(
+            "    #[serde(default, with = \"::laz_client::base64::option\", skip_serializing_if = \"Option::is_none\")]\n    pub thumbnail: Option<Vec<u8>>,\n"
+        ));
+        assert!(code.contains("    pub checksum: Vec<u8>,\n"));
+
+        let mut with_example = schema.clone();
+        with_example["value"]["fields"][2]["example"] = json!([1, 2]);
+        let code = generate_struct_type_from_schema("Upload", &with_example);
+        assert!(code.contains("    /// Example: `[1,2]`\n    pub checksum: Vec<u8>,\n"));
+    }
+
+    #[test]
+    fn test_any_values_pass_through_unwrapped() {
+        let metadata = json!({
+            "functions": [{
+                "function_name": "raw_config",
+                "is_mutation": false,
+                "input_type_name": null,
+                "output_type_name": "Value"
+            }],
+            "endpoints_discovery": []
+        });
+        let code =
+            generate_client_code_from_metadata_json("http://localhost:5150", &metadata.to_string())
+                .unwrap();
+        assert!(!code.contains("pub struct Value"));
+        assert!(code.contains(
+            "pub async fn raw_config(&self) -> Result<serde_json::Value, ::laz_client::RpcClientError>"
+        ));
+        assert_eq!(get_rust_type_from_schema(&json!({ "kind": "Any" })), "serde_json::Value");
+    }
+
+    #[test]
+    fn test_nullable_fields_are_sent_as_null() {
+        let string = json!({ "kind": "Primitive", "value": "String" });
+        let schema = json!({
+            "kind": "Struct",
+            "value": {
+                "type_name": "Profile",
+                "fields": [
+                    {
+                        "field_name": "nickname",
+                        "field_type": { "kind": "Container", "value": { "container_type": "Option", "type_params": [string] } },
+                        "optional": true,
+                        "nullable": true
+                    },
+                    { "field_name": "bio", "field_type": string, "optional": true }
+                ]
+            }
+        });
+        let code = generate_struct_type_from_schema("Profile", &schema);
+        assert!(code.contains("{\n    pub nickname: Option<String>,\n"));
+        assert!(code.contains(
+            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub bio: Option<String>,\n"
+        ));
+    }
+
+    #[test]
+    fn test_unit_outputs_skip_body_decoding() {
+        let code = generate_typed_function_impl("clear_cache", "Delete", None, "()", None, "/api/clear_cache", &CallArgs::default());
+        assert!(code.contains("-> Result<(), ::laz_client::RpcClientError>"));
+        assert!(code.contains("self.inner.call_endpoint_unit(\"/api/clear_cache\", ::laz_client::HttpMethod::Delete"));
+        assert!(!code.contains("from_value"));
+
+        assert_eq!(http_method_variant(Some("delete"), true), "Delete");
+        assert_eq!(http_method_variant(None, false), "Get");
+
+        let func = json!({ "output_type_name": "Ack", "output_schema_json": "{\"kind\":\"Unit\"}" });
+        assert!(is_unit_output(&func));
+        assert_eq!(get_rust_type_from_schema(&json!({ "kind": "Unit" })), "()");
+        assert!(generate_type_from_schema("Ack", Some("{\"kind\":\"Unit\"}")).contains("pub struct Ack;"));
+    }
+
+    #[test]
+    fn test_path_params_become_arguments() {
+        let func = json!({
+            "params": [{
+                "name": "org, userId",
+                "extractor": "Path",
+                "path_params": [
+                    { "name": "org", "position": 0, "schema": { "kind": "Primitive", "value": "String" } },
+                    { "name": "userId", "position": 1, "schema": { "kind": "Primitive", "value": "i64" } }
+                ]
+            }]
+        });
+        let code = generate_typed_function_impl("get_member", "Get", None, "Member", None, "/orgs/{org}/users/{userId}", &call_args(&func));
+        assert!(code.contains("pub async fn get_member(&self, org: String, user_id: i64) -> Result<Member,"));
+        assert!(code.contains(
+            "let endpoint = ::laz_client::fill_path_params(\"/orgs/{org}/users/{userId}\", &[(\"org\", ::laz_client::path_value(&org)), (\"userId\", ::laz_client::path_value(&user_id))]);"
+        ));
+        assert!(code.contains("self.inner.call_endpoint(&endpoint, ::laz_client::HttpMethod::Get, None)"));
+    }
+
+    #[test]
+    fn test_query_inputs_go_in_the_query_string() {
+        let func = json!({ "params": [{ "name": "filters", "extractor": "Query" }] });
+        let code = generate_typed_function_impl("search", "Post", Some("SearchFilters"), "Results", None, "/api/search", &call_args(&func));
+        assert!(code.contains("pub async fn search(&self, params: SearchFilters)"));
+        assert!(code.contains(
+            "let endpoint = format!(\"{}?{}\", \"/api/search\", ::laz_client::encode_query(&params)?);"
+        ));
+        assert!(code.contains("call_endpoint(&endpoint, ::laz_client::HttpMethod::Post, None)"));
+    }
+
+    #[test]
+    fn test_queries_beside_a_body_become_their_own_argument() {
+        let func = json!({
+            "input_type_name": "TagBody",
+            "params": [
+                {
+                    "name": "id",
+                    "extractor": "Path",
+                    "role": "path",
+                    "path_params": [{ "name": "id", "position": 0, "schema": { "kind": "Primitive", "value": "i64" } }]
+                },
+                {
+                    "name": "filters",
+                    "extractor": "Query",
+                    "role": "query",
+                    "type_name": "TagFilters",
+                    "inner_type_schema": { "kind": "Struct", "value": { "type_name": "TagFilters", "fields": [] } }
+                },
+                { "name": "body", "extractor": "Json", "role": "body", "type_name": "TagBody" }
+            ]
+        });
+        let code = generate_typed_function_impl("tag_tree", "Post", Some("TagBody"), "()", None, "/trees/{id}/tags", &call_args(&func));
+        assert!(code.contains("pub async fn tag_tree(&self, id: i64, params: TagBody, query: TagFilters)"));
+        assert!(code.contains("let endpoint = format!(\"{}?{}\", endpoint, ::laz_client::encode_query(&query)?);"));
+        assert!(code.contains("call_endpoint_unit(&endpoint, ::laz_client::HttpMethod::Post, Some(serde_json::to_value(&params)?))"));
+    }
+
+    #[test]
+    fn test_multipart_functions_take_a_form() {
+        let func = json!({ "is_multipart": true, "params": [{ "name": "multipart", "extractor": "Multipart" }] });
+        let code = generate_typed_function_impl("upload_avatar", "Post", None, "()", None, "/api/avatar", &call_args(&func));
+        assert!(code.contains("pub async fn upload_avatar(&self, form: ::laz_client::multipart::Form) -> Result<(),"));
+        assert!(code.contains(
+            "self.inner.call_endpoint_multipart_unit(\"/api/avatar\", ::laz_client::HttpMethod::Post, form)"
+        ));
+    }
+
+    #[test]
+    fn test_auth_functions_require_credentials() {
+        let func = json!({ "auth": { "scheme": "api_key", "header": "X-Key" } });
+        let code = generate_typed_function_impl("profile", "Get", None, "Profile", None, "/api/profile", &call_args(&func));
+        assert!(code.contains(
+            "self.inner.require_credentials(&::laz_client::AuthScheme::ApiKey { header: \"X-Key\".to_string() })?;"
+        ));
+        let code = generate_typed_function_impl("health", "Get", None, "Health", None, "/api/health", &CallArgs::default());
+        assert!(!code.contains("require_credentials"));
+    }
+
+    #[test]
+    fn test_public_names_become_snake_case_methods() {
+        assert_eq!(to_snake_case("getUserProfile"), "get_user_profile");
+        assert_eq!(to_snake_case("getURLPath"), "get_url_path");
+        assert_eq!(to_snake_case("list_users"), "list_users");
+
+        let metadata = json!({
+            "functions": [{
+                "function_name": "getUserProfile",
+                "rust_name": "profile_v2",
+                "is_mutation": false,
+                "output_type_name": "String",
+                "path": "/api/profile_v2",
+            }],
+            "endpoints_discovery": [{ "uri": "/api/profile_v2", "methods": ["GET"] }],
+        });
+        let code = generate_client_code_from_metadata_json("http://localhost", &metadata.to_string()).unwrap();
+        assert!(code.contains("pub async fn get_user_profile(&self)"));
+        assert!(code.contains("\"/api/profile_v2\""));
+    }
+
+    #[test]
+    fn test_namespaces_become_sub_clients() {
+        let metadata = json!({
+            "functions": [
+                { "function_name": "login", "namespace": "user_auth", "is_mutation": true, "output_type_name": "String", "path": "/auth/login" },
+                { "function_name": "health", "is_mutation": false, "output_type_name": "String", "path": "/health" },
+            ],
+            "endpoints_discovery": [],
+        });
+        let code = generate_client_code_from_metadata_json("http://localhost", &metadata.to_string()).unwrap();
+        assert!(code.contains("pub fn user_auth(&self) -> UserAuthClient<'_>"));
+        assert!(code.contains("pub struct UserAuthClient<'a> {\n    inner: &'a ::laz_client::LocoClient,\n}"));
+        let sub_client = &code[code.find("impl UserAuthClient<'_>").unwrap()..];
+        assert!(sub_client.contains("pub async fn login(&self)"));
+        assert!(!sub_client.contains("pub async fn health(&self)"));
+    }
+
+    #[test]
+    fn test_stream_bodies_return_byte_streams() {
+        let code = generate_stream_body_impl("export_users", "Get", None, "/api/export", &CallArgs::default());
+        assert!(code.contains("pub async fn export_users(&self) -> Result<impl ::laz_client::futures_util::Stream<Item = Result<::laz_client::Bytes, ::laz_client::RpcClientError>>"));
+        assert!(code.contains("self.inner.stream_endpoint(\"/api/export\", ::laz_client::HttpMethod::Get, None).await"));
+    }
+
+    #[test]
+    fn test_idempotent_mutations_take_a_key() {
+        let func = json!({ "idempotent": true });
+        let code = generate_typed_function_impl("charge", "Post", Some("ChargeParams"), "Receipt", None, "/api/charge", &call_args(&func));
+        assert!(code.contains("pub async fn charge(&self, params: ChargeParams, idempotency_key: Option<String>) -> Result<Receipt,"));
+        assert!(code.contains(
+            "self.inner.call_endpoint_idempotent(\"/api/charge\", ::laz_client::HttpMethod::Post, Some(serde_json::to_value(&params)?), idempotency_key)"
+        ));
+    }
+
+    #[test]
+    fn test_paginated_queries_get_page_streams() {
+        let metadata = json!({
+            "functions": [{
+                "function_name": "list_trees",
+                "is_mutation": false,
+                "input_type_name": "TreeFilter",
+                "output_type_name": "TreePage",
+                "path": "/api/trees",
+                "pagination": { "style": "cursor", "cursor_field": "after", "next_cursor_field": "next" },
+            }],
+            "endpoints_discovery": [],
+        });
+        let code = generate_client_code_from_metadata_json("http://localhost", &metadata.to_string()).unwrap();
+        assert!(code.contains("pub async fn list_trees(&self, params: TreeFilter)"));
+        assert!(code.contains("pub fn list_trees_pages(&self, params: TreeFilter) -> Result<impl ::laz_client::futures_util::Stream<Item = Result<TreePage, ::laz_client::RpcClientError>> + '_,"));
+        assert!(code.contains("inner.call_endpoint(&endpoint, ::laz_client::HttpMethod::Get, Some(params)).await"));
+    }
+
+    #[test]
+    fn test_versioned_functions_check_availability() {
+        let func = json!({ "function_name": "archive", "since": "1.2", "removed": "2.0" });
+        let code = generate_typed_function_impl("archive", "Post", None, "()", None, "/api/archive", &call_args(&func));
+        assert!(code.contains("self.inner.check_available(\"archive\", Some(\"1.2\"), Some(\"2.0\"))?;"));
+
+        assert!(available_in(&func, "1.10"));
+        assert!(!available_in(&func, "1.1.9"));
+        assert!(!available_in(&func, "v2.0.0"));
+    }
+
+    #[test]
+    fn test_timeouts_default_per_function_and_can_be_overridden() {
+        let func = json!({ "timeout_ms": 2000 });
+        let code = generate_typed_function_impl("slow_report", "Get", None, "String", None, "/api/report", &call_args(&func));
+        assert!(code.contains("pub async fn slow_report(&self, timeout: Option<::std::time::Duration>)"));
+        assert!(code.contains(
+            "let value = ::laz_client::with_timeout(timeout.unwrap_or(::std::time::Duration::from_millis(2000)), self.inner.call_endpoint(\"/api/report\", ::laz_client::HttpMethod::Get, None)).await?;"
+        ));
+    }
+
+    #[test]
+    fn test_typed_headers_are_required_from_the_client() {
+        let func = json!({ "params": [
+            { "name": "agent", "extractor": "TypedHeader", "role": "header", "header": "User-Agent" },
+            { "name": "auth", "extractor": "TypedHeader", "role": "header", "header": "Authorization" },
+        ] });
+        let code = generate_typed_function_impl("whoami", "Get", None, "String", None, "/api/whoami", &call_args(&func));
+        assert!(code.contains("pub async fn whoami(&self) ->"));
+        assert!(code.contains("self.inner.require_header(\"User-Agent\")?;"));
+        assert!(!code.contains("require_header(\"Authorization\")"));
+    }
+
+    #[test]
+    fn test_cached_queries_use_the_response_cache() {
+        let func = json!({ "cache_max_age": 60 });
+        let code = generate_typed_function_impl("list_trees", "Get", None, "Vec<String>", None, "/api/trees", &call_args(&func));
+        assert!(code.contains(
+            "self.inner.call_endpoint_cached(\"/api/trees\", ::laz_client::HttpMethod::Get, None, ::std::time::Duration::from_secs(60)).await?;"
+        ));
+        let code = generate_typed_function_impl("ping", "Get", None, "()", None, "/api/ping", &call_args(&func));
+        assert!(code.contains("self.inner.call_endpoint_unit(\"/api/ping\""));
+    }
+
+    #[test]
+    fn test_functions_are_selected_by_tag() {
+        let func = json!({ "function_name": "ban_user", "tags": ["admin", "users"] });
+        assert!(has_any_tag(&func, &["billing", "admin"]));
+        assert!(!has_any_tag(&func, &["billing"]));
+        assert!(!has_any_tag(&json!({ "function_name": "ping" }), &["admin"]));
+    }
+
+    #[test]
+    fn test_rate_limits_are_documented() {
+        let metadata = json!({
+            "functions": [{
+                "function_name": "send_invite",
+                "is_mutation": true,
+                "output_type_name": "()",
+                "path": "/api/invites",
+                "rate_limit": { "requests": 10, "period_secs": 60 },
+            }],
+            "endpoints_discovery": [],
+        });
+        let code = generate_client_code_from_metadata_json("http://localhost", &metadata.to_string()).unwrap();
+        assert!(code.contains("/// Limited to 10 requests per 60 seconds;"));
+    }
+
+    #[test]
+    fn test_subscriptions_return_streams() {
+        let code = generate_subscription_impl("ticks", "Get", Some("TickFilter"), "Tick", "/api/ticks", &CallArgs::default());
+        assert!(code.contains("pub async fn ticks(&self, params: TickFilter) -> Result<impl ::laz_client::futures_util::Stream<Item = Result<Tick, ::laz_client::RpcClientError>>"));
+        assert!(code.contains(
+            "self.inner.subscribe_endpoint(\"/api/ticks\", ::laz_client::HttpMethod::Get, Some(serde_json::to_value(&params)?))"
+        ));
+    }
+
+    #[test]
+    fn test_field_defaults_generate_default_impl() {
+        let schema = json!({
+            "kind": "Struct",
+            "value": {
+                "type_name": "ListParams",
+                "fields": [
+                    {
+                        "field_name": "limit",
+                        "field_type": { "kind": "Primitive", "value": "i64" },
+                        "optional": true,
+                        "default": { "value": 20 }
+                    },
+                    {
+                        "field_name": "tags",
+                        "field_type": {
+                            "kind": "Container",
+                            "value": { "container_type": "Vec", "type_params": [{ "kind": "Primitive", "value": "String" }] }
+                        },
+                        "optional": true,
+                        "default": "implicit"
+                    }
+                ]
+            }
+        });
+        let code = generate_struct_type_from_schema("ListParams", &schema);
+        assert!(code.contains("    #[serde(default = \"__laz_default_ListParams_limit\", skip_serializing_if = \"Option::is_none\")]\n    pub limit: Option<i64>,"));
+        assert!(code.contains("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub tags: Option<Vec<String>>,"));
+        assert!(code.contains("impl Default for ListParams {"));
+        assert!(code.contains("            limit: __laz_default_ListParams_limit(),\n            tags: Default::default(),\n"));
+        assert!(code.contains(
+            "fn __laz_default_ListParams_limit() -> Option<i64> {\n    serde_json::from_str(\"20\")"
+        ));
+    }
+
+    #[test]
+    fn test_generated_client_embeds_schema_digest() {
+        let metadata = json!({
+            "functions": [],
+            "endpoints_discovery": [],
+            "schema_digest": "00ff00ff00ff00ff",
+            "api_version": "1.4.0"
+        });
+        let code =
+            generate_client_code_from_metadata_json("http://localhost:5150", &metadata.to_string())
+                .unwrap();
+        assert!(code.contains(
+            "pub const SCHEMA_DIGEST: Option<&'static str> = Some(\"00ff00ff00ff00ff\");"
+        ));
+        assert!(code.contains("pub const API_VERSION: Option<&'static str> = Some(\"1.4.0\");"));
+    }
+
+    #[test]
+    fn test_type_table_resolves_recursive_refs() {
+        let metadata = json!({
+            "functions": [{
+                "function_name": "get_tree",
+                "is_mutation": false,
+                "input_type_name": null,
+                "output_type_name": "TreeNode"
+            }],
+            "endpoints_discovery": [],
+            "types": {
+                "TreeNode": {
+                    "kind": "Struct",
+                    "value": {
+                        "type_name": "TreeNode",
+                        "fields": [
+                            {
+                                "field_name": "children",
+                                "field_type": {
+                                    "kind": "Container",
+                                    "value": {
+                                        "container_type": "Vec",
+                                        "type_params": [{ "kind": "Ref", "value": "TreeNode" }]
+                                    }
+                                },
+                                "optional": false
+                            },
+                            {
+                                "field_name": "owner",
+                                "field_type": { "kind": "Ref", "value": "Owner" },
+                                "optional": false
+                            }
+                        ]
+                    }
+                }
+            }
+        });
+        let code =
+            generate_client_code_from_metadata_json("http://localhost:5150", &metadata.to_string())
+                .unwrap();
+        assert!(code.contains("pub struct TreeNode {"));
+        assert!(code.contains("pub children: Vec<TreeNode>,"));
+        // Unresolved references fall back to an opaque newtype
+        assert!(code.contains("pub struct Owner(pub serde_json::Value);"));
+    }
+
+    #[test]
+    fn test_websocket_functions_open_typed_sessions() {
+        let call_args = CallArgs {
+            path: vec![PathArg { name: "room".to_string(), rust_type: "String".to_string() }],
+            ..CallArgs::default()
+        };
+        let code = generate_websocket_impl("chat", Some("ChatMessage"), "ChatEvent", "/api/rooms/{room}/chat", &call_args);
+        assert!(code.contains("#[cfg(not(target_arch = \"wasm32\"))]"));
+        assert!(code.contains(
+            "pub async fn chat(&self, room: String) -> Result<::laz_client::WsSession<ChatMessage, ChatEvent>, ::laz_client::RpcClientError>"
+        ));
+        assert!(code.contains("self.inner.connect_ws(&endpoint).await"));
     }
 
-    endpoints.keys().next().cloned()
+    #[test]
+    fn test_conflicting_functions_refuse_generation() {
+        let metadata = json!({
+            "functions": [],
+            "types": {},
+            "conflicts": [{
+                "function_name": "plant_tree",
+                "handlers": ["app::a::plant_tree", "app::b::plant_tree"]
+            }]
+        });
+        let error = generate_client_code_from_metadata_json("http://localhost", &metadata.to_string()).unwrap_err();
+        assert!(error.to_string().contains("plant_tree (app::a::plant_tree, app::b::plant_tree)"));
+    }
+
+    #[test]
+    fn test_newer_metadata_formats_refuse_generation() {
+        let metadata = json!({ "schema_version": laz_types::METADATA_SCHEMA_VERSION + 1, "functions": [] });
+        let error = generate_client_code_from_metadata_json("http://localhost", &metadata.to_string()).unwrap_err();
+        assert!(error.to_string().contains("upgrade laz"));
+    }
+
+    #[test]
+    fn test_tuple_structs_become_newtypes() {
+        let user_id = json!({ "kind": "Newtype", "value": { "kind": "Primitive", "value": "i64" } });
+        assert_eq!(
+            generate_type_from_schema("UserId", Some(&user_id.to_string())),
+            "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub struct UserId(pub i64);\n"
+        );
+        let point = json!({ "kind": "Tuple", "value": [
+            { "kind": "Primitive", "value": "f64" },
+            { "kind": "Primitive", "value": "f64" }
+        ]});
+        assert!(generate_type_from_schema("Point", Some(&point.to_string()))
+            .contains("pub struct Point(pub f64, pub f64);"));
+        assert_eq!(get_rust_type_from_schema(&point), "(f64, f64)");
+    }
+
+    #[test]
+    fn test_enum_variants_carry_data() {
+        let schema = json!({
+            "kind": "Enum",
+            "value": {
+                "type_name": "Event",
+                "variants": [
+                    {
+                        "variant_name": "Created",
+                        "inner_schema": null,
+                        "fields": [
+                            {
+                                "field_name": "id",
+                                "field_type": { "kind": "Primitive", "value": "i64" },
+                                "optional": false
+                            },
+                            {
+                                "field_name": "at",
+                                "field_type": { "kind": "Primitive", "value": "String" },
+                                "optional": false
+                            }
+                        ]
+                    },
+                    {
+                        "variant_name": "Renamed",
+                        "inner_schema": { "kind": "Primitive", "value": "String" }
+                    },
+                    {
+                        "variant_name": "Pair",
+                        "inner_schema": { "kind": "Tuple", "value": [
+                            { "kind": "Primitive", "value": "String" },
+                            { "kind": "Primitive", "value": "i32" }
+                        ]}
+                    },
+                    { "variant_name": "Deleted", "inner_schema": null }
+                ]
+            }
+        });
+        let code = generate_enum_type_from_schema("Event", &schema);
+        assert!(code.contains("    Created {\n        id: i64,\n        at: String,\n    },\n"));
+        assert!(code.contains("    Renamed(String),\n"));
+        assert!(code.contains("    Pair(String, i32),\n"));
+        assert!(code.contains("    Deleted,\n"));
+    }
+
+    #[test]
+    fn test_enum_tagging_is_replicated() {
+        let schema = json!({
+            "kind": "Enum",
+            "value": {
+                "type_name": "Shape",
+                "variants": [{ "variant_name": "Empty", "inner_schema": null }],
+                "tagging": { "style": "adjacent", "tag": "t", "content": "c" }
+            }
+        });
+        let code = generate_enum_type_from_schema("Shape", &schema);
+        assert!(code.starts_with(
+            "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\n#[serde(tag = \"t\", content = \"c\")]\npub enum Shape {\n"
+        ));
+        assert_eq!(
+            enum_tagging_attr(&json!({ "style": "internal", "tag": "type" })),
+            "#[serde(tag = \"type\")]\n"
+        );
+        assert_eq!(enum_tagging_attr(&json!({ "style": "untagged" })), "#[serde(untagged)]\n");
+        assert_eq!(enum_tagging_attr(&json!({ "style": "external" })), "");
+    }
+
+    #[test]
+    fn test_constraints_are_documented() {
+        let constraints = json!({ "min": 1.0, "max": 100.0, "one_of": ["a", "b"] });
+        assert_eq!(
+            constraints_doc(&constraints).unwrap(),
+            "Constraints: min 1.0, max 100.0, one of \"a\", \"b\""
+        );
+        assert_eq!(constraints_doc(&json!({})), None);
+    }
+
+    #[test]
+    fn test_decimal_schema_never_becomes_float() {
+        let decimal = json!({ "kind": "Decimal", "value": "rust_decimal :: Decimal" });
+        let expected = if cfg!(feature = "decimal") {
+            "::laz_client::rust_decimal::Decimal"
+        } else {
+            "String"
+        };
+        assert_eq!(get_rust_type_from_schema(&decimal), expected);
+    }
 }
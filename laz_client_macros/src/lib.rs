@@ -144,7 +144,7 @@ fn load_cached_code_from_disk() -> Result<String, Box<dyn std::error::Error>> {
     for path in &candidates {
         eprintln!("  - {}", path.display());
         if path.exists() {
-            if let Ok(metadata) = std::fs::metadata(&path) {
+            if let Ok(metadata) = std::fs::metadata(path) {
                 if let Ok(created) = metadata.created() {
                     existing_files.push((path.clone(), created, metadata.len()));
                 } else {
@@ -178,7 +178,7 @@ fn load_cached_code_from_disk() -> Result<String, Box<dyn std::error::Error>> {
         eprintln!("  - {} ({} bytes)", path.display(), size);
 
         // Check if this is the full type-safe client or the fallback
-        if let Ok(content) = std::fs::read_to_string(&path) {
+        if let Ok(content) = std::fs::read_to_string(path) {
             if content.contains("Auto-generated type-safe RPC client for server at:") {
                 eprintln!("    -> This appears to be the full type-safe client!");
                 return Ok(content);
@@ -196,7 +196,7 @@ fn load_cached_code_from_disk() -> Result<String, Box<dyn std::error::Error>> {
             "laz_client_macros: Using fallback client from: {}",
             path.display()
         );
-        return fs::read_to_string(&path).map_err(|e| {
+        return fs::read_to_string(path).map_err(|e| {
             format!(
                 "Failed to read generated code from {}: {}",
                 path.display(),
@@ -2,17 +2,32 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
-    Data, DeriveInput, Fields, GenericArgument, PathArguments, Type, TypePath, parse_macro_input,
+    Data, DeriveInput, ext::IdentExt, Fields, GenericArgument, Meta, PathArguments, Token, Type, TypePath,
+    parse_macro_input, punctuated::Punctuated,
 };
 
-#[proc_macro_derive(LazSchema)]
+#[proc_macro_derive(LazSchema, attributes(laz))]
 pub fn derive_laz_schema(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let type_name = input.ident.to_string();
 
+    let rename_all = serde_attr_value(&input.attrs, "rename_all");
+    let container_default = serde_attr_flag(&input.attrs, "default");
+
     let schema = match &input.data {
-        Data::Struct(data) => generate_struct_schema(&type_name, &data.fields),
-        Data::Enum(data) => generate_enum_schema(&type_name, &data.variants),
+        Data::Struct(data) => generate_struct_schema(
+            &type_name,
+            &data.fields,
+            rename_all.as_deref(),
+            container_default,
+        ),
+        Data::Enum(data) => generate_enum_schema(
+            &type_name,
+            &data.variants,
+            rename_all.as_deref(),
+            serde_attr_value(&input.attrs, "rename_all_fields").as_deref(),
+            enum_tagging(&input.attrs),
+        ),
         Data::Union(_) => panic!("Unions not supported for LazSchema derive"),
     };
 
@@ -25,8 +40,31 @@ pub fn derive_laz_schema(input: TokenStream) -> TokenStream {
         proc_macro2::Span::call_site(),
     );
     let type_name_literal = proc_macro2::Literal::string(&type_name);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Generic types register a template; concrete uses register their own instances
+    let type_params: Vec<String> = input.generics.type_params().map(|p| p.ident.to_string()).collect();
+    let registration = if type_params.is_empty() {
+        quote! {
+            laz_types::TypeSchemaEntry {
+                type_name: #type_name_literal,
+                getter: #getter_fn,
+            }
+        }
+    } else {
+        quote! {
+            laz_types::GenericSchemaEntry {
+                type_name: #type_name_literal,
+                params: &[#(#type_params),*],
+                getter: #getter_fn,
+            }
+        }
+    };
 
     let expanded = quote! {
+        impl #impl_generics laz_types::LazSchema for #ident #ty_generics #where_clause {}
+
         fn #schema_fn() -> laz_types::TypeSchema {
             #schema
         }
@@ -39,10 +77,7 @@ pub fn derive_laz_schema(input: TokenStream) -> TokenStream {
         #[allow(non_upper_case_globals)]
         const _: fn() = || {
             ::inventory::submit! {
-                laz_types::TypeSchemaEntry {
-                    type_name: #type_name_literal,
-                    getter: #getter_fn,
-                }
+                #registration
             };
         };
     };
@@ -53,9 +88,17 @@ pub fn derive_laz_schema(input: TokenStream) -> TokenStream {
 fn generate_enum_schema(
     type_name: &str,
     variants: &syn::punctuated::Punctuated<syn::Variant, syn::token::Comma>,
+    rename_all: Option<&str>,
+    rename_all_fields: Option<&str>,
+    tagging: proc_macro2::TokenStream,
 ) -> proc_macro2::TokenStream {
     let variant_schemas = variants.iter().map(|v| {
-        let variant_name = v.ident.to_string();
+        let variant_name = v.ident.unraw().to_string();
+        let rename = option_tokens(wire_name(
+            &variant_name,
+            &v.attrs,
+            rename_all.map(|rule| apply_rename_rule(&variant_name, rule, true)),
+        ));
         let inner_schema = match &v.fields {
             Fields::Unit => quote! { None },
             Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
@@ -63,13 +106,30 @@ fn generate_enum_schema(
                 let inner = type_to_schema(field_ty);
                 quote! { Some(Box::new(#inner)) }
             }
-            _ => quote! { None }, // Complex variants treated as opaque
+            // Tuple variants serialize their payloads as a JSON array, like a tuple
+            Fields::Unnamed(fields) => {
+                let payloads = fields.unnamed.iter().map(field_to_schema);
+                quote! {
+                    Some(Box::new(laz_types::TypeSchema::Tuple(vec![#(Box::new(#payloads)),*])))
+                }
+            }
+            Fields::Named(_) => quote! { None },
+        };
+        let fields = match &v.fields {
+            Fields::Named(fields) => {
+                let fields_rename_all =
+                    serde_attr_value(&v.attrs, "rename_all").or(rename_all_fields.map(String::from));
+                named_fields_schema(fields, fields_rename_all.as_deref(), false)
+            }
+            _ => quote! { Vec::new() },
         };
 
         quote! {
             laz_types::VariantSchema {
                 variant_name: #variant_name.to_string(),
                 inner_schema: #inner_schema,
+                rename: #rename,
+                fields: #fields,
             }
         }
     });
@@ -78,68 +138,250 @@ fn generate_enum_schema(
         laz_types::TypeSchema::Enum(laz_types::EnumSchema {
             type_name: #type_name.to_string(),
             variants: vec![#(#variant_schemas),*],
+            tagging: #tagging,
         })
     }
 }
 
+/// Enum representation from the container's serde `tag`, `content` and `untagged` attributes
+fn enum_tagging(attrs: &[syn::Attribute]) -> proc_macro2::TokenStream {
+    if serde_attr_flag(attrs, "untagged") {
+        return quote! { laz_types::EnumTagging::Untagged };
+    }
+    match (serde_attr_value(attrs, "tag"), serde_attr_value(attrs, "content")) {
+        (Some(tag), Some(content)) => quote! {
+            laz_types::EnumTagging::Adjacent { tag: #tag.to_string(), content: #content.to_string() }
+        },
+        (Some(tag), None) => quote! { laz_types::EnumTagging::Internal { tag: #tag.to_string() } },
+        _ => quote! { laz_types::EnumTagging::External },
+    }
+}
+
 /// Generates schema for struct fields
-fn generate_struct_schema(type_name: &str, fields: &Fields) -> proc_macro2::TokenStream {
+fn generate_struct_schema(
+    type_name: &str,
+    fields: &Fields,
+    rename_all: Option<&str>,
+    container_default: bool,
+) -> proc_macro2::TokenStream {
     match fields {
         Fields::Named(fields) => {
-            let field_schemas = fields.named.iter().map(|f| {
-                let field_name = f.ident.as_ref().unwrap().to_string();
-                let is_optional = is_optional_type(&f.ty);
-                let field_type = type_to_schema(&f.ty);
-
-                quote! {
-                    laz_types::FieldSchema {
-                        field_name: #field_name.to_string(),
-                        field_type: Box::new(#field_type),
-                        optional: #is_optional,
-                    }
-                }
-            });
-
+            let fields = named_fields_schema(fields, rename_all, container_default);
             quote! {
                 laz_types::TypeSchema::Struct(laz_types::StructSchema {
                     type_name: #type_name.to_string(),
-                    fields: vec![#(#field_schemas),*],
+                    fields: #fields,
                 })
             }
         }
 
-        Fields::Unnamed(_) => {
-            // Tuple struct - treat as opaque
-            quote! {
-                laz_types::TypeSchema::Opaque(#type_name.to_string())
+        Fields::Unnamed(fields) => {
+            let field_schemas: Vec<_> = fields
+                .unnamed
+                .iter()
+                .filter(|f| !serde_attr_flag(&f.attrs, "skip"))
+                .map(field_to_schema)
+                .collect();
+
+            // serde serializes single-field tuple structs as their inner value
+            match field_schemas.as_slice() {
+                [inner] => quote! {
+                    laz_types::TypeSchema::Newtype(Box::new(#inner))
+                },
+                _ => quote! {
+                    laz_types::TypeSchema::Tuple(vec![#(Box::new(#field_schemas)),*])
+                },
             }
         }
 
+        // Unit structs serialize as `null`, like `()`
         Fields::Unit => {
-            // Unit struct
             quote! {
-                laz_types::TypeSchema::Struct(laz_types::StructSchema {
-                    type_name: #type_name.to_string(),
-                    fields: vec![],
-                })
+                laz_types::TypeSchema::Unit
             }
         }
     }
 }
 
+/// Builds a `Vec<FieldSchema>` expression for named fields of a struct or enum variant
+fn named_fields_schema(
+    fields: &syn::FieldsNamed,
+    rename_all: Option<&str>,
+    container_default: bool,
+) -> proc_macro2::TokenStream {
+    let field_pushes = fields
+        .named
+        .iter()
+        .filter(|f| !serde_attr_flag(&f.attrs, "skip"))
+        .map(|f| {
+            if serde_attr_flag(&f.attrs, "flatten") {
+                return flatten_field_tokens(f);
+            }
+
+            let field_name = f.ident.as_ref().unwrap().unraw().to_string();
+            let serde_default = container_default
+                || serde_attr_flag(&f.attrs, "default")
+                || serde_attr_value(&f.attrs, "default").is_some();
+            let default = field_default(&f.attrs, serde_default)
+                .unwrap_or_else(|e| e.to_compile_error());
+            let example = field_example(&f.attrs).unwrap_or_else(|e| e.to_compile_error());
+            let is_nullable = is_optional_type(&f.ty);
+            // Fields that may be absent from the JSON are optional on the wire
+            let is_optional = is_nullable
+                || serde_default
+                || serde_attr_value(&f.attrs, "skip_serializing_if").is_some()
+                || serde_attr_flag(&f.attrs, "skip_serializing")
+                || serde_attr_flag(&f.attrs, "skip_deserializing");
+            let field_type = field_to_schema(f);
+            let rename = option_tokens(wire_name(
+                &field_name,
+                &f.attrs,
+                rename_all.map(|rule| apply_rename_rule(&field_name, rule, false)),
+            ));
+            let description = option_tokens(doc_comment(&f.attrs));
+            let deprecated = option_tokens(deprecation(&f.attrs));
+            let constraints = field_constraints(&f.attrs).unwrap_or_else(|e| e.to_compile_error());
+
+            quote! {
+                fields.push(laz_types::FieldSchema {
+                    field_name: #field_name.to_string(),
+                    field_type: Box::new(#field_type),
+                    optional: #is_optional,
+                    nullable: #is_nullable,
+                    rename: #rename,
+                    description: #description,
+                    deprecated: #deprecated,
+                    constraints: #constraints,
+                    default: #default,
+                    example: #example,
+                });
+            }
+        });
+
+    quote! {{
+        #[allow(unused_mut)]
+        let mut fields: Vec<laz_types::FieldSchema> = Vec::new();
+        #(#field_pushes)*
+        fields
+    }}
+}
+
+/// Wire encodings forced by a field's `#[serde(with = ...)]` helper
+#[derive(Clone, Copy, Default)]
+struct EncodingOverride {
+    datetime: Option<&'static str>,
+    bytes: Option<&'static str>,
+}
+
+/// Schema for a struct field, honoring `#[serde(with = ...)]` encodings for date/time and
+/// byte types
+fn field_to_schema(field: &syn::Field) -> proc_macro2::TokenStream {
+    let mut overrides = EncodingOverride::default();
+    if let Some(with) = serde_attr_value(&field.attrs, "with") {
+        if with.contains("ts_") || with.contains("timestamp") {
+            overrides.datetime = Some("Unix");
+        } else if with.contains("rfc3339") {
+            overrides.datetime = Some("Rfc3339");
+        } else if with.contains("base64") {
+            overrides.bytes = Some("Base64");
+        }
+    }
+    type_to_schema_with(&field.ty, overrides)
+}
+
 fn type_to_schema(ty: &Type) -> proc_macro2::TokenStream {
+    type_to_schema_with(ty, EncodingOverride::default())
+}
+
+/// Build a schema for `ty`, forcing the overridden encodings onto date/time and byte types
+fn type_to_schema_with(ty: &Type, overrides: EncodingOverride) -> proc_macro2::TokenStream {
     match ty {
         Type::Path(type_path) => {
-            // Check if it's a container type (Vec, Option, Result)
-            if let Some(container) = get_container_type(type_path) {
-                let inner_schema = type_to_schema(get_inner_type(type_path).unwrap());
-                
+            // Box<T>, Arc<T>, Rc<T> and Cow<T> serialize as T
+            if let Some(inner) = smart_pointer_inner(type_path) {
+                type_to_schema_with(inner, overrides)
+            // PhantomData<T> carries no data and serializes as `null`
+            } else if type_path.path.segments.last().is_some_and(|s| s.ident == "PhantomData") {
+                quote! {
+                    laz_types::TypeSchema::Unit
+                }
+            // Check if it's a known chrono/time type
+            } else if let Some(encoding) = datetime_encoding(type_path) {
+                datetime_schema(type_path, overrides.datetime.unwrap_or(encoding))
+            // Byte buffers: serde writes them as integer arrays unless a base64 helper is used
+            } else if is_bytes_type(type_path) {
+                let encoding = syn::Ident::new(
+                    overrides.bytes.unwrap_or("Array"),
+                    proc_macro2::Span::call_site(),
+                );
+                quote! {
+                    laz_types::TypeSchema::Bytes(laz_types::BytesEncoding::#encoding)
+                }
+            // Check if it's a map type (HashMap, BTreeMap)
+            } else if let Some((key_ty, value_ty)) = get_map_types(type_path) {
+                let key_schema = type_to_schema(key_ty);
+                let value_schema = type_to_schema(value_ty);
+
+                quote! {
+                    laz_types::TypeSchema::Map {
+                        key_type: Box::new(#key_schema),
+                        value_type: Box::new(#value_schema)
+                    }
+                }
+            // Check if it's a container type (Vec, Option, Result, HashSet, BTreeSet)
+            } else if let Some(container) = get_container_type(type_path) {
+                let param_schemas = get_type_args(type_path)
+                    .into_iter()
+                    .map(|ty| type_to_schema_with(ty, overrides));
+
                 quote! {
                     laz_types::TypeSchema::Container {
                         container_type: #container.to_string(),
-                        inner_type: Box::new(#inner_schema)
+                        type_params: vec![#(#param_schemas),*]
                     }
                 }
+            // i128/u128 exceed what JSON numbers can carry and travel as strings
+            } else if type_path.path.is_ident("i128") || type_path.path.is_ident("u128") {
+                let type_str = quote::quote!(#type_path).to_string();
+                quote! {
+                    laz_types::TypeSchema::Int128(#type_str.to_string())
+                }
+            } else if let Some(primitive) = non_zero_primitive(type_path) {
+                quote! {
+                    laz_types::TypeSchema::NonZero(#primitive.to_string())
+                }
+            // rust_decimal::Decimal and bigdecimal::BigDecimal travel as strings
+            } else if type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "Decimal" || s.ident == "BigDecimal")
+            {
+                let type_str = quote::quote!(#type_path).to_string();
+                quote! {
+                    laz_types::TypeSchema::Decimal(#type_str.to_string())
+                }
+            // serde_json::Value accepts any JSON
+            } else if type_path.path.segments.last().is_some_and(|s| s.ident == "Value") {
+                quote! {
+                    laz_types::TypeSchema::Any
+                }
+            // uuid::Uuid is described by its bare name regardless of how it was imported
+            } else if type_path.path.segments.last().is_some_and(|s| s.ident == "Uuid") {
+                quote! {
+                    laz_types::TypeSchema::Primitive("Uuid".to_string())
+                }
+            // Generic user-defined types are registered per instantiation, e.g. `Paginated_User`
+            } else if let Some(args) = custom_type_name(type_path)
+                .map(|_| get_type_args(type_path))
+                .filter(|args| !args.is_empty())
+            {
+                generic_instance_schema(type_path, &args)
+            // User-defined types are referenced by name and described once in the type table
+            } else if let Some(name) = custom_type_name(type_path) {
+                quote! {
+                    laz_types::TypeSchema::Ref(#name.to_string())
+                }
             } else {
                 let type_str = quote::quote!(#type_path).to_string();
                 // Primitive or custom type
@@ -149,6 +391,29 @@ fn type_to_schema(ty: &Type) -> proc_macro2::TokenStream {
             }
         }
 
+        Type::Array(array) => {
+            let inner_schema = type_to_schema(&array.elem);
+            let len = &array.len;
+
+            quote! {
+                laz_types::TypeSchema::Array {
+                    inner: Box::new(#inner_schema),
+                    len: (#len) as usize
+                }
+            }
+        }
+
+        Type::Tuple(tuple) if tuple.elems.is_empty() => quote! {
+            laz_types::TypeSchema::Unit
+        },
+
+        Type::Tuple(tuple) => {
+            let elem_schemas = tuple.elems.iter().map(type_to_schema);
+            quote! {
+                laz_types::TypeSchema::Tuple(vec![#(Box::new(#elem_schemas)),*])
+            }
+        }
+
         Type::Reference(_) => {
             let type_str = quote!(ty).to_string();
             quote! {
@@ -165,6 +430,416 @@ fn type_to_schema(ty: &Type) -> proc_macro2::TokenStream {
     }
 }
 
+/// Name of a user-defined type, or None for language primitives and `serde_json::Value`
+fn custom_type_name(type_path: &TypePath) -> Option<String> {
+    if type_path.qself.is_some() {
+        return None;
+    }
+    let name = type_path.path.segments.last()?.ident.to_string();
+    (!laz_types::is_primitive_type_name(&name)).then_some(name)
+}
+
+/// Reference a concrete instantiation of a generic type, registering its schema so the
+/// type table can resolve it
+fn generic_instance_schema(type_path: &TypePath, args: &[&Type]) -> proc_macro2::TokenStream {
+    let base = type_path.path.segments.last().unwrap().ident.to_string();
+    let instance_name = laz_types::generic_instance_name(&quote!(#type_path).to_string());
+    let arg_schemas = args.iter().map(|ty| type_to_schema(ty));
+
+    quote! {{
+        fn __laz_get_instance_schema() -> &'static laz_types::TypeSchema {
+            static SCHEMA: ::std::sync::OnceLock<laz_types::TypeSchema> = ::std::sync::OnceLock::new();
+            SCHEMA.get_or_init(|| {
+                laz_types::instantiate_generic(#base, #instance_name, vec![#(#arg_schemas),*])
+            })
+        }
+        ::inventory::submit! {
+            laz_types::TypeSchemaEntry {
+                type_name: #instance_name,
+                getter: __laz_get_instance_schema,
+            }
+        }
+        laz_types::TypeSchema::Ref(#instance_name.to_string())
+    }}
+}
+
+fn datetime_schema(type_path: &TypePath, encoding: &str) -> proc_macro2::TokenStream {
+    let type_str = quote::quote!(#type_path).to_string();
+    let encoding = syn::Ident::new(encoding, proc_macro2::Span::call_site());
+    quote! {
+        laz_types::TypeSchema::DateTime {
+            type_name: #type_str.to_string(),
+            encoding: laz_types::DateTimeEncoding::#encoding,
+        }
+    }
+}
+
+/// `Vec<u8>`, `bytes::Bytes`/`BytesMut` and `serde_bytes::ByteBuf`
+fn is_bytes_type(type_path: &TypePath) -> bool {
+    let Some(last) = type_path.path.segments.last() else {
+        return false;
+    };
+    match last.ident.to_string().as_str() {
+        "Bytes" | "BytesMut" | "ByteBuf" => true,
+        "Vec" => matches!(
+            get_type_args(type_path).as_slice(),
+            [Type::Path(inner)] if inner.path.is_ident("u8")
+        ),
+        _ => false,
+    }
+}
+
+/// Map known chrono/time types to their default serde encoding
+fn datetime_encoding(type_path: &TypePath) -> Option<&'static str> {
+    let segments = &type_path.path.segments;
+    let from_time_crate = segments.len() > 1 && segments[0].ident == "time";
+    match segments.last()?.ident.to_string().as_str() {
+        "DateTime" | "OffsetDateTime" => Some("Rfc3339"),
+        "NaiveDateTime" | "PrimitiveDateTime" => Some("NaiveDateTime"),
+        "NaiveDate" => Some("Date"),
+        "NaiveTime" => Some("Time"),
+        "Date" if from_time_crate => Some("Date"),
+        "Time" if from_time_crate => Some("Time"),
+        _ => None,
+    }
+}
+
+/// Inline the fields of a `#[serde(flatten)]` struct, resolved from the registry at runtime
+fn flatten_field_tokens(field: &syn::Field) -> proc_macro2::TokenStream {
+    // Option<Inner> flattens to Inner's fields, all of which may then be absent
+    let (inner_ty, force_optional) = match &field.ty {
+        Type::Path(type_path) if is_optional_type(&field.ty) => {
+            match get_type_args(type_path).first() {
+                Some(Type::Path(inner)) => (inner, true),
+                _ => return quote! {},
+            }
+        }
+        Type::Path(type_path) => (type_path, false),
+        _ => return quote! {},
+    };
+    // Flattened maps capture unknown keys and contribute no named fields
+    if get_map_types(inner_ty).is_some() {
+        return quote! {};
+    }
+    let inner_name = inner_ty.path.segments.last().unwrap().ident.to_string();
+
+    quote! {
+        if let Some(laz_types::TypeSchema::Struct(inner)) = laz_types::lookup_type_schema(#inner_name) {
+            fields.extend(inner.fields.iter().cloned().map(|mut f| {
+                f.optional |= #force_optional;
+                f
+            }));
+        }
+    }
+}
+
+/// Resolve the serialized name of a field or variant, returning it only when it differs
+fn wire_name(name: &str, attrs: &[syn::Attribute], renamed_by_rule: Option<String>) -> Option<String> {
+    serde_attr_value(attrs, "rename")
+        .or(renamed_by_rule)
+        .filter(|wire| wire != name)
+}
+
+/// Apply a serde `rename_all` rule to a snake_case field or PascalCase variant name
+fn apply_rename_rule(name: &str, rule: &str, is_variant: bool) -> String {
+    // Split into lowercase words regardless of the source convention
+    let words: Vec<String> = if is_variant {
+        let mut words = Vec::new();
+        let mut current = String::new();
+        for ch in name.chars() {
+            if ch.is_uppercase() && !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            current.push(ch.to_ascii_lowercase());
+        }
+        if !current.is_empty() {
+            words.push(current);
+        }
+        words
+    } else {
+        name.split('_').map(str::to_string).collect()
+    };
+    let capitalize = |w: &String| {
+        let mut chars = w.chars();
+        chars
+            .next()
+            .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    };
+
+    match rule {
+        "lowercase" if is_variant => words.concat(),
+        "UPPERCASE" if is_variant => words.concat().to_ascii_uppercase(),
+        "lowercase" => name.to_ascii_lowercase(),
+        "UPPERCASE" => name.to_ascii_uppercase(),
+        "PascalCase" => words.iter().map(capitalize).collect(),
+        "camelCase" => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+            .collect(),
+        "snake_case" => words.join("_"),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_ascii_uppercase(),
+        "kebab-case" => words.join("-"),
+        "SCREAMING-KEBAB-CASE" => words.join("-").to_ascii_uppercase(),
+        _ => name.to_string(),
+    }
+}
+
+/// Collect `///` doc comments into a single description
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).trim_end().to_string())
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn option_tokens(value: Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(v) => quote! { Some(#v.to_string()) },
+        None => quote! { None },
+    }
+}
+
+/// Check for a bare flag like `skip` or `flatten` in the `#[serde(...)]` attributes
+fn serde_attr_flag(attrs: &[syn::Attribute], key: &str) -> bool {
+    attr_flag(attrs, "serde", key)
+}
+
+/// Read a `key = "value"` entry from the `#[serde(...)]` attributes
+fn serde_attr_value(attrs: &[syn::Attribute], key: &str) -> Option<String> {
+    attr_value(attrs, "serde", key)
+}
+
+/// Check for a bare flag in attributes of the form `#[<namespace>(...)]`
+fn attr_flag(attrs: &[syn::Attribute], namespace: &str, key: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|a| a.path().is_ident(namespace))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .any(|meta| matches!(meta, Meta::Path(path) if path.is_ident(key)))
+}
+
+/// Read a `key = "value"` entry from attributes of the form `#[<namespace>(...)]`
+fn attr_value(attrs: &[syn::Attribute], namespace: &str, key: &str) -> Option<String> {
+    for attr in attrs.iter().filter(|a| a.path().is_ident(namespace)) {
+        let Ok(metas) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+        else {
+            continue;
+        };
+        for meta in metas {
+            if let Meta::NameValue(nv) = meta {
+                if nv.path.is_ident(key) {
+                    if let syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }) = &nv.value
+                    {
+                        return Some(s.value());
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Validation constraints from `#[laz(min = 1, max = 100, pattern = "...", one_of = ["a", "b"])]`
+fn field_constraints(attrs: &[syn::Attribute]) -> syn::Result<proc_macro2::TokenStream> {
+    let mut min = None;
+    let mut max = None;
+    let mut min_length = None;
+    let mut max_length = None;
+    let mut pattern = None;
+    let mut one_of: Vec<String> = Vec::new();
+
+    for attr in attrs.iter().filter(|a| a.path().is_ident("laz")) {
+        let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in metas {
+            let Meta::NameValue(nv) = meta else {
+                continue;
+            };
+            let Some(key) = nv.path.get_ident().map(|i| i.to_string()) else {
+                continue;
+            };
+            match key.as_str() {
+                "min" => min = Some(number_value(&nv.value)?),
+                "max" => max = Some(number_value(&nv.value)?),
+                "min_length" => min_length = Some(length_value(&nv.value)?),
+                "max_length" => max_length = Some(length_value(&nv.value)?),
+                "pattern" => pattern = Some(string_value(&nv.value)?),
+                "one_of" => match &nv.value {
+                    syn::Expr::Array(array) => {
+                        one_of = array
+                            .elems
+                            .iter()
+                            .map(string_value)
+                            .collect::<syn::Result<_>>()?;
+                    }
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "expected an array of strings, e.g. one_of = [\"a\", \"b\"]",
+                        ))
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    let min = option_number_tokens(min);
+    let max = option_number_tokens(max);
+    let min_length = option_number_tokens(min_length);
+    let max_length = option_number_tokens(max_length);
+    let pattern = option_tokens(pattern);
+    Ok(quote! {
+        laz_types::FieldConstraints {
+            min: #min,
+            max: #max,
+            min_length: #min_length,
+            max_length: #max_length,
+            pattern: #pattern,
+            one_of: vec![#(#one_of.to_string()),*],
+        }
+    })
+}
+
+fn option_number_tokens<T: quote::ToTokens>(value: Option<T>) -> proc_macro2::TokenStream {
+    match value {
+        Some(v) => quote! { Some(#v) },
+        None => quote! { None },
+    }
+}
+
+/// Numeric literal, optionally negated, as f64
+fn number_value(expr: &syn::Expr) -> syn::Result<f64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => i.base10_parse::<f64>(),
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Float(f),
+            ..
+        }) => f.base10_parse::<f64>(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => number_value(expr).map(|n| -n),
+        other => Err(syn::Error::new_spanned(other, "expected a number")),
+    }
+}
+
+fn length_value(expr: &syn::Expr) -> syn::Result<usize> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => i.base10_parse::<usize>(),
+        other => Err(syn::Error::new_spanned(other, "expected a non-negative integer")),
+    }
+}
+
+fn string_value(expr: &syn::Expr) -> syn::Result<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => Ok(s.value()),
+        other => Err(syn::Error::new_spanned(other, "expected a string literal")),
+    }
+}
+
+/// Field default from `#[laz(default = ...)]`, falling back to an undescribed default when
+/// serde fills the field in
+fn field_default(attrs: &[syn::Attribute], serde_default: bool) -> syn::Result<proc_macro2::TokenStream> {
+    Ok(match laz_json_value(attrs, "default")? {
+        Some(text) => quote! { Some(laz_types::FieldDefault::parse(#text)) },
+        None if serde_default => quote! { Some(laz_types::FieldDefault::Implicit) },
+        None => quote! { None },
+    })
+}
+
+/// Sample value from `#[laz(example = ...)]`
+fn field_example(attrs: &[syn::Attribute]) -> syn::Result<proc_macro2::TokenStream> {
+    Ok(match laz_json_value(attrs, "example")? {
+        Some(text) => quote! { Some(laz_types::json_or_string(#text)) },
+        None => quote! { None },
+    })
+}
+
+/// Text of a `#[laz(key = ...)]` value for [`laz_types::json_or_string`]. Strings may hold
+/// JSON (`example = "[1, 2]"`); number and bool literals are JSON already.
+fn laz_json_value(attrs: &[syn::Attribute], key: &str) -> syn::Result<Option<String>> {
+    for attr in attrs.iter().filter(|a| a.path().is_ident("laz")) {
+        let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in metas {
+            let Meta::NameValue(nv) = meta else {
+                continue;
+            };
+            if !nv.path.is_ident(key) {
+                continue;
+            }
+            return match &nv.value {
+                syn::Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+                    syn::Lit::Str(s) => Ok(Some(s.value())),
+                    syn::Lit::Int(_) | syn::Lit::Float(_) | syn::Lit::Bool(_) => {
+                        Ok(Some(quote!(#lit).to_string()))
+                    }
+                    other => Err(syn::Error::new_spanned(other, format!("unsupported {} value", key))),
+                },
+                other => Err(syn::Error::new_spanned(other, format!("expected a literal {} value", key))),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Deprecation note from `#[laz(deprecated = "...")]` or the standard `#[deprecated]` attribute.
+/// A deprecation without a note yields an empty string.
+fn deprecation(attrs: &[syn::Attribute]) -> Option<String> {
+    if let Some(note) = attr_value(attrs, "laz", "deprecated") {
+        return Some(note);
+    }
+    if attr_flag(attrs, "laz", "deprecated") {
+        return Some(String::new());
+    }
+    let attr = attrs.iter().find(|a| a.path().is_ident("deprecated"))?;
+    Some(match &attr.meta {
+        Meta::NameValue(syn::MetaNameValue {
+            value:
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }),
+            ..
+        }) => s.value(),
+        Meta::List(_) => attr_value(std::slice::from_ref(attr), "deprecated", "note")
+            .unwrap_or_default(),
+        _ => String::new(),
+    })
+}
+
 /// Check if type is Optional<T>
 fn is_optional_type(ty: &Type) -> bool {
     if let Type::Path(type_path) = ty {
@@ -175,16 +850,60 @@ fn is_optional_type(ty: &Type) -> bool {
     false
 }
 
-/// Extract inner type from Option<T> or Vec<T>
-fn get_inner_type(type_path: &TypePath) -> Option<&Type> {
+/// Extract all generic type arguments, e.g. [T] from Vec<T> or [T, E] from Result<T, E>
+fn get_type_args(type_path: &TypePath) -> Vec<&Type> {
     if let Some(segment) = type_path.path.segments.last() {
         if let PathArguments::AngleBracketed(args) = &segment.arguments {
-            if let Some(GenericArgument::Type(inner_ty)) = args.args.first() {
-                return Some(inner_ty);
-            }
+            return args
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect();
         }
     }
-    None
+    Vec::new()
+}
+
+/// Extract key and value types from HashMap<K, V> or BTreeMap<K, V>
+fn get_map_types(type_path: &TypePath) -> Option<(&Type, &Type)> {
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "HashMap" && segment.ident != "BTreeMap" {
+        return None;
+    }
+    match get_type_args(type_path).as_slice() {
+        [key_ty, value_ty, ..] => Some((key_ty, value_ty)),
+        _ => None,
+    }
+}
+
+/// Underlying integer of `NonZeroU32`-style aliases and the generic `NonZero<u32>`
+fn non_zero_primitive(type_path: &TypePath) -> Option<String> {
+    let segment = type_path.path.segments.last()?;
+    let primitive = match get_type_args(type_path).as_slice() {
+        [Type::Path(inner)] if segment.ident == "NonZero" => inner.path.get_ident()?.to_string(),
+        [] => segment.ident.to_string().strip_prefix("NonZero")?.to_lowercase(),
+        _ => return None,
+    };
+    let is_integer = matches!(
+        primitive.as_str(),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+    );
+    is_integer.then_some(primitive)
+}
+
+/// Pointee of a smart pointer such as `Box<T>` or `Arc<T>`
+fn smart_pointer_inner(type_path: &TypePath) -> Option<&Type> {
+    let segment = type_path.path.segments.last()?;
+    if !laz_types::is_smart_pointer_name(&segment.ident.to_string()) {
+        return None;
+    }
+    match get_type_args(type_path).as_slice() {
+        [inner] => Some(inner),
+        _ => None,
+    }
 }
 
 /// Check if type is a container and return container name
@@ -197,6 +916,8 @@ fn get_container_type(type_path: &TypePath) -> Option<&'static str> {
             "Vec" => Some("Vec"),
             "Option" => Some("Option"),
             "Result" => Some("Result"),
+            "HashSet" => Some("HashSet"),
+            "BTreeSet" => Some("BTreeSet"),
             _ => None,
         })
 }
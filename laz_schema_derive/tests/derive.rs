@@ -0,0 +1,306 @@
+//! Schemas recorded by `#[derive(LazSchema)]`, looked up through the registry like the
+//! server does.
+
+#![allow(dead_code)]
+
+use laz_schema_derive::LazSchema;
+use laz_types::{find_type_schema, BytesEncoding, EnumSchema, EnumTagging, FieldDefault, StructSchema, TypeSchema};
+
+fn schema(name: &str) -> &'static TypeSchema {
+    find_type_schema(name).unwrap_or_else(|| panic!("{} schema not registered", name))
+}
+
+fn struct_schema(name: &str) -> &'static StructSchema {
+    match schema(name) {
+        TypeSchema::Struct(schema) => schema,
+        other => panic!("{} isn't a struct: {:?}", name, other),
+    }
+}
+
+fn enum_schema(name: &str) -> &'static EnumSchema {
+    match schema(name) {
+        TypeSchema::Enum(schema) => schema,
+        other => panic!("{} isn't an enum: {:?}", name, other),
+    }
+}
+
+#[derive(LazSchema)]
+struct Nested {
+    outcome: Result<String, i32>,
+    scores: std::collections::HashMap<String, i64>,
+    position: [f32; 3],
+    tags: std::collections::BTreeSet<String>,
+    extra: serde_json::Value,
+}
+
+#[test]
+fn test_nested_types_get_nested_schemas() {
+    let fields = &struct_schema("Nested").fields;
+    assert!(matches!(
+        fields[0].field_type.as_ref(),
+        TypeSchema::Container { container_type, type_params }
+            if container_type == "Result" && type_params.len() == 2
+    ));
+    assert!(matches!(fields[1].field_type.as_ref(), TypeSchema::Map { .. }));
+    assert!(matches!(fields[2].field_type.as_ref(), TypeSchema::Array { len: 3, .. }));
+    assert!(matches!(
+        fields[3].field_type.as_ref(),
+        TypeSchema::Container { container_type, .. } if container_type == "BTreeSet"
+    ));
+    assert!(matches!(fields[4].field_type.as_ref(), TypeSchema::Any));
+}
+
+#[derive(LazSchema, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Renamed {
+    user_id: i64,
+    #[serde(rename = "kind")]
+    r#type: String,
+}
+
+#[test]
+fn test_serde_wire_names_are_recorded() {
+    let schema = struct_schema("Renamed");
+    let json = serde_json::to_value(Renamed { user_id: 1, r#type: "admin".to_string() }).unwrap();
+    for field in &schema.fields {
+        assert!(json.get(field.wire_name()).is_some(), "missing {}", field.wire_name());
+    }
+    assert_eq!(schema.fields[1].field_name, "type");
+}
+
+#[derive(LazSchema, serde::Serialize, serde::Deserialize)]
+struct Audit {
+    /// User who created the record
+    created_by: String,
+}
+
+#[derive(LazSchema, serde::Serialize, serde::Deserialize)]
+struct Flattened {
+    id: i64,
+    #[serde(skip)]
+    cache_key: String,
+    #[serde(default)]
+    retries: u32,
+    #[serde(flatten)]
+    audit: Audit,
+}
+
+#[test]
+fn test_skip_flatten_and_default_apply() {
+    let schema = struct_schema("Flattened");
+    let names: Vec<_> = schema.fields.iter().map(|f| f.field_name.as_str()).collect();
+    assert_eq!(names, ["id", "retries", "created_by"]);
+    assert!(schema.fields[1].optional);
+    assert!(!schema.fields[2].optional);
+}
+
+#[test]
+fn test_field_docs_become_descriptions() {
+    assert_eq!(struct_schema("Audit").fields[0].description.as_deref(), Some("User who created the record"));
+}
+
+#[derive(LazSchema, serde::Serialize, serde::Deserialize)]
+struct Deprecated {
+    #[laz(deprecated = "use display_name")]
+    nickname: String,
+    #[laz(deprecated)]
+    legacy_id: i64,
+    display_name: String,
+}
+
+#[test]
+fn test_field_deprecation_is_recorded() {
+    let notes: Vec<_> = struct_schema("Deprecated").fields.iter().map(|f| f.deprecated.as_deref()).collect();
+    assert_eq!(notes, [Some("use display_name"), Some(""), None]);
+}
+
+#[derive(LazSchema)]
+struct Pointers {
+    parent: Option<Box<Audit>>,
+    label: std::sync::Arc<str>,
+    audits: std::rc::Rc<Vec<Audit>>,
+}
+
+#[test]
+fn test_smart_pointers_are_transparent() {
+    let fields = &struct_schema("Pointers").fields;
+    assert!(matches!(
+        fields[0].field_type.as_ref(),
+        TypeSchema::Container { type_params, .. }
+            if matches!(&type_params[0], TypeSchema::Ref(name) if name == "Audit")
+    ));
+    assert!(matches!(fields[1].field_type.as_ref(), TypeSchema::Primitive(p) if p == "str"));
+    assert!(matches!(
+        fields[2].field_type.as_ref(),
+        TypeSchema::Container { container_type, .. } if container_type == "Vec"
+    ));
+}
+
+#[derive(LazSchema)]
+struct UserId(i64);
+
+#[derive(LazSchema)]
+struct Point(f64, f64);
+
+#[test]
+fn test_tuple_structs_are_described() {
+    assert!(matches!(
+        schema("UserId"),
+        TypeSchema::Newtype(inner) if matches!(inner.as_ref(), TypeSchema::Primitive(p) if p == "i64")
+    ));
+    assert!(matches!(schema("Point"), TypeSchema::Tuple(items) if items.len() == 2));
+}
+
+#[derive(LazSchema, serde::Serialize)]
+#[serde(rename_all_fields = "camelCase")]
+enum Event {
+    Created { event_id: i64, at: String },
+    Renamed(String),
+    Moved(String, i32),
+    Deleted,
+}
+
+#[test]
+fn test_struct_variant_fields_are_recorded() {
+    let schema = enum_schema("Event");
+    let wire_names: Vec<_> = schema.variants[0].fields.iter().map(|f| f.wire_name()).collect();
+    assert_eq!(wire_names, ["eventId", "at"]);
+    assert!(schema.variants[1].fields.is_empty());
+    assert!(schema.variants[1].inner_schema.is_some());
+    assert!(matches!(
+        schema.variants[2].inner_schema.as_deref(),
+        Some(TypeSchema::Tuple(items)) if items.len() == 2
+    ));
+    assert_eq!(schema.tagging, EnumTagging::External);
+}
+
+#[derive(LazSchema, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Notice {
+    Maintenance { minutes: u32 },
+    Outage,
+}
+
+#[derive(LazSchema, serde::Serialize)]
+#[serde(untagged)]
+enum Id {
+    Numeric(i64),
+    Named(String),
+}
+
+#[test]
+fn test_enum_tagging_is_recorded() {
+    assert_eq!(enum_schema("Notice").tagging, EnumTagging::Internal { tag: "type".to_string() });
+    assert_eq!(enum_schema("Id").tagging, EnumTagging::Untagged);
+}
+
+#[derive(LazSchema)]
+struct Signup {
+    #[laz(min_length = 3, max_length = 32, pattern = "^[a-z0-9_]+$")]
+    username: String,
+    #[laz(min = 13, max = 130)]
+    age: u32,
+    #[laz(one_of = ["free", "pro"])]
+    plan: String,
+}
+
+#[test]
+fn test_field_constraints_are_recorded() {
+    let fields = &struct_schema("Signup").fields;
+    let username = &fields[0].constraints;
+    assert_eq!(username.min_length, Some(3));
+    assert_eq!(username.max_length, Some(32));
+    assert_eq!(username.pattern.as_deref(), Some("^[a-z0-9_]+$"));
+    assert_eq!(fields[1].constraints.min, Some(13.0));
+    assert_eq!(fields[1].constraints.max, Some(130.0));
+    assert_eq!(fields[2].constraints.one_of, ["free", "pro"]);
+}
+
+#[derive(LazSchema, serde::Serialize)]
+struct Upload {
+    #[serde(with = "laz_types::base64")]
+    content: Vec<u8>,
+    checksum: Vec<u8>,
+}
+
+#[test]
+fn test_bytes_encoding_is_recorded() {
+    let encodings: Vec<_> = struct_schema("Upload")
+        .fields
+        .iter()
+        .map(|f| match f.field_type.as_ref() {
+            TypeSchema::Bytes(encoding) => *encoding,
+            other => panic!("expected bytes, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(encodings, [BytesEncoding::Base64, BytesEncoding::Array]);
+
+    let json = serde_json::to_value(Upload { content: b"laz".to_vec(), checksum: vec![1] }).unwrap();
+    assert_eq!(json["content"], "bGF6");
+}
+
+#[derive(LazSchema, serde::Serialize)]
+struct Ledger {
+    #[serde(with = "laz_types::int128")]
+    balance: i128,
+    owner: std::num::NonZeroU32,
+    sequence: std::num::NonZero<i64>,
+}
+
+#[test]
+fn test_wide_and_non_zero_integers_are_recorded() {
+    let kinds: Vec<_> = struct_schema("Ledger")
+        .fields
+        .iter()
+        .map(|f| match f.field_type.as_ref() {
+            TypeSchema::Int128(name) => format!("int128 {}", name),
+            TypeSchema::NonZero(name) => format!("non_zero {}", name),
+            other => panic!("unexpected schema {:?}", other),
+        })
+        .collect();
+    assert_eq!(kinds, ["int128 i128", "non_zero u32", "non_zero i64"]);
+}
+
+#[derive(LazSchema)]
+struct Ack;
+
+#[derive(LazSchema)]
+struct Marker {
+    id: i64,
+    marker: std::marker::PhantomData<Audit>,
+}
+
+#[test]
+fn test_unit_types_have_unit_schema() {
+    assert!(matches!(schema("Ack"), TypeSchema::Unit));
+    assert!(matches!(struct_schema("Marker").fields[1].field_type.as_ref(), TypeSchema::Unit));
+}
+
+#[derive(LazSchema, serde::Deserialize)]
+struct List {
+    #[laz(default = 20)]
+    limit: u32,
+    #[laz(default = "newest", example = "oldest")]
+    order: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[test]
+fn test_field_defaults_are_recorded() {
+    let fields = &struct_schema("List").fields;
+    let defaults: Vec<_> = fields.iter().map(|f| f.default.clone()).collect();
+    assert_eq!(
+        defaults,
+        [
+            Some(FieldDefault::Value(serde_json::json!(20))),
+            Some(FieldDefault::Value(serde_json::json!("newest"))),
+            Some(FieldDefault::Implicit),
+        ]
+    );
+    assert_eq!(fields[1].example, Some(serde_json::json!("oldest")));
+    // `#[laz(default)]` only documents; serde still requires the field
+    assert!(!fields[0].optional);
+    assert!(fields[2].optional);
+    assert!(!fields[2].nullable);
+}
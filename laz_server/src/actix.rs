@@ -0,0 +1,323 @@
+//! laz on actix-web, enabled by the `actix` feature.
+//!
+//! [`configure`] serves the metadata routes and [`dispatch`] the dispatch endpoint, when
+//! the endpoint enables it, from the same registry as on axum. Of a [`LazEndpoint`]'s
+//! options, the API version, title, metadata path, token, signing secret and dispatch
+//! apply; the checks layered over axum routes, the playground, topics, WebSockets and CORS
+//! don't. actix can't list an app's routes, so functions are found by the `path` they
+//! declare, or by endpoint discovery data the app registers with
+//! [`register_endpoints_discovery`](crate::register_endpoints_discovery) before calling
+//! [`configure`].
+//!
+//! ```rust,ignore
+//! let laz = LazEndpoint::new().with_version("1.2.0").with_dispatch();
+//! HttpServer::new(move || {
+//!     App::new()
+//!         .configure(laz_server::actix::configure(&laz))
+//!         .service(web::resource("/api/trees").route(web::post().to(plant_tree)))
+//!         .wrap(actix_web::middleware::from_fn(laz_server::actix::dispatch))
+//! })
+//! ```
+
+use std::sync::Arc;
+
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Method, StatusCode, Uri,
+    },
+    middleware::Next,
+    web, HttpRequest, HttpResponse,
+};
+
+use crate::cache::{Document, DocumentCache};
+use crate::matching::RouteTable;
+use crate::{
+    discovered_endpoints, dispatch::Call, metadata_auth, metadata_document, openapi_document, LazEndpoint,
+    MetadataQuery, RpcError, ServerVersion, DEFAULT_METADATA_PATH, DISPATCH_PATH, HEALTH_PATH, INDEX_PATH,
+    OPENAPI_PATH, TYPESCRIPT_PATH, VERSION_PATH,
+};
+
+/// Service configuration mounting laz's own routes describing the API, as set up by
+/// `endpoint`: the index, metadata, OpenAPI and TypeScript documents, health and version
+pub fn configure(endpoint: &LazEndpoint) -> impl FnOnce(&mut web::ServiceConfig) {
+    let api_version = endpoint.api_version.clone();
+    let metadata_path = endpoint.metadata_path.clone().unwrap_or_else(|| DEFAULT_METADATA_PATH.to_string());
+    let token = endpoint.metadata_token.clone();
+    let signing_secret = endpoint.signing_secret.clone();
+    let title = endpoint.title.clone().unwrap_or_else(|| "API".to_string());
+    let dispatch = endpoint.dispatch;
+    let routes = dispatch
+        .then(|| Arc::new(RouteTable::new(laz_types::get_public_function_metadata(), &discovered_endpoints())));
+    move |cfg| {
+        let document_cache = Arc::new(DocumentCache::default());
+        let metadata = {
+            let api_version = api_version.clone();
+            let token = token.clone();
+            move |request: HttpRequest, query: web::Query<MetadataQuery>| {
+                let document = if !is_authorized(&request, token.as_ref()) {
+                    None
+                } else if query.is_unfiltered() {
+                    Some(document_cache.get_or_build(|| {
+                        Document::json(&metadata_document(api_version.as_deref(), &query))
+                    }))
+                } else {
+                    Some(Arc::new(Document::json(&metadata_document(api_version.as_deref(), &query))))
+                };
+                let signing_secret = signing_secret.clone();
+                async move {
+                    let Some(document) = document else {
+                        return unauthorized();
+                    };
+                    let mut response = with_etag(&request, &document);
+                    if let (Some(secret), StatusCode::OK) = (&signing_secret, response.status()) {
+                        let signature = laz_types::signature::sign(secret.0.as_bytes(), &document.body);
+                        response.headers_mut().insert(
+                            HeaderName::from_bytes(laz_types::signature::HEADER.as_bytes()).expect("valid header name"),
+                            HeaderValue::from_str(&signature).expect("signatures are ASCII"),
+                        );
+                    }
+                    response
+                }
+            }
+        };
+
+        let openapi_cache = Arc::new(DocumentCache::default());
+        let openapi_version = api_version.clone().unwrap_or_else(|| "0.0.0".to_string());
+        // Describes the same functions as the metadata, so the same token guards it
+        let openapi = {
+            let token = token.clone();
+            move |request: HttpRequest| {
+                let document = is_authorized(&request, token.as_ref()).then(|| {
+                    openapi_cache.get_or_build(|| Document::json(&openapi_document(&title, &openapi_version)))
+                });
+                async move {
+                    match document {
+                        Some(document) => with_etag(&request, &document),
+                        None => unauthorized(),
+                    }
+                }
+            }
+        };
+
+        let typescript_cache = Arc::new(DocumentCache::default());
+        let typescript = move |request: HttpRequest| {
+            let document = is_authorized(&request, token.as_ref()).then(|| {
+                typescript_cache.get_or_build(|| {
+                    let module = laz_types::typescript::client_module(&discovered_endpoints());
+                    Document::text(module, "application/typescript; charset=utf-8")
+                })
+            });
+            async move {
+                match document {
+                    Some(document) => with_etag(&request, &document),
+                    None => unauthorized(),
+                }
+            }
+        };
+
+        // Left open, so clients can find the metadata and check compatibility before
+        // presenting a token
+        let mut index = serde_json::json!({
+            "metadata": metadata_path,
+            "openapi": OPENAPI_PATH,
+            "typescript": TYPESCRIPT_PATH,
+            "health": HEALTH_PATH,
+            "version": VERSION_PATH,
+        });
+        if dispatch {
+            index["rpc"] = DISPATCH_PATH.into();
+        }
+        let version = ServerVersion {
+            laz_version: env!("CARGO_PKG_VERSION").to_string(),
+            api_version,
+            schema_digest: laz_types::metadata_digest(),
+            total_functions: laz_types::get_public_function_metadata().len(),
+        };
+
+        let index = move || {
+            let index = index.clone();
+            async move { HttpResponse::Ok().json(index) }
+        };
+        let health = || async { HttpResponse::Ok().json(serde_json::json!({ "status": "ok" })) };
+        let version = move || {
+            let version = version.clone();
+            async move { HttpResponse::Ok().json(version) }
+        };
+
+        cfg.route(&metadata_path, web::get().to(metadata))
+            .route(OPENAPI_PATH, web::get().to(openapi))
+            .route(TYPESCRIPT_PATH, web::get().to(typescript))
+            .route(INDEX_PATH, web::get().to(index))
+            .route(HEALTH_PATH, web::get().to(health))
+            .route(VERSION_PATH, web::get().to(version));
+        if let Some(routes) = routes {
+            cfg.app_data(Dispatching(routes));
+        }
+    }
+}
+
+/// App data telling [`dispatch`] the endpoint enabled it, with the routes calls go to
+struct Dispatching(Arc<RouteTable>);
+
+/// Middleware serving `POST /_laz/rpc` like
+/// [`LazEndpoint::with_dispatch`](crate::LazEndpoint::with_dispatch). Calls are checked
+/// against the function's input schema, then rewritten into a request at its route and
+/// passed on to the app, so they run through the same extractors and inner middleware as a
+/// direct request. Wrap the app with it through `actix_web::middleware::from_fn`; it passes
+/// every request on unless the endpoint given to [`configure`] enables dispatch.
+pub async fn dispatch(
+    mut request: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let routes = match request.app_data::<Dispatching>() {
+        Some(Dispatching(routes)) if request.method() == Method::POST && request.path() == DISPATCH_PATH => {
+            routes.clone()
+        }
+        _ => return next.call(request).await.map(ServiceResponse::map_into_boxed_body),
+    };
+    match forward(&mut request, &routes).await {
+        Ok(()) => next.call(request).await.map(ServiceResponse::map_into_boxed_body),
+        Err(error) => Ok(request.into_response(error_response(&error))),
+    }
+}
+
+/// Turn the dispatch request into the call it carries, keeping its other headers
+async fn forward(request: &mut ServiceRequest, routes: &RouteTable) -> Result<(), RpcError> {
+    let body = request
+        .extract::<web::Bytes>()
+        .await
+        .map_err(|e| RpcError::bad_request(format!("Invalid RPC call: {}", e)))?;
+    let call: Call =
+        serde_json::from_slice(&body).map_err(|e| RpcError::bad_request(format!("Invalid RPC call: {}", e)))?;
+    let (parts, body) = crate::dispatch::forwarded_request(routes, call, false)?.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| RpcError::internal(format!("Dispatched call couldn't be read: {}", e)))?;
+
+    // actix speaks another version of the `http` types, so they're converted through text
+    let uri: Uri = parts
+        .uri
+        .to_string()
+        .parse()
+        .map_err(|e| RpcError::bad_request(format!("Invalid RPC call: {}", e)))?;
+    let head = request.head_mut();
+    head.method = Method::from_bytes(parts.method.as_str().as_bytes()).expect("valid method");
+    head.uri = uri.clone();
+    head.headers.remove(header::CONTENT_LENGTH);
+    head.headers.remove(header::CONTENT_TYPE);
+    for (name, value) in &parts.headers {
+        head.headers.insert(
+            HeaderName::from_bytes(name.as_str().as_bytes()).expect("valid header name"),
+            HeaderValue::from_bytes(value.as_bytes()).expect("valid header value"),
+        );
+    }
+    // The router matches the path it was given when the request arrived
+    request.match_info_mut().get_mut().update(&uri);
+    request.set_payload(Payload::from(body));
+    Ok(())
+}
+
+fn is_authorized(request: &HttpRequest, token: Option<&metadata_auth::Token>) -> bool {
+    token.is_none_or(|token| {
+        let authorization = request.headers().get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+        metadata_auth::presents(authorization, &token.0)
+    })
+}
+
+fn unauthorized() -> HttpResponse {
+    let error = RpcError::new(
+        axum::http::StatusCode::UNAUTHORIZED,
+        "unauthorized",
+        "The metadata endpoint requires a token",
+    );
+    let mut response = error_response(&error);
+    response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+fn error_response(error: &RpcError) -> HttpResponse {
+    let status = StatusCode::from_u16(error.status.as_u16()).expect("valid status");
+    HttpResponse::build(status).json(&error.body)
+}
+
+/// `document` tagged with its ETag, or `304 Not Modified` when the request's
+/// `If-None-Match` already names it
+fn with_etag(request: &HttpRequest, document: &Document) -> HttpResponse {
+    let if_none_match =
+        request.headers().get_all(header::IF_NONE_MATCH).filter_map(|value| value.to_str().ok());
+    let not_modified = document.is_named_by(if_none_match);
+    let mut response = if not_modified { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+    response
+        .insert_header((header::ETAG, document.etag()))
+        // Revalidate every time rather than trusting a stale copy
+        .insert_header((header::CACHE_CONTROL, "no-cache"));
+    if not_modified {
+        response.finish()
+    } else {
+        response.content_type(document.content_type()).body(document.body.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    #[tokio::test]
+    async fn test_metadata_and_dispatch_are_served() {
+        let laz = LazEndpoint::new().with_dispatch();
+        // `register` is declared at `PUT /api/auth/register` by the crate's tests
+        let app = test::init_service(
+            App::new()
+                .configure(configure(&laz))
+                .route("/api/auth/register", web::put().to(|| async { "registered" }))
+                .wrap(actix_web::middleware::from_fn(dispatch)),
+        )
+        .await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri(INDEX_PATH).to_request()).await;
+        let index: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(index["rpc"], DISPATCH_PATH);
+
+        let request = test::TestRequest::get().uri(DEFAULT_METADATA_PATH).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+        let document = laz_types::MetadataDocument::from_json(
+            std::str::from_utf8(&test::read_body(response).await).unwrap(),
+        )
+        .unwrap();
+        assert!(document.functions.iter().any(|f| f.function_name == "register"));
+        let request = test::TestRequest::get()
+            .uri(DEFAULT_METADATA_PATH)
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::NOT_MODIFIED);
+
+        let call = |body: &'static str| test::TestRequest::post().uri(DISPATCH_PATH).set_payload(body).to_request();
+        let response = test::call_service(&app, call(r#"{ "function": "register" }"#)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(&test::read_body(response).await[..], b"registered");
+        let response = test::call_service(&app, call(r#"{ "function": "deregister" }"#)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_is_only_served_when_enabled() {
+        let app = test::init_service(
+            App::new()
+                .configure(configure(&LazEndpoint::new()))
+                .route("/api/auth/register", web::put().to(|| async { "registered" }))
+                .wrap(actix_web::middleware::from_fn(dispatch)),
+        )
+        .await;
+        let request = test::TestRequest::post()
+            .uri(DISPATCH_PATH)
+            .set_payload(r#"{ "function": "register" }"#)
+            .to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::NOT_FOUND);
+    }
+}
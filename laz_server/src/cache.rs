@@ -0,0 +1,173 @@
+//! `Cache-Control` headers for queries declared with `cache = "..."`, and the serialized
+//! documents laz serves with ETags letting clients revalidate them.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, PoisonError, RwLock,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{MatchedPath, Request, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::matching::RouteTable;
+
+/// Middleware marking successful responses of cached queries as cacheable for their
+/// declared max age, unless the handler set `Cache-Control` itself
+pub(crate) async fn cache_control(State(routes): State<Arc<RouteTable>>, request: Request, next: Next) -> Response {
+    let max_age = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| routes.find(path.as_str(), request.method().as_str()))
+        .and_then(|function| function.cache_max_age);
+    let mut response = next.run(request).await;
+    if let Some(max_age) = max_age {
+        if response.status().is_success() && !response.headers().contains_key(CACHE_CONTROL) {
+            let value = HeaderValue::from_str(&format!("max-age={}", max_age)).expect("valid header value");
+            response.headers_mut().insert(CACHE_CONTROL, value);
+        }
+    }
+    response
+}
+
+/// A serialized document and its ETag
+pub(crate) struct Document {
+    pub(crate) body: Bytes,
+    content_type: &'static str,
+    etag: HeaderValue,
+}
+
+impl Document {
+    pub(crate) fn json(document: &impl Serialize) -> Self {
+        let document = serde_json::to_value(document).expect("laz documents always serialize");
+        Self::new(
+            serde_json::to_vec(&document).expect("JSON values always serialize"),
+            "application/json",
+            &laz_types::json_fingerprint(&document),
+        )
+    }
+
+    pub(crate) fn text(document: String, content_type: &'static str) -> Self {
+        let fingerprint = laz_types::text_fingerprint(&document);
+        Self::new(document.into_bytes(), content_type, &fingerprint)
+    }
+
+    fn new(body: Vec<u8>, content_type: &'static str, fingerprint: &str) -> Self {
+        Self {
+            body: Bytes::from(body),
+            content_type,
+            etag: HeaderValue::from_str(&format!("\"{}\"", fingerprint)).expect("valid header value"),
+        }
+    }
+
+    pub(crate) fn etag(&self) -> &str {
+        self.etag.to_str().expect("ETags are ASCII")
+    }
+
+    pub(crate) fn content_type(&self) -> &'static str {
+        self.content_type
+    }
+
+    /// Whether one of a request's `If-None-Match` values names this document. Weak and
+    /// strong tags compare alike here, since the document has one representation.
+    pub(crate) fn is_named_by<'a>(&self, if_none_match: impl IntoIterator<Item = &'a str>) -> bool {
+        if_none_match
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == self.etag())
+    }
+}
+
+/// Bumped by [`crate::invalidate_metadata_cache`], so every [`DocumentCache`] rebuilds
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Serialized document built on first use and kept until the next invalidation
+#[derive(Default)]
+pub(crate) struct DocumentCache {
+    current: RwLock<Option<(u64, Arc<Document>)>>,
+}
+
+impl DocumentCache {
+    pub(crate) fn get_or_build(&self, build: impl FnOnce() -> Document) -> Arc<Document> {
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if let Some((built, document)) = &*self.current.read().unwrap_or_else(PoisonError::into_inner) {
+            if *built == generation {
+                return document.clone();
+            }
+        }
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        // Another request may have rebuilt it while this one waited for the lock
+        if let Some((built, document)) = &*current {
+            if *built == generation {
+                return document.clone();
+            }
+        }
+        let document = Arc::new(build());
+        *current = Some((generation, document.clone()));
+        document
+    }
+}
+
+/// `document` tagged with its ETag, or `304 Not Modified` when the request's
+/// `If-None-Match` already names it
+pub(crate) fn with_etag(headers: &HeaderMap, document: &Document) -> Response {
+    let if_none_match = headers.get_all(IF_NONE_MATCH).iter().filter_map(|value| value.to_str().ok());
+    let mut response = if document.is_named_by(if_none_match) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        ([(CONTENT_TYPE, HeaderValue::from_static(document.content_type()))], document.body.clone()).into_response()
+    };
+    let headers = response.headers_mut();
+    headers.insert(ETAG, document.etag.clone());
+    // Revalidate every time rather than trusting a stale copy
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_matching_etags_answer_not_modified() {
+        let document = Document::json(&json!({ "functions": [], "schema_digest": "abc" }));
+        let first = with_etag(&HeaderMap::new(), &document);
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()[ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(&format!("\"other\", W/{}", etag.to_str().unwrap())).unwrap());
+        let revalidated = with_etag(&headers, &document);
+        assert_eq!(revalidated.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(revalidated.headers()[ETAG], etag);
+
+        let changed = with_etag(&headers, &Document::json(&json!({ "functions": [], "schema_digest": "def" })));
+        assert_eq!(changed.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_document_cache_rebuilds_after_invalidation() {
+        let cache = DocumentCache::default();
+        let first = cache.get_or_build(|| Document::json(&json!({ "n": 1 })));
+        let reused = cache.get_or_build(|| unreachable!("cached document was rebuilt"));
+        assert!(Arc::ptr_eq(&first, &reused));
+
+        invalidate();
+        let rebuilt = cache.get_or_build(|| Document::text("n = 2".to_string(), "text/plain"));
+        assert_ne!(rebuilt.etag, first.etag);
+    }
+}
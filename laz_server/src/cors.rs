@@ -0,0 +1,139 @@
+//! Cross-origin access to laz's own `/_laz/*` routes, set with
+//! [`LazEndpoint::with_cors`](crate::LazEndpoint::with_cors).
+//!
+//! The app's routes are left alone; they keep whatever CORS policy the app layers itself.
+
+use std::time::Duration;
+
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use laz_types::HttpMethod;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Origins, headers and methods browsers may use with the laz routes
+///
+/// ```rust,ignore
+/// LazEndpoint::new().with_cors(LazCors::origins(["https://app.example.com"]).allow_header("x-tenant"))
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazCors {
+    /// `None` allows any origin
+    origins: Option<Vec<HeaderValue>>,
+    headers: Vec<HeaderName>,
+    methods: Vec<Method>,
+    max_age: Option<Duration>,
+}
+
+impl LazCors {
+    /// Allow requests from any origin
+    pub fn any_origin() -> Self {
+        Self::with_origins(None)
+    }
+
+    /// Allow requests from `origins`, e.g. `https://app.example.com`
+    ///
+    /// # Panics
+    ///
+    /// If an origin isn't a valid header value.
+    pub fn origins(origins: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let origins = origins
+            .into_iter()
+            .map(|origin| {
+                let origin = origin.as_ref();
+                HeaderValue::from_str(origin).unwrap_or_else(|_| panic!("invalid CORS origin `{}`", origin))
+            })
+            .collect();
+        Self::with_origins(Some(origins))
+    }
+
+    /// Lets browsers send the metadata token and JSON calls, and read the documents
+    fn with_origins(origins: Option<Vec<HeaderValue>>) -> Self {
+        Self {
+            origins,
+            headers: vec![header::AUTHORIZATION, header::CONTENT_TYPE, header::IF_NONE_MATCH],
+            methods: vec![Method::GET, Method::POST],
+            max_age: None,
+        }
+    }
+
+    /// Also allow request header `name`
+    ///
+    /// # Panics
+    ///
+    /// If `name` isn't a valid header name.
+    pub fn allow_header(mut self, name: &str) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).unwrap_or_else(|_| panic!("invalid header name `{}`", name));
+        self.headers.push(name);
+        self
+    }
+
+    /// Also allow `method`
+    pub fn allow_method(mut self, method: HttpMethod) -> Self {
+        self.methods.push(Method::from_bytes(method.as_str().as_bytes()).expect("valid method"));
+        self
+    }
+
+    /// How long browsers may cache the answer to a preflight request
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub(crate) fn layer(&self) -> CorsLayer {
+        let origin = match &self.origins {
+            Some(origins) => AllowOrigin::list(origins.iter().cloned()),
+            None => AllowOrigin::any(),
+        };
+        let layer = CorsLayer::new()
+            .allow_origin(origin)
+            .allow_headers(self.headers.clone())
+            .allow_methods(self.methods.clone())
+            // Lets clients revalidate cached documents and check signed ones
+            .expose_headers([
+                header::ETAG,
+                HeaderName::from_bytes(laz_types::signature::HEADER.as_bytes()).expect("valid header name"),
+            ]);
+        match self.max_age {
+            Some(max_age) => layer.max_age(max_age),
+            None => layer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_preflights_are_answered_for_allowed_origins() {
+        let cors = LazCors::origins(["https://app.example.com"]).allow_header("x-tenant");
+        let app = Router::new().route(crate::INDEX_PATH, get(|| async { "{}" })).layer(cors.layer());
+        let preflight = |origin: &str| {
+            Request::options(crate::INDEX_PATH)
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "x-tenant")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(preflight("https://app.example.com")).await.unwrap();
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+        assert!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap().contains("x-tenant"));
+
+        let response = app.oneshot(preflight("https://evil.example.com")).await.unwrap();
+        assert!(!response.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn test_etag_and_signature_are_exposed() {
+        let app = Router::new().route(crate::INDEX_PATH, get(|| async { "{}" })).layer(LazCors::any_origin().layer());
+        let request = Request::get(crate::INDEX_PATH).header(header::ORIGIN, "https://app.example.com");
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let exposed = response.headers()[header::ACCESS_CONTROL_EXPOSE_HEADERS].to_str().unwrap().to_lowercase();
+        assert!(exposed.contains("etag"), "{}", exposed);
+        assert!(exposed.contains(&laz_types::signature::HEADER.to_lowercase()), "{}", exposed);
+    }
+}
@@ -0,0 +1,208 @@
+//! Single-endpoint dispatch: `POST /_laz/rpc` with `{ "function": "login", "params": {..} }`.
+//!
+//! Calls are checked against the function's input schema, then replayed against the app's
+//! own router at the function's route, so they run through the same extractors and
+//! middleware as a direct request. Clients no longer have to work out which URI serves a
+//! function.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        Extensions, HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Router,
+};
+use laz_types::BodyEncoding;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tower::ServiceExt;
+
+use crate::matching::RouteTable;
+use crate::{validation, RpcError};
+
+/// Largest dispatched call read, matching axum's default body limit
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Body of a dispatched call
+#[derive(Debug, Deserialize)]
+pub(crate) struct Call {
+    pub(crate) function: String,
+    #[serde(default)]
+    params: Option<Value>,
+    /// Values of the route's path parameters, looked up in `params` when left out
+    #[serde(default)]
+    path: Option<Map<String, Value>>,
+}
+
+/// The app's routes and the router serving them
+pub(crate) struct Dispatcher {
+    pub(crate) routes: RouteTable,
+    pub(crate) app: Router,
+}
+
+impl Dispatcher {
+    /// Run `call` at its function's route, with the headers and extensions of the request
+    /// carrying it. Subscriptions are refused unless `subscriptions` is set.
+    pub(crate) async fn run(
+        &self,
+        call: Call,
+        headers: &HeaderMap,
+        extensions: Extensions,
+        subscriptions: bool,
+    ) -> Result<Response, RpcError> {
+        let forwarded = forwarded_request(&self.routes, call, subscriptions)?;
+        let (mut forwarded_parts, forwarded_body) = forwarded.into_parts();
+        // Credentials and connection info apply to the call as much as to its carrier
+        let mut forwarded_headers = headers.clone();
+        forwarded_headers.remove(CONTENT_LENGTH);
+        forwarded_headers.remove(CONTENT_TYPE);
+        forwarded_headers.extend(forwarded_parts.headers.drain());
+        forwarded_parts.headers = forwarded_headers;
+        forwarded_parts.extensions = extensions;
+        // The carrier's matched route would be prefixed to the function's, hiding the call
+        // from the layers that find functions by route
+        forwarded_parts.extensions.remove::<MatchedPath>();
+        let forwarded = Request::from_parts(forwarded_parts, forwarded_body);
+
+        match self.app.clone().oneshot(forwarded).await {
+            Ok(response) => Ok(response),
+            Err(infallible) => match infallible {},
+        }
+    }
+}
+
+/// Handler of the dispatch endpoint
+pub(crate) async fn dispatch(State(dispatcher): State<Arc<Dispatcher>>, request: Request) -> Response {
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return RpcError::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", "Request body is too large")
+                .into_response();
+        }
+    };
+    let call: Call = match serde_json::from_slice(&bytes) {
+        Ok(call) => call,
+        Err(e) => return RpcError::bad_request(format!("Invalid RPC call: {}", e)).into_response(),
+    };
+    dispatcher
+        .run(call, &parts.headers, parts.extensions, false)
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+}
+
+/// Request for `call` at its function's route
+pub(crate) fn forwarded_request(routes: &RouteTable, call: Call, subscriptions: bool) -> Result<Request, RpcError> {
+    let Some((path, method, function)) = routes.route_of(&call.function) else {
+        return Err(RpcError::not_found(format!("Unknown RPC function `{}`", call.function)));
+    };
+    if (function.is_subscription && !subscriptions) || function.is_websocket || function.is_multipart || function.stream_body {
+        return Err(RpcError::bad_request(format!("`{}` can't be called through dispatch", call.function)));
+    }
+    if let (Some(params), Some(schema)) = (
+        &call.params,
+        laz_types::find_function_schemas(&function.function_name)
+            .and_then(|schemas| schemas.request.as_ref().or(schemas.query.as_ref())),
+    ) {
+        validation::check_value(params, schema)?;
+    }
+
+    let path_values = call.path.as_ref().or(call.params.as_ref().and_then(Value::as_object));
+    let mut uri = fill_path(path, path_values)?;
+    let builder = Request::builder().method(Method::from_bytes(method.as_str().as_bytes()).expect("valid method"));
+    let params = call.params.filter(|params| !params.is_null());
+    let request = match params {
+        Some(params) if function.input_in_query() => {
+            let query = urlencoded(&params)?;
+            if !query.is_empty() {
+                uri = format!("{}?{}", uri, query);
+            }
+            builder.uri(uri).body(Body::empty())
+        }
+        Some(params) if function.body_encoding == BodyEncoding::Form => builder
+            .uri(uri)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/x-www-form-urlencoded"))
+            .body(Body::from(urlencoded(&params)?)),
+        Some(params) => builder
+            .uri(uri)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(Body::from(params.to_string())),
+        None => builder.uri(uri).body(Body::empty()),
+    };
+    request.map_err(|e| RpcError::bad_request(format!("Invalid RPC call: {}", e)))
+}
+
+/// Route template `path` with its `{name}` segments replaced by `values`
+fn fill_path(path: &str, values: Option<&Map<String, Value>>) -> Result<String, RpcError> {
+    let segments: Result<Vec<String>, RpcError> = path
+        .split('/')
+        .map(|segment| {
+            let Some(name) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+                return Ok(segment.to_string());
+            };
+            let name = name.trim_start_matches('*');
+            match values.and_then(|values| values.get(name)) {
+                Some(Value::String(value)) => Ok(encode_segment(value)),
+                Some(value @ (Value::Number(_) | Value::Bool(_))) => Ok(value.to_string()),
+                _ => Err(RpcError::bad_request(format!("Missing path parameter `{}`", name))),
+            }
+        })
+        .collect();
+    Ok(segments?.join("/"))
+}
+
+/// Percent-encode everything but unreserved characters, so values can't add segments
+fn encode_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn urlencoded(params: &Value) -> Result<String, RpcError> {
+    serde_urlencoded::to_string(params)
+        .map_err(|e| RpcError::bad_request(format!("Params can't be sent as a query string or form: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_path_parameters_are_filled_from_params() {
+        let params = json!({ "org": "acme/north", "id": 7, "name": "Oak" });
+        assert_eq!(
+            fill_path("/orgs/{org}/trees/{id}", params.as_object()).unwrap(),
+            "/orgs/acme%2Fnorth/trees/7"
+        );
+        assert!(fill_path("/trees/{id}", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_calls_are_replayed_at_the_function_route() {
+        // `register` is declared at `PUT /api/auth/register` by the crate's tests
+        let register = laz_types::find_function_metadata("register").unwrap();
+        let dispatcher = Arc::new(Dispatcher {
+            routes: RouteTable::new([&*register], &[]),
+            app: Router::new().route("/api/auth/register", axum::routing::put(|| async { "registered" })),
+        });
+        let call = |body: &'static str| Request::post(crate::DISPATCH_PATH).body(Body::from(body)).unwrap();
+
+        let response = dispatch(State(dispatcher.clone()), call(r#"{ "function": "register" }"#)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"registered");
+
+        let response = dispatch(State(dispatcher), call(r#"{ "function": "deregister" }"#)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
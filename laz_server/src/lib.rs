@@ -2,27 +2,263 @@
 //! 
 //! This crate provides server-side RPC functionality with automatic type generation
 //! and seamless integration with Loco.rs applications.
+//!
+//! # Route matching
+//!
+//! Validation, rate limits, middleware, tracing, metrics and dispatch find the function a
+//! request calls by the route template it hit and its method. A function is served at its
+//! declared `path`, else at the route in the endpoint discovery data whose URI names its
+//! handler, with its declared method or one that route accepts. Register the discovery
+//! data with [`register_endpoints_discovery`] before mounting the endpoint; functions
+//! neither declaring a path nor named by a route are left alone.
+
+// Lets the RPC macros refer to `::laz_server` from this crate's own tests
+extern crate self as laz_server;
 
 use async_trait::async_trait;
-use axum::{routing::get, Json};
+use axum::{
+    handler::Handler,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    extract::Query,
+    routing::{get, MethodFilter, MethodRouter},
+    Json,
+};
 use loco_rs::{
     app::{AppContext, Initializer},
     Result,
 };
 use serde_json::Value;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(feature = "actix")]
+pub mod actix;
+mod cache;
+mod cors;
+mod dispatch;
+mod matching;
+mod metadata_auth;
+mod middleware;
+mod metrics;
+mod rate_limit;
+mod router;
+mod socket;
+mod spans;
+pub mod topics;
+mod validation;
+mod verify;
 
 pub use laz_types::*;
+pub use cors::LazCors;
+pub use metrics::LazMetrics;
+pub use middleware::LazMiddleware;
+pub use router::LazRouterExt;
+pub use verify::{verify_routes, RouteReport};
+/// Used by handlers declared with `trace`
+pub use tracing;
 
 /// Re-export the server macros and LazSchema derive
-pub use laz_server_macros::{rpc_query, rpc_mutation};
+pub use laz_server_macros::{rpc_query, rpc_mutation, rpc_subscription, rpc_ws, rpc_impl};
 pub use laz_schema_derive::LazSchema;
 
 /// Global registry for endpoint discovery
 static ENDPOINTS_DISCOVERY: OnceLock<Vec<(String, Vec<String>)>> = OnceLock::new();
 
-/// Initializer that exposes RPC metadata via HTTP endpoint
-pub struct LazEndpoint;
+/// Where the metadata document is served unless [`LazEndpoint::with_metadata_path`] moves it
+pub const DEFAULT_METADATA_PATH: &str = "/_laz/metadata";
+
+/// Index document advertising where the other laz routes are, e.g.
+/// `{ "metadata": "/_laz/metadata", "openapi": "/_laz/openapi.json", .. }`
+pub const INDEX_PATH: &str = "/_laz";
+
+/// Answers `{ "status": "ok" }` once the app is serving requests
+pub const HEALTH_PATH: &str = "/_laz/health";
+
+/// Reports the server's [`ServerVersion`]
+pub const VERSION_PATH: &str = "/_laz/version";
+
+/// Serves the [`openapi_document`], for Swagger UI and API gateways
+pub const OPENAPI_PATH: &str = "/_laz/openapi.json";
+
+/// Serves TypeScript declarations for the registered types and a fetch-based client, see
+/// [`laz_types::typescript::client_module`]
+pub const TYPESCRIPT_PATH: &str = "/_laz/types.ts";
+
+/// Serves the API explorer enabled by [`LazEndpoint::with_playground`]
+pub const PLAYGROUND_PATH: &str = "/_laz/playground";
+
+/// Accepts calls to any RPC function by name when [`LazEndpoint::with_dispatch`] is set
+pub const DISPATCH_PATH: &str = "/_laz/rpc";
+
+/// WebSocket carrying calls and subscriptions when [`LazEndpoint::with_ws_transport`] is set
+pub const WS_PATH: &str = "/_laz/ws";
+
+/// Server-sent events of the topics declared by `rpc_subscription(topic = "...")`
+pub const TOPICS_PATH: &str = "/_laz/topics/{topic}";
+
+/// Serves the call metrics recorded by [`LazMetrics`] in the Prometheus text format
+pub const METRICS_PATH: &str = "/_laz/metrics";
+
+/// Single-page explorer driven by the metadata document
+const PLAYGROUND_HTML: &str = include_str!("playground.html");
+
+/// Initializer that exposes RPC metadata via HTTP endpoint. Apps without Loco mount it
+/// with [`LazRouterExt::with_laz`].
+#[derive(Debug, Clone, Default)]
+pub struct LazEndpoint {
+    api_version: Option<String>,
+    rate_limiting: bool,
+    validation: bool,
+    tracing: bool,
+    middleware: middleware::MiddlewareChain,
+    playground: bool,
+    dispatch: bool,
+    ws_transport: bool,
+    metadata_token: Option<metadata_auth::Token>,
+    signing_secret: Option<metadata_auth::Token>,
+    metadata_path: Option<String>,
+    title: Option<String>,
+    cors: Option<LazCors>,
+    snapshot_path: Option<std::path::PathBuf>,
+}
+
+impl LazEndpoint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare the semantic version of the API, reported as `api_version` in the metadata
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = Some(version.into());
+        self
+    }
+
+    /// Title of the OpenAPI document served at [`OPENAPI_PATH`], `API` unless set
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Serve an explorer at [`PLAYGROUND_PATH`] listing every RPC function with its schemas
+    /// and a form to call it. It reads the same metadata as clients, asking for the token
+    /// when [`with_auth`](Self::with_auth) is set.
+    pub fn with_playground(mut self) -> Self {
+        self.playground = true;
+        self
+    }
+
+    /// Accept `POST /_laz/rpc` with `{ "function": "login", "params": { .. } }`, calling the
+    /// named function at its own route after checking `params` against its input schema.
+    /// Path parameters are taken from an optional `path` object, else from `params`. The
+    /// route is found by [route matching](crate#route-matching).
+    pub fn with_dispatch(mut self) -> Self {
+        self.dispatch = true;
+        self
+    }
+
+    /// Serve a WebSocket at [`WS_PATH`] multiplexing calls and subscriptions over one
+    /// connection, dispatched like [`with_dispatch`](Self::with_dispatch) calls. Frames are
+    /// JSON objects tagged with a `type`: clients send `call`, `subscribe` and `unsubscribe`,
+    /// and the server answers with `result`, `error`, `event` and `complete`.
+    pub fn with_ws_transport(mut self) -> Self {
+        self.ws_transport = true;
+        self
+    }
+
+    /// Enforce the `rate_limit` declared by RPC functions, answering requests over budget
+    /// with `429 Too Many Requests`. Requests are matched to functions by
+    /// [route matching](crate#route-matching).
+    pub fn with_rate_limiting(mut self) -> Self {
+        self.rate_limiting = true;
+        self
+    }
+
+    /// Check JSON request bodies and query strings against the input schema of their
+    /// function before the handler runs, answering mismatches with `422 Unprocessable
+    /// Entity` and the path of every offending value. See [route matching](crate#route-matching).
+    pub fn with_validation(mut self) -> Self {
+        self.validation = true;
+        self
+    }
+
+    /// Run `middleware` around every call of an RPC function, after the middleware added
+    /// before it. Its `before_call` sees the call's params as JSON and may change or reject
+    /// them, and its `after_call` sees the handler's result. Dispatched calls go through it
+    /// too. See [route matching](crate#route-matching).
+    pub fn with_middleware(mut self, middleware: impl LazMiddleware) -> Self {
+        self.middleware.0.push(Arc::new(middleware));
+        self
+    }
+
+    /// Run every call of an RPC function inside an `rpc` span carrying `rpc.function`,
+    /// `rpc.is_mutation` and `rpc.schema_fingerprint`, recorded by whatever `tracing`
+    /// subscriber the app installed. See [route matching](crate#route-matching).
+    pub fn with_tracing(mut self) -> Self {
+        self.tracing = true;
+        self
+    }
+
+    /// Write the metadata document to `path` once the routes are mounted, like
+    /// [`write_metadata_snapshot`] but with this endpoint's API version. Failing to write it
+    /// fails startup.
+    pub fn with_metadata_snapshot(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.snapshot_path = Some(path.into());
+        self
+    }
+
+    /// Let browsers on other origins use the laz routes, such as the metadata, playground
+    /// and dispatch endpoints. The app's own routes are left alone.
+    pub fn with_cors(mut self, cors: LazCors) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// Require `Authorization: Bearer <token>` on `/_laz/metadata`, answering other requests
+    /// with `401 Unauthorized`. Clients and client codegen send the token set in
+    /// `LAZ_METADATA_TOKEN`.
+    pub fn with_auth(mut self, token: impl Into<String>) -> Self {
+        self.metadata_token = Some(metadata_auth::Token(token.into().into()));
+        self
+    }
+
+    /// Sign the metadata document with HMAC-SHA256 under `secret`, sent in the
+    /// [`signature::HEADER`] header. Clients and client codegen holding the secret in
+    /// `LAZ_METADATA_SECRET` refuse documents whose signature is missing or wrong.
+    pub fn with_signing_secret(mut self, secret: impl Into<String>) -> Self {
+        self.signing_secret = Some(metadata_auth::Token(secret.into().into()));
+        self
+    }
+
+    /// Serve the metadata document at `path` instead of [`DEFAULT_METADATA_PATH`]. Clients
+    /// find it through the index document at [`INDEX_PATH`].
+    ///
+    /// # Panics
+    ///
+    /// If `path` doesn't start with `/` or is the index path itself.
+    pub fn with_metadata_path(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        assert!(path.starts_with('/'), "metadata path `{}` must start with `/`", path);
+        assert!(path != INDEX_PATH, "metadata path can't be the index path `{}`", INDEX_PATH);
+        assert!(
+            ![
+                HEALTH_PATH,
+                VERSION_PATH,
+                OPENAPI_PATH,
+                TYPESCRIPT_PATH,
+                PLAYGROUND_PATH,
+                DISPATCH_PATH,
+                WS_PATH,
+                METRICS_PATH,
+            ]
+            .contains(&path.as_str())
+                && !path.starts_with("/_laz/topics/"),
+            "metadata path `{}` is reserved by laz",
+            path
+        );
+        self.metadata_path = Some(path);
+        self
+    }
+}
 
 #[async_trait]
 impl Initializer for LazEndpoint {
@@ -32,58 +268,276 @@ impl Initializer for LazEndpoint {
 
     /// Mounts the RPC metadata endpoint AFTER all routes are registered
     async fn after_routes(&self, router: axum::routing::Router, _ctx: &AppContext) -> Result<axum::routing::Router> {
+        if let Some(path) = &self.snapshot_path {
+            write_snapshot(path, self.api_version.as_deref())?;
+        }
+        Ok(router.with_laz(self))
+    }
+}
+
+impl LazEndpoint {
+    /// Layer the app's routes with the enabled RPC checks and add the dispatch routes
+    pub(crate) fn layer_rpc(&self, router: axum::Router) -> axum::Router {
+        // `internal` only hides functions from clients; they're checked like any other
+        let functions = laz_types::get_all_function_metadata();
+        // Closest to the handlers, so middleware only sees requests the other layers let through
+        let router = if self.middleware.0.is_empty() {
+            router
+        } else {
+            let interceptor = middleware::Interceptor {
+                routes: matching::RouteTable::new(functions.iter().copied(), &discovered_endpoints()),
+                chain: self.middleware.clone(),
+            };
+            router.layer(axum::middleware::from_fn_with_state(Arc::new(interceptor), middleware::intercept))
+        };
+        // Layered first, so requests over their rate limit aren't read for validation
+        let router = if self.validation {
+            let validated = matching::RouteTable::new(
+                functions
+                    .iter()
+                    .copied()
+                    .filter(|f| {
+                        laz_types::find_function_schemas(&f.function_name)
+                            .is_some_and(|s| s.request.is_some() || s.query.is_some())
+                    }),
+                &discovered_endpoints(),
+            );
+            router.layer(axum::middleware::from_fn_with_state(Arc::new(validated), validation::validate_request))
+        } else {
+            router
+        };
+        let router = if self.rate_limiting {
+            let limiter = rate_limit::RateLimiter::new(&functions, &discovered_endpoints());
+            router.layer(axum::middleware::from_fn_with_state(Arc::new(limiter), rate_limit::enforce))
+        } else {
+            router
+        };
+        // Queries declared with `cache` tell HTTP caches how long their responses stay fresh
+        let cached = matching::RouteTable::new(
+            functions.iter().copied().filter(|f| f.cache_max_age.is_some()),
+            &discovered_endpoints(),
+        );
+        let router = if cached.is_empty() {
+            router
+        } else {
+            router.layer(axum::middleware::from_fn_with_state(Arc::new(cached), cache::cache_control))
+        };
+        // Layered last, so the span covers requests rejected by the other layers too
+        let router = if self.tracing {
+            let tracer = spans::Tracer::new(&functions, &discovered_endpoints());
+            router.layer(axum::middleware::from_fn_with_state(Arc::new(tracer), spans::trace))
+        } else {
+            router
+        };
+        if !self.dispatch && !self.ws_transport {
+            return router;
+        }
+        // Calls are replayed against the app's routes as layered so far, never laz's own
+        let dispatcher = dispatch::Dispatcher {
+            routes: matching::RouteTable::new(laz_types::get_public_function_metadata(), &discovered_endpoints()),
+            app: router.clone(),
+        };
+        let mut dispatch_router = axum::Router::new();
+        if self.dispatch {
+            dispatch_router = dispatch_router.route(DISPATCH_PATH, axum::routing::post(dispatch::dispatch));
+        }
+        if self.ws_transport {
+            dispatch_router = dispatch_router.route(WS_PATH, get(socket::upgrade));
+        }
+        let dispatch_router = dispatch_router.with_state(Arc::new(dispatcher));
+        let dispatch_router = match &self.cors {
+            Some(cors) => dispatch_router.layer(cors.layer()),
+            None => dispatch_router,
+        };
+        router.merge(dispatch_router)
+    }
+
+    /// laz's own routes describing the API: the index, metadata, OpenAPI and TypeScript
+    /// documents, health, version, topics and the playground
+    pub(crate) fn metadata_router(&self) -> axum::Router {
+        let functions = laz_types::get_public_function_metadata();
+        for conflict in laz_types::function_conflicts(functions.iter().copied()) {
+            tracing::error!(
+                "RPC function {} is registered by several handlers ({}); clients can't call it until \
+                 they have distinct names or namespaces",
+                conflict.function_name,
+                conflict.handlers.join(", ")
+            );
+        }
+        let api_version = self.api_version.clone();
+        let metadata_path = self.metadata_path.as_deref().unwrap_or(DEFAULT_METADATA_PATH);
+        // Built once and served as bytes; rebuilding a large document per request shows up under load
+        let document_cache = Arc::new(cache::DocumentCache::default());
+        let signing_secret = self.signing_secret.clone();
         let meta_router = axum::Router::new().route(
-            "/_laz/metadata",
-            get(|| async move {
-                let metadata = laz_types::get_all_function_metadata();
-                let functions: Vec<Value> = metadata
-                    .into_iter()
-                    .map(|m| {
-                        let input_schema_json = m
-                            .input_type_name
-                            .as_ref()
-                            .and_then(|name| laz_types::find_type_schema(name))
-                            .and_then(|schema| serde_json::to_string(schema).ok());
-                        let output_schema_json = laz_types::find_type_schema(&m.output_type_name)
-                            .and_then(|schema| serde_json::to_string(schema).ok());
-
-                        serde_json::json!({
-                            "function_name": m.function_name,
-                            "is_mutation": m.is_mutation,
-                            "is_async": m.is_async,
-                            "input_type_name": m.input_type_name,
-                            "output_type_name": m.output_type_name,
-                            "params": m.params,
-                            "input_schema_json": input_schema_json,
-                            "output_schema_json": output_schema_json,
-                        })
-                    })
-                    .collect();
-
-                let endpoints_discovery = get_endpoints_discovery()
-                    .map(|endpoints| {
-                        endpoints.iter().map(|(uri, actions)| {
-                            serde_json::json!({
-                                "uri": uri,
-                                "methods": actions
-                            })
-                        }).collect::<Vec<_>>()
-                    })
-                    .unwrap_or_default();
-
-                Json(serde_json::json!({
-                    "total_functions": functions.len(),
-                    "functions": functions,
-                    "endpoints_discovery": endpoints_discovery,
-                    "total_endpoints": endpoints_discovery.len()
-                }))
+            metadata_path,
+            get(|Query(query): Query<MetadataQuery>, headers: axum::http::HeaderMap| async move {
+                // Filtered views are cheap to build and too many to keep
+                let document = if query.is_unfiltered() {
+                    document_cache.get_or_build(|| cache::Document::json(&metadata_document(api_version.as_deref(), &query)))
+                } else {
+                    Arc::new(cache::Document::json(&metadata_document(api_version.as_deref(), &query)))
+                };
+                let mut response = cache::with_etag(&headers, &document);
+                if let (Some(secret), StatusCode::OK) = (&signing_secret, response.status()) {
+                    let signature = laz_types::signature::sign(secret.0.as_bytes(), &document.body);
+                    response.headers_mut().insert(
+                        laz_types::signature::HEADER,
+                        axum::http::HeaderValue::from_str(&signature).expect("signatures are ASCII"),
+                    );
+                }
+                response
             }),
         );
+        let title = self.title.clone().unwrap_or_else(|| "API".to_string());
+        let openapi_version = self.api_version.clone().unwrap_or_else(|| "0.0.0".to_string());
+        let openapi_cache = Arc::new(cache::DocumentCache::default());
+        // Describes the same functions as the metadata, so the same token guards it
+        let meta_router = meta_router.route(
+            OPENAPI_PATH,
+            get(|headers: axum::http::HeaderMap| async move {
+                let document =
+                    openapi_cache.get_or_build(|| cache::Document::json(&openapi_document(&title, &openapi_version)));
+                cache::with_etag(&headers, &document)
+            }),
+        );
+        let typescript_cache = Arc::new(cache::DocumentCache::default());
+        let meta_router = meta_router.route(
+            TYPESCRIPT_PATH,
+            get(|headers: axum::http::HeaderMap| async move {
+                let document = typescript_cache.get_or_build(|| {
+                    let module = laz_types::typescript::client_module(&discovered_endpoints());
+                    cache::Document::text(module, "application/typescript; charset=utf-8")
+                });
+                cache::with_etag(&headers, &document)
+            }),
+        );
+
+        let has_topics = functions.iter().any(|f| f.topic.is_some());
+        let meta_router = if has_topics {
+            meta_router.route(TOPICS_PATH, get(topics::serve))
+        } else {
+            meta_router
+        };
+
+        let meta_router = match &self.metadata_token {
+            Some(token) => meta_router.layer(axum::middleware::from_fn_with_state(token.clone(), metadata_auth::require_token)),
+            None => meta_router,
+        };
+        // Left open, so clients can find the metadata and check compatibility before
+        // presenting a token
+        let mut index = serde_json::json!({
+            "metadata": metadata_path,
+            "openapi": OPENAPI_PATH,
+            "typescript": TYPESCRIPT_PATH,
+            "health": HEALTH_PATH,
+            "version": VERSION_PATH,
+        });
+        if self.playground {
+            index["playground"] = PLAYGROUND_PATH.into();
+        }
+        if self.dispatch {
+            index["rpc"] = DISPATCH_PATH.into();
+        }
+        if self.ws_transport {
+            index["ws"] = WS_PATH.into();
+        }
+        if has_topics {
+            index["topics"] = TOPICS_PATH.into();
+        }
+        let version = ServerVersion {
+            laz_version: env!("CARGO_PKG_VERSION").to_string(),
+            api_version: self.api_version.clone(),
+            schema_digest: laz_types::metadata_digest(),
+            total_functions: laz_types::get_public_function_metadata().len(),
+        };
+        let meta_router = meta_router
+            .route(INDEX_PATH, get(|| async move { Json(index) }))
+            .route(HEALTH_PATH, get(|| async { Json(serde_json::json!({ "status": "ok" })) }))
+            .route(VERSION_PATH, get(|| async move { Json(version) }));
+        // A static page; the metadata it loads is what the token guards
+        let meta_router = if self.playground {
+            meta_router.route(PLAYGROUND_PATH, get(|| async { axum::response::Html(PLAYGROUND_HTML) }))
+        } else {
+            meta_router
+        };
+
+        // Outermost, so preflight requests are answered before the token is asked for
+        match &self.cors {
+            Some(cors) => meta_router.layer(cors.layer()),
+            None => meta_router,
+        }
+    }
+}
+
+/// Query parameters narrowing the metadata document, e.g. `?namespace=auth&limit=20`
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct MetadataQuery {
+    function: Option<String>,
+    namespace: Option<String>,
+    mutations_only: bool,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl MetadataQuery {
+    fn is_unfiltered(&self) -> bool {
+        self.function.is_none() && self.namespace.is_none() && !self.mutations_only && self.offset == 0 && self.limit.is_none()
+    }
+
+    fn matches(&self, function: &FunctionMetadata) -> bool {
+        self.function.as_ref().is_none_or(|name| *name == function.function_name)
+            && self.namespace.as_ref().is_none_or(|namespace| function.namespace.as_ref() == Some(namespace))
+            && (!self.mutations_only || function.is_mutation)
+    }
+}
+
+/// The metadata document served by [`LazEndpoint`]. `total_functions` counts every function
+/// matching `query`, of which `functions` holds the requested page, and `types` only what
+/// that page refers to.
+fn metadata_document(api_version: Option<&str>, query: &MetadataQuery) -> MetadataDocument {
+    let mut metadata = laz_types::get_public_function_metadata();
+    metadata.sort_by(|a, b| a.function_name.cmp(&b.function_name));
+    let conflicts = laz_types::function_conflicts(metadata.iter().copied());
+    metadata.retain(|m| query.matches(m));
+    let total_functions = metadata.len();
+    let page: Vec<&FunctionMetadata> =
+        metadata.into_iter().skip(query.offset).take(query.limit.unwrap_or(usize::MAX)).collect();
+    let types = laz_types::type_table_for(page.iter().copied())
+        .into_iter()
+        .map(|(name, schema)| (name, schema.clone()))
+        .collect();
+    let endpoints = discovered_endpoints();
+    let functions = page.into_iter().map(|m| FunctionDocument::new(m, &endpoints)).collect();
 
-        Ok(router.merge(meta_router))
+    MetadataDocument {
+        schema_version: laz_types::METADATA_SCHEMA_VERSION,
+        total_functions,
+        functions,
+        total_endpoints: endpoints.len(),
+        endpoints_discovery: endpoints,
+        types,
+        schema_digest: Some(laz_types::metadata_digest()),
+        api_version: api_version.map(String::from),
+        conflicts,
     }
 }
 
+/// Write the metadata document served at `/_laz/metadata` to `path`, byte for byte, so
+/// client codegen can run from the file through `LAZ_METADATA_SNAPSHOT` instead of asking a
+/// live server. Register the endpoint discovery data first, so routes are resolved.
+pub fn write_metadata_snapshot(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    write_snapshot(path.as_ref(), None)
+}
+
+fn write_snapshot(path: &std::path::Path, api_version: Option<&str>) -> std::io::Result<()> {
+    let document = cache::Document::json(&metadata_document(api_version, &MetadataQuery::default()));
+    std::fs::write(path, &document.body)?;
+    tracing::info!("Wrote RPC metadata snapshot to {}", path.display());
+    Ok(())
+}
+
 /// Get the endpoints discovery data for RPC metadata
 pub fn get_endpoints_discovery() -> Option<&'static Vec<(String, Vec<String>)>> {
     ENDPOINTS_DISCOVERY.get()
@@ -92,6 +546,152 @@ pub fn get_endpoints_discovery() -> Option<&'static Vec<(String, Vec<String>)>>
 /// Register endpoint discovery data
 pub fn register_endpoints_discovery(endpoints: Vec<(String, Vec<String>)>) {
     let _ = ENDPOINTS_DISCOVERY.set(endpoints);
+    invalidate_metadata_cache();
+}
+
+/// Drop every cached metadata document, so the next request rebuilds it from the registry
+pub fn invalidate_metadata_cache() {
+    cache::invalidate();
+}
+
+/// OpenAPI 3.1 document for all registered RPC functions, using the registered
+/// endpoint discovery data for paths and methods
+pub fn openapi_document(title: &str, version: &str) -> Value {
+    laz_types::openapi::openapi_document(title, version, &discovered_endpoints())
+}
+
+fn discovered_endpoints() -> Vec<EndpointDiscovery> {
+    get_endpoints_discovery()
+        .map(|endpoints| {
+            endpoints
+                .iter()
+                .map(|(uri, methods)| EndpointDiscovery {
+                    uri: uri.clone(),
+                    methods: methods.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Failure of an RPC handler, sent to the client as a JSON [`RpcErrorBody`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub status: StatusCode,
+    pub body: RpcErrorBody,
+}
+
+impl RpcError {
+    pub fn new(status: StatusCode, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: RpcErrorBody::new(code, message),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request", message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    /// Failure carrying a function's declared application error (`error = ...`),
+    /// which typed clients decode from the body's `details`
+    pub fn application<E: serde::Serialize + std::fmt::Display>(error: &E) -> Self {
+        let details = serde_json::to_value(error).unwrap_or(Value::Null);
+        Self::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            RpcErrorBody::APPLICATION_ERROR,
+            error.to_string(),
+        )
+        .with_details(details)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.body = self.body.with_details(details);
+        self
+    }
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.body.fmt(f)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl IntoResponse for RpcError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body)).into_response()
+    }
+}
+
+impl From<LazError> for RpcError {
+    fn from(error: LazError) -> Self {
+        let status = match error {
+            LazError::FunctionNotFound(_) => StatusCode::NOT_FOUND,
+            LazError::InvalidParameter(_) | LazError::JsonError(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            body: RpcErrorBody::from(&error),
+        }
+    }
+}
+
+/// Route of an RPC function declared with `route`, registered by the RPC macros
+pub struct RouteEntry {
+    pub path: &'static str,
+    pub router: fn() -> MethodRouter<AppContext>,
+}
+
+inventory::collect!(RouteEntry);
+
+/// Serve `handler` for `method`; used by the routes the RPC macros register
+pub fn method_router<H, T>(method: HttpMethod, handler: H) -> MethodRouter<AppContext>
+where
+    H: Handler<T, AppContext>,
+    T: 'static,
+{
+    let filter = match method {
+        HttpMethod::Get => MethodFilter::GET,
+        HttpMethod::Post => MethodFilter::POST,
+        HttpMethod::Put => MethodFilter::PUT,
+        HttpMethod::Patch => MethodFilter::PATCH,
+        HttpMethod::Delete => MethodFilter::DELETE,
+    };
+    axum::routing::on(filter, handler)
+}
+
+/// Router serving every RPC function declared with `route`, at its `path` and method.
+///
+/// Merge it into the app from an initializer's `after_routes`:
+/// `router.merge(laz_server::routes().with_state(ctx.clone()))`.
+pub fn routes() -> axum::Router<AppContext> {
+    let mut by_path: std::collections::BTreeMap<&str, MethodRouter<AppContext>> = Default::default();
+    for entry in inventory::iter::<RouteEntry> {
+        let router = (entry.router)();
+        let router = match by_path.remove(entry.path) {
+            Some(existing) => existing.merge(router),
+            None => router,
+        };
+        by_path.insert(entry.path, router);
+    }
+    by_path
+        .into_iter()
+        .fold(axum::Router::new(), |app, (path, router)| app.route(path, router))
 }
 
 /// Helper to collect route information from Loco.rs AppRoutes
@@ -112,9 +712,10 @@ pub fn collect_routes(app_routes: &loco_rs::controller::AppRoutes) -> Vec<(Strin
 /// Re-export commonly used items
 pub mod prelude {
     pub use crate::{
-        LazEndpoint, LazError, ServerAddr, FunctionMetadata, TypeSchema,
-        get_all_function_metadata, get_all_type_schemas, find_type_schema,
-        rpc_query, rpc_mutation, LazSchema,
+        routes, LazEndpoint, LazError, RpcError, RpcErrorBody, ServerAddr, FunctionMetadata, TypeSchema,
+        get_all_function_metadata, get_all_type_schemas, get_type_schema_map, find_type_schema,
+        find_function_metadata, iter_function_metadata,
+        rpc_query, rpc_mutation, rpc_subscription, rpc_ws, rpc_impl, LazSchema,
     };
 }
 
@@ -122,6 +723,12 @@ pub mod prelude {
 mod tests {
     use super::*;
 
+    #[test]
+    #[should_panic(expected = "can't be the index path")]
+    fn test_metadata_path_must_differ_from_the_index() {
+        let _ = LazEndpoint::new().with_metadata_path(INDEX_PATH);
+    }
+
     #[test]
     fn test_server_addr_creation() {
         let addr = ServerAddr {
@@ -130,4 +737,919 @@ mod tests {
         };
         assert_eq!(addr.base_url(), "http://localhost:8080");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_rpc_error_response_carries_body() {
+        let response = RpcError::from(LazError::InvalidParameter("email".to_string())).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: RpcErrorBody = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body.code, "invalid_parameter");
+        assert_eq!(body.message, "Invalid parameter: email");
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema, serde::Serialize, serde::Deserialize)]
+    struct AuditFixture {
+        /// User who created the record
+        created_by: String,
+    }
+
+    /// Old login flow
+    #[allow(dead_code)]
+    #[rpc_query(output = String, deprecated = "use login_v2")]
+    async fn legacy_login() -> String {
+        String::new()
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(path = "/api/auth/register", method = PUT, output = String, route)]
+    async fn register() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_records_path_and_method() {
+        let metadata = find_function_metadata("register").expect("register not registered");
+        assert_eq!(metadata.path.as_deref(), Some("/api/auth/register"));
+        assert_eq!(metadata.http_method(), HttpMethod::Put);
+        let legacy = find_function_metadata("legacy_login").unwrap();
+        assert_eq!(legacy.path, None);
+        assert_eq!(legacy.http_method(), HttpMethod::Get);
+    }
+
+    #[test]
+    fn test_metadata_query_narrows_the_document() {
+        let query = MetadataQuery { function: Some("register".to_string()), ..Default::default() };
+        let document = metadata_document(None, &query);
+        assert_eq!(document.total_functions, 1);
+        assert_eq!(document.functions[0].function_name, "register");
+
+        let mutations = metadata_document(None, &MetadataQuery { mutations_only: true, ..Default::default() });
+        let all = metadata_document(None, &MetadataQuery::default());
+        assert!(mutations.total_functions < all.total_functions);
+
+        let page = metadata_document(None, &MetadataQuery { offset: 1, limit: Some(2), ..Default::default() });
+        assert_eq!(page.total_functions, all.total_functions);
+        let names = |document: &MetadataDocument| -> Vec<String> {
+            document.functions.iter().map(|f| f.function_name.clone()).collect()
+        };
+        assert_eq!(names(&page)[..], names(&all)[1..3]);
+    }
+
+    #[test]
+    fn test_snapshot_matches_the_served_document() {
+        let path = std::env::temp_dir().join(format!("laz_snapshot_{}.json", std::process::id()));
+        write_metadata_snapshot(&path).unwrap();
+        let snapshot = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let served = cache::Document::json(&metadata_document(None, &MetadataQuery::default()));
+        assert_eq!(snapshot, served.body);
+    }
+
+    #[test]
+    fn test_route_flag_registers_routes() {
+        let mut paths: Vec<&str> = inventory::iter::<RouteEntry>.into_iter().map(|r| r.path).collect();
+        paths.sort();
+        assert_eq!(paths, ["/api/auth/register", "/trees/{id}/photo"]);
+        // Building the router panics on invalid or conflicting paths
+        let _ = routes();
+    }
+
+    // `any()` never holds, like a feature that's switched off
+    #[rpc_mutation(path = "/api/billing/invoices", output = String, route)]
+    #[cfg(any())]
+    async fn issue_invoice() -> axum::Json<String> {
+        axum::Json(String::new())
+    }
+
+    #[test]
+    fn test_cfg_disabled_handlers_are_not_registered() {
+        assert!(find_function_metadata("issue_invoice").is_none());
+        assert!(laz_types::find_function_schemas("issue_invoice").is_none());
+        assert!(inventory::iter::<RouteEntry>.into_iter().all(|r| r.path != "/api/billing/invoices"));
+    }
+
+    #[allow(dead_code)]
+    #[rpc_subscription(output = TreeFixture)]
+    async fn audit_feed() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_subscription_is_flagged() {
+        let metadata = find_function_metadata("audit_feed").expect("audit_feed not registered");
+        assert!(metadata.is_subscription);
+        assert!(!metadata.is_mutation);
+        assert_eq!(metadata.http_method(), HttpMethod::Get);
+        assert!(!find_function_metadata("legacy_login").unwrap().is_subscription);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_subscription(output = TreeFixture, topic = "plantings")]
+    async fn planting_feed() -> impl axum::response::IntoResponse {
+        topics::subscribe("plantings")
+    }
+
+    #[test]
+    fn test_rpc_subscription_records_topic() {
+        assert_eq!(find_function_metadata("planting_feed").unwrap().topic.as_deref(), Some("plantings"));
+        assert_eq!(find_function_metadata("audit_feed").unwrap().topic, None);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_subscription(output = TreeFixture, topic = "pruning", auth = bearer)]
+    async fn pruning_feed() -> impl axum::response::IntoResponse {
+        topics::subscribe("pruning")
+    }
+
+    #[tokio::test]
+    async fn test_topics_of_auth_functions_are_not_served_anonymously() {
+        use tower::ServiceExt;
+        let topic = |name: &str| {
+            axum::extract::Request::get(format!("/_laz/topics/{}", name)).body(axum::body::Body::empty()).unwrap()
+        };
+        let app = LazEndpoint::new().metadata_router();
+        assert_eq!(app.clone().oneshot(topic("plantings")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app.oneshot(topic("pruning")).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        let app = LazEndpoint::new().with_auth("secret").metadata_router();
+        assert_eq!(app.oneshot(topic("plantings")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_ws(input = String, output = TreeFixture)]
+    async fn tree_watch(ws: axum::extract::ws::WebSocketUpgrade) -> axum::response::Response {
+        ws.on_upgrade(|_socket| async {})
+    }
+
+    #[test]
+    fn test_rpc_ws_registers_message_schemas() {
+        let metadata = find_function_metadata("tree_watch").expect("tree_watch not registered");
+        assert!(metadata.is_websocket);
+        assert!(!metadata.is_subscription);
+        assert_eq!(metadata.http_method(), HttpMethod::Get);
+        // The upgrade is the handshake, not a client param
+        assert!(metadata.params.iter().all(|p| !p.role.is_client_visible()));
+
+        let schemas = laz_types::find_function_schemas("tree_watch").expect("tree_watch schemas not registered");
+        assert!(schemas.request.is_none());
+        let messages = schemas.messages.as_ref().expect("no message schemas");
+        assert!(matches!(&messages.client, TypeSchema::Primitive(name) if name == "String"));
+        assert!(matches!(&messages.server, TypeSchema::Ref(name) if name == "TreeFixture"));
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema, serde::Serialize)]
+    struct GroveFixture {
+        name: String,
+    }
+
+    trait Census {
+        async fn count_groves() -> axum::Json<u32>;
+    }
+
+    #[rpc_impl]
+    impl GroveFixture {
+        #[rpc_query]
+        async fn list_groves() -> axum::Json<Vec<Self>> {
+            axum::Json(vec![Self { name: "oak".to_string() }])
+        }
+    }
+
+    #[rpc_impl]
+    impl Census for GroveFixture {
+        #[rpc_query]
+        async fn count_groves() -> axum::Json<u32> {
+            axum::Json(GroveFixture::list_groves().await.0.len() as u32)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rpc_impl_registers_associated_functions() {
+        let list = find_function_metadata("list_groves").expect("list_groves not registered");
+        assert_eq!(list.self_type.as_deref(), Some("GroveFixture"));
+        // `Self` is resolved to the implementing type
+        assert!(list.output_type_name.contains("GroveFixture"));
+        assert!(list.handler_path().ends_with("::tests::GroveFixture::list_groves"));
+
+        let count = find_function_metadata("count_groves").expect("count_groves not registered");
+        assert_eq!(count.self_type.as_deref(), Some("<GroveFixture as Census>"));
+        // The methods stay callable through their type
+        assert_eq!(<GroveFixture as Census>::count_groves().await.0, 1);
+    }
+
+    #[test]
+    fn test_rpc_attr_records_deprecation() {
+        let metadata = get_all_function_metadata()
+            .into_iter()
+            .find(|m| m.function_name == "legacy_login")
+            .expect("legacy_login metadata not registered");
+        assert_eq!(metadata.deprecated.as_deref(), Some("use login_v2"));
+        assert_eq!(metadata.description.as_deref(), Some("Old login flow"));
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema)]
+    struct TreeFixture {
+        label: String,
+        children: Vec<TreeFixture>,
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture)]
+    async fn get_tree() -> TreeFixture {
+        TreeFixture { label: String::new(), children: Vec::new() }
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture)]
+    async fn get_forest() -> Result<axum::Json<Vec<TreeFixture>>, RpcError> {
+        Ok(axum::Json(Vec::new()))
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query]
+    async fn get_tree_page() -> Result<axum::Json<PageFixture<TreeFixture>>, RpcError> {
+        Ok(axum::Json(PageFixture { items: Vec::new(), total: 0 }))
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation]
+    async fn prune_trees() {}
+
+    #[allow(dead_code)]
+    #[rpc_query(path = "/orgs/{org}/trees/{id}")]
+    async fn get_org_tree(
+        axum::extract::Path((org, id)): axum::extract::Path<(String, i64)>,
+    ) -> axum::Json<TreeFixture> {
+        let _ = (org, id);
+        axum::Json(TreeFixture { label: String::new(), children: Vec::new() })
+    }
+
+    #[test]
+    fn test_path_extractors_record_path_params() {
+        let metadata = find_function_metadata("get_org_tree").expect("get_org_tree not registered");
+        assert_eq!(metadata.input_type_name, None);
+        let params = &metadata.params[0].path_params;
+        let described: Vec<_> = params
+            .iter()
+            .map(|p| match &p.schema {
+                TypeSchema::Primitive(ty) => format!("{}@{}: {}", p.name, p.position, ty),
+                other => panic!("unexpected schema {:?}", other),
+            })
+            .collect();
+        assert_eq!(described, ["org@0: String", "id@1: i64"]);
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema, serde::Deserialize)]
+    struct TreeSearchFixture {
+        label: Option<String>,
+        tags: Vec<String>,
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query]
+    async fn search_trees(
+        axum::extract::Query(filters): axum::extract::Query<TreeSearchFixture>,
+    ) -> axum::Json<Vec<TreeFixture>> {
+        let _ = filters;
+        axum::Json(Vec::new())
+    }
+
+    #[test]
+    fn test_query_extractors_record_input_schema() {
+        let metadata = find_function_metadata("search_trees").expect("search_trees not registered");
+        assert_eq!(metadata.input_type_name.as_deref(), Some("TreeSearchFixture"));
+        assert_eq!(metadata.params[0].extractor, "Query");
+        assert!(matches!(
+            &metadata.params[0].inner_type_schema,
+            TypeSchema::Struct(s) if s.type_name == "TreeSearchFixture" && s.fields.len() == 2
+        ));
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema, serde::Deserialize)]
+    struct TreeTagFixture {
+        tag: String,
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(path = "/trees/{id}/tags")]
+    async fn tag_tree(
+        axum::extract::Path(id): axum::extract::Path<i64>,
+        axum::extract::Query(filters): axum::extract::Query<TreeSearchFixture>,
+        axum::Json(body): axum::Json<TreeTagFixture>,
+    ) {
+        let _ = (id, filters, body);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation]
+    async fn replant_tree(
+        axum::extract::State(ctx): axum::extract::State<AppContext>,
+        axum::Extension(tenant): axum::Extension<String>,
+        axum::Json(body): axum::Json<TreeTagFixture>,
+    ) {
+        let _ = (ctx, tenant, body);
+    }
+
+    #[test]
+    fn test_server_only_extractors_are_never_the_input() {
+        let metadata = find_function_metadata("replant_tree").expect("replant_tree not registered");
+        assert_eq!(metadata.input_type_name.as_deref(), Some("TreeTagFixture"));
+        let visible: Vec<_> = metadata
+            .params
+            .iter()
+            .filter(|p| p.role.is_client_visible())
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(visible, ["body"]);
+        assert!(matches!(metadata.params[0].inner_type_schema, TypeSchema::Opaque(_)));
+    }
+
+    #[test]
+    fn test_every_typed_extractor_records_its_role() {
+        let metadata = find_function_metadata("tag_tree").expect("tag_tree not registered");
+        assert_eq!(metadata.input_type_name.as_deref(), Some("TreeTagFixture"));
+        let roles: Vec<_> = metadata
+            .params
+            .iter()
+            .map(|p| (p.role, p.type_name.as_deref()))
+            .collect();
+        assert_eq!(
+            roles,
+            [
+                (ParamRole::Path, Some("i64")),
+                (ParamRole::Query, Some("TreeSearchFixture")),
+                (ParamRole::Body, Some("TreeTagFixture")),
+            ]
+        );
+    }
+
+    // Stand-ins for the `headers` crate's typed headers, matched by name
+    #[allow(dead_code)]
+    struct TypedHeader<T>(T);
+    struct UserAgent;
+    struct Authorization<S>(S);
+    struct Bearer;
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture)]
+    async fn claim_tree(
+        TypedHeader(_agent): TypedHeader<UserAgent>,
+        TypedHeader(_auth): TypedHeader<Authorization<Bearer>>,
+        _headers: axum::http::HeaderMap,
+    ) -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_header_extractors_record_required_headers() {
+        let metadata = find_function_metadata("claim_tree").expect("claim_tree not registered");
+        let headers: Vec<_> = metadata.params.iter().map(|p| (p.role, p.header.as_deref())).collect();
+        assert_eq!(
+            headers,
+            [
+                (ParamRole::Header, Some("User-Agent")),
+                (ParamRole::Header, Some("Authorization")),
+                (ParamRole::Header, None),
+            ]
+        );
+        assert_eq!(metadata.input_type_name, None);
+        assert_eq!(metadata.auth, Some(AuthScheme::Bearer));
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(path = "/trees/{id}/photo", route)]
+    async fn upload_tree_photo(
+        axum::extract::Path(id): axum::extract::Path<i64>,
+        mut multipart: axum::extract::Multipart,
+    ) {
+        let _ = (id, multipart.next_field().await);
+    }
+
+    #[test]
+    fn test_multipart_extractors_are_flagged() {
+        let metadata = find_function_metadata("upload_tree_photo").expect("upload_tree_photo not registered");
+        assert!(metadata.is_multipart);
+        assert_eq!(metadata.body_encoding, BodyEncoding::Multipart);
+        assert_eq!(metadata.input_type_name, None);
+        assert_eq!(metadata.params[1].extractor, "Multipart");
+        assert!(!find_function_metadata("search_trees").unwrap().is_multipart);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation]
+    async fn rename_tree(axum::Form(body): axum::Form<TreeTagFixture>) {
+        let _ = body;
+    }
+
+    #[test]
+    fn test_form_extractors_record_the_body_encoding() {
+        let metadata = find_function_metadata("rename_tree").expect("rename_tree not registered");
+        assert_eq!(metadata.body_encoding, BodyEncoding::Form);
+        assert_eq!(metadata.input_type_name.as_deref(), Some("TreeTagFixture"));
+        assert_eq!(find_function_metadata("tag_tree").unwrap().body_encoding, BodyEncoding::Json);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture, auth = bearer)]
+    async fn my_tree() -> String {
+        String::new()
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture, auth = api_key("X-Key"))]
+    async fn partner_tree() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_records_auth() {
+        let metadata = find_function_metadata("my_tree").expect("my_tree not registered");
+        assert_eq!(metadata.auth, Some(AuthScheme::Bearer));
+        let partner = find_function_metadata("partner_tree").unwrap();
+        assert_eq!(partner.auth, Some(AuthScheme::ApiKey { header: "X-Key".to_string() }));
+        assert_eq!(find_function_metadata("legacy_login").unwrap().auth, None);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(name = "getTreeOfTheDay", output = TreeFixture)]
+    async fn tree_of_the_day_v2() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_overrides_public_name() {
+        assert!(find_function_metadata("tree_of_the_day_v2").is_none());
+        let metadata = find_function_metadata("getTreeOfTheDay").expect("getTreeOfTheDay not registered");
+        assert_eq!(metadata.rust_name.as_deref(), Some("tree_of_the_day_v2"));
+        assert_eq!(metadata.handler_name(), "tree_of_the_day_v2");
+        assert_eq!(find_function_metadata("legacy_login").unwrap().handler_name(), "legacy_login");
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(namespace = "forestry", output = TreeFixture)]
+    async fn plant_tree() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_records_namespace() {
+        let metadata = find_function_metadata("plant_tree").expect("plant_tree not registered");
+        assert_eq!(metadata.namespace.as_deref(), Some("forestry"));
+        assert_eq!(find_function_metadata("legacy_login").unwrap().namespace, None);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(stream_body = true)]
+    async fn export_trees() -> Response {
+        ().into_response()
+    }
+
+    #[test]
+    fn test_stream_body_is_flagged() {
+        let metadata = find_function_metadata("export_trees").expect("export_trees not registered");
+        assert!(metadata.stream_body);
+        assert_eq!(metadata.output_type_name, "Bytes");
+        assert!(!find_function_metadata("legacy_login").unwrap().stream_body);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(output = TreeFixture, idempotent = true)]
+    async fn adopt_tree() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_idempotent_is_flagged() {
+        assert!(find_function_metadata("adopt_tree").expect("adopt_tree not registered").idempotent);
+        assert!(!find_function_metadata("plant_tree").unwrap().idempotent);
+    }
+
+    #[derive(LazSchema, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TreeCursorFixture {
+        after: Option<String>,
+        page_size: Option<u32>,
+    }
+
+    #[derive(LazSchema, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TreeListFixture {
+        trees: Vec<String>,
+        next_cursor: Option<String>,
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(paginated = true)]
+    async fn browse_trees(
+        axum::extract::Query(_params): axum::extract::Query<TreeCursorFixture>,
+    ) -> Json<TreeListFixture> {
+        Json(TreeListFixture { trees: Vec::new(), next_cursor: None })
+    }
+
+    #[test]
+    fn test_paginated_records_convention() {
+        let metadata = find_function_metadata("browse_trees").expect("browse_trees not registered");
+        assert_eq!(
+            metadata.pagination,
+            Some(Pagination::Cursor {
+                cursor_field: "after".to_string(),
+                next_cursor_field: "nextCursor".to_string(),
+                items_field: Some("trees".to_string()),
+            })
+        );
+        assert_eq!(find_function_metadata("search_trees").unwrap().pagination, None);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(output = TreeFixture, since = "1.2", removed = "2.0")]
+    async fn graft_tree() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_records_version_range() {
+        let metadata = find_function_metadata("graft_tree").expect("graft_tree not registered");
+        assert_eq!(metadata.since.as_deref(), Some("1.2"));
+        assert_eq!(metadata.removed.as_deref(), Some("2.0"));
+        assert!(metadata.available_in("1.10.3"));
+        assert!(!metadata.available_in("1.1"));
+        assert!(!metadata.available_in("2.0"));
+        assert!(find_function_metadata("legacy_login").unwrap().available_in("0.1"));
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture, tags = ["garden", "admin"])]
+    async fn inspect_tree() -> String {
+        String::new()
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture, tags = "garden")]
+    async fn measure_tree() -> String {
+        String::new()
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture, timeout_ms = 2_500)]
+    async fn survey_forest() -> String {
+        String::new()
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture, cache = "5min")]
+    async fn count_rings() -> String {
+        String::new()
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(output = TreeFixture, status = 201, error_status = [404, 409])]
+    async fn plant_seedling() -> String {
+        String::new()
+    }
+
+    #[rpc_query(output = TreeFixture, trace)]
+    async fn trace_tree(offset: i64) -> Result<String, std::num::ParseIntError> {
+        let rings: i64 = "7".parse()?;
+        if offset < 0 {
+            return Ok(String::new());
+        }
+        Ok((rings + offset).to_string())
+    }
+
+    #[rpc_mutation(output = TreeFixture, trace = true)]
+    fn prune_tree(branches: u32) -> u32 {
+        branches.saturating_sub(1)
+    }
+
+    #[tokio::test]
+    async fn test_traced_handlers_behave_as_written() {
+        assert_eq!(trace_tree(1).await.unwrap(), "8");
+        assert_eq!(trace_tree(-1).await.unwrap(), "");
+        assert_eq!(prune_tree(3), 2);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = crate::tests::TreeFixture, method = GET)]
+    async fn find_tree() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_accepts_type_paths() {
+        let metadata = find_function_metadata("find_tree").expect("find_tree not registered");
+        assert_eq!(metadata.output_type_name, "TreeFixture");
+        assert_eq!(metadata.method, Some(HttpMethod::Get));
+    }
+
+    #[test]
+    fn test_rpc_attr_records_cache_max_age() {
+        assert_eq!(find_function_metadata("count_rings").unwrap().cache_max_age, Some(300));
+        assert_eq!(find_function_metadata("graft_tree").unwrap().cache_max_age, None);
+    }
+
+    #[test]
+    fn test_rpc_attr_records_statuses() {
+        let metadata = find_function_metadata("plant_seedling").unwrap();
+        assert_eq!(metadata.status, Some(201));
+        assert_eq!(metadata.error_statuses, [404, 409]);
+        assert_eq!(find_function_metadata("graft_tree").unwrap().status, None);
+    }
+
+    #[test]
+    fn test_rpc_attr_records_timeout() {
+        assert_eq!(find_function_metadata("survey_forest").unwrap().timeout_ms, Some(2500));
+        assert_eq!(find_function_metadata("graft_tree").unwrap().timeout_ms, None);
+    }
+
+    #[test]
+    fn test_rpc_attr_records_tags() {
+        assert_eq!(find_function_metadata("inspect_tree").unwrap().tags, ["garden", "admin"]);
+        assert_eq!(find_function_metadata("measure_tree").unwrap().tags, ["garden"]);
+        assert!(find_function_metadata("graft_tree").unwrap().tags.is_empty());
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(output = TreeFixture, rate_limit = "5/min")]
+    async fn water_tree() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_records_rate_limit() {
+        let metadata = find_function_metadata("water_tree").expect("water_tree not registered");
+        assert_eq!(metadata.rate_limit, Some(RateLimit { requests: 5, period_secs: 60 }));
+        assert_eq!(find_function_metadata("graft_tree").unwrap().rate_limit, None);
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(path = "/api/trees/soak", output = String, rate_limit = "1/min")]
+    async fn soak_tree() -> String {
+        String::new()
+    }
+
+    #[tokio::test]
+    async fn test_dispatched_calls_are_rate_limited() {
+        use tower::ServiceExt;
+
+        let app = axum::Router::new()
+            .route("/api/trees/soak", axum::routing::post(|| async { "soaked" }))
+            .with_laz(&LazEndpoint::new().with_rate_limiting().with_dispatch());
+        let call = || {
+            axum::extract::Request::post(DISPATCH_PATH)
+                .body(axum::body::Body::from(r#"{ "function": "soak_tree" }"#))
+                .unwrap()
+        };
+        assert_eq!(app.clone().oneshot(call()).await.unwrap().status(), axum::http::StatusCode::OK);
+        assert_eq!(
+            app.oneshot(call()).await.unwrap().status(),
+            axum::http::StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(path = "/api/trees/fell", output = String, rate_limit = "1/min", internal)]
+    async fn fell_tree() -> String {
+        String::new()
+    }
+
+    #[tokio::test]
+    async fn test_internal_functions_are_rate_limited() {
+        use tower::ServiceExt;
+
+        let app = axum::Router::new()
+            .route("/api/trees/fell", axum::routing::post(|| async { "felled" }))
+            .with_laz(&LazEndpoint::new().with_rate_limiting());
+        let call = || axum::extract::Request::post("/api/trees/fell").body(axum::body::Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(call()).await.unwrap().status(), axum::http::StatusCode::OK);
+        assert_eq!(
+            app.oneshot(call()).await.unwrap().status(),
+            axum::http::StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = TreeFixture, internal)]
+    async fn reindex_trees() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_internal_functions_are_hidden_from_public_metadata() {
+        assert!(find_function_metadata("reindex_trees").expect("reindex_trees not registered").internal);
+        let public = laz_types::get_public_function_metadata();
+        assert!(public.iter().all(|m| m.function_name != "reindex_trees"));
+        assert!(public.iter().any(|m| m.function_name == "graft_tree"));
+    }
+
+    #[test]
+    fn test_output_is_inferred_from_return_type() {
+        let page = find_function_metadata("get_tree_page").expect("get_tree_page not registered");
+        assert_eq!(page.output_type_name, "PageFixture_TreeFixture");
+        let prune = find_function_metadata("prune_trees").expect("prune_trees not registered");
+        assert_eq!(prune.output_type_name, "()");
+        // An explicit attribute still wins
+        let forest = find_function_metadata("get_forest").unwrap();
+        assert_eq!(forest.output_type_name, "TreeFixture");
+    }
+
+    #[allow(dead_code)]
+    struct ApiResult<T>(Result<T, RpcError>);
+
+    #[allow(dead_code)]
+    #[rpc_query(unwrap = "ApiResult, Json")]
+    async fn get_grove() -> ApiResult<axum::Json<TreeFixture>> {
+        ApiResult(Err(RpcError::new(StatusCode::NOT_FOUND, "not_found", "No grove")))
+    }
+
+    #[test]
+    fn test_unwrap_looks_through_custom_wrappers() {
+        let grove = find_function_metadata("get_grove").expect("get_grove not registered");
+        assert_eq!(grove.output_type_name, "TreeFixture");
+        assert!(matches!(&grove.return_type, TypeSchema::Struct(s) if s.type_name == "TreeFixture"));
+    }
+
+    #[test]
+    fn test_return_types_resolve_to_schemas() {
+        let tree = find_function_metadata("get_tree").expect("get_tree not registered");
+        assert!(matches!(&tree.return_type, TypeSchema::Struct(s) if s.type_name == "TreeFixture"));
+        let forest = find_function_metadata("get_forest").expect("get_forest not registered");
+        assert!(matches!(
+            &forest.return_type,
+            TypeSchema::Container { container_type, type_params }
+                if container_type == "Vec"
+                    && matches!(&type_params[0], TypeSchema::Ref(name) if name == "TreeFixture")
+        ));
+        let legacy = find_function_metadata("legacy_login").unwrap();
+        assert!(matches!(&legacy.return_type, TypeSchema::Primitive(p) if p == "String"));
+    }
+
+    #[test]
+    fn test_type_table_holds_recursive_types_once() {
+        let table = type_table();
+        let Some(TypeSchema::Struct(schema)) = table.get("TreeFixture") else {
+            panic!("TreeFixture missing from type table");
+        };
+        assert!(matches!(
+            schema.fields[1].field_type.as_ref(),
+            TypeSchema::Container { type_params, .. }
+                if matches!(&type_params[0], TypeSchema::Ref(name) if name == "TreeFixture")
+        ));
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema)]
+    struct PageFixture<T> {
+        items: Vec<T>,
+        total: u64,
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = PageFixture<AuditFixture>)]
+    async fn list_audits() -> PageFixture<AuditFixture> {
+        PageFixture { items: Vec::new(), total: 0 }
+    }
+
+    #[test]
+    fn test_generic_instances_become_concrete_types() {
+        let table = type_table();
+        let Some(TypeSchema::Struct(schema)) = table.get("PageFixture_AuditFixture") else {
+            panic!("PageFixture_AuditFixture missing from type table");
+        };
+        assert_eq!(schema.type_name, "PageFixture_AuditFixture");
+        assert!(matches!(
+            schema.fields[0].field_type.as_ref(),
+            TypeSchema::Container { type_params, .. }
+                if matches!(&type_params[0], TypeSchema::Ref(name) if name == "AuditFixture")
+        ));
+        assert!(table.contains_key("AuditFixture"));
+        assert!(!table.contains_key("PageFixture"));
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query(output = std::sync::Arc<PageFixture<Box<TreeFixture>>>)]
+    async fn shared_trees() -> std::sync::Arc<PageFixture<Box<TreeFixture>>> {
+        std::sync::Arc::new(PageFixture { items: Vec::new(), total: 0 })
+    }
+
+    #[test]
+    fn test_smart_pointers_are_transparent_in_outputs() {
+        let metadata = find_function_metadata("shared_trees").expect("shared_trees not registered");
+        assert_eq!(metadata.output_type_name, "PageFixture_TreeFixture");
+        assert!(matches!(
+            &metadata.return_type,
+            TypeSchema::Struct(s) if s.type_name == "PageFixture_TreeFixture"
+        ));
+        assert!(type_table().contains_key("PageFixture_TreeFixture"));
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema)]
+    struct SignupFixture {
+        #[laz(min_length = 3, max_length = 32, pattern = "^[a-z0-9_]+$")]
+        username: String,
+        #[laz(min = 13, max = 130)]
+        age: u32,
+        #[laz(one_of = ["free", "pro"])]
+        plan: String,
+    }
+
+    #[test]
+    fn test_type_schema_map_is_keyed_by_name() {
+        let map = get_type_schema_map();
+        assert!(matches!(
+            map.get("AuditFixture"),
+            Some(TypeSchema::Struct(s)) if s.type_name == "AuditFixture"
+        ));
+        // Generic templates are only registered through their instances
+        assert!(!map.contains_key("PageFixture"));
+        assert!(map.contains_key("PageFixture_AuditFixture"));
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema, serde::Serialize)]
+    enum SignupErrorFixture {
+        UsernameTaken,
+        TooYoung { minimum_age: u32 },
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(
+        input = SignupFixture,
+        output = String,
+        error = SignupErrorFixture,
+        example = r#"{"username": "ada", "age": 36, "plan": "pro"}"#
+    )]
+    async fn signup() -> String {
+        String::new()
+    }
+
+    #[test]
+    fn test_rpc_attr_records_error_type() {
+        let metadata = get_all_function_metadata()
+            .into_iter()
+            .find(|m| m.function_name == "signup")
+            .expect("signup metadata not registered");
+        assert_eq!(metadata.error_type_name.as_deref(), Some("SignupErrorFixture"));
+        assert_eq!(metadata.example.as_ref().unwrap()["plan"], "pro");
+        assert!(type_table().contains_key("SignupErrorFixture"));
+    }
+
+    #[test]
+    fn test_registry_queries() {
+        let signup = find_function_metadata("signup").expect("signup metadata not registered");
+        assert!(signup.is_mutation);
+        assert!(functions_by_mutation(true).iter().any(|m| m.function_name == "signup"));
+        assert!(functions_by_mutation(false).iter().all(|m| !m.is_mutation));
+
+        // `list_audits` reaches AuditFixture through its PageFixture_AuditFixture output
+        let users: Vec<_> = functions_for_type("AuditFixture")
+            .into_iter()
+            .map(|m| m.function_name.clone())
+            .collect();
+        assert_eq!(users, ["list_audits"]);
+
+        register_function_metadata(make_function_metadata(
+            "runtime_ping".to_string(),
+            Vec::new(),
+            TypeSchema::Primitive("bool".to_string()),
+            None,
+            "bool".to_string(),
+            true,
+            false,
+        ));
+        assert!(matches!(
+            find_function_metadata("runtime_ping"),
+            Some(std::borrow::Cow::Owned(_))
+        ));
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation(output = ())]
+    async fn clear_cache() {}
+
+    #[test]
+    fn test_unit_outputs_have_unit_schema() {
+        let metadata = find_function_metadata("clear_cache").expect("clear_cache not registered");
+        assert_eq!(metadata.output_type_name, "()");
+        assert!(matches!(metadata.return_type, TypeSchema::Unit));
+    }
+
+    #[test]
+    fn test_openapi_document_references_components() {
+        let document = openapi_document("Fixtures", "0.1.0");
+        let operation = &document["paths"]["/list_audits"]["get"];
+        assert_eq!(
+            operation["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/PageFixture_AuditFixture"
+        );
+        let schemas = &document["components"]["schemas"];
+        assert_eq!(schemas["PageFixture_AuditFixture"]["type"], "object");
+        assert_eq!(schemas["AuditFixture"]["required"], serde_json::json!(["created_by"]));
+    }
+}
@@ -0,0 +1,61 @@
+//! Matching of requests to the RPC functions serving them, as described under
+//! [route matching](crate#route-matching). Requests are matched by axum's
+//! [`MatchedPath`](axum::extract::MatchedPath), the route template they hit.
+
+use laz_types::{EndpointDiscovery, FunctionMetadata, HttpMethod};
+
+/// Functions keyed by the route template and method serving them
+pub(crate) struct RouteTable {
+    entries: Vec<RouteTableEntry>,
+}
+
+struct RouteTableEntry {
+    path: String,
+    method: HttpMethod,
+    function: FunctionMetadata,
+}
+
+impl RouteTable {
+    /// Routes of `functions`; functions without a declared `path` are located among the
+    /// discovered `endpoints`, and left out when none names them
+    pub(crate) fn new<'a>(
+        functions: impl IntoIterator<Item = &'a FunctionMetadata>,
+        endpoints: &[EndpointDiscovery],
+    ) -> Self {
+        let entries = functions
+            .into_iter()
+            .filter_map(|function| {
+                let Some((path, method)) = function.route(endpoints) else {
+                    tracing::warn!("No route found for RPC function {}", function.function_name);
+                    return None;
+                };
+                Some(RouteTableEntry {
+                    path,
+                    method,
+                    function: function.clone(),
+                })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// The function served at route template `path` with `method`
+    pub(crate) fn find(&self, path: &str, method: &str) -> Option<&FunctionMetadata> {
+        self.entries
+            .iter()
+            .find(|entry| entry.path == path && entry.method.as_str().eq_ignore_ascii_case(method))
+            .map(|entry| &entry.function)
+    }
+
+    /// Route template and method serving the function named `name`
+    pub(crate) fn route_of(&self, name: &str) -> Option<(&str, HttpMethod, &FunctionMetadata)> {
+        self.entries
+            .iter()
+            .find(|entry| entry.function.function_name == name)
+            .map(|entry| (entry.path.as_str(), entry.method, &entry.function))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,90 @@
+//! Token protection of the `/_laz/metadata` route.
+//!
+//! Requests must carry `Authorization: Bearer <token>`; others are answered with
+//! `401 Unauthorized`. Tokens are compared in constant time. The secret the document is
+//! signed with, see [`laz_types::signature`], is kept in a [`Token`] as well.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::RpcError;
+
+/// Token required by the metadata route, kept out of `Debug` output
+#[derive(Clone)]
+pub(crate) struct Token(pub(crate) Arc<str>);
+
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Token(..)")
+    }
+}
+
+/// Middleware rejecting requests without the metadata token
+pub(crate) async fn require_token(State(token): State<Token>, request: Request, next: Next) -> Response {
+    if is_authorized(request.headers(), &token.0) {
+        return next.run(request).await;
+    }
+    let mut response =
+        RpcError::new(StatusCode::UNAUTHORIZED, "unauthorized", "The metadata endpoint requires a token").into_response();
+    response.headers_mut().insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    presents(headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok()), token)
+}
+
+/// Whether an `Authorization` header value carries `token` as a bearer token
+pub(crate) fn presents(authorization: Option<&str>, token: &str) -> bool {
+    authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()))
+}
+
+/// Compare without stopping at the first difference, so timing doesn't reveal how much
+/// of a guess was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_matching_bearer_token_is_authorized() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert!(is_authorized(&headers("Bearer s3cret"), "s3cret"));
+        assert!(!is_authorized(&headers("Bearer s3cre"), "s3cret"));
+        assert!(!is_authorized(&headers("Basic s3cret"), "s3cret"));
+        assert!(!is_authorized(&HeaderMap::new(), "s3cret"));
+        assert_eq!(format!("{:?}", Token("s3cret".into())), "Token(..)");
+    }
+
+    #[tokio::test]
+    async fn test_metadata_is_signed_with_the_secret() {
+        use tower::ServiceExt;
+
+        let app = crate::LazEndpoint::new().with_signing_secret("s3cret").metadata_router();
+        let request = Request::get(crate::DEFAULT_METADATA_PATH).body(axum::body::Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let signature = response.headers()[laz_types::signature::HEADER].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(laz_types::signature::verify(b"s3cret", &body, &signature));
+        assert!(!laz_types::signature::verify(b"guess", &body, &signature));
+    }
+}
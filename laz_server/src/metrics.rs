@@ -0,0 +1,235 @@
+//! Prometheus metrics of RPC calls, recorded and served by [`LazMetrics`].
+//!
+//! Each function, found by [route matching](crate#route-matching), gets a call counter by
+//! response status, a histogram of the time until its handler answered, and the request
+//! and response sizes taken from `Content-Length`.
+//! Streamed responses have no known size; they are timed until their headers are sent.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use axum::{
+    body::HttpBody,
+    extract::{MatchedPath, Request, State},
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    middleware::Next,
+    response::Response,
+    routing::get,
+};
+use loco_rs::{
+    app::{AppContext, Initializer},
+    Result,
+};
+
+use crate::matching::RouteTable;
+use crate::METRICS_PATH;
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Initializer recording metrics of every RPC call and serving them at [`METRICS_PATH`] in
+/// the Prometheus text format. See [route matching](crate#route-matching).
+///
+/// ```rust,ignore
+/// initializers.push(Box::new(laz_server::LazMetrics::new()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LazMetrics {}
+
+impl LazMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Initializer for LazMetrics {
+    fn name(&self) -> String {
+        "laz-metrics".to_string()
+    }
+
+    /// Layers the recorder over the app's routes, then mounts the metrics route unrecorded
+    async fn after_routes(&self, router: axum::routing::Router, _ctx: &AppContext) -> Result<axum::routing::Router> {
+        let functions = laz_types::get_all_function_metadata();
+        let recorder = Arc::new(Recorder::new(RouteTable::new(
+            functions.iter().copied(),
+            &crate::discovered_endpoints(),
+        )));
+        let router = router.layer(axum::middleware::from_fn_with_state(recorder.clone(), record));
+        Ok(router.route(
+            METRICS_PATH,
+            get(|| async move { ([(CONTENT_TYPE, "text/plain; version=0.0.4")], recorder.render()) }),
+        ))
+    }
+}
+
+/// Metrics of every function called so far
+pub(crate) struct Recorder {
+    routes: RouteTable,
+    /// Metrics by function name, sorted so the output is stable
+    functions: Mutex<BTreeMap<String, FunctionMetrics>>,
+}
+
+#[derive(Default)]
+struct FunctionMetrics {
+    /// Calls by response status
+    calls: BTreeMap<u16, u64>,
+    /// Calls per latency bucket, not cumulative; the last one counts the slowest
+    latency_buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    latency_seconds: f64,
+    request_bytes: Sizes,
+    response_bytes: Sizes,
+}
+
+#[derive(Default)]
+struct Sizes {
+    sum: u64,
+    count: u64,
+}
+
+impl Sizes {
+    fn add(&mut self, bytes: Option<u64>) {
+        if let Some(bytes) = bytes {
+            self.sum += bytes;
+            self.count += 1;
+        }
+    }
+}
+
+impl Recorder {
+    pub(crate) fn new(routes: RouteTable) -> Self {
+        Self {
+            routes,
+            functions: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn record(
+        &self,
+        function: &str,
+        status: StatusCode,
+        elapsed: Duration,
+        request_bytes: Option<u64>,
+        response_bytes: Option<u64>,
+    ) {
+        let mut functions = self.functions.lock().unwrap_or_else(PoisonError::into_inner);
+        let metrics = functions.entry(function.to_string()).or_default();
+        *metrics.calls.entry(status.as_u16()).or_default() += 1;
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound).unwrap_or(LATENCY_BUCKETS.len());
+        metrics.latency_buckets[bucket] += 1;
+        metrics.latency_seconds += seconds;
+        metrics.request_bytes.add(request_bytes);
+        metrics.response_bytes.add(response_bytes);
+    }
+
+    /// Everything recorded, in the Prometheus text exposition format
+    pub(crate) fn render(&self) -> String {
+        let functions = self.functions.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut out = String::new();
+
+        out.push_str("# HELP laz_rpc_calls_total RPC calls by function and response status.\n");
+        out.push_str("# TYPE laz_rpc_calls_total counter\n");
+        for (name, metrics) in functions.iter() {
+            for (status, count) in &metrics.calls {
+                let _ = writeln!(out, "laz_rpc_calls_total{{function=\"{}\",status=\"{}\"}} {}", label(name), status, count);
+            }
+        }
+
+        out.push_str("# HELP laz_rpc_call_duration_seconds Time until RPC handlers answered.\n");
+        out.push_str("# TYPE laz_rpc_call_duration_seconds histogram\n");
+        for (name, metrics) in functions.iter() {
+            let name = label(name);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&metrics.latency_buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "laz_rpc_call_duration_seconds_bucket{{function=\"{}\",le=\"{}\"}} {}",
+                    name, bound, cumulative
+                );
+            }
+            let total: u64 = metrics.latency_buckets.iter().sum();
+            let _ = writeln!(out, "laz_rpc_call_duration_seconds_bucket{{function=\"{}\",le=\"+Inf\"}} {}", name, total);
+            let _ = writeln!(out, "laz_rpc_call_duration_seconds_sum{{function=\"{}\"}} {}", name, metrics.latency_seconds);
+            let _ = writeln!(out, "laz_rpc_call_duration_seconds_count{{function=\"{}\"}} {}", name, total);
+        }
+
+        let sizes = |out: &mut String, metric: &str, help: &str, sizes_of: fn(&FunctionMetrics) -> &Sizes| {
+            let _ = writeln!(out, "# HELP {} {}", metric, help);
+            let _ = writeln!(out, "# TYPE {} summary", metric);
+            for (name, metrics) in functions.iter() {
+                let sizes = sizes_of(metrics);
+                let _ = writeln!(out, "{}_sum{{function=\"{}\"}} {}", metric, label(name), sizes.sum);
+                let _ = writeln!(out, "{}_count{{function=\"{}\"}} {}", metric, label(name), sizes.count);
+            }
+        };
+        sizes(&mut out, "laz_rpc_request_size_bytes", "Sizes of RPC request bodies.", |m| &m.request_bytes);
+        sizes(&mut out, "laz_rpc_response_size_bytes", "Sizes of RPC response bodies.", |m| &m.response_bytes);
+        out
+    }
+}
+
+/// `value` escaped for use as a label value
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+/// Middleware recording the calls of RPC functions; other requests pass untouched
+pub(crate) async fn record(State(recorder): State<Arc<Recorder>>, request: Request, next: Next) -> Response {
+    let function = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| recorder.routes.find(path.as_str(), request.method().as_str()))
+        .map(|function| function.function_name.clone());
+    let Some(function) = function else {
+        return next.run(request).await;
+    };
+    let request_bytes = content_length(request.headers()).or_else(|| request.body().size_hint().exact());
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let response_bytes = content_length(response.headers()).or_else(|| response.body().size_hint().exact());
+    recorder.record(&function, response.status(), started.elapsed(), request_bytes, response_bytes);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_calls_are_counted_timed_and_sized() {
+        // `register` is declared at `PUT /api/auth/register` by the crate's tests
+        let register = laz_types::find_function_metadata("register").unwrap();
+        let recorder = Arc::new(Recorder::new(RouteTable::new([&*register], &[])));
+        let app = Router::new()
+            .route("/api/auth/register", axum::routing::put(|| async { "registered" }))
+            .route("/api/other", get(|| async { "other" }))
+            .layer(axum::middleware::from_fn_with_state(recorder.clone(), record));
+
+        for uri in ["/api/auth/register", "/api/other"] {
+            let method = if uri == "/api/other" { "GET" } else { "PUT" };
+            let request = Request::builder().method(method).uri(uri).body(Body::from("{}")).unwrap();
+            assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::OK);
+        }
+
+        let text = recorder.render();
+        assert!(text.contains("laz_rpc_calls_total{function=\"register\",status=\"200\"} 1\n"));
+        assert!(text.contains("laz_rpc_call_duration_seconds_bucket{function=\"register\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains("laz_rpc_request_size_bytes_sum{function=\"register\"} 2\n"));
+        assert!(text.contains("laz_rpc_response_size_bytes_sum{function=\"register\"} 10\n"));
+        assert!(!text.contains("other"));
+    }
+}
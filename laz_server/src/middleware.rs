@@ -0,0 +1,354 @@
+//! Interception of RPC calls by [`LazMiddleware`] chains.
+//!
+//! Requests are matched by [route matching](crate#route-matching). Their params are read as
+//! JSON from the body, the form or the query string, handed to every middleware's
+//! `before_call` in order, and written back if one changed them. The handler's result is handed to every
+//! `after_call` in reverse order. Streamed, multipart and WebSocket functions have no params
+//! or result to hand over: their `before_call` sees `null`, and `after_call` isn't called.
+//! Neither is it for results over 2 MiB, which are passed on as they are.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{
+    body::{Body, Bytes},
+    extract::{MatchedPath, Request, State},
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap, StatusCode, Uri,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::StreamExt;
+use laz_types::{BodyEncoding, FunctionMetadata};
+use serde_json::Value;
+
+use crate::matching::RouteTable;
+use crate::{validation, RpcError};
+
+/// Largest body or result read, matching axum's default body limit
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Hooks run around every RPC call when added with
+/// [`LazEndpoint::with_middleware`](crate::LazEndpoint::with_middleware)
+///
+/// ```rust,ignore
+/// struct Audit;
+///
+/// #[async_trait::async_trait]
+/// impl LazMiddleware for Audit {
+///     async fn before_call(&self, function: &FunctionMetadata, params: &mut Value, _: &HeaderMap) -> Result<(), RpcError> {
+///         tracing::info!(function = %function.function_name, %params, "audit");
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait LazMiddleware: Send + Sync + 'static {
+    /// Runs before the handler with the call's params, which it may change. Returning an
+    /// error answers the call with it, skipping the handler and later middleware.
+    async fn before_call(
+        &self,
+        _function: &FunctionMetadata,
+        _params: &mut Value,
+        _headers: &HeaderMap,
+    ) -> Result<(), RpcError> {
+        Ok(())
+    }
+
+    /// Runs after the handler with its result, which it may change. Results over 2 MiB
+    /// skip it.
+    async fn after_call(&self, _function: &FunctionMetadata, _result: &mut Result<Value, RpcError>) {}
+}
+
+/// Middleware run in order around every call
+#[derive(Clone, Default)]
+pub(crate) struct MiddlewareChain(pub(crate) Vec<Arc<dyn LazMiddleware>>);
+
+impl std::fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MiddlewareChain({} middleware)", self.0.len())
+    }
+}
+
+/// The chain and the routes it intercepts
+pub(crate) struct Interceptor {
+    pub(crate) routes: RouteTable,
+    pub(crate) chain: MiddlewareChain,
+}
+
+/// Middleware running the chain around calls of RPC functions
+pub(crate) async fn intercept(State(interceptor): State<Arc<Interceptor>>, request: Request, next: Next) -> Response {
+    let Some(function) = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| interceptor.routes.find(path.as_str(), request.method().as_str()))
+        .cloned()
+    else {
+        return next.run(request).await;
+    };
+    let chain = &interceptor.chain.0;
+
+    let streamed = function.is_subscription || function.is_websocket || function.is_multipart || function.stream_body;
+    if streamed {
+        let mut params = Value::Null;
+        for middleware in chain {
+            if let Err(error) = middleware.before_call(&function, &mut params, request.headers()).await {
+                return error.into_response();
+            }
+        }
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_BODY_BYTES).await else {
+        return RpcError::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", "Request body is too large")
+            .into_response();
+    };
+    let source = ParamSource::of(&function, &parts.headers, !bytes.is_empty());
+    let schemas = laz_types::find_function_schemas(&function.function_name);
+    let original = match source {
+        ParamSource::Json => serde_json::from_slice(&bytes).ok(),
+        ParamSource::Form => std::str::from_utf8(&bytes).ok().and_then(|form| validation::query_value(form, None)),
+        ParamSource::Query => validation::query_value(
+            parts.uri.query().unwrap_or_default(),
+            schemas.and_then(|schemas| schemas.query.as_ref()),
+        ),
+        ParamSource::None => None,
+    }
+    .unwrap_or(Value::Null);
+
+    let mut params = original.clone();
+    for middleware in chain {
+        if let Err(error) = middleware.before_call(&function, &mut params, &parts.headers).await {
+            return error.into_response();
+        }
+    }
+    let body = if params == original {
+        Body::from(bytes)
+    } else {
+        parts.headers.remove(CONTENT_LENGTH);
+        match rewrite(source, &params, &mut parts.uri) {
+            Ok(body) => body.unwrap_or_else(|| Body::from(bytes)),
+            Err(error) => return error.into_response(),
+        }
+    };
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_result(body).await {
+        Ok(bytes) => bytes,
+        Err(body) => return Response::from_parts(parts, body),
+    };
+    let original = if parts.status.is_success() {
+        Ok(result_value(&bytes))
+    } else {
+        Err(RpcError {
+            status: parts.status,
+            body: error_body(parts.status, &bytes),
+        })
+    };
+    let mut result = original.clone();
+    for middleware in chain.iter().rev() {
+        middleware.after_call(&function, &mut result).await;
+    }
+    if result == original {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    match result {
+        Ok(value) => {
+            let status = if parts.status.is_success() { parts.status } else { StatusCode::OK };
+            // The rest of the handler's headers still apply to the changed result
+            parts.headers.remove(CONTENT_LENGTH);
+            parts.headers.remove(CONTENT_TYPE);
+            (status, parts.headers, Json(value)).into_response()
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+/// The whole result body, or a body replaying it when it's over [`MAX_BODY_BYTES`] or
+/// fails partway
+async fn buffer_result(body: Body) -> Result<Bytes, Body> {
+    let mut stream = body.into_data_stream();
+    let mut chunks = Vec::new();
+    let mut len = 0;
+    while len <= MAX_BODY_BYTES {
+        match stream.next().await {
+            Some(Ok(chunk)) => {
+                len += chunk.len();
+                chunks.push(chunk);
+            }
+            Some(Err(error)) => {
+                let read = futures_util::stream::iter(chunks.into_iter().map(Ok));
+                return Err(Body::from_stream(read.chain(futures_util::stream::once(async { Err(error) }))));
+            }
+            None => return Ok(chunks.concat().into()),
+        }
+    }
+    let read = futures_util::stream::iter(chunks.into_iter().map(Ok));
+    Err(Body::from_stream(read.chain(stream)))
+}
+
+/// Where a call's params travel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParamSource {
+    Json,
+    Form,
+    Query,
+    None,
+}
+
+impl ParamSource {
+    fn of(function: &FunctionMetadata, headers: &HeaderMap, has_body: bool) -> Self {
+        if function.input_in_query() {
+            return ParamSource::Query;
+        }
+        let content_type = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default();
+        match function.body_encoding {
+            _ if !has_body => ParamSource::None,
+            BodyEncoding::Form if content_type.starts_with("application/x-www-form-urlencoded") => ParamSource::Form,
+            _ if content_type.starts_with("application/json") => ParamSource::Json,
+            _ => ParamSource::None,
+        }
+    }
+}
+
+/// Write changed `params` back where they came from, returning the new body if they travel
+/// in one
+fn rewrite(source: ParamSource, params: &Value, uri: &mut Uri) -> Result<Option<Body>, RpcError> {
+    let urlencoded = |params: &Value| {
+        serde_urlencoded::to_string(params)
+            .map_err(|e| RpcError::internal(format!("Changed params can't be sent as a query string or form: {}", e)))
+    };
+    match source {
+        ParamSource::Json => Ok(Some(Body::from(params.to_string()))),
+        ParamSource::Form => Ok(Some(Body::from(urlencoded(params)?))),
+        ParamSource::Query => {
+            let query = urlencoded(params)?;
+            let path_and_query = if query.is_empty() {
+                uri.path().to_string()
+            } else {
+                format!("{}?{}", uri.path(), query)
+            };
+            *uri = path_and_query
+                .parse()
+                .map_err(|e| RpcError::internal(format!("Changed params make an invalid URI: {}", e)))?;
+            Ok(None)
+        }
+        // Params that weren't read can't be written back
+        ParamSource::None => Ok(None),
+    }
+}
+
+/// Result of a successful call: its JSON body, `null` for handlers returning `()` and a
+/// string for plain-text ones
+pub(crate) fn result_value(body: &[u8]) -> Value {
+    if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(body).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()))
+    }
+}
+
+/// The error a failed call answered with, or a generic one for bodies that aren't an
+/// [`RpcErrorBody`](laz_types::RpcErrorBody)
+pub(crate) fn error_body(status: StatusCode, body: &[u8]) -> laz_types::RpcErrorBody {
+    serde_json::from_slice(body).unwrap_or_else(|_| laz_types::RpcErrorBody {
+        code: format!("http_{}", status.as_u16()),
+        message: String::from_utf8_lossy(body).into_owned(),
+        details: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    /// Rejects calls without a header, stamps params and redacts results
+    struct Stamp;
+
+    #[async_trait]
+    impl LazMiddleware for Stamp {
+        async fn before_call(
+            &self,
+            function: &FunctionMetadata,
+            params: &mut Value,
+            headers: &HeaderMap,
+        ) -> Result<(), RpcError> {
+            if !headers.contains_key("x-caller") {
+                return Err(RpcError::new(StatusCode::FORBIDDEN, "forbidden", "No caller"));
+            }
+            params["stamped_by"] = json!(function.function_name);
+            Ok(())
+        }
+
+        async fn after_call(&self, _function: &FunctionMetadata, result: &mut Result<Value, RpcError>) {
+            if let Ok(value) = result {
+                value["secret"] = json!("redacted");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_rewrites_params_and_results() {
+        // `register` is declared at `PUT /api/auth/register` by the crate's tests
+        let register = laz_types::find_function_metadata("register").unwrap();
+        let interceptor = Arc::new(Interceptor {
+            routes: RouteTable::new([&*register], &[]),
+            chain: MiddlewareChain(vec![Arc::new(Stamp)]),
+        });
+        let app = Router::new()
+            .route(
+                "/api/auth/register",
+                axum::routing::put(|Json(mut params): Json<Value>| async move {
+                    params["secret"] = json!("hunter2");
+                    ([("x-request-id", "7")], Json(params))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(interceptor, intercept));
+        let call = |caller: bool| {
+            let mut request = Request::put("/api/auth/register").header(CONTENT_TYPE, "application/json");
+            if caller {
+                request = request.header("x-caller", "test");
+            }
+            request.body(Body::from(r#"{ "name": "Ada" }"#)).unwrap()
+        };
+
+        let response = app.clone().oneshot(call(false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.oneshot(call(true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-request-id"], "7");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({ "name": "Ada", "stamped_by": "register", "secret": "redacted" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_large_results_pass_through() {
+        let register = laz_types::find_function_metadata("register").unwrap();
+        let interceptor = Arc::new(Interceptor {
+            routes: RouteTable::new([&*register], &[]),
+            chain: MiddlewareChain(vec![Arc::new(Stamp)]),
+        });
+        let large = "a".repeat(MAX_BODY_BYTES + 1);
+        let app = Router::new()
+            .route("/api/auth/register", axum::routing::put(move || async move { large }))
+            .layer(axum::middleware::from_fn_with_state(interceptor, intercept));
+        let request = Request::put("/api/auth/register").header("x-caller", "test").body(Body::empty()).unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), MAX_BODY_BYTES + 1);
+    }
+}
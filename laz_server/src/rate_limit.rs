@@ -0,0 +1,126 @@
+//! Enforcement of the `rate_limit` hints of RPC functions.
+//!
+//! Each limited function gets a token bucket holding up to `requests` tokens, refilled
+//! evenly over its period and shared by all callers. Requests over budget, found by
+//! [route matching](crate#route-matching), are answered with `429 Too Many Requests` and a
+//! `Retry-After` header.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use laz_types::{EndpointDiscovery, FunctionMetadata, RateLimit};
+
+use crate::matching::RouteTable;
+use crate::RpcError;
+
+/// Token buckets of the rate-limited functions
+pub(crate) struct RateLimiter {
+    routes: RouteTable,
+    /// Buckets by function name
+    buckets: HashMap<String, Mutex<Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Buckets for the `functions` declaring a rate limit
+    pub(crate) fn new(functions: &[&FunctionMetadata], endpoints: &[EndpointDiscovery]) -> Self {
+        let limited: Vec<&FunctionMetadata> =
+            functions.iter().copied().filter(|f| f.rate_limit.is_some()).collect();
+        let buckets = limited
+            .iter()
+            .filter_map(|function| {
+                let bucket = Bucket {
+                    tokens: f64::from(function.rate_limit?.requests),
+                    updated: Instant::now(),
+                };
+                Some((function.function_name.clone(), Mutex::new(bucket)))
+            })
+            .collect();
+        Self {
+            routes: RouteTable::new(limited, endpoints),
+            buckets,
+        }
+    }
+
+    /// Take a token for a request to `path` with `method`, or tell how long until one is
+    /// available. Requests to routes without a limit always pass.
+    fn acquire(&self, path: &str, method: &str) -> Result<(), (&FunctionMetadata, RateLimit, Duration)> {
+        let Some(function) = self.routes.find(path, method) else {
+            return Ok(());
+        };
+        let (Some(limit), Some(bucket)) = (function.rate_limit, self.buckets.get(&function.function_name)) else {
+            return Ok(());
+        };
+        let capacity = f64::from(limit.requests);
+        let per_second = capacity / limit.period().as_secs_f64();
+        let mut bucket = bucket.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / per_second);
+            Err((function, limit, wait))
+        }
+    }
+}
+
+/// Middleware rejecting requests over their function's rate limit
+pub(crate) async fn enforce(State(limiter): State<Arc<RateLimiter>>, request: Request, next: Next) -> Response {
+    let Some(path) = request.extensions().get::<MatchedPath>() else {
+        return next.run(request).await;
+    };
+    if let Err((function, limit, wait)) = limiter.acquire(path.as_str(), request.method().as_str()) {
+        let message = format!("{} is limited to {} requests", function.function_name, limit);
+        let mut response = RpcError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", message).into_response();
+        let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+        return response;
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_over_budget_wait_for_refill() {
+        let mut function = laz_types::make_function_metadata(
+            "send_invite".to_string(),
+            vec![],
+            laz_types::TypeSchema::Unit,
+            None,
+            "()".to_string(),
+            true,
+            true,
+        );
+        function.rate_limit = Some("2/min".parse().unwrap());
+        let endpoints = [EndpointDiscovery {
+            uri: "/api/invites/send-invite".to_string(),
+            methods: vec!["POST".to_string()],
+        }];
+        let limiter = RateLimiter::new(&[&function], &endpoints);
+
+        assert!(limiter.acquire("/api/invites/send-invite", "GET").is_ok());
+        assert!(limiter.acquire("/api/invites/send-invite", "POST").is_ok());
+        assert!(limiter.acquire("/api/invites/send-invite", "POST").is_ok());
+        let (function, _, wait) = limiter.acquire("/api/invites/send-invite", "POST").unwrap_err();
+        assert_eq!(function.function_name, "send_invite");
+        assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30));
+    }
+}
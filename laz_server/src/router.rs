@@ -0,0 +1,71 @@
+//! laz on a bare `axum::Router`, for apps that don't run on Loco.
+//!
+//! [`LazEndpoint`]'s initializer does the same through these methods. Without Loco the app's
+//! routes can't be discovered, so functions are found by the `path` they declare, or by
+//! endpoint discovery data the app registers with
+//! [`register_endpoints_discovery`](crate::register_endpoints_discovery).
+
+use axum::Router;
+
+use crate::LazEndpoint;
+
+/// Mount laz on an `axum::Router`
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/api/trees", post(plant_tree))
+///     .with_laz_rpc()
+///     .with_laz_metadata();
+/// ```
+pub trait LazRouterExt {
+    /// Serve laz's own routes describing the API, such as the metadata at
+    /// [`DEFAULT_METADATA_PATH`](crate::DEFAULT_METADATA_PATH), with default options
+    fn with_laz_metadata(self) -> Self;
+
+    /// Layer the RPC functions' routes with what they declare, such as the `Cache-Control`
+    /// of cached queries, with default options
+    fn with_laz_rpc(self) -> Self;
+
+    /// Both of the above with the options set on `endpoint`, like its checks, dispatch
+    /// routes and metadata token. Its snapshot isn't written; call
+    /// [`write_metadata_snapshot`](crate::write_metadata_snapshot) for that.
+    fn with_laz(self, endpoint: &LazEndpoint) -> Self;
+}
+
+impl LazRouterExt for Router {
+    fn with_laz_metadata(self) -> Self {
+        self.merge(LazEndpoint::default().metadata_router())
+    }
+
+    fn with_laz_rpc(self) -> Self {
+        LazEndpoint::default().layer_rpc(self)
+    }
+
+    /// The RPC layers go on first, so the metadata routes stay outside them
+    fn with_laz(self, endpoint: &LazEndpoint) -> Self {
+        endpoint.layer_rpc(self).merge(endpoint.metadata_router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_bare_routers_serve_the_metadata() {
+        let app = Router::new()
+            .route("/api/ping", get(|| async { "pong" }))
+            .with_laz_rpc()
+            .with_laz_metadata();
+        for uri in ["/api/ping", crate::DEFAULT_METADATA_PATH, crate::HEALTH_PATH] {
+            let response = app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        }
+
+        let app = Router::new().with_laz(&LazEndpoint::new().with_auth("secret"));
+        let request = Request::get(crate::DEFAULT_METADATA_PATH).body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+}
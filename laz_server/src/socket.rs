@@ -0,0 +1,284 @@
+//! The `/_laz/ws` transport: RPC calls and subscriptions multiplexed over one WebSocket.
+//!
+//! Clients send JSON text frames naming a request id of their choosing:
+//!
+//! - `{ "type": "call", "id": 1, "function": "get_tree", "params": { .. } }`
+//! - `{ "type": "subscribe", "id": 2, "function": "tree_feed", "params": { .. } }`
+//! - `{ "type": "unsubscribe", "id": 2 }`
+//!
+//! Calls are answered with `{ "type": "result", "id": 1, "result": .. }` or
+//! `{ "type": "error", "id": 1, "error": { "code": .., "message": .. } }`. Subscriptions
+//! send `{ "type": "event", "id": 2, "data": .. }` for every event, then
+//! `{ "type": "complete", "id": 2 }` when the stream ends or is unsubscribed. Both are
+//! dispatched like `/_laz/rpc` calls, so they run through the function's own route with
+//! the headers of the upgrade request.
+//!
+//! A socket runs at most [`MAX_CALLS`] calls and [`MAX_SUBSCRIPTIONS`] subscriptions at once.
+//! Further calls wait for a running one to finish, and further subscriptions are refused.
+//! Frames are read no faster than the client takes the answers.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{Extensions, HeaderMap, StatusCode},
+    response::Response,
+};
+use futures_util::{SinkExt, StreamExt};
+use laz_types::RpcErrorBody;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    sync::{mpsc, Semaphore},
+    task::JoinHandle,
+};
+
+use crate::dispatch::{Call, Dispatcher};
+use crate::middleware::{error_body, result_value};
+use crate::RpcError;
+
+/// Largest call result read, matching axum's default body limit
+const MAX_RESULT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Calls a socket runs at once
+const MAX_CALLS: usize = 32;
+
+/// Subscriptions a socket keeps open at once
+const MAX_SUBSCRIPTIONS: usize = 64;
+
+/// Frames waiting to be sent before calls and subscriptions wait for the client
+const FRAME_CAPACITY: usize = 256;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientFrame {
+    Call {
+        id: u64,
+        #[serde(flatten)]
+        call: Call,
+    },
+    Subscribe {
+        id: u64,
+        #[serde(flatten)]
+        call: Call,
+    },
+    Unsubscribe {
+        id: u64,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerFrame {
+    Result { id: u64, result: Value },
+    /// `id` is left out for frames that couldn't be read
+    Error { id: Option<u64>, error: RpcErrorBody },
+    Event { id: u64, data: Value },
+    Complete { id: u64 },
+}
+
+/// Handler of the transport route, upgrading the connection
+pub(crate) async fn upgrade(
+    State(dispatcher): State<Arc<Dispatcher>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| serve(socket, dispatcher, headers))
+}
+
+async fn serve(socket: WebSocket, dispatcher: Arc<Dispatcher>, headers: HeaderMap) {
+    let headers = Arc::new(headers);
+    let (mut sink, mut stream) = socket.split();
+    // Calls and subscriptions run concurrently, handing their frames to the writer to send
+    let (frames, mut outgoing) = mpsc::channel::<ServerFrame>(FRAME_CAPACITY);
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = outgoing.recv().await {
+            let text = serde_json::to_string(&frame).expect("frames always serialize");
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+    let calls = Arc::new(Semaphore::new(MAX_CALLS));
+    // Subscriptions by id, with the number telling a resubscription from the one it replaced
+    let mut subscriptions: HashMap<u64, (u64, JoinHandle<()>)> = HashMap::new();
+    let mut subscribed = 0;
+    let (finished, mut finishing) = mpsc::unbounded_channel();
+
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let frame = match serde_json::from_str::<ClientFrame>(&text) {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        let error = RpcError::bad_request(format!("Invalid frame: {}", e));
+                        let _ = frames.send(ServerFrame::Error { id: None, error: error.body }).await;
+                        continue;
+                    }
+                };
+                match frame {
+                    ClientFrame::Call { id, call } => {
+                        let permit = calls.clone().acquire_owned().await.expect("the semaphore is never closed");
+                        let (dispatcher, headers, frames) = (dispatcher.clone(), headers.clone(), frames.clone());
+                        tokio::spawn(async move {
+                            let _ = frames.send(run_call(&dispatcher, &headers, id, call).await).await;
+                            drop(permit);
+                        });
+                    }
+                    ClientFrame::Subscribe { id, call } => {
+                        if subscriptions.len() >= MAX_SUBSCRIPTIONS && !subscriptions.contains_key(&id) {
+                            let message = format!("A socket can't keep more than {} subscriptions", MAX_SUBSCRIPTIONS);
+                            let error = RpcError::new(StatusCode::TOO_MANY_REQUESTS, "too_many_subscriptions", message);
+                            let _ = frames.send(ServerFrame::Error { id: Some(id), error: error.body }).await;
+                            continue;
+                        }
+                        let (dispatcher, headers, frames) = (dispatcher.clone(), headers.clone(), frames.clone());
+                        subscribed += 1;
+                        let (number, finished) = (subscribed, finished.clone());
+                        let task = tokio::spawn(async move {
+                            run_subscription(&dispatcher, &headers, id, call, &frames).await;
+                            let _ = finished.send((id, number));
+                        });
+                        if let Some((_, previous)) = subscriptions.insert(id, (number, task)) {
+                            previous.abort();
+                        }
+                    }
+                    ClientFrame::Unsubscribe { id } => {
+                        if let Some((_, task)) = subscriptions.remove(&id) {
+                            task.abort();
+                            let _ = frames.send(ServerFrame::Complete { id }).await;
+                        }
+                    }
+                }
+            }
+            Some((id, number)) = finishing.recv() => {
+                if subscriptions.get(&id).is_some_and(|(current, _)| *current == number) {
+                    subscriptions.remove(&id);
+                }
+            }
+        }
+    }
+    for (_, task) in subscriptions.values() {
+        task.abort();
+    }
+    writer.abort();
+}
+
+async fn run_call(dispatcher: &Dispatcher, headers: &HeaderMap, id: u64, call: Call) -> ServerFrame {
+    let response = match dispatcher.run(call, headers, Extensions::new(), false).await {
+        Ok(response) => response,
+        Err(error) => return ServerFrame::Error { id: Some(id), error: error.body },
+    };
+    let status = response.status();
+    let body = match axum::body::to_bytes(response.into_body(), MAX_RESULT_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            let error = RpcError::internal("Result is too large to send");
+            return ServerFrame::Error { id: Some(id), error: error.body };
+        }
+    };
+    if status.is_success() {
+        ServerFrame::Result { id, result: result_value(&body) }
+    } else {
+        ServerFrame::Error { id: Some(id), error: error_body(status, &body) }
+    }
+}
+
+async fn run_subscription(
+    dispatcher: &Dispatcher,
+    headers: &HeaderMap,
+    id: u64,
+    call: Call,
+    frames: &mpsc::Sender<ServerFrame>,
+) {
+    let response = match dispatcher.run(call, headers, Extensions::new(), true).await {
+        Ok(response) => response,
+        Err(error) => {
+            let _ = frames.send(ServerFrame::Error { id: Some(id), error: error.body }).await;
+            return;
+        }
+    };
+    let status = response.status();
+    if !status.is_success() {
+        let body = axum::body::to_bytes(response.into_body(), MAX_RESULT_BYTES).await.unwrap_or_default();
+        let _ = frames.send(ServerFrame::Error { id: Some(id), error: error_body(status, &body) }).await;
+        return;
+    }
+
+    let mut events = response.into_body().into_data_stream();
+    // Kept as bytes, since a chunk may end partway through a character
+    let mut buffer = Vec::new();
+    while let Some(Ok(chunk)) = events.next().await {
+        buffer.extend_from_slice(&chunk);
+        for data in take_events(&mut buffer) {
+            if frames.send(ServerFrame::Event { id, data }).await.is_err() {
+                return;
+            }
+        }
+    }
+    let _ = frames.send(ServerFrame::Complete { id }).await;
+}
+
+/// Data of the complete server-sent events at the start of `buffer`, removing them from it.
+/// Data that isn't JSON is passed on as a string.
+fn take_events(buffer: &mut Vec<u8>) -> Vec<Value> {
+    let mut events = Vec::new();
+    while let Some(end) = buffer.windows(2).position(|pair| pair == b"\n\n") {
+        let event: Vec<u8> = buffer.drain(..end + 2).collect();
+        let event = String::from_utf8_lossy(&event);
+        let data: Vec<&str> = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| data.strip_prefix(' ').unwrap_or(data))
+            .collect();
+        // Comments and keep-alives carry no data
+        if data.is_empty() {
+            continue;
+        }
+        let data = data.join("\n");
+        events.push(serde_json::from_str(&data).unwrap_or(Value::String(data)));
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_events_are_taken_once_complete() {
+        let mut buffer = b"data: {\"n\":1}\n\n: keep-alive\n\ndata: plain\n\ndata: {\"n\"".to_vec();
+        assert_eq!(take_events(&mut buffer), [json!({ "n": 1 }), json!("plain")]);
+        assert_eq!(buffer, b"data: {\"n\"");
+
+        // A character split between chunks is decoded once the event is complete
+        let event = "data: \"caf\u{e9}\"\n\n".as_bytes();
+        let (first, second) = event.split_at(event.len() - 4);
+        let mut buffer = first.to_vec();
+        assert!(take_events(&mut buffer).is_empty());
+        buffer.extend_from_slice(second);
+        assert_eq!(take_events(&mut buffer), [json!("caf\u{e9}")]);
+    }
+
+    #[test]
+    fn test_frames_use_tagged_json() {
+        let frame: ClientFrame =
+            serde_json::from_value(json!({ "type": "call", "id": 3, "function": "get_tree", "params": { "id": 7 } }))
+                .unwrap();
+        assert!(matches!(frame, ClientFrame::Call { id: 3, ref call } if call.function == "get_tree"));
+        assert_eq!(
+            serde_json::to_value(ServerFrame::Event { id: 3, data: json!(true) }).unwrap(),
+            json!({ "type": "event", "id": 3, "data": true })
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Tracing spans around RPC calls.
+//!
+//! Calls of the functions found by [route matching](crate#route-matching) run inside an
+//! `rpc` span carrying `rpc.function`, `rpc.is_mutation` and `rpc.schema_fingerprint`. The
+//! span's `otel.name` is the function name, so OpenTelemetry exporters name it after the
+//! function too. `http.status_code` is recorded once the handler answers.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use laz_types::{EndpointDiscovery, FunctionMetadata};
+use tracing::{field::Empty, Instrument};
+
+use crate::matching::RouteTable;
+
+/// Routes of the traced functions, with their schema fingerprints
+pub(crate) struct Tracer {
+    routes: RouteTable,
+    /// Fingerprints by function name, computed once
+    fingerprints: HashMap<String, String>,
+}
+
+impl Tracer {
+    pub(crate) fn new(functions: &[&FunctionMetadata], endpoints: &[EndpointDiscovery]) -> Self {
+        Self {
+            routes: RouteTable::new(functions.iter().copied(), endpoints),
+            fingerprints: functions
+                .iter()
+                .map(|function| (function.function_name.clone(), function.schema_fingerprint()))
+                .collect(),
+        }
+    }
+}
+
+/// Middleware running calls of RPC functions inside a span named after them
+pub(crate) async fn trace(State(tracer): State<Arc<Tracer>>, request: Request, next: Next) -> Response {
+    let Some(function) = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| tracer.routes.find(path.as_str(), request.method().as_str()))
+    else {
+        return next.run(request).await;
+    };
+    let span = tracing::info_span!(
+        "rpc",
+        otel.name = %function.function_name,
+        rpc.function = %function.function_name,
+        rpc.is_mutation = function.is_mutation,
+        rpc.schema_fingerprint = tracer.fingerprints.get(&function.function_name).map(String::as_str),
+        http.status_code = Empty,
+    );
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.status_code", response.status().as_u16());
+    response
+}
@@ -0,0 +1,101 @@
+//! Per-topic broadcast channels for `rpc_subscription` handlers.
+//!
+//! Anything in the app can [`publish`] an event to a topic, and every stream returned by
+//! [`subscribe`] for that topic sends it to its client as a server-sent event. Topics are
+//! created on first use. Subscribers that fall more than [`CAPACITY`] events behind skip
+//! the ones they missed.
+//!
+//! ```rust,ignore
+//! #[rpc_subscription(output = TreeEvent, topic = "trees")]
+//! pub async fn tree_feed() -> impl IntoResponse {
+//!     laz_server::topics::subscribe("trees")
+//! }
+//!
+//! laz_server::topics::publish("trees", &TreeEvent::Planted { id })?;
+//! ```
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use axum::{
+    extract::Path,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures_util::Stream;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::RpcError;
+
+/// Events buffered per topic for subscribers that are behind
+pub const CAPACITY: usize = 128;
+
+/// Senders by topic; events are serialized once, however many subscribers there are
+static TOPICS: OnceLock<Mutex<HashMap<String, broadcast::Sender<Arc<str>>>>> = OnceLock::new();
+
+fn sender(topic: &str) -> broadcast::Sender<Arc<str>> {
+    let mut topics = TOPICS.get_or_init(Default::default).lock().unwrap_or_else(PoisonError::into_inner);
+    topics.entry(topic.to_string()).or_insert_with(|| broadcast::channel(CAPACITY).0).clone()
+}
+
+/// Send `event` to every current subscriber of `topic`, returning how many there are
+pub fn publish<T: Serialize>(topic: &str, event: &T) -> Result<usize, serde_json::Error> {
+    let data: Arc<str> = serde_json::to_string(event)?.into();
+    // Sending fails only when nobody is subscribed
+    Ok(sender(topic).send(data).unwrap_or(0))
+}
+
+/// Server-sent events carrying everything published to `topic` from now on, each with one
+/// JSON-encoded event
+pub fn subscribe(topic: &str) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = sender(topic).subscribe();
+    let topic = topic.to_string();
+    let events = futures_util::stream::unfold(receiver, move |mut receiver| {
+        let topic = topic.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(data) => return Some((Ok(Event::default().data(&*data)), receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Subscriber of topic {} skipped {} events", topic, skipped);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Handler of `/_laz/topics/{topic}`, serving the topics declared by public subscriptions.
+/// The subscription's own route, and whatever checks credentials there, never runs, so
+/// topics of functions declaring `auth` are only streamed from that route.
+pub(crate) async fn serve(Path(topic): Path<String>) -> Response {
+    let declaring: Vec<_> = laz_types::get_public_function_metadata()
+        .into_iter()
+        .filter(|function| function.topic.as_deref() == Some(topic.as_str()))
+        .collect();
+    if declaring.is_empty() || declaring.iter().any(|function| function.auth.is_some()) {
+        return RpcError::not_found(format!("Unknown topic `{}`", topic)).into_response();
+    }
+    subscribe(&topic).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_published_events() {
+        assert_eq!(publish("saplings", &1).unwrap(), 0);
+        let mut events = subscribe("saplings").into_response().into_body().into_data_stream();
+        assert_eq!(publish("saplings", &serde_json::json!({ "id": 7 })).unwrap(), 1);
+        let chunk = events.next().await.unwrap().unwrap();
+        assert_eq!(&chunk[..], b"data: {\"id\":7}\n\n");
+    }
+}
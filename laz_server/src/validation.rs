@@ -0,0 +1,199 @@
+//! Validation of JSON request bodies and query strings against the schemas registered by
+//! the RPC macros.
+//!
+//! Requests that don't match are answered with `422 Unprocessable Entity` before the
+//! handler runs, listing every mismatch with the path to the offending value. Bodies that
+//! aren't JSON and query strings that don't decode are left for the handler's extractor to
+//! reject.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use laz_types::{FieldSchema, TypeSchema};
+use serde_json::{Map, Value};
+
+use crate::matching::RouteTable;
+use crate::RpcError;
+
+/// Largest body read for validation, matching axum's default body limit
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Middleware rejecting JSON bodies and query strings that don't match their function's
+/// input schema
+pub(crate) async fn validate_request(State(routes): State<Arc<RouteTable>>, request: Request, next: Next) -> Response {
+    let schemas = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| routes.find(path.as_str(), request.method().as_str()))
+        .and_then(|function| laz_types::find_function_schemas(&function.function_name));
+    if let Some(schema) = schemas.and_then(|schemas| schemas.query.as_ref()) {
+        if let Err(error) = check_query(request.uri().query().unwrap_or_default(), schema) {
+            return error.into_response();
+        }
+    }
+    let schema = schemas.and_then(|schemas| schemas.request.as_ref());
+    let is_json = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let Some(schema) = schema.filter(|_| is_json) else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return RpcError::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", "Request body is too large")
+                .into_response();
+        }
+    };
+    if let Err(error) = check_body(&bytes, schema) {
+        return error.into_response();
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+/// Check a JSON body against `schema`; bodies that don't parse are let through
+fn check_body(bytes: &[u8], schema: &TypeSchema) -> Result<(), RpcError> {
+    let Ok(value) = serde_json::from_slice::<Value>(bytes) else {
+        return Ok(());
+    };
+    check_value(&value, schema)
+}
+
+/// Check a query string against `schema`; query strings that don't decode are let through
+fn check_query(query: &str, schema: &TypeSchema) -> Result<(), RpcError> {
+    match query_value(query, Some(schema)) {
+        Some(value) => check_value(&value, schema),
+        None => Ok(()),
+    }
+}
+
+/// A query string or form body as a JSON object, with values typed by `schema` where it has
+/// a field of their name; `None` when it doesn't decode
+pub(crate) fn query_value(query: &str, schema: Option<&TypeSchema>) -> Option<Value> {
+    let pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query).ok()?;
+    let fields = match schema.map(resolve) {
+        Some(TypeSchema::Struct(s)) => s.fields.as_slice(),
+        _ => &[],
+    };
+    Some(Value::Object(query_object(pairs, fields)))
+}
+
+/// Query parameters as the JSON object they stand for. They all arrive as strings, so those
+/// of fields typed as numbers or booleans are parsed first; what doesn't parse stays a
+/// string, for validation to report.
+fn query_object(pairs: Vec<(String, String)>, fields: &[FieldSchema]) -> Map<String, Value> {
+    pairs
+        .into_iter()
+        .map(|(name, text)| {
+            let field_type = fields.iter().find(|f| f.wire_name() == name).map(|f| resolve(&f.field_type));
+            let parsed = match field_type {
+                Some(TypeSchema::Primitive(ty) | TypeSchema::NonZero(ty))
+                    if !matches!(ty.as_str(), "String" | "str" | "char" | "Uuid") =>
+                {
+                    serde_json::from_str(&text).ok().filter(|v: &Value| v.is_number() || v.is_boolean())
+                }
+                _ => None,
+            };
+            (name, parsed.unwrap_or(Value::String(text)))
+        })
+        .collect()
+}
+
+/// `schema` with references, newtypes and options looked through
+fn resolve(schema: &TypeSchema) -> &TypeSchema {
+    match schema {
+        TypeSchema::Ref(name) => laz_types::find_type_schema(name).map_or(schema, resolve),
+        TypeSchema::Newtype(inner) => resolve(inner),
+        TypeSchema::Container { container_type, type_params } if container_type == "Option" && type_params.len() == 1 => {
+            resolve(&type_params[0])
+        }
+        _ => schema,
+    }
+}
+
+/// Check a JSON value against `schema`, describing every mismatch in the error
+pub(crate) fn check_value(value: &Value, schema: &TypeSchema) -> Result<(), RpcError> {
+    laz_types::validate(value, schema).map_err(|errors| {
+        let message = match errors.as_slice() {
+            [error] => error.to_string(),
+            _ => format!("{} values don't match the input schema", errors.len()),
+        };
+        RpcError::new(StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", message)
+            .with_details(serde_json::to_value(errors).unwrap_or(Value::Null))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rpc_mutation, rpc_query, LazSchema};
+
+    #[allow(dead_code)]
+    #[derive(LazSchema, serde::Deserialize)]
+    struct SaplingFixture {
+        name: String,
+        height: u32,
+    }
+
+    #[allow(dead_code)]
+    #[rpc_mutation]
+    async fn plant_sapling(axum::Json(body): axum::Json<SaplingFixture>) {
+        let _ = body;
+    }
+
+    #[allow(dead_code)]
+    #[derive(LazSchema, serde::Deserialize)]
+    struct SaplingSearchFixture {
+        species: String,
+        min_height: Option<u32>,
+        potted: bool,
+    }
+
+    #[allow(dead_code)]
+    #[rpc_query]
+    async fn search_saplings(axum::extract::Query(search): axum::extract::Query<SaplingSearchFixture>) {
+        let _ = search;
+    }
+
+    #[test]
+    fn test_query_strings_are_read_by_their_schema() {
+        let schemas = laz_types::find_function_schemas("search_saplings").expect("search_saplings not registered");
+        assert!(schemas.request.is_none());
+        let schema = schemas.query.as_ref().expect("no query schema");
+        assert!(check_query("species=12&min_height=3&potted=true", schema).is_ok());
+        assert!(check_query("species=oak&potted=false", schema).is_ok());
+
+        let error = check_query("species=oak&min_height=tall", schema).unwrap_err();
+        assert_eq!(error.status, StatusCode::UNPROCESSABLE_ENTITY);
+        let details = error.body.details.expect("no details");
+        let paths: Vec<_> = details.as_array().unwrap().iter().map(|e| e["path"].clone()).collect();
+        assert_eq!(paths, ["$.min_height", "$.potted"]);
+    }
+
+    #[test]
+    fn test_mismatched_bodies_list_every_field_path() {
+        let schemas = laz_types::find_function_schemas("plant_sapling").expect("plant_sapling not registered");
+        assert!(matches!(schemas.response, TypeSchema::Unit));
+        let schema = schemas.request.as_ref().expect("no request schema");
+        assert!(check_body(br#"{"name": "oak", "height": 12}"#, schema).is_ok());
+        // Malformed JSON is the extractor's to reject
+        assert!(check_body(b"{", schema).is_ok());
+
+        let error = check_body(br#"{"name": 7, "height": -1}"#, schema).unwrap_err();
+        assert_eq!(error.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.body.code, "validation_failed");
+        let details = error.body.details.expect("no details");
+        let paths: Vec<_> = details.as_array().unwrap().iter().map(|e| e["path"].clone()).collect();
+        assert_eq!(paths, ["$.name", "$.height"]);
+    }
+}
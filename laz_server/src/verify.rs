@@ -0,0 +1,168 @@
+//! Startup check that the registered RPC functions and the app's routes agree.
+//!
+//! A function is routed when the app serves its resolved path with its method, or when it
+//! is mounted by [`routes`](crate::routes). Routes under `/_` belong to Loco and laz and are
+//! never reported as unannotated.
+
+use std::fmt;
+
+use laz_types::{EndpointDiscovery, FunctionMetadata};
+use serde::Serialize;
+
+use crate::RouteEntry;
+
+/// Disagreements between the RPC functions and the app's routes found by [`verify_routes`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RouteReport {
+    /// Names of the functions no route serves
+    pub unroutable_functions: Vec<String>,
+    /// Routes serving no RPC function, with the methods no function claims
+    pub unannotated_routes: Vec<EndpointDiscovery>,
+}
+
+impl RouteReport {
+    /// Whether every function is routed and every route annotated
+    pub fn is_clean(&self) -> bool {
+        self.unroutable_functions.is_empty() && self.unannotated_routes.is_empty()
+    }
+
+    /// Fail when some function has no route, so startup stops before clients break.
+    /// Unannotated routes are allowed, since apps commonly serve more than RPC functions.
+    pub fn require_routed(self) -> loco_rs::Result<Self> {
+        if self.unroutable_functions.is_empty() {
+            Ok(self)
+        } else {
+            Err(loco_rs::Error::Message(self.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for RouteReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return f.write_str("every RPC function is routed and every route annotated");
+        }
+        let mut parts = Vec::new();
+        if !self.unroutable_functions.is_empty() {
+            parts.push(format!("RPC functions without a route: {}", self.unroutable_functions.join(", ")));
+        }
+        if !self.unannotated_routes.is_empty() {
+            let routes: Vec<String> = self
+                .unannotated_routes
+                .iter()
+                .map(|route| format!("{} {}", route.methods.join("|"), route.uri))
+                .collect();
+            parts.push(format!("routes without an RPC function: {}", routes.join(", ")));
+        }
+        f.write_str(&parts.join("; "))
+    }
+}
+
+/// Cross-reference every registered RPC function against the app's routes, logging a
+/// warning for each function no route serves and each route serving no function.
+/// Call [`RouteReport::require_routed`] on the result to fail startup instead.
+///
+/// ```rust,ignore
+/// laz_server::verify_routes(&app_routes).require_routed()?;
+/// ```
+pub fn verify_routes(app_routes: &loco_rs::controller::AppRoutes) -> RouteReport {
+    let endpoints: Vec<EndpointDiscovery> = crate::collect_routes(app_routes)
+        .into_iter()
+        .map(|(uri, methods)| EndpointDiscovery { uri, methods })
+        .collect();
+    let report = route_report(&laz_types::get_all_function_metadata(), &endpoints);
+    for function in &report.unroutable_functions {
+        tracing::warn!(rpc.function = %function, "RPC function has no route; clients can't call it");
+    }
+    for route in &report.unannotated_routes {
+        tracing::warn!(
+            http.route = %route.uri,
+            http.methods = %route.methods.join(","),
+            "Route serves no RPC function; it is missing from the metadata"
+        );
+    }
+    report
+}
+
+pub(crate) fn route_report(functions: &[&FunctionMetadata], endpoints: &[EndpointDiscovery]) -> RouteReport {
+    let mounted: Vec<&str> = inventory::iter::<RouteEntry>.into_iter().map(|entry| entry.path).collect();
+    let mut claimed: Vec<(String, String)> = Vec::new();
+    let mut unroutable_functions = Vec::new();
+    for function in functions {
+        let served = function.route(endpoints).filter(|(path, method)| {
+            mounted.contains(&path.as_str())
+                || endpoints.iter().any(|e| {
+                    &e.uri == path && e.methods.iter().any(|m| m.eq_ignore_ascii_case(method.as_str()))
+                })
+        });
+        match served {
+            Some((path, method)) => claimed.push((path, method.as_str().to_string())),
+            None => unroutable_functions.push(function.function_name.clone()),
+        }
+    }
+    unroutable_functions.sort();
+
+    let unannotated_routes = endpoints
+        .iter()
+        .filter(|e| !e.uri.starts_with("/_"))
+        .filter_map(|e| {
+            let methods: Vec<String> = e
+                .methods
+                .iter()
+                .filter(|m| !claimed.iter().any(|(path, method)| path == &e.uri && method.eq_ignore_ascii_case(m)))
+                .cloned()
+                .collect();
+            (!methods.is_empty()).then(|| EndpointDiscovery { uri: e.uri.clone(), methods })
+        })
+        .collect();
+    RouteReport { unroutable_functions, unannotated_routes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(name: &str, is_mutation: bool) -> FunctionMetadata {
+        laz_types::make_function_metadata(
+            name.to_string(),
+            vec![],
+            laz_types::TypeSchema::Unit,
+            None,
+            "()".to_string(),
+            true,
+            is_mutation,
+        )
+    }
+
+    fn endpoint(uri: &str, methods: &[&str]) -> EndpointDiscovery {
+        EndpointDiscovery {
+            uri: uri.to_string(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_report_lists_unroutable_functions_and_unannotated_routes() {
+        let plant = function("plant_tree", true);
+        let mut fell = function("fell_tree", true);
+        fell.path = Some("/api/trees/fell".to_string());
+        let forest = function("list_forests", false);
+        let endpoints = [
+            endpoint("/api/trees/plant-tree", &["POST", "DELETE"]),
+            endpoint("/api/reports", &["GET"]),
+            endpoint("/_health", &["GET"]),
+        ];
+
+        let report = route_report(&[&plant, &fell, &forest], &endpoints);
+        assert_eq!(report.unroutable_functions, ["fell_tree", "list_forests"]);
+        assert_eq!(
+            report.unannotated_routes,
+            [endpoint("/api/trees/plant-tree", &["DELETE"]), endpoint("/api/reports", &["GET"])]
+        );
+        assert!(report.clone().require_routed().is_err());
+
+        let report = route_report(&[&plant], &endpoints[..1]);
+        assert!(report.unroutable_functions.is_empty());
+        assert!(report.require_routed().is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{FnArg, ItemFn, Pat, ReturnType, Type, TypePath, parse_macro_input};
+use syn::{FnArg, ItemFn, Pat, ReturnType, Token, Type, TypePath, parse::{Parse, ParseStream}, parse_macro_input, spanned::Spanned};
 
 /// Helper struct to hold parameter information during macro expansion
 struct ParamInfoParts {
@@ -10,58 +10,394 @@ struct ParamInfoParts {
     extractor: String,
     inner_type: String,
     inner_type_path: Option<TypePath>,
+    /// `laz_types::path_params(..)` call for `Path<T>` extractors
+    path_params: Option<proc_macro2::TokenStream>,
+    /// Header read by a `TypedHeader<T>` extractor
+    header: Option<String>,
+    /// Credentials carried by a `TypedHeader<Authorization<Bearer>>` or `<Basic>` extractor
+    credentials: Option<laz_types::AuthScheme>,
+}
+
+impl ParamInfoParts {
+    fn role(&self) -> laz_types::ParamRole {
+        laz_types::ParamRole::of_extractor(&self.extractor)
+    }
+
+    /// Name of the extracted type, used for schema lookup
+    fn type_name(&self) -> Option<String> {
+        let type_path = self.inner_type_path.as_ref()?;
+        Some(type_path.path.segments.last()?.ident.to_string())
+    }
 }
 
 /// Marks a function as an RPC query (GET request handler)
 /// Place this OUTERMOST (above #[debug_handler] and route macros)
 #[proc_macro_attribute]
 pub fn rpc_query(attr: TokenStream, item: TokenStream) -> TokenStream {
-    build_metadata(attr, item, false)
+    build_metadata(attr, item, RpcKind::Query)
 }
 
 /// Marks a function as an RPC mutation (POST/PUT/PATCH/DELETE handler)
 /// Place this OUTERMOST (above #[debug_handler] and route macros)
 #[proc_macro_attribute]
 pub fn rpc_mutation(attr: TokenStream, item: TokenStream) -> TokenStream {
-    build_metadata(attr, item, true)
+    build_metadata(attr, item, RpcKind::Mutation)
+}
+
+/// Marks a function as an RPC subscription: a GET handler streaming `output` values as
+/// server-sent events, one JSON value per event
+/// Place this OUTERMOST (above #[debug_handler] and route macros)
+#[proc_macro_attribute]
+pub fn rpc_subscription(attr: TokenStream, item: TokenStream) -> TokenStream {
+    build_metadata(attr, item, RpcKind::Subscription)
+}
+
+/// Marks a function as a WebSocket RPC: a GET handler taking a `WebSocketUpgrade`, over
+/// whose socket clients send `input` messages and receive `output` ones, one JSON value per
+/// text frame
+/// Place this OUTERMOST (above #[debug_handler] and route macros)
+#[proc_macro_attribute]
+pub fn rpc_ws(attr: TokenStream, item: TokenStream) -> TokenStream {
+    build_metadata(attr, item, RpcKind::WebSocket)
+}
+
+/// Lets the RPC attributes be used on associated functions of an `impl` block, inherent or
+/// of a trait; their registrations are placed after the block, naming the handlers through
+/// the implementing type, e.g. `TreeController::list`
+#[proc_macro_attribute]
+pub fn rpc_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(proc_macro2::Span::call_site(), "`rpc_impl` takes no options")
+            .to_compile_error()
+            .into();
+    }
+    let mut item_impl = parse_macro_input!(item as syn::ItemImpl);
+    // The registrations name the handler from outside the impl, which has no type parameters
+    if let Some(param) = item_impl.generics.params.iter().find(|p| !matches!(p, syn::GenericParam::Lifetime(_))) {
+        return syn::Error::new_spanned(param, "`rpc_impl` doesn't support generic impls")
+            .to_compile_error()
+            .into();
+    }
+    let Type::Path(self_ty) = &*item_impl.self_ty else {
+        return syn::Error::new_spanned(&item_impl.self_ty, "`rpc_impl` needs an impl for a named type")
+            .to_compile_error()
+            .into();
+    };
+    let owner = Owner {
+        self_ty: self_ty.clone(),
+        trait_path: item_impl.trait_.as_ref().map(|(_, path, _)| path.clone()),
+    };
+    // Like a method's own `#[cfg(...)]`, one on the impl also gates its registrations
+    let impl_cfgs: Vec<syn::Attribute> =
+        item_impl.attrs.iter().filter(|a| a.path().is_ident("cfg")).cloned().collect();
+    let impl_cfgs = quote! { #(#impl_cfgs)* };
+    let mut registrations = Vec::new();
+    for impl_item in &mut item_impl.items {
+        let syn::ImplItem::Fn(method) = impl_item else { continue };
+        let Some(index) = method.attrs.iter().position(|a| rpc_kind(a.path()).is_some()) else { continue };
+        let rpc = method.attrs.remove(index);
+        let kind = rpc_kind(rpc.path()).unwrap_or(RpcKind::Query);
+        let attr = match rpc.meta {
+            syn::Meta::Path(_) => proc_macro2::TokenStream::new(),
+            syn::Meta::List(list) => list.tokens,
+            syn::Meta::NameValue(meta) => {
+                registrations.push(syn::Error::new_spanned(meta, "expected `#[rpc_query(...)]`").to_compile_error());
+                continue;
+            }
+        };
+        let handler = ItemFn {
+            attrs: std::mem::take(&mut method.attrs),
+            vis: method.vis.clone(),
+            sig: method.sig.clone(),
+            block: Box::new(method.block.clone()),
+        };
+        let expanded = proc_macro2::TokenStream::from(expand_handler(attr.into(), handler.clone(), kind, Some(&owner)));
+        // The handler leads the expansion and goes back in the impl; on errors only
+        // `compile_error!`s are emitted, so the method is kept as written
+        let mut items = match syn::parse2::<syn::File>(expanded) {
+            Ok(file) => file.items,
+            Err(err) => vec![syn::Item::Verbatim(err.to_compile_error())],
+        };
+        let emitted = match items.first() {
+            Some(syn::Item::Fn(_)) => {
+                let expanded_handler = items.remove(0);
+                syn::parse2::<syn::ImplItemFn>(quote! { #expanded_handler })
+            }
+            _ => syn::parse2::<syn::ImplItemFn>(quote! { #handler }),
+        };
+        match emitted {
+            Ok(emitted) => *method = emitted,
+            Err(err) => registrations.push(err.to_compile_error()),
+        }
+        registrations.extend(items.into_iter().map(|item| quote! { #impl_cfgs #item }));
+    }
+    quote! {
+        #item_impl
+        #(#registrations)*
+    }
+    .into()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RpcKind {
+    Query,
+    Mutation,
+    Subscription,
+    WebSocket,
+}
+
+/// Kind of RPC attribute a path names, e.g. `rpc_query` or `laz_server::rpc_query`
+fn rpc_kind(path: &syn::Path) -> Option<RpcKind> {
+    match path.segments.last()?.ident.to_string().as_str() {
+        "rpc_query" => Some(RpcKind::Query),
+        "rpc_mutation" => Some(RpcKind::Mutation),
+        "rpc_subscription" => Some(RpcKind::Subscription),
+        "rpc_ws" => Some(RpcKind::WebSocket),
+        _ => None,
+    }
+}
+
+/// Type whose `impl` block declares a handler
+struct Owner {
+    self_ty: TypePath,
+    trait_path: Option<syn::Path>,
+}
+
+impl Owner {
+    /// The type as named outside the impl, e.g. `<TreeController as Resource>`
+    fn qualified(&self) -> proc_macro2::TokenStream {
+        let self_ty = &self.self_ty;
+        match &self.trait_path {
+            Some(trait_path) => quote! { <#self_ty as #trait_path> },
+            None => quote! { #self_ty },
+        }
+    }
+
+    /// Name recorded as the handler's `self_type`
+    fn name(&self) -> String {
+        let path_string = |path: &syn::Path| {
+            path.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>().join("::")
+        };
+        match &self.trait_path {
+            Some(trait_path) => format!("<{} as {}>", path_string(&self.self_ty.path), path_string(trait_path)),
+            None => path_string(&self.self_ty.path),
+        }
+    }
+
+    /// Prefix keeping the registrations of same-named methods in one module apart
+    fn ident_prefix(&self) -> String {
+        let last_ident = |path: &syn::Path| path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+        match &self.trait_path {
+            Some(trait_path) => format!("{}_{}_", last_ident(&self.self_ty.path), last_ident(trait_path)),
+            None => format!("{}_", last_ident(&self.self_ty.path)),
+        }
+    }
+
+    /// `tokens` with `Self` replaced by the implementing type, so they mean the same
+    /// outside the impl
+    fn resolve_self(&self, tokens: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let self_ty = &self.self_ty;
+        tokens
+            .into_iter()
+            .flat_map(|tree| match tree {
+                proc_macro2::TokenTree::Ident(ident) if ident == "Self" => quote! { #self_ty },
+                proc_macro2::TokenTree::Group(group) => {
+                    let mut resolved = proc_macro2::Group::new(group.delimiter(), self.resolve_self(group.stream()));
+                    resolved.set_span(group.span());
+                    quote! { #resolved }
+                }
+                tree => quote! { #tree },
+            })
+            .collect()
+    }
 }
 
 /// Shared implementation that extracts metadata and registers it
-fn build_metadata(attr: TokenStream, item: TokenStream, is_mutation: bool) -> TokenStream {
+fn build_metadata(attr: TokenStream, item: TokenStream, kind: RpcKind) -> TokenStream {
     let input_fn = parse_macro_input!(item as ItemFn); // parse into syntax tree representing the function
+    expand_handler(attr, input_fn, kind, None)
+}
+
+/// Expansion of an RPC attribute: the handler followed by its registrations. `owner` is the
+/// type of the `impl` block declaring a handler under `rpc_impl`.
+fn expand_handler(attr: TokenStream, input_fn: ItemFn, kind: RpcKind, owner: Option<&Owner>) -> TokenStream {
+    let is_mutation = kind == RpcKind::Mutation;
+    let is_subscription = kind == RpcKind::Subscription;
+    let is_websocket = kind == RpcKind::WebSocket;
+    // Axum calls handlers as plain functions
+    if let Some(receiver) = input_fn.sig.receiver() {
+        return syn::Error::new_spanned(
+            receiver,
+            "RPC handlers can't take `self`; declare them as associated functions of an `#[rpc_impl]` block",
+        )
+        .to_compile_error()
+        .into();
+    }
+    // The handler keeps its signature; metadata is read from one meaning the same outside the impl
+    let emitted_sig = input_fn.sig.clone();
+    let (input_fn, attr) = match owner {
+        Some(owner) => {
+            let sig = &input_fn.sig;
+            let resolved = ItemFn {
+                sig: match syn::parse2(owner.resolve_self(quote! { #sig })) {
+                    Ok(sig) => sig,
+                    Err(err) => return err.to_compile_error().into(),
+                },
+                ..input_fn
+            };
+            (resolved, owner.resolve_self(attr.into()).into())
+        }
+        None => (input_fn, attr),
+    };
     let function_name_ident = &input_fn.sig.ident; // Extract the function name identifier
     let function_name_str = function_name_ident.to_string(); // Convert to string for storage in metadata
+    // Metadata describes one concrete signature, which a type parameter doesn't have
+    if let Some(param) = input_fn.sig.generics.params.iter().find(|p| !matches!(p, syn::GenericParam::Lifetime(_))) {
+        return syn::Error::new_spanned(
+            param,
+            "RPC handlers can't be generic over types; wrap each instantiation in its own handler, \
+             e.g. `async fn list_users(..) -> Json<Vec<User>> { list::<User>(..).await }`",
+        )
+        .to_compile_error()
+        .into();
+    }
     let is_async = input_fn.sig.asyncness.is_some(); // Check if function is async
     let params_parts = extract_params(&input_fn.sig.inputs);
-    let return_type_str = extract_return_type(&input_fn.sig.output);
-    let attrs = &input_fn.attrs;
+    // `#[laz(...)]` is only meaningful to this macro; don't re-emit it on the handler
+    let attrs: Vec<&syn::Attribute> = input_fn
+        .attrs
+        .iter()
+        .filter(|a| !a.path().is_ident("laz"))
+        .collect();
+    // Registrations exist exactly when the handler does, so a handler compiled out by
+    // `#[cfg(...)]` is neither registered nor published
+    let cfgs: Vec<&syn::Attribute> = input_fn.attrs.iter().filter(|a| a.path().is_ident("cfg")).collect();
+    let cfgs = quote! { #(#cfgs)* };
     let vis = &input_fn.vis; // Preserve visibility (pub, pub(crate), etc.)
-    let sig = &input_fn.sig; // Preserve function signature (name, generics, parameters, return type)
+    let sig = &emitted_sig; // Preserve function signature (name, generics, parameters, return type)
     let block = &input_fn.block; // Preserve function body/block
     let params_array = build_params_array(&params_parts);
+    let is_multipart = params_parts.iter().any(|p| p.extractor == "Multipart");
+    let body_encoding = syn::Ident::new(
+        &format!("{:?}", laz_types::BodyEncoding::of_extractors(params_parts.iter().map(|p| p.extractor.as_str()))),
+        proc_macro2::Span::call_site(),
+    );
 
-    // Parse attribute arguments: input=Type, output=Type
-    let (attr_input, attr_output) = parse_io_attr(attr);
-    // Infer input type name if not provided: take first param with an inner_type_path
-    let inferred_input = params_parts.iter().find_map(|p| {
-        p.inner_type_path
-            .as_ref()
-            .map(|tp| tp.path.segments.last().unwrap().ident.to_string())
-    });
-    let input_type_name = attr_input.or(inferred_input);
+    // Parse attribute arguments: input=Type, output=Type, error=Type, deprecated="note"
+    let rpc_attr = match syn::parse::<RpcAttr>(attr) {
+        Ok(rpc_attr) => rpc_attr,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let stream_body = rpc_attr.stream_body;
+    // `unwrap = "ApiResult, Json"` names wrappers to look through, besides `Result` and `Json`
+    let wrappers = match &rpc_attr.unwrap {
+        Some(unwrap) => match parse_wrappers(unwrap) {
+            Ok(wrappers) => wrappers,
+            Err(message) => return unwrap.error(message),
+        },
+        None => Vec::new(),
+    };
+    let response_type = response_type(&input_fn.sig.output, &wrappers);
+    let idempotent = rpc_attr.idempotent;
+    let internal = rpc_attr.internal;
+    if idempotent && !is_mutation {
+        return syn::Error::new_spanned(&input_fn.sig.ident, "`idempotent` only applies to `rpc_mutation`")
+            .to_compile_error()
+            .into();
+    }
+    // Neither message type can be read off a handler that only takes the upgrade
+    if is_websocket && (rpc_attr.input.is_none() || rpc_attr.output.is_none()) {
+        return syn::Error::new_spanned(
+            &input_fn.sig.ident,
+            "`rpc_ws` needs the message types, e.g. `input = ClientMessage, output = ServerMessage`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if is_websocket && stream_body {
+        return syn::Error::new_spanned(&input_fn.sig.ident, "`stream_body` doesn't apply to `rpc_ws`")
+            .to_compile_error()
+            .into();
+    }
+    // Infer input type name if not provided: the body when there is one, else the query,
+    // else the first other param with an inner_type_path. Path params travel in the URL,
+    // never as the input; a query read alongside a body is recorded as its own param, and
+    // server-only extractors like `State<AppContext>` are never the input.
+    let role_rank = |p: &ParamInfoParts| match p.role() {
+        laz_types::ParamRole::Body => 0,
+        laz_types::ParamRole::Query => 1,
+        _ => 2,
+    };
+    let inferred_input = params_parts
+        .iter()
+        .filter(|p| p.path_params.is_none() && p.role().is_client_visible())
+        .filter(|p| p.inner_type_path.is_some())
+        .min_by_key(|p| role_rank(p))
+        .and_then(|p| p.type_name());
+    let mut instances = rpc_attr.instances;
+    // User types the function's schemas are built from, asserted to derive LazSchema
+    let mut schema_types = rpc_attr.schema_types;
+    if rpc_attr.input.is_none() {
+        let body = params_parts
+            .iter()
+            .filter(|p| ["Json", "Query", "Form"].contains(&p.extractor.as_str()))
+            .find_map(|p| p.inner_type_path.clone());
+        if let Some(body) = body {
+            collect_schema_types(&Type::Path(body), &mut schema_types);
+        }
+    }
+    let input_type_name = rpc_attr.input.or(inferred_input);
+    // `paginated` recognises the convention from the registered schemas at runtime
+    let pagination_tokens = match rpc_attr.paginated.as_ref().filter(|style| &***style != "false") {
+        None => quote! { None },
+        Some(paginated) if kind != RpcKind::Query => {
+            return paginated.error("`paginated` only applies to `rpc_query`");
+        }
+        Some(paginated) if input_type_name.is_none() => {
+            return paginated.error("`paginated` needs an input type holding the page or cursor");
+        }
+        Some(style) => {
+            let style_tokens = match &**style {
+                "true" => quote! { None },
+                name => match name.parse::<laz_types::PaginationStyle>() {
+                    Ok(style) => {
+                        let variant = syn::Ident::new(&format!("{:?}", style), proc_macro2::Span::call_site());
+                        quote! { Some(laz_types::PaginationStyle::#variant) }
+                    }
+                    Err(message) => return style.error(message),
+                },
+            };
+            let input = input_type_name.as_deref().unwrap_or_default();
+            quote! { laz_types::Pagination::infer(#input, &output_type_name, #style_tokens) }
+        }
+    };
 
-    // Output is required; if not provided, emit a compile error
-    let output_type_name = match attr_output {
+    // `output = TypeName` overrides the type inferred from the signature
+    let declared_output = rpc_attr.output.is_some();
+    let output_type_name = match rpc_attr.output {
         Some(t) => t,
-        None => {
+        // Streamed bodies are read as bytes, so their handlers may return any response
+        None if rpc_attr.stream_body => "Bytes".to_string(),
+        None if is_opaque_response(&response_type) => {
             return syn::Error::new_spanned(
-                &input_fn.sig.ident,
-                "rpc_query/rpc_mutation requires an `output = TypeName` attribute",
+                &input_fn.sig.output,
+                "can't infer the output type of this handler; add an `output = TypeName` attribute",
             )
             .to_compile_error()
             .into();
         }
+        None => {
+            let (name, instance) = resolve_type_name(&quote!(#response_type).to_string());
+            instances.extend(instance);
+            collect_schema_types(&response_type, &mut schema_types);
+            name
+        }
     };
+    let schema_assertions = schema_types.iter().map(|ty| {
+        quote::quote_spanned! {ty.span()=>
+            __laz_assert_schema::<#ty>();
+        }
+    });
 
     // Prepare tokens as string literals for interpolation
     let input_type_name_tokens: proc_macro2::TokenStream = if let Some(s) = &input_type_name {
@@ -71,34 +407,320 @@ fn build_metadata(attr: TokenStream, item: TokenStream, is_mutation: bool) -> To
         quote::quote! { None }
     };
     let output_type_name_lit = proc_macro2::Literal::string(&output_type_name);
+    let return_type_tokens = type_schema_tokens(&response_type, true);
+    // Only JSON bodies and query strings can be checked against a schema before the handler runs
+    let extractor_schema_tokens = |extractor: &str| match params_parts.iter().find(|p| p.extractor == extractor) {
+        Some(ParamInfoParts { inner_type_path: Some(inner), .. }) => {
+            let schema = type_schema_tokens(&Type::Path(inner.clone()), true);
+            quote! { Some(#schema) }
+        }
+        _ => quote! { None },
+    };
+    let request_schema_tokens = extractor_schema_tokens("Json");
+    let query_schema_tokens = extractor_schema_tokens("Query");
+    let declared_schema = |type_name: &str| {
+        if laz_types::is_primitive_type_name(type_name) {
+            quote! { laz_types::TypeSchema::Primitive(#type_name.to_owned()) }
+        } else {
+            // Resolved through the registry when used, like nested types
+            quote! { laz_types::TypeSchema::Ref(#type_name.to_owned()) }
+        }
+    };
+    // The upgrade answers with no body; the socket carries the declared messages
+    let (response_schema_tokens, message_schemas_tokens) = if is_websocket {
+        let client = declared_schema(input_type_name.as_deref().unwrap_or_default());
+        let server = declared_schema(&output_type_name);
+        (
+            quote! { laz_types::TypeSchema::Unit },
+            quote! { Some(laz_types::MessageSchemas { client: #client, server: #server }) },
+        )
+    } else if !declared_output {
+        (return_type_tokens.clone(), quote! { None })
+    } else {
+        (declared_schema(&output_type_name), quote! { None })
+    };
+    let method = match &rpc_attr.method {
+        Some(method) => match method.parse::<laz_types::HttpMethod>() {
+            Ok(method) => Some(method),
+            Err(message) => return method.error(message),
+        },
+        None => None,
+    };
+    let method_tokens = match method {
+        Some(method) => {
+            let variant = syn::Ident::new(&format!("{:?}", method), proc_macro2::Span::call_site());
+            quote! { Some(laz_types::HttpMethod::#variant) }
+        }
+        None => quote! { None },
+    };
+    let (handler_path, self_type_tokens) = match owner {
+        Some(owner) => {
+            let qualified = owner.qualified();
+            let name = owner.name();
+            (quote! { #qualified::#function_name_ident }, quote! { Some(#name.to_owned()) })
+        }
+        None => (quote! { #function_name_ident }, quote! { None }),
+    };
+    // `route` also mounts the handler at its path in `laz_server::routes()`
+    let route_registration = match (&rpc_attr.path, rpc_attr.route) {
+        (Some(path), true) => {
+            let variant = syn::Ident::new(
+                &format!("{:?}", method.unwrap_or(laz_types::HttpMethod::default_for(is_mutation))),
+                proc_macro2::Span::call_site(),
+            );
+            quote! {
+                #cfgs
+                #[allow(non_upper_case_globals)]
+                const _: fn() = || {
+                    ::inventory::submit! {
+                        ::laz_server::RouteEntry {
+                            path: #path,
+                            router: || ::laz_server::method_router(laz_types::HttpMethod::#variant, #handler_path),
+                        }
+                    };
+                };
+            }
+        }
+        (None, true) => {
+            return syn::Error::new_spanned(&input_fn.sig.ident, "`route` needs a `path = \"...\"`")
+                .to_compile_error()
+                .into();
+        }
+        (_, false) => quote! {},
+    };
+    // Without `auth`, a `TypedHeader<Authorization<Bearer>>` param says what the function requires
+    let auth = match &rpc_attr.auth {
+        Some(auth) => match auth.parse::<laz_types::AuthScheme>() {
+            Ok(scheme) => Some(scheme),
+            Err(message) => return auth.error(message),
+        },
+        None => params_parts.iter().find_map(|p| p.credentials.clone()),
+    };
+    let auth_tokens = match auth {
+        Some(laz_types::AuthScheme::Bearer) => quote! { Some(laz_types::AuthScheme::Bearer) },
+        Some(laz_types::AuthScheme::Basic) => quote! { Some(laz_types::AuthScheme::Basic) },
+        Some(laz_types::AuthScheme::ApiKey { header }) => {
+            quote! { Some(laz_types::AuthScheme::ApiKey { header: #header.to_owned() }) }
+        }
+        None => quote! { None },
+    };
+    for version in [&rpc_attr.since, &rpc_attr.removed].into_iter().flatten() {
+        let valid = version
+            .trim_start_matches('v')
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+        if !valid {
+            return version.error(format!("`{}` is not a version like `1.2` or `1.2.0`", &**version));
+        }
+    }
+    if let (Some(since), Some(removed)) = (&rpc_attr.since, &rpc_attr.removed) {
+        if laz_types::compare_versions(since, removed).is_ge() {
+            return removed.error("`removed` must be a later version than `since`");
+        }
+    }
+    let since_tokens = match &rpc_attr.since {
+        Some(version) => quote! { Some(#version.to_owned()) },
+        None => quote! { None },
+    };
+    let removed_tokens = match &rpc_attr.removed {
+        Some(version) => quote! { Some(#version.to_owned()) },
+        None => quote! { None },
+    };
+    let rate_limit_tokens = match &rpc_attr.rate_limit {
+        Some(rate_limit) => match rate_limit.parse::<laz_types::RateLimit>() {
+            Ok(laz_types::RateLimit { requests, period_secs }) => {
+                quote! { Some(laz_types::RateLimit { requests: #requests, period_secs: #period_secs }) }
+            }
+            Err(message) => return rate_limit.error(message),
+        },
+        None => quote! { None },
+    };
+    let tags = match &rpc_attr.tags {
+        Some(tags) => match parse_tags(tags) {
+            Ok(tags) => tags,
+            Err(message) => return tags.error(message),
+        },
+        None => Vec::new(),
+    };
+    let timeout_ms_tokens = match &rpc_attr.timeout_ms {
+        Some(timeout_ms) => match timeout_ms.replace('_', "").parse::<u64>() {
+            Ok(ms) if ms > 0 => quote! { Some(#ms) },
+            _ => return timeout_ms.error("`timeout_ms` expects a positive number of milliseconds"),
+        },
+        None => quote! { None },
+    };
+    let topic_tokens = match &rpc_attr.topic {
+        Some(topic) if kind != RpcKind::Subscription => return topic.error("`topic` only applies to `rpc_subscription`"),
+        Some(topic) if topic.is_empty() || topic.contains('/') => {
+            return topic.error("`topic` expects a name without `/`, e.g. `\"trees\"`")
+        }
+        Some(topic) => quote! { Some(#topic.to_owned()) },
+        None => quote! { None },
+    };
+    let cache_max_age_tokens = match &rpc_attr.cache {
+        Some(cache) if kind != RpcKind::Query => return cache.error("`cache` only applies to `rpc_query`"),
+        Some(cache) => match laz_types::parse_duration(cache) {
+            Ok(max_age) if max_age.as_secs() > 0 => {
+                let secs = max_age.as_secs();
+                quote! { Some(#secs) }
+            }
+            Ok(_) => return cache.error("`cache` needs a duration of at least one second"),
+            Err(message) => return cache.error(message),
+        },
+        None => quote! { None },
+    };
+    let status_tokens = match &rpc_attr.status {
+        Some(status) => match status.parse::<u16>() {
+            Ok(code @ 200..=399) => quote! { Some(#code) },
+            _ => return status.error("`status` expects a success status like `201`"),
+        },
+        None => quote! { None },
+    };
+    let error_statuses = match &rpc_attr.error_status {
+        Some(statuses) => match parse_statuses(statuses) {
+            Ok(codes) if codes.iter().all(|code| (400..=599).contains(code)) => codes,
+            _ => return statuses.error("`error_status` expects error statuses like `[404, 409]`"),
+        },
+        None => Vec::new(),
+    };
+    let namespace_tokens = match &rpc_attr.namespace {
+        Some(namespace) => quote! { Some(#namespace.to_owned()) },
+        None => quote! { None },
+    };
+    let path_tokens = match &rpc_attr.path {
+        Some(path) => quote! { Some(#path.to_owned()) },
+        None => quote! { None },
+    };
+    let example_tokens = match &rpc_attr.example {
+        Some(example) => quote! { Some(laz_types::json_or_string(#example)) },
+        None => quote! { None },
+    };
+    let error_type_name_tokens = match &rpc_attr.error {
+        Some(name) => quote! { Some(#name.to_owned()) },
+        None => quote! { None },
+    };
+    let description_tokens = match doc_comment(&input_fn.attrs) {
+        Some(doc) => quote! { Some(#doc.to_owned()) },
+        None => quote! { None },
+    };
+    let deprecated_tokens = match rpc_attr.deprecated.map(|note| note.value).or_else(|| deprecation(&input_fn.attrs)) {
+        Some(note) => quote! { Some(#note.to_owned()) },
+        None => quote! { None },
+    };
 
+    let ident_prefix = owner.map(Owner::ident_prefix).unwrap_or_default();
     let metadata_fn = syn::Ident::new(
-        &format!("__laz_get_metadata_{}", function_name_str),
+        &format!("__laz_get_metadata_{}{}", ident_prefix, function_name_str),
         proc_macro2::Span::call_site(),
     );
-    let function_name_lit = proc_macro2::Literal::string(&function_name_str);
+    let schemas_fn = syn::Ident::new(
+        &format!("__laz_get_schemas_{}{}", ident_prefix, function_name_str),
+        proc_macro2::Span::call_site(),
+    );
+    // `name = "..."` gives the function a public name independent of the Rust one
+    let (public_name, rust_name_tokens) = match rpc_attr.name {
+        Some(name) if name.trim().is_empty() => return name.error("`name` must not be empty"),
+        Some(name) if *name != *function_name_str => (name.value, quote! { Some(#function_name_str.to_owned()) }),
+        _ => (function_name_str.clone(), quote! { None }),
+    };
+    let function_name_lit = proc_macro2::Literal::string(&public_name);
+    // `trace` runs the original body inside a span named after the function
+    let body = if rpc_attr.trace {
+        let input_field = match &input_type_name {
+            Some(input) => quote! { input_type = #input, },
+            None => quote! {},
+        };
+        let span = quote! {
+            ::laz_server::tracing::info_span!(#function_name_lit, is_mutation = #is_mutation, #input_field)
+        };
+        if is_async {
+            quote! {{
+                ::laz_server::tracing::Instrument::instrument(async move #block, #span).await
+            }}
+        } else {
+            quote! {{
+                let __laz_span = #span;
+                let __laz_entered = __laz_span.enter();
+                #block
+            }}
+        }
+    } else {
+        quote! { #block }
+    };
 
     // Generate the final output code
     let expanded = quote! {
         // PRESERVE ORIGINAL FUNCTION
         #(#attrs)*
-        #vis #sig #block
+        #vis #sig #body
         
+        #cfgs
         fn #metadata_fn() -> &'static laz_types::FunctionMetadata {
             static METADATA: ::std::sync::OnceLock<laz_types::FunctionMetadata> = ::std::sync::OnceLock::new();
             METADATA.get_or_init(|| {
+                let output_type_name = #output_type_name_lit.to_owned();
                 laz_types::FunctionMetadata {
-                    function_name: #function_name_str.to_owned(),
+                    function_name: #function_name_lit.to_owned(),
+                    rust_name: #rust_name_tokens,
+                    module_path: Some(module_path!().to_owned()),
+                    self_type: #self_type_tokens,
                     params: #params_array,
-                    return_type: laz_types::TypeSchema::Primitive(#return_type_str.to_owned()),
+                    return_type: #return_type_tokens,
                     input_type_name: #input_type_name_tokens,
-                    output_type_name: #output_type_name_lit.to_owned(),
+                    pagination: #pagination_tokens,
+                    output_type_name,
                     is_async: #is_async,
                     is_mutation: #is_mutation,
+                    is_subscription: #is_subscription,
+                    is_websocket: #is_websocket,
+                    is_multipart: #is_multipart,
+                    body_encoding: laz_types::BodyEncoding::#body_encoding,
+                    stream_body: #stream_body,
+                    idempotent: #idempotent,
+                    since: #since_tokens,
+                    removed: #removed_tokens,
+                    description: #description_tokens,
+                    deprecated: #deprecated_tokens,
+                    error_type_name: #error_type_name_tokens,
+                    example: #example_tokens,
+                    path: #path_tokens,
+                    method: #method_tokens,
+                    auth: #auth_tokens,
+                    namespace: #namespace_tokens,
+                    tags: vec![#(#tags.to_owned()),*],
+                    timeout_ms: #timeout_ms_tokens,
+                    cache_max_age: #cache_max_age_tokens,
+                    topic: #topic_tokens,
+                    status: #status_tokens,
+                    error_statuses: vec![#(#error_statuses),*],
+                    rate_limit: #rate_limit_tokens,
+                    internal: #internal,
                 }
             })
         }
 
+        #cfgs
+        fn #schemas_fn() -> &'static laz_types::FunctionSchemas {
+            static SCHEMAS: ::std::sync::OnceLock<laz_types::FunctionSchemas> = ::std::sync::OnceLock::new();
+            SCHEMAS.get_or_init(|| laz_types::FunctionSchemas {
+                request: #request_schema_tokens,
+                query: #query_schema_tokens,
+                response: #response_schema_tokens,
+                messages: #message_schemas_tokens,
+            })
+        }
+
+        #(#cfgs #instances)*
+
+        #cfgs
+        const _: fn() = || {
+            fn __laz_assert_schema<T: laz_types::LazSchema + ?Sized>() {}
+            #(#schema_assertions)*
+        };
+
+        #route_registration
+
+        #cfgs
         #[allow(non_upper_case_globals)]
         const _: fn() = || {
             ::inventory::submit! {
@@ -107,6 +729,12 @@ fn build_metadata(attr: TokenStream, item: TokenStream, is_mutation: bool) -> To
                     getter: #metadata_fn,
                 }
             };
+            ::inventory::submit! {
+                laz_types::FunctionSchemasEntry {
+                    function_name: #function_name_lit,
+                    getter: #schemas_fn,
+                }
+            };
         };
     };
 
@@ -114,75 +742,601 @@ fn build_metadata(attr: TokenStream, item: TokenStream, is_mutation: bool) -> To
     TokenStream::from(expanded)
 }
 
+/// Options accepted by #[rpc_query(...)], #[rpc_mutation(...)] and the other RPC attributes
+#[derive(Default)]
+struct RpcAttr {
+    /// Short names of the types in `input`/`output`/`error`, used for schema lookup
+    input: Option<String>,
+    output: Option<String>,
+    /// Application error type, returned to clients in the error body's `details`
+    error: Option<String>,
+    /// Schema registrations for generic instantiations named in `input`/`output`/`error`
+    instances: Vec<proc_macro2::TokenStream>,
+    /// User types named in `input`/`output`/`error`, with their full paths
+    schema_types: Vec<Type>,
+    /// Deprecation note; empty when deprecated without a note
+    deprecated: Option<AttrValue>,
+    /// Sample input, as JSON text
+    example: Option<AttrValue>,
+    /// Route path the handler is mounted at
+    path: Option<AttrValue>,
+    /// HTTP verb, e.g. `PUT`
+    method: Option<AttrValue>,
+    /// Required credentials, e.g. `bearer`
+    auth: Option<AttrValue>,
+    /// Public name, e.g. `getUserProfile`
+    name: Option<AttrValue>,
+    /// Group the function is listed under, e.g. `auth`
+    namespace: Option<AttrValue>,
+    /// Mount the handler at `path` in `laz_server::routes()`
+    route: bool,
+    /// The response is a large body clients read as a stream
+    stream_body: bool,
+    /// The mutation may be retried with an `Idempotency-Key`
+    idempotent: bool,
+    /// Run the handler inside a tracing span named after the function
+    trace: bool,
+    /// Pagination style: `true`, `page` or `cursor`
+    paginated: Option<AttrValue>,
+    /// API version that introduced the function
+    since: Option<AttrValue>,
+    /// API version that drops the function
+    removed: Option<AttrValue>,
+    /// Hidden from the public metadata and generated clients
+    internal: bool,
+    /// Request budget, e.g. `10/min`
+    rate_limit: Option<AttrValue>,
+    /// Categories, e.g. `["auth", "admin"]`
+    tags: Option<AttrValue>,
+    /// Default client-side timeout in milliseconds
+    timeout_ms: Option<AttrValue>,
+    /// How long query responses may be cached, e.g. `60s`
+    cache: Option<AttrValue>,
+    /// Broadcast topic a subscription streams, e.g. `trees`
+    topic: Option<AttrValue>,
+    /// Success status, e.g. `201`
+    status: Option<AttrValue>,
+    /// Error statuses, e.g. `[404, 409]`
+    error_status: Option<AttrValue>,
+    /// Wrapper types around the payload in the return type, e.g. `"ApiResult, Json"`
+    unwrap: Option<AttrValue>,
+}
+
+/// Value of an option, remembering where it was written so errors about it point there
+struct AttrValue {
+    value: String,
+    span: proc_macro2::Span,
+}
 
+impl AttrValue {
+    /// Compile error about this value
+    fn error(&self, message: impl std::fmt::Display) -> TokenStream {
+        syn::Error::new(self.span, message).to_compile_error().into()
+    }
+}
 
-/// Parse attribute like: #[rpc_query(input = Foo, output = Bar)]
-fn parse_io_attr(attr: TokenStream) -> (Option<String>, Option<String>) {
-    let ts = proc_macro2::TokenStream::from(attr);
-    let mut input_ty: Option<String> = None;
-    let mut output_ty: Option<String> = None;
+impl std::ops::Deref for AttrValue {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Emitted as a string literal
+impl quote::ToTokens for AttrValue {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let mut lit = proc_macro2::Literal::string(&self.value);
+        lit.set_span(self.span);
+        tokens.extend([proc_macro2::TokenTree::Literal(lit)]);
+    }
+}
+
+/// Options that take a value
+const VALUE_OPTIONS: &[&str] = &[
+    "example", "path", "method", "auth", "name", "namespace", "since", "removed", "rate_limit", "tags",
+    "timeout_ms", "cache", "status", "error_status", "unwrap", "topic",
+];
+
+impl Parse for RpcAttr {
+    /// Parse options like `input = Foo, output = crate::views::Bar, error = FooError,
+    /// deprecated = "use bar", path = "/api/foo", method = PUT, auth = bearer, name = "getFoo", route`
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut rpc_attr = RpcAttr::default();
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            let name = key.to_string();
+            let has_value = input.peek(Token![=]);
+            let is_flag = matches!(name.as_str(), "route" | "stream_body" | "idempotent" | "internal" | "trace");
+            let is_type = matches!(name.as_str(), "input" | "output" | "error");
+            let takes_value = is_type || VALUE_OPTIONS.contains(&name.as_str());
+            if !is_flag && !takes_value && !matches!(name.as_str(), "deprecated" | "paginated") {
+                return Err(syn::Error::new(key.span(), format!("unknown option `{}`", name)));
+            }
+            if takes_value && !has_value {
+                return Err(syn::Error::new(key.span(), format!("`{}` expects `{} = ...`", name, name)));
+            }
+            if has_value {
+                input.parse::<Token![=]>()?;
+            }
 
-    // Very small hand-rolled parser: key = Type, separated by commas
-    let mut iter = ts.into_iter().peekable();
-    while let Some(tt) = iter.next() {
-        if let proc_macro2::TokenTree::Ident(ident) = tt {
-            let key = ident.to_string();
-            // expect '='
-            if let Some(proc_macro2::TokenTree::Punct(p)) = iter.next() {
-                if p.as_char() != '=' {
-                    continue;
+            if is_type {
+                let ty: Type = input.parse()?;
+                let type_name = rpc_attr.io_type(&ty);
+                match name.as_str() {
+                    "input" => rpc_attr.input = Some(type_name),
+                    "output" => rpc_attr.output = Some(type_name),
+                    _ => rpc_attr.error = Some(type_name),
+                }
+            } else if is_flag {
+                let flag = !has_value || input.parse::<syn::LitBool>()?.value;
+                match name.as_str() {
+                    "route" => rpc_attr.route = flag,
+                    "stream_body" => rpc_attr.stream_body = flag,
+                    "idempotent" => rpc_attr.idempotent = flag,
+                    "trace" => rpc_attr.trace = flag,
+                    _ => rpc_attr.internal = flag,
                 }
             } else {
-                continue;
+                // `deprecated` and `paginated` may be given bare
+                let value = if has_value {
+                    attr_value(input)?
+                } else {
+                    let bare = if name == "paginated" { "true" } else { "" };
+                    AttrValue { value: bare.to_string(), span: key.span() }
+                };
+                let slot = match name.as_str() {
+                    "deprecated" => &mut rpc_attr.deprecated,
+                    "paginated" => &mut rpc_attr.paginated,
+                    "example" => &mut rpc_attr.example,
+                    "path" => &mut rpc_attr.path,
+                    "method" => &mut rpc_attr.method,
+                    "auth" => &mut rpc_attr.auth,
+                    "name" => &mut rpc_attr.name,
+                    "namespace" => &mut rpc_attr.namespace,
+                    "since" => &mut rpc_attr.since,
+                    "removed" => &mut rpc_attr.removed,
+                    "rate_limit" => &mut rpc_attr.rate_limit,
+                    "tags" => &mut rpc_attr.tags,
+                    "timeout_ms" => &mut rpc_attr.timeout_ms,
+                    "status" => &mut rpc_attr.status,
+                    "error_status" => &mut rpc_attr.error_status,
+                    "unwrap" => &mut rpc_attr.unwrap,
+                    "topic" => &mut rpc_attr.topic,
+                    _ => &mut rpc_attr.cache,
+                };
+                *slot = Some(value);
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(rpc_attr)
+    }
+}
+
+impl RpcAttr {
+    /// Record a type named in `input`/`output`/`error` and return its short name. The full
+    /// path is kept for the `LazSchema` assertions.
+    fn io_type(&mut self, ty: &Type) -> String {
+        collect_schema_types(ty, &mut self.schema_types);
+        let (name, instance) = resolve_type_name(&quote!(#ty).to_string());
+        self.instances.extend(instance);
+        name
+    }
+}
+
+/// Parse an option's value up to the next comma, unquoting a single string literal
+fn attr_value(input: ParseStream) -> syn::Result<AttrValue> {
+    let span = input.span();
+    let mut tokens = Vec::new();
+    while !input.is_empty() && !input.peek(Token![,]) {
+        tokens.push(input.parse::<proc_macro2::TokenTree>()?);
+    }
+    if tokens.is_empty() {
+        return Err(input.error("expected a value"));
+    }
+    Ok(AttrValue { value: attr_value_string(tokens), span })
+}
+
+/// Tags given as `["auth", "admin"]` or a single `"auth"`
+fn parse_tags(value: &str) -> Result<Vec<String>, String> {
+    let invalid = || format!("`tags` expects string literals like `[\"auth\", \"admin\"]`, found `{}`", value);
+    // A single string literal reaches here already unquoted
+    if !value.trim_start().starts_with('[') {
+        return Ok(vec![value.to_string()]);
+    }
+    let Ok(syn::Expr::Array(array)) = syn::parse_str::<syn::Expr>(value) else {
+        return Err(invalid());
+    };
+    array
+        .elems
+        .into_iter()
+        .map(|expr| match expr {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) if !s.value().is_empty() => Ok(s.value()),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+/// Statuses given as `[404, 409]` or a single `404`
+/// Parse wrapper type names like `"ApiResult, Json"` or `[ApiResult, Json]`
+fn parse_wrappers(value: &str) -> Result<Vec<String>, String> {
+    let value = value.trim();
+    let list = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(list) => list,
+        None => value,
+    };
+    list.split(',')
+        .map(|name| name.trim().trim_matches('"'))
+        .map(|name| match syn::parse_str::<syn::Ident>(name) {
+            Ok(_) => Ok(name.to_string()),
+            Err(_) => Err(format!("`unwrap` expects type names like `\"ApiResult, Json\"`, found `{}`", name)),
+        })
+        .collect()
+}
+
+fn parse_statuses(value: &str) -> Result<Vec<u16>, ()> {
+    let value = value.trim();
+    let list = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(list) => list,
+        None => value,
+    };
+    list.split(',').map(|code| code.trim().parse::<u16>().map_err(drop)).collect()
+}
+
+/// Render an attribute value, unquoting a single string literal
+fn attr_value_string(tokens: Vec<proc_macro2::TokenTree>) -> String {
+    if let [proc_macro2::TokenTree::Literal(lit)] = tokens.as_slice() {
+        if let Ok(syn::Lit::Str(s)) = syn::parse_str::<syn::Lit>(&lit.to_string()) {
+            return s.value();
+        }
+    }
+    tokens.iter().map(|t| t.to_string()).collect()
+}
+
+/// Reduce type path string to last segment as a conservative type "name"
+fn last_path_segment(ty_str: &str) -> String {
+    ty_str
+        .split("::")
+        .last()
+        .map(|s| s.trim().trim_matches('<').trim_matches('>'))
+        .unwrap_or(ty_str)
+        .to_string()
+}
+
+/// Name a type given in the attribute. Instantiations of user generics such as
+/// `Paginated<User>` get a concrete name (`Paginated_User`) plus a registration of their schema.
+fn resolve_type_name(ty_str: &str) -> (String, Option<proc_macro2::TokenStream>) {
+    let Ok(type_path) = syn::parse_str::<syn::TypePath>(ty_str) else {
+        return (last_path_segment(ty_str), None);
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return (last_path_segment(ty_str), None);
+    };
+    let base = segment.ident.to_string();
+    if laz_types::is_smart_pointer_name(&base) {
+        if let Some(inner) = smart_pointer_inner(&type_path) {
+            return resolve_type_name(&quote!(#inner).to_string());
+        }
+    }
+    let args: Vec<&syn::Type> = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let is_std_wrapper = matches!(
+        base.as_str(),
+        "Vec" | "Option" | "Result" | "HashMap" | "BTreeMap" | "HashSet" | "BTreeSet" | "Box" | "Json"
+    );
+    if args.is_empty() || is_std_wrapper {
+        return (last_path_segment(ty_str), None);
+    }
+
+    let instance_name = laz_types::generic_instance_name(ty_str);
+    let arg_schemas = args.iter().map(|ty| {
+        let ty = &strip_smart_pointers(ty);
+        let ty_str = quote!(#ty).to_string();
+        match ty {
+            syn::Type::Path(p) if p.path.segments.last().is_some_and(|s| s.arguments.is_empty()) => {
+                let name = p.path.segments.last().unwrap().ident.to_string();
+                if laz_types::is_primitive_type_name(&name) {
+                    quote! { laz_types::TypeSchema::Primitive(#name.to_string()) }
+                } else {
+                    quote! { laz_types::TypeSchema::Ref(#name.to_string()) }
+                }
+            }
+            syn::Type::Path(_) => {
+                let name = laz_types::generic_instance_name(&ty_str);
+                quote! { laz_types::TypeSchema::Ref(#name.to_string()) }
+            }
+            _ => quote! { laz_types::TypeSchema::Opaque(#ty_str.to_string()) },
+        }
+    });
+
+    let registration = quote! {
+        #[allow(non_upper_case_globals)]
+        const _: fn() = || {
+            fn __laz_get_instance_schema() -> &'static laz_types::TypeSchema {
+                static SCHEMA: ::std::sync::OnceLock<laz_types::TypeSchema> = ::std::sync::OnceLock::new();
+                SCHEMA.get_or_init(|| {
+                    laz_types::instantiate_generic(#base, #instance_name, vec![#(#arg_schemas),*])
+                })
+            }
+            ::inventory::submit! {
+                laz_types::TypeSchemaEntry {
+                    type_name: #instance_name,
+                    getter: __laz_get_instance_schema,
+                }
+            };
+        };
+    };
+    (instance_name, Some(registration))
+}
+
+/// Deprecation note from `#[laz(deprecated...)]` or the standard `#[deprecated...]` attribute
+fn deprecation(attrs: &[syn::Attribute]) -> Option<String> {
+    for attr in attrs {
+        let note_from = |list: &syn::MetaList, key: &str| -> Option<String> {
+            let metas = list
+                .parse_args_with(
+                    syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+                )
+                .ok()?;
+            metas.into_iter().find_map(|meta| match meta {
+                syn::Meta::Path(path) if path.is_ident(key) => Some(String::new()),
+                syn::Meta::NameValue(nv) if nv.path.is_ident(key) => Some(lit_str(&nv.value)),
+                _ => None,
+            })
+        };
+
+        if attr.path().is_ident("laz") {
+            if let syn::Meta::List(list) = &attr.meta {
+                if let Some(note) = note_from(list, "deprecated") {
+                    return Some(note);
+                }
             }
-            // parse a Type path (sequence of Idens and '::' and generics - we only capture last ident as name)
-            let mut ty_str = String::new();
-            let mut depth: i32 = 0;
-            while let Some(next) = iter.peek() {
-                match next {
-                    proc_macro2::TokenTree::Punct(p) if depth == 0 && p.as_char() == ',' => break,
-                    proc_macro2::TokenTree::Group(g) => {
-                        ty_str.push_str(&g.stream().to_string());
-                        depth += 1;
-                        iter.next();
+        } else if attr.path().is_ident("deprecated") {
+            return Some(match &attr.meta {
+                syn::Meta::NameValue(nv) => lit_str(&nv.value),
+                syn::Meta::List(list) => note_from(list, "note").unwrap_or_default(),
+                syn::Meta::Path(_) => String::new(),
+            });
+        }
+    }
+    None
+}
+
+fn lit_str(expr: &syn::Expr) -> String {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => s.value(),
+        _ => String::new(),
+    }
+}
+
+/// Collect `///` doc comments on the handler into a single description
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).trim_end().to_string())
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// The type a handler responds with: its return type without `Result`, `Json` and smart
+/// pointer wrappers
+fn response_type(output: &ReturnType, wrappers: &[String]) -> Type {
+    let ReturnType::Type(_, ty) = output else {
+        return syn::parse_quote!(());
+    };
+    let mut ty = strip_smart_pointers(ty);
+    while let Type::Path(type_path) = &ty {
+        let Some(segment) = type_path.path.segments.last() else {
+            break;
+        };
+        if segment.ident != "Result" && segment.ident != "Json" && !wrappers.iter().any(|w| segment.ident == w) {
+            break;
+        }
+        let Some(inner) = type_args(segment).into_iter().next() else {
+            break;
+        };
+        ty = inner.clone();
+    }
+    ty
+}
+
+/// Whether a response type says nothing about the body, e.g. `impl IntoResponse`,
+/// `Response` or an `Sse` stream
+fn is_opaque_response(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_none_or(|s| matches!(s.ident.to_string().as_str(), "Response" | "Sse" | "Html" | "Redirect")),
+        Type::Tuple(_) => false,
+        _ => true,
+    }
+}
+
+fn type_args(segment: &syn::PathSegment) -> Vec<&Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Schema of a response or path parameter type. User types at the top level resolve to
+/// their registered schema when the metadata is built, and nested ones are referenced by
+/// name as in the type table; anything else unresolvable is `Opaque`.
+fn type_schema_tokens(ty: &Type, top_level: bool) -> proc_macro2::TokenStream {
+    let type_str = quote!(#ty).to_string();
+    let opaque = quote! { laz_types::TypeSchema::Opaque(#type_str.to_owned()) };
+    match ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => quote! { laz_types::TypeSchema::Unit },
+        Type::Tuple(tuple) => {
+            let items = tuple.elems.iter().map(|ty| type_schema_tokens(ty, false));
+            quote! { laz_types::TypeSchema::Tuple(vec![#(Box::new(#items)),*]) }
+        }
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let Some(segment) = type_path.path.segments.last() else {
+                return opaque;
+            };
+            let name = segment.ident.to_string();
+            let args = type_args(segment);
+            match (name.as_str(), args.as_slice()) {
+                ("HashMap" | "BTreeMap", [key, value]) => {
+                    let key = type_schema_tokens(key, false);
+                    let value = type_schema_tokens(value, false);
+                    quote! {
+                        laz_types::TypeSchema::Map {
+                            key_type: Box::new(#key),
+                            value_type: Box::new(#value),
+                        }
                     }
-                    other => {
-                        ty_str.push_str(&other.to_string());
-                        iter.next();
+                }
+                ("Vec" | "Option" | "Result" | "HashSet" | "BTreeSet", _) => {
+                    let params = args.iter().map(|ty| type_schema_tokens(ty, false));
+                    quote! {
+                        laz_types::TypeSchema::Container {
+                            container_type: #name.to_owned(),
+                            type_params: vec![#(#params),*],
+                        }
                     }
                 }
-            }
-            // consume optional trailing comma
-            if let Some(proc_macro2::TokenTree::Punct(p)) = iter.peek() {
-                if p.as_char() == ',' {
-                    iter.next();
+                ("Value", _) => quote! { laz_types::TypeSchema::Any },
+                _ if laz_types::is_primitive_type_name(&name) => {
+                    quote! { laz_types::TypeSchema::Primitive(#name.to_owned()) }
+                }
+                _ => {
+                    // Generic instances are registered under their concrete name
+                    let name = if args.is_empty() {
+                        name
+                    } else {
+                        laz_types::generic_instance_name(&type_str)
+                    };
+                    if top_level {
+                        quote! {
+                            laz_types::find_type_schema(#name)
+                                .cloned()
+                                .unwrap_or_else(|| #opaque)
+                        }
+                    } else {
+                        quote! { laz_types::TypeSchema::Ref(#name.to_owned()) }
+                    }
                 }
             }
+        }
+        _ => opaque,
+    }
+}
 
-            // Reduce type path string to last segment as a conservative type "name"
-            let type_name = ty_str
-                .split("::")
-                .last()
-                .map(|s| s.trim().trim_matches('<').trim_matches('>'))
-                .unwrap_or(&ty_str)
-                .to_string();
-
-            if key == "input" {
-                input_ty = Some(type_name);
-            } else if key == "output" {
-                output_ty = Some(type_name);
+/// Collect the user-defined types in `ty`, which must derive `LazSchema` to have a schema.
+/// Primitives, std containers and the well-known types `LazSchema` maps by name are skipped.
+fn collect_schema_types(ty: &Type, types: &mut Vec<Type>) {
+    match ty {
+        Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                collect_schema_types(elem, types);
+            }
+        }
+        Type::Reference(reference) => collect_schema_types(&reference.elem, types),
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            if let Some(inner) = smart_pointer_inner(type_path) {
+                return collect_schema_types(inner, types);
+            }
+            let Some(segment) = type_path.path.segments.last() else {
+                return;
+            };
+            let name = segment.ident.to_string();
+            let well_known = laz_types::is_primitive_type_name(&name)
+                || name.starts_with("NonZero")
+                || matches!(
+                    name.as_str(),
+                    "Vec" | "VecDeque" | "Option" | "Result" | "HashMap" | "BTreeMap" | "HashSet"
+                        | "BTreeSet" | "PhantomData" | "Uuid" | "Decimal" | "BigDecimal" | "DateTime"
+                        | "OffsetDateTime" | "NaiveDateTime" | "PrimitiveDateTime" | "NaiveDate"
+                        | "NaiveTime" | "Date" | "Time" | "Self"
+                );
+            if !well_known {
+                types.push(ty.clone());
+            }
+            for arg in type_args(segment) {
+                collect_schema_types(arg, types);
             }
         }
+        _ => {}
     }
+}
 
-    (input_ty, output_ty)
+/// Pointee of `Box<T>`, `Arc<T>`, `Rc<T>` or `Cow<T>`
+fn smart_pointer_inner(type_path: &TypePath) -> Option<&Type> {
+    let segment = type_path.path.segments.last()?;
+    if !laz_types::is_smart_pointer_name(&segment.ident.to_string()) {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let mut types = args.args.iter().filter_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    match (types.next(), types.next()) {
+        (Some(inner), None) => Some(inner),
+        _ => None,
+    }
 }
 
-fn extract_return_type(output: &ReturnType) -> String {
-    match output {
-        ReturnType::Default => "()".to_string(),
-        ReturnType::Type(_, ty) => quote::quote!(#ty).to_string(),
+/// Replace smart pointers anywhere in `ty` with their pointee, since they serialize identically
+fn strip_smart_pointers(ty: &Type) -> Type {
+    let Type::Path(type_path) = ty else {
+        return ty.clone();
+    };
+    if let Some(inner) = smart_pointer_inner(type_path) {
+        return strip_smart_pointers(inner);
     }
+    let mut type_path = type_path.clone();
+    for segment in type_path.path.segments.iter_mut() {
+        if let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments {
+            for arg in args.args.iter_mut() {
+                if let syn::GenericArgument::Type(ty) = arg {
+                    *ty = strip_smart_pointers(ty);
+                }
+            }
+        }
+    }
+    Type::Path(type_path)
 }
 
 /// Extracts detailed information from function parameters
@@ -193,21 +1347,32 @@ fn extract_params(
 
     for input in inputs {
         if let FnArg::Typed(pat_type) = input {
-            // Extract the binding name (e.g., "params" in `Json(params)`)
-            if let Pat::Ident(pat_ident) = &*pat_type.pat {
-                let name = pat_ident.ident.to_string();
+            // Extract the binding names (e.g., "params" in `Json(params)`)
+            if let Some(bindings) = pattern_bindings(&pat_type.pat) {
+                let name = bindings.join(", ");
 
                 // Parse the type annotation (e.g., `Json<RegisterParams>`)
                 if let Type::Path(type_path) = &*pat_type.ty {
                     let (extractor, inner_type, inner_type_path) =
                         parse_extractor_with_path(type_path);
+                    let path_params = (extractor == "Path")
+                        .then(|| type_path.path.segments.last().and_then(|s| type_args(s).first().copied()))
+                        .flatten()
+                        .map(|inner| {
+                            let schema = type_schema_tokens(&strip_smart_pointers(inner), true);
+                            quote! { laz_types::path_params(&[#(#bindings),*], #schema) }
+                        });
 
+                    let (header, credentials) = typed_header(type_path).unzip();
                     params.push(ParamInfoParts {
                         name,
                         full_type: quote::quote!(#type_path).to_string(),
                         extractor,
                         inner_type,
                         inner_type_path, // For schema lookup
+                        path_params,
+                        header,
+                        credentials: credentials.flatten(),
                     });
                 } else {
                     // Fallback for non-path types (e.g., references, slices)
@@ -218,6 +1383,9 @@ fn extract_params(
                         extractor: "Unknown".to_string(),
                         inner_type: "Unknown".to_string(),
                         inner_type_path: None,
+                        path_params: None,
+                        header: None,
+                        credentials: None,
                     });
                 }
             }
@@ -227,6 +1395,54 @@ fn extract_params(
     params
 }
 
+/// Header name of a `TypedHeader<T>` extractor, spelled from `T` (`UserAgent` reads
+/// `User-Agent`), with the credentials scheme of an `Authorization<Bearer>` or `<Basic>`
+fn typed_header(type_path: &TypePath) -> Option<(String, Option<laz_types::AuthScheme>)> {
+    let segment = type_path.path.segments.last().filter(|s| s.ident == "TypedHeader")?;
+    let Type::Path(header) = type_args(segment).first()? else {
+        return None;
+    };
+    let header = header.path.segments.last()?;
+    let mut name = String::new();
+    for c in header.ident.to_string().chars() {
+        if c.is_uppercase() && !name.is_empty() {
+            name.push('-');
+        }
+        name.push(c);
+    }
+    let credentials = match type_args(header).first() {
+        Some(Type::Path(scheme)) if header.ident == "Authorization" => {
+            match scheme.path.segments.last()?.ident.to_string().as_str() {
+                "Bearer" => Some(laz_types::AuthScheme::Bearer),
+                "Basic" => Some(laz_types::AuthScheme::Basic),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    Some((name, credentials))
+}
+
+/// Names bound by a parameter pattern: `id`, `Path(id)` or `Path((org, id))`
+fn pattern_bindings(pat: &Pat) -> Option<Vec<String>> {
+    match pat {
+        Pat::Ident(pat_ident) => Some(vec![pat_ident.ident.to_string()]),
+        Pat::TupleStruct(tuple_struct) if tuple_struct.elems.len() == 1 => {
+            pattern_bindings(&tuple_struct.elems[0])
+        }
+        Pat::Tuple(tuple) => tuple
+            .elems
+            .iter()
+            .map(|elem| match elem {
+                Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+                Pat::Wild(_) => Some("_".to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
 /// Parses extractor type and captures the TypePath for schema lookup
 fn parse_extractor_with_path(type_path: &TypePath) -> (String, String, Option<TypePath>) {
     if let Some(segment) = type_path.path.segments.last() {
@@ -234,17 +1450,19 @@ fn parse_extractor_with_path(type_path: &TypePath) -> (String, String, Option<Ty
 
         // Check for generic arguments like Json<T>, State<T>, Path<T>
         if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-            if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                if let Type::Path(inner_path) = inner_ty {
-                    let inner_type = quote::quote!(#inner_path).to_string();
-
-                    // Return the TypePath for common extractors
-                    if ["Json", "State", "Path", "Query", "Form"].contains(&extractor.as_str()) {
-                        return (extractor, inner_type, Some(inner_path.clone()));
-                    }
+            if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+                let Type::Path(inner_path) = strip_smart_pointers(inner) else {
+                    let full_type = quote::quote!(#type_path).to_string();
+                    return (extractor, full_type, None);
+                };
+                let inner_type = quote::quote!(#inner_path).to_string();
 
-                    return (extractor, inner_type, None);
+                // Return the TypePath for common extractors
+                if ["Json", "State", "Path", "Query", "Form"].contains(&extractor.as_str()) {
+                    return (extractor, inner_type, Some(inner_path));
                 }
+
+                return (extractor, inner_type, None);
             }
         }
 
@@ -264,11 +1482,20 @@ fn build_params_array(parts: &[ParamInfoParts]) -> proc_macro2::TokenStream {
         let full_type = &p.full_type;
         let extractor = &p.extractor;
         let inner_type = &p.inner_type;
+        let role = syn::Ident::new(&format!("{:?}", p.role()), proc_macro2::Span::call_site());
+        let type_name_tokens = match p.type_name() {
+            Some(type_name) => quote! { Some(#type_name.to_string()) },
+            None => quote! { None },
+        };
 
         let inner_type_lit = proc_macro2::Literal::string(inner_type);
 
-        let schema_lookup = if let Some(inner_path) = &p.inner_type_path {
-            let type_name = inner_path.path.segments.last().unwrap().ident.to_string();
+        let schema_lookup = if !p.role().is_client_visible() {
+            // Server state has no schema clients need
+            quote! { laz_types::TypeSchema::Opaque(#inner_type_lit.to_string()) }
+        } else if p.role() == laz_types::ParamRole::Header {
+            quote! { laz_types::TypeSchema::Primitive("String".to_string()) }
+        } else if let Some(type_name) = p.type_name() {
             let type_name_lit = proc_macro2::Literal::string(&type_name);
 
             quote! {
@@ -282,12 +1509,22 @@ fn build_params_array(parts: &[ParamInfoParts]) -> proc_macro2::TokenStream {
             }
         };
 
+        let path_params = p.path_params.clone().unwrap_or_else(|| quote! { Vec::new() });
+        let header_tokens = match &p.header {
+            Some(header) => quote! { Some(#header.to_string()) },
+            None => quote! { None },
+        };
+
         quote! {
             laz_types::ParamInfo {
                 name: #name.to_string(),
                 full_type: #full_type.to_string(),
                 extractor: #extractor.to_string(),
+                role: laz_types::ParamRole::#role,
+                type_name: #type_name_tokens,
                 inner_type_schema: #schema_lookup,
+                path_params: #path_params,
+                header: #header_tokens,
             }
         }
     });
@@ -0,0 +1,388 @@
+//! Random JSON values conforming to a [`TypeSchema`], for fuzzing handlers and
+//! round-tripping generated clients without hand-written fixtures.
+//!
+//! Generation is deterministic for a given seed, so a failing case can be replayed.
+//! Values honor field constraints other than `pattern`; pattern-constrained strings use the
+//! field's example when it has one. Named types are resolved through the registry, and
+//! past [`Generator::max_depth`] optional values, collections and enum variants are kept
+//! minimal so recursive types terminate. With the `proptest` feature, [`strategy`] wraps
+//! a generator as a proptest `Strategy`.
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    base64, find_type_schema, BytesEncoding, DateTimeEncoding, EnumSchema, EnumTagging,
+    FieldConstraints, FieldSchema, TypeSchema, VariantSchema,
+};
+
+/// Seeded generator of schema-conforming values
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+    max_len: usize,
+    max_depth: usize,
+    depth: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            max_len: 4,
+            max_depth: 4,
+            depth: 0,
+        }
+    }
+
+    /// Largest size of generated strings and collections, unless constraints require more
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Nesting depth after which generation switches to the smallest possible values
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// A random value matching `schema`
+    pub fn value(&mut self, schema: &TypeSchema) -> Value {
+        self.generate(schema, &FieldConstraints::default())
+    }
+
+    /// splitmix64
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform integer in `lo..=hi`
+    fn range(&mut self, lo: i128, hi: i128) -> i128 {
+        if hi <= lo {
+            return lo;
+        }
+        let span = (hi - lo) as u128 + 1;
+        let wide = (self.next_u64() as u128) << 64 | self.next_u64() as u128;
+        lo + (wide % span) as i128
+    }
+
+    fn index(&mut self, len: usize) -> usize {
+        self.range(0, len as i128 - 1) as usize
+    }
+
+    fn chance(&mut self, one_in: u64) -> bool {
+        self.next_u64().is_multiple_of(one_in)
+    }
+
+    fn shallow(&self) -> bool {
+        self.depth >= self.max_depth
+    }
+
+    fn length(&mut self, constraints: &FieldConstraints) -> usize {
+        let min = constraints.min_length.unwrap_or(0);
+        let cap = if self.shallow() { min } else { self.max_len.max(min) };
+        let max = constraints.max_length.unwrap_or(cap).min(cap).max(min);
+        self.range(min as i128, max as i128) as usize
+    }
+
+    fn generate(&mut self, schema: &TypeSchema, constraints: &FieldConstraints) -> Value {
+        self.depth += 1;
+        let value = self.generate_inner(schema, constraints);
+        self.depth -= 1;
+        value
+    }
+
+    fn generate_inner(&mut self, schema: &TypeSchema, constraints: &FieldConstraints) -> Value {
+        match schema {
+            TypeSchema::Primitive(name) => self.primitive(name, constraints),
+            TypeSchema::Struct(s) => Value::Object(self.fields(&s.fields)),
+            TypeSchema::Enum(e) => self.enumeration(e),
+            TypeSchema::Container {
+                container_type,
+                type_params,
+            } => match (container_type.as_str(), type_params.as_slice()) {
+                ("Option", [_]) if self.shallow() || self.chance(3) => Value::Null,
+                ("Option", [inner]) => self.generate(inner, constraints),
+                ("Vec", [inner]) => {
+                    let len = self.length(constraints);
+                    Value::Array((0..len).map(|_| self.generate(inner, &Default::default())).collect())
+                }
+                ("HashSet" | "BTreeSet", [inner]) => {
+                    let len = self.length(constraints);
+                    let mut items: Vec<Value> = Vec::new();
+                    for _ in 0..len {
+                        let item = self.generate(inner, &Default::default());
+                        if !items.contains(&item) {
+                            items.push(item);
+                        }
+                    }
+                    Value::Array(items)
+                }
+                ("Result", [ok, err]) => {
+                    if self.chance(2) {
+                        json!({ "Ok": self.generate(ok, &Default::default()) })
+                    } else {
+                        json!({ "Err": self.generate(err, &Default::default()) })
+                    }
+                }
+                _ => Value::Null,
+            },
+            TypeSchema::Map {
+                key_type,
+                value_type,
+            } => {
+                let len = self.length(constraints);
+                let mut map = Map::new();
+                for _ in 0..len {
+                    let key = match self.generate(key_type, &Default::default()) {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    map.insert(key, self.generate(value_type, &Default::default()));
+                }
+                Value::Object(map)
+            }
+            TypeSchema::Array { inner, len } => {
+                Value::Array((0..*len).map(|_| self.generate(inner, &Default::default())).collect())
+            }
+            TypeSchema::DateTime { encoding, .. } => {
+                let date = format!("20{:02}-{:02}-{:02}", self.range(0, 99), self.range(1, 12), self.range(1, 28));
+                let time = format!("{:02}:{:02}:{:02}", self.range(0, 23), self.range(0, 59), self.range(0, 59));
+                match encoding {
+                    DateTimeEncoding::Rfc3339 => json!(format!("{}T{}Z", date, time)),
+                    DateTimeEncoding::NaiveDateTime => json!(format!("{}T{}", date, time)),
+                    DateTimeEncoding::Date => json!(date),
+                    DateTimeEncoding::Time => json!(time),
+                    DateTimeEncoding::Unix => json!(self.range(0, 4_102_444_800) as i64),
+                }
+            }
+            TypeSchema::Decimal(_) => json!(format!("{}.{:02}", self.range(-1000, 1000), self.range(0, 99))),
+            TypeSchema::Int128(name) => json!(self.int128(name, false)),
+            TypeSchema::NonZero(name) if name == "i128" || name == "u128" => json!(self.int128(name, true)),
+            TypeSchema::NonZero(name) => match self.primitive(name, constraints) {
+                Value::Number(n) if n.as_f64() == Some(0.0) => json!(1),
+                other => other,
+            },
+            TypeSchema::Bytes(encoding) => {
+                let len = self.length(constraints);
+                let bytes: Vec<u8> = (0..len).map(|_| self.next_u64() as u8).collect();
+                match encoding {
+                    BytesEncoding::Base64 => json!(base64::encode(&bytes)),
+                    BytesEncoding::Array => json!(bytes),
+                }
+            }
+            TypeSchema::Tuple(items) => {
+                Value::Array(items.iter().map(|item| self.generate(item, &Default::default())).collect())
+            }
+            TypeSchema::Newtype(inner) => self.generate(inner, constraints),
+            TypeSchema::Ref(name) => match find_type_schema(name) {
+                Some(resolved) => self.generate_inner(resolved, constraints),
+                None => Value::Null,
+            },
+            TypeSchema::Any => self.primitive("String", constraints),
+            TypeSchema::Unit | TypeSchema::Opaque(_) => Value::Null,
+        }
+    }
+
+    fn primitive(&mut self, name: &str, constraints: &FieldConstraints) -> Value {
+        if !constraints.one_of.is_empty() {
+            let i = self.index(constraints.one_of.len());
+            return json!(constraints.one_of[i]);
+        }
+        let bounded = |lo: i128, hi: i128| {
+            // Keep values small unless the constraints demand otherwise
+            let lo = constraints.min.map_or(lo.max(-1000), |min| lo.max(min.ceil() as i128));
+            let hi = constraints.max.map_or(hi.min(1000), |max| hi.min(max.floor() as i128));
+            (lo, hi.max(lo))
+        };
+        let (lo, hi) = match name {
+            "i8" => bounded(i8::MIN.into(), i8::MAX.into()),
+            "i16" => bounded(i16::MIN.into(), i16::MAX.into()),
+            "i32" => bounded(i32::MIN.into(), i32::MAX.into()),
+            "i64" | "isize" => bounded(i64::MIN.into(), i64::MAX.into()),
+            "u8" => bounded(0, u8::MAX.into()),
+            "u16" => bounded(0, u16::MAX.into()),
+            "u32" => bounded(0, u32::MAX.into()),
+            "u64" | "usize" => bounded(0, u64::MAX.into()),
+            "f32" | "f64" => {
+                let lo = constraints.min.unwrap_or(-1000.0);
+                let hi = constraints.max.unwrap_or(1000.0).max(lo);
+                let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+                return json!(lo + (hi - lo) * unit);
+            }
+            "String" | "str" | "Value" => {
+                let len = self.length(constraints);
+                return json!(self.string(len));
+            }
+            "char" => return json!(self.string(1)),
+            "bool" => return json!(self.chance(2)),
+            "Uuid" => {
+                let hex = format!("{:016x}{:016x}", self.next_u64(), self.next_u64());
+                return json!(format!(
+                    "{}-{}-4{}-a{}-{}",
+                    &hex[0..8],
+                    &hex[8..12],
+                    &hex[13..16],
+                    &hex[17..20],
+                    &hex[20..32]
+                ));
+            }
+            _ => return Value::Null,
+        };
+        let n = self.range(lo, hi);
+        match i64::try_from(n) {
+            Ok(n) => json!(n),
+            Err(_) => json!(n as u64),
+        }
+    }
+
+    fn string(&mut self, len: usize) -> String {
+        const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+        (0..len).map(|_| CHARS[self.index(CHARS.len())] as char).collect()
+    }
+
+    fn int128(&mut self, name: &str, non_zero: bool) -> String {
+        let magnitude = (self.next_u64() as u128) << 64 | self.next_u64() as u128;
+        let magnitude = if name == "i128" { magnitude >> 1 } else { magnitude };
+        let magnitude = if non_zero { magnitude.max(1) } else { magnitude };
+        if name == "i128" && self.chance(2) {
+            format!("-{}", magnitude.max(1))
+        } else {
+            magnitude.to_string()
+        }
+    }
+
+    fn fields(&mut self, fields: &[FieldSchema]) -> Map<String, Value> {
+        let mut object = Map::new();
+        for field in fields {
+            let may_skip = field.optional || field.default.is_some();
+            if may_skip && (self.shallow() || self.chance(3)) {
+                continue;
+            }
+            let value = if field.nullable && (self.shallow() || self.chance(4)) {
+                Value::Null
+            } else if let (Some(_), Some(example)) = (&field.constraints.pattern, &field.example) {
+                example.clone()
+            } else {
+                self.generate(&field.field_type, &field.constraints)
+            };
+            object.insert(field.wire_name().to_string(), value);
+        }
+        object
+    }
+
+    fn enumeration(&mut self, schema: &EnumSchema) -> Value {
+        if schema.variants.is_empty() {
+            return Value::Null;
+        }
+        // Prefer payload-free variants once deep enough, so recursive enums bottom out
+        let units: Vec<&VariantSchema> =
+            schema.variants.iter().filter(|v| v.inner_schema.is_none() && v.fields.is_empty()).collect();
+        let variant = if self.shallow() && !units.is_empty() {
+            units[self.index(units.len())]
+        } else {
+            &schema.variants[self.index(schema.variants.len())]
+        };
+
+        let name = variant.wire_name();
+        let payload = if !variant.fields.is_empty() {
+            Some(Value::Object(self.fields(&variant.fields)))
+        } else {
+            variant.inner_schema.as_deref().map(|inner| self.generate(inner, &Default::default()))
+        };
+        match (&schema.tagging, payload) {
+            (EnumTagging::External, Some(payload)) => json!({ name: payload }),
+            (EnumTagging::External, None) => json!(name),
+            (EnumTagging::Internal { tag }, payload) => {
+                let mut object = match payload {
+                    Some(Value::Object(object)) => object,
+                    _ => Map::new(),
+                };
+                object.insert(tag.clone(), json!(name));
+                Value::Object(object)
+            }
+            (EnumTagging::Adjacent { tag, content }, Some(payload)) => {
+                json!({ tag.as_str(): name, content.as_str(): payload })
+            }
+            (EnumTagging::Adjacent { tag, .. }, None) => json!({ tag.as_str(): name }),
+            (EnumTagging::Untagged, payload) => payload.unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// A random value matching `schema`, using default limits
+pub fn arbitrary_value(schema: &TypeSchema, seed: u64) -> Value {
+    Generator::new(seed).value(schema)
+}
+
+/// Proptest strategy producing values that match `schema`
+#[cfg(feature = "proptest")]
+pub fn strategy(schema: TypeSchema) -> impl proptest::strategy::Strategy<Value = Value> {
+    use proptest::strategy::Strategy;
+
+    proptest::num::u64::ANY.prop_map(move |seed| arbitrary_value(&schema, seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate, StructSchema};
+
+    fn field(name: &str, field_type: TypeSchema) -> FieldSchema {
+        FieldSchema {
+            field_name: name.to_string(),
+            field_type: Box::new(field_type),
+            optional: false,
+            nullable: false,
+            rename: None,
+            description: None,
+            deprecated: None,
+            constraints: FieldConstraints::default(),
+            default: None,
+            example: None,
+        }
+    }
+
+    #[test]
+    fn test_generated_values_validate() {
+        let mut age = field("age", TypeSchema::Primitive("u8".to_string()));
+        age.constraints.min = Some(18.0);
+        age.constraints.max = Some(21.0);
+        let mut plan = field("plan", TypeSchema::Primitive("String".to_string()));
+        plan.constraints.one_of = vec!["free".to_string(), "pro".to_string()];
+        let mut nickname = field(
+            "nickname",
+            TypeSchema::Container {
+                container_type: "Option".to_string(),
+                type_params: vec![TypeSchema::Primitive("String".to_string())],
+            },
+        );
+        nickname.optional = true;
+        nickname.nullable = true;
+        let schema = TypeSchema::Struct(StructSchema {
+            type_name: "Signup".to_string(),
+            fields: vec![
+                age,
+                plan,
+                nickname,
+                field("avatar", TypeSchema::Bytes(BytesEncoding::Base64)),
+                field("balance", TypeSchema::Int128("i128".to_string())),
+                field("joined", TypeSchema::DateTime {
+                    type_name: "DateTime<Utc>".to_string(),
+                    encoding: DateTimeEncoding::Rfc3339,
+                }),
+                field("id", TypeSchema::Primitive("Uuid".to_string())),
+            ],
+        });
+
+        for seed in 0..200 {
+            let value = arbitrary_value(&schema, seed);
+            assert_eq!(validate(&value, &schema), Ok(()), "seed {} produced {}", seed, value);
+        }
+        assert_eq!(arbitrary_value(&schema, 7), arbitrary_value(&schema, 7));
+    }
+}
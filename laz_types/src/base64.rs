@@ -0,0 +1,93 @@
+//! Serde helper encoding byte buffers as base64 strings (standard alphabet, padded).
+//!
+//! Use it on `Vec<u8>` fields with `#[serde(with = "laz_types::base64")]`, or on
+//! `Option<Vec<u8>>` fields with `#[serde(default, with = "laz_types::base64::option")]`.
+//! `LazSchema` records such fields as [`BytesEncoding::Base64`](crate::BytesEncoding).
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn serialize<S: Serializer>(bytes: impl AsRef<[u8]>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(bytes.as_ref()))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    decode(&encoded).map_err(de::Error::custom)
+}
+
+/// The same encoding for optional buffers
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<impl AsRef<[u8]>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => serializer.serialize_some(&encode(bytes.as_ref())),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(encoded) => decode(&encoded).map(Some).map_err(de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode base64, with or without padding
+pub fn decode(encoded: &str) -> Result<Vec<u8>, String> {
+    let digits = encoded.trim_end_matches('=').as_bytes();
+    if digits.len() % 4 == 1 {
+        return Err(format!("invalid base64 length {}", encoded.len()));
+    }
+
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = ALPHABET
+                .iter()
+                .position(|a| a == c)
+                .ok_or_else(|| format!("invalid base64 character {:?}", *c as char))?;
+            n |= (value as u32) << (18 - 6 * i);
+        }
+        out.extend(n.to_be_bytes()[1..chunk.len()].iter());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for input in [&b""[..], b"f", b"fo", b"foo", b"foob", b"\x00\xff\x10"] {
+            let encoded = encode(input);
+            assert_eq!(decode(&encoded).unwrap(), input);
+        }
+        assert_eq!(encode(b"foob"), "Zm9vYg==");
+        assert_eq!(decode("Zm9vYg").unwrap(), b"foob");
+        assert!(decode("Zm9v*").is_err());
+    }
+}
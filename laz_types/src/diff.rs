@@ -0,0 +1,477 @@
+//! Compatibility diffing between two versions of an API's metadata.
+//!
+//! Whether a change is breaking depends on which way the data flows: a new required
+//! field breaks callers when it's part of a function's input, but not when it's part of
+//! its output, while a new enum variant is the other way around.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnumTagging, FieldSchema, FunctionMetadata, TypeSchema, VariantSchema};
+
+/// Classified differences between two metadata snapshots
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    /// Whether any change breaks existing clients
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.breaking)
+    }
+
+    /// The changes that break existing clients
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|c| c.breaking)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// A single difference, located by a path like `create_user.input.email`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub breaking: bool,
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.breaking { "breaking" } else { "compatible" };
+        write!(f, "[{}] {}: {:?}", severity, self.path, self.kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ChangeKind {
+    FunctionAdded,
+    FunctionRemoved,
+    /// A query became a mutation or vice versa
+    FunctionKindChanged { is_mutation: bool },
+    TypeChanged { old: String, new: String },
+    FieldAdded { optional: bool },
+    FieldRemoved,
+    /// The field's wire name changed
+    FieldRenamed { old: String, new: String },
+    OptionalityChanged { optional: bool },
+    NullabilityChanged { nullable: bool },
+    VariantAdded,
+    VariantRemoved,
+    /// The enum's JSON representation changed, e.g. from external to internal tagging
+    TaggingChanged { old: EnumTagging, new: EnumTagging },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Direction {
+    Input,
+    Output,
+}
+
+/// Compare two sets of function metadata by name, signature and the structure of their
+/// input and output types.
+///
+/// Types are resolved from the schemas the metadata carries for the return type and each
+/// extracted parameter. Types only reachable through those are compared by name; use
+/// [`diff_with_types`] with each side's full type table to compare them too.
+pub fn diff(old: &[FunctionMetadata], new: &[FunctionMetadata]) -> SchemaDiff {
+    diff_with_types(old, &embedded_types(old), new, &embedded_types(new))
+}
+
+/// Schemas embedded in `functions`' metadata, by the type name they're declared under
+fn embedded_types(functions: &[FunctionMetadata]) -> BTreeMap<String, TypeSchema> {
+    let mut types = BTreeMap::new();
+    for function in functions {
+        let params = function
+            .params
+            .iter()
+            .filter_map(|p| Some((p.type_name.as_deref()?, &p.inner_type_schema)));
+        let embedded = std::iter::once((function.output_type_name.trim(), &function.return_type)).chain(params);
+        for (name, schema) in embedded {
+            // References and opaque types say nothing about the structure
+            if !matches!(schema, TypeSchema::Ref(_) | TypeSchema::Opaque(_)) {
+                types.entry(name.to_string()).or_insert_with(|| schema.clone());
+            }
+        }
+    }
+    types
+}
+
+/// Like [`diff`], additionally comparing field and variant structure of input and output
+/// types resolved from each side's type table (see [`crate::type_table`]).
+pub fn diff_with_types(
+    old: &[FunctionMetadata],
+    old_types: &BTreeMap<String, TypeSchema>,
+    new: &[FunctionMetadata],
+    new_types: &BTreeMap<String, TypeSchema>,
+) -> SchemaDiff {
+    let mut differ = Differ {
+        old_types,
+        new_types,
+        visited: HashSet::new(),
+        changes: Vec::new(),
+    };
+
+    for old_fn in old {
+        let Some(new_fn) = new.iter().find(|f| f.function_name == old_fn.function_name) else {
+            differ.push(&old_fn.function_name, ChangeKind::FunctionRemoved, true);
+            continue;
+        };
+        differ.compare_functions(old_fn, new_fn);
+    }
+    for new_fn in new {
+        if !old.iter().any(|f| f.function_name == new_fn.function_name) {
+            differ.push(&new_fn.function_name, ChangeKind::FunctionAdded, false);
+        }
+    }
+
+    SchemaDiff {
+        changes: differ.changes,
+    }
+}
+
+struct Differ<'a> {
+    old_types: &'a BTreeMap<String, TypeSchema>,
+    new_types: &'a BTreeMap<String, TypeSchema>,
+    visited: HashSet<(String, String, Direction)>,
+    changes: Vec<SchemaChange>,
+}
+
+impl Differ<'_> {
+    fn push(&mut self, path: &str, kind: ChangeKind, breaking: bool) {
+        self.changes.push(SchemaChange {
+            path: path.to_string(),
+            kind,
+            breaking,
+        });
+    }
+
+    fn compare_functions(&mut self, old: &FunctionMetadata, new: &FunctionMetadata) {
+        let name = &old.function_name;
+        if old.is_mutation != new.is_mutation {
+            let kind = ChangeKind::FunctionKindChanged {
+                is_mutation: new.is_mutation,
+            };
+            self.push(name, kind, true);
+        }
+
+        match (&old.input_type_name, &new.input_type_name) {
+            (Some(old_input), Some(new_input)) => self.compare_named(
+                old_input,
+                new_input,
+                &format!("{}.input", name),
+                Direction::Input,
+            ),
+            (None, None) => {}
+            (old_input, new_input) => {
+                let kind = ChangeKind::TypeChanged {
+                    old: old_input.clone().unwrap_or_default(),
+                    new: new_input.clone().unwrap_or_default(),
+                };
+                // Dropping the input only breaks nothing if callers sent none
+                self.push(&format!("{}.input", name), kind, new_input.is_some());
+            }
+        }
+
+        self.compare_named(
+            &old.output_type_name,
+            &new.output_type_name,
+            &format!("{}.output", name),
+            Direction::Output,
+        );
+
+        let error_path = format!("{}.error", name);
+        match (&old.error_type_name, &new.error_type_name) {
+            (Some(old_error), Some(new_error)) => {
+                self.compare_named(old_error, new_error, &error_path, Direction::Output)
+            }
+            (None, None) => {}
+            // Undeclared errors still arrive as a plain error body
+            (old_error, new_error) => {
+                let kind = ChangeKind::TypeChanged {
+                    old: old_error.clone().unwrap_or_default(),
+                    new: new_error.clone().unwrap_or_default(),
+                };
+                self.push(&error_path, kind, false);
+            }
+        }
+    }
+
+    fn compare_named(&mut self, old: &str, new: &str, path: &str, direction: Direction) {
+        if old != new {
+            let kind = ChangeKind::TypeChanged {
+                old: old.to_string(),
+                new: new.to_string(),
+            };
+            self.push(path, kind, true);
+            return;
+        }
+        self.compare_refs(old, new, path, direction);
+    }
+
+    fn compare_refs(&mut self, old: &str, new: &str, path: &str, direction: Direction) {
+        if !self.visited.insert((old.to_string(), new.to_string(), direction)) {
+            return;
+        }
+        if let (Some(old_schema), Some(new_schema)) = (self.old_types.get(old), self.new_types.get(new)) {
+            self.compare(old_schema, new_schema, path, direction);
+        }
+    }
+
+    fn compare(&mut self, old: &TypeSchema, new: &TypeSchema, path: &str, direction: Direction) {
+        match (old, new) {
+            (TypeSchema::Ref(old_name), TypeSchema::Ref(new_name)) if old_name == new_name => {
+                self.compare_refs(old_name, new_name, path, direction)
+            }
+            (TypeSchema::Struct(old_struct), TypeSchema::Struct(new_struct)) => {
+                self.compare_fields(&old_struct.fields, &new_struct.fields, path, direction)
+            }
+            (TypeSchema::Enum(old_enum), TypeSchema::Enum(new_enum)) => {
+                if old_enum.tagging != new_enum.tagging {
+                    let kind = ChangeKind::TaggingChanged {
+                        old: old_enum.tagging.clone(),
+                        new: new_enum.tagging.clone(),
+                    };
+                    self.push(path, kind, true);
+                }
+                self.compare_variants(&old_enum.variants, &new_enum.variants, path, direction)
+            }
+            (
+                TypeSchema::Container {
+                    container_type: old_container,
+                    type_params: old_params,
+                },
+                TypeSchema::Container {
+                    container_type: new_container,
+                    type_params: new_params,
+                },
+            ) if old_container == new_container && old_params.len() == new_params.len() => {
+                for (old_param, new_param) in old_params.iter().zip(new_params) {
+                    self.compare(old_param, new_param, path, direction);
+                }
+            }
+            (
+                TypeSchema::Map {
+                    key_type: old_key,
+                    value_type: old_value,
+                },
+                TypeSchema::Map {
+                    key_type: new_key,
+                    value_type: new_value,
+                },
+            ) => {
+                self.compare(old_key, new_key, path, direction);
+                self.compare(old_value, new_value, path, direction);
+            }
+            (TypeSchema::Newtype(old_inner), TypeSchema::Newtype(new_inner)) => {
+                self.compare(old_inner, new_inner, path, direction)
+            }
+            _ => {
+                let (old_label, new_label) = (type_label(old), type_label(new));
+                if old_label != new_label {
+                    let kind = ChangeKind::TypeChanged {
+                        old: old_label,
+                        new: new_label,
+                    };
+                    self.push(path, kind, true);
+                }
+            }
+        }
+    }
+
+    fn compare_fields(&mut self, old: &[FieldSchema], new: &[FieldSchema], path: &str, direction: Direction) {
+        for old_field in old {
+            let field_path = format!("{}.{}", path, old_field.field_name);
+            let Some(new_field) = new.iter().find(|f| f.field_name == old_field.field_name) else {
+                // Inputs tolerate fields the server no longer reads; outputs lose data
+                let breaking = direction == Direction::Output && !old_field.optional;
+                self.push(&field_path, ChangeKind::FieldRemoved, breaking);
+                continue;
+            };
+
+            if old_field.wire_name() != new_field.wire_name() {
+                let kind = ChangeKind::FieldRenamed {
+                    old: old_field.wire_name().to_string(),
+                    new: new_field.wire_name().to_string(),
+                };
+                self.push(&field_path, kind, true);
+            }
+            if old_field.optional != new_field.optional {
+                // Inputs may become more lenient, outputs more strict
+                let breaking = match direction {
+                    Direction::Input => !new_field.optional,
+                    Direction::Output => new_field.optional,
+                };
+                let kind = ChangeKind::OptionalityChanged {
+                    optional: new_field.optional,
+                };
+                self.push(&field_path, kind, breaking);
+            }
+            if old_field.nullable != new_field.nullable {
+                // Same rule as optionality: inputs may accept null, outputs may not start sending it
+                let breaking = match direction {
+                    Direction::Input => !new_field.nullable,
+                    Direction::Output => new_field.nullable,
+                };
+                let kind = ChangeKind::NullabilityChanged {
+                    nullable: new_field.nullable,
+                };
+                self.push(&field_path, kind, breaking);
+            }
+            self.compare(&old_field.field_type, &new_field.field_type, &field_path, direction);
+        }
+
+        for new_field in new {
+            if !old.iter().any(|f| f.field_name == new_field.field_name) {
+                let breaking = direction == Direction::Input && !new_field.optional;
+                let kind = ChangeKind::FieldAdded {
+                    optional: new_field.optional,
+                };
+                self.push(&format!("{}.{}", path, new_field.field_name), kind, breaking);
+            }
+        }
+    }
+
+    fn compare_variants(
+        &mut self,
+        old: &[VariantSchema],
+        new: &[VariantSchema],
+        path: &str,
+        direction: Direction,
+    ) {
+        for old_variant in old {
+            let variant_path = format!("{}::{}", path, old_variant.wire_name());
+            match new.iter().find(|v| v.wire_name() == old_variant.wire_name()) {
+                Some(new_variant) => {
+                    self.compare_fields(&old_variant.fields, &new_variant.fields, &variant_path, direction);
+                    if let (Some(old_inner), Some(new_inner)) =
+                        (&old_variant.inner_schema, &new_variant.inner_schema)
+                    {
+                        self.compare(old_inner, new_inner, &variant_path, direction);
+                    }
+                }
+                None => {
+                    // Callers may still send the removed variant
+                    let breaking = direction == Direction::Input;
+                    self.push(&variant_path, ChangeKind::VariantRemoved, breaking);
+                }
+            }
+        }
+        for new_variant in new {
+            if !old.iter().any(|v| v.wire_name() == new_variant.wire_name()) {
+                // Existing clients can't deserialize a variant they don't know
+                let breaking = direction == Direction::Output;
+                let variant_path = format!("{}::{}", path, new_variant.wire_name());
+                self.push(&variant_path, ChangeKind::VariantAdded, breaking);
+            }
+        }
+    }
+}
+
+/// Short description of a schema for `TypeChanged` reports
+fn type_label(schema: &TypeSchema) -> String {
+    serde_json::to_string(schema).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{make_function_metadata, FieldConstraints, StructSchema};
+
+    fn function(name: &str, input: &str, output: &str) -> FunctionMetadata {
+        make_function_metadata(
+            name.to_string(),
+            Vec::new(),
+            TypeSchema::Primitive(output.to_string()),
+            Some(input.to_string()),
+            output.to_string(),
+            true,
+            true,
+        )
+    }
+
+    fn user_struct(fields: &[(&str, bool)]) -> TypeSchema {
+        TypeSchema::Struct(StructSchema {
+            type_name: "User".to_string(),
+            fields: fields
+                .iter()
+                .map(|(name, optional)| FieldSchema {
+                    field_name: name.to_string(),
+                    field_type: Box::new(TypeSchema::Primitive("String".to_string())),
+                    optional: *optional,
+                    nullable: false,
+                    rename: None,
+                    description: None,
+                    deprecated: None,
+                    constraints: FieldConstraints::default(),
+                    default: None,
+                    example: None,
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn test_function_level_changes() {
+        let old = vec![function("login", "Credentials", "Token"), function("logout", "Token", "bool")];
+        let new = vec![function("login", "Credentials", "Session"), function("refresh", "Token", "Token")];
+        let diff = diff(&old, &new);
+
+        let kinds: Vec<_> = diff.changes.iter().map(|c| (c.path.as_str(), c.breaking)).collect();
+        assert_eq!(
+            kinds,
+            [("login.output", true), ("logout", true), ("refresh", false)]
+        );
+        assert!(diff.is_breaking());
+    }
+
+    #[test]
+    fn test_field_changes_depend_on_direction() {
+        let old_fn = vec![function("update_user", "User", "User")];
+        let old_types = BTreeMap::from([("User".to_string(), user_struct(&[("name", false)]))]);
+        let new_types = BTreeMap::from([(
+            "User".to_string(),
+            user_struct(&[("name", false), ("email", false), ("nickname", true)]),
+        )]);
+
+        let diff = diff_with_types(&old_fn, &old_types, &old_fn, &new_types);
+        let breaking: Vec<_> = diff.breaking_changes().map(|c| c.path.as_str()).collect();
+        // A new required field breaks callers sending the input, not readers of the output
+        assert_eq!(breaking, ["update_user.input.email"]);
+        assert_eq!(diff.changes.len(), 4);
+    }
+
+    #[test]
+    fn test_diff_resolves_the_types_the_metadata_embeds() {
+        let with_output = |schema: TypeSchema| {
+            let mut function = function("get_user", "UserId", "User");
+            function.return_type = schema;
+            vec![function]
+        };
+        let old = with_output(user_struct(&[("name", false)]));
+        let mut renamed = user_struct(&[("name", false)]);
+        if let TypeSchema::Struct(user) = &mut renamed {
+            user.fields[0].rename = Some("fullName".to_string());
+        }
+        let new = with_output(renamed);
+
+        let diff = diff(&old, &new);
+        assert_eq!(
+            diff.changes,
+            [SchemaChange {
+                path: "get_user.output.name".to_string(),
+                kind: ChangeKind::FieldRenamed {
+                    old: "name".to_string(),
+                    new: "fullName".to_string(),
+                },
+                breaking: true,
+            }]
+        );
+    }
+}
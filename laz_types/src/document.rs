@@ -0,0 +1,236 @@
+//! The metadata document served at `/_laz/metadata`, written by the server and read by
+//! clients and codegen.
+//!
+//! Adding a field doesn't change the format: readers default fields a document lacks and
+//! ignore ones they don't know. Changes older readers would misread bump
+//! [`METADATA_SCHEMA_VERSION`], and readers refuse documents newer than the version they
+//! were built with instead of silently misreading them.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    find_type_schema, AuthScheme, BodyEncoding, EndpointDiscovery, FunctionConflict, FunctionMetadata, HttpMethod,
+    LazError, Pagination, ParamInfo, RateLimit, TypeSchema,
+};
+
+/// Version of the metadata document format written and understood by this crate
+pub const METADATA_SCHEMA_VERSION: u32 = 1;
+
+/// Everything clients need to know about a server's API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataDocument {
+    /// Format of the document; `0` for servers from before the format was versioned
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Functions matching the request's filters, of which `functions` may be a page
+    #[serde(default)]
+    pub total_functions: usize,
+    pub functions: Vec<FunctionDocument>,
+    #[serde(default)]
+    pub endpoints_discovery: Vec<EndpointDiscovery>,
+    #[serde(default)]
+    pub total_endpoints: usize,
+    /// Schemas of the named types `functions` refer to
+    #[serde(default)]
+    pub types: BTreeMap<String, TypeSchema>,
+    /// See [`metadata_digest`](crate::metadata_digest)
+    #[serde(default)]
+    pub schema_digest: Option<String>,
+    #[serde(default)]
+    pub api_version: Option<String>,
+    /// Names registered by more than one handler
+    #[serde(default)]
+    pub conflicts: Vec<FunctionConflict>,
+}
+
+impl MetadataDocument {
+    /// Parse a document, refusing one written in a newer format than this crate knows
+    pub fn from_json(json: &str) -> Result<Self, LazError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let schema_version = value.get("schema_version").and_then(serde_json::Value::as_u64).unwrap_or(0);
+        if schema_version > u64::from(METADATA_SCHEMA_VERSION) {
+            return Err(LazError::ServerError(format!(
+                "metadata document has schema version {}, but this laz understands versions up to {}; upgrade laz",
+                schema_version, METADATA_SCHEMA_VERSION
+            )));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// A function as described to clients, with its route resolved and its schemas attached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionDocument {
+    pub function_name: String,
+    #[serde(default)]
+    pub rust_name: Option<String>,
+    #[serde(default)]
+    pub is_mutation: bool,
+    #[serde(default)]
+    pub is_subscription: bool,
+    #[serde(default)]
+    pub is_websocket: bool,
+    #[serde(default)]
+    pub is_multipart: bool,
+    #[serde(default)]
+    pub body_encoding: BodyEncoding,
+    #[serde(default)]
+    pub stream_body: bool,
+    #[serde(default)]
+    pub idempotent: bool,
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+    #[serde(default)]
+    pub since: Option<String>,
+    #[serde(default)]
+    pub removed: Option<String>,
+    #[serde(default)]
+    pub is_async: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub deprecated: Option<String>,
+    #[serde(default)]
+    pub example: Option<serde_json::Value>,
+    /// Route the function is served at; `None` when no route was found for it
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub method: Option<HttpMethod>,
+    #[serde(default)]
+    pub auth: Option<AuthScheme>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub cache_max_age: Option<u64>,
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub error_statuses: Vec<u16>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub input_type_name: Option<String>,
+    pub output_type_name: String,
+    /// Parameters clients supply; server-only extractors like `State` are left out
+    #[serde(default)]
+    pub params: Vec<ParamInfo>,
+    /// [`TypeSchema`] of the input type, as a JSON string
+    #[serde(default)]
+    pub input_schema_json: Option<String>,
+    /// [`TypeSchema`] of the output type, as a JSON string
+    #[serde(default)]
+    pub output_schema_json: Option<String>,
+    #[serde(default)]
+    pub error_type_name: Option<String>,
+    /// [`TypeSchema`] of the error type, as a JSON string
+    #[serde(default)]
+    pub error_schema_json: Option<String>,
+}
+
+impl FunctionDocument {
+    /// Describe `function`, resolving its route against `endpoints`
+    pub fn new(function: &FunctionMetadata, endpoints: &[EndpointDiscovery]) -> Self {
+        let schema_json =
+            |name: &str| find_type_schema(name).and_then(|schema| serde_json::to_string(schema).ok());
+        let output_schema_json = match function.output_type_name.as_str() {
+            "()" => serde_json::to_string(&TypeSchema::Unit).ok(),
+            name => schema_json(name),
+        };
+        // Clients call the resolved route rather than guessing it from the handler name
+        let (path, method) = match function.route(endpoints) {
+            Some((path, method)) => (Some(path), method),
+            None => (None, function.http_method()),
+        };
+        Self {
+            function_name: function.function_name.clone(),
+            rust_name: function.rust_name.clone(),
+            is_mutation: function.is_mutation,
+            is_subscription: function.is_subscription,
+            is_websocket: function.is_websocket,
+            is_multipart: function.is_multipart,
+            body_encoding: function.body_encoding,
+            stream_body: function.stream_body,
+            idempotent: function.idempotent,
+            pagination: function.pagination.clone(),
+            since: function.since.clone(),
+            removed: function.removed.clone(),
+            is_async: function.is_async,
+            description: function.description.clone(),
+            deprecated: function.deprecated.clone(),
+            example: function.example.clone(),
+            path,
+            method: Some(method),
+            auth: function.auth.clone(),
+            namespace: function.namespace.clone(),
+            tags: function.tags.clone(),
+            timeout_ms: function.timeout_ms,
+            cache_max_age: function.cache_max_age,
+            topic: function.topic.clone(),
+            status: function.status,
+            error_statuses: function.error_statuses.clone(),
+            rate_limit: function.rate_limit,
+            input_type_name: function.input_type_name.clone(),
+            output_type_name: function.output_type_name.clone(),
+            params: function.params.iter().filter(|p| p.role.is_client_visible()).cloned().collect(),
+            input_schema_json: function.input_type_name.as_deref().and_then(schema_json),
+            output_schema_json,
+            error_type_name: function.error_type_name.clone(),
+            error_schema_json: function.error_type_name.as_deref().and_then(schema_json),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_documents_round_trip_and_newer_formats_are_refused() {
+        let function = crate::make_function_metadata(
+            "plant_tree".to_string(),
+            vec![],
+            TypeSchema::Unit,
+            None,
+            "()".to_string(),
+            true,
+            true,
+        );
+        let document = MetadataDocument {
+            schema_version: METADATA_SCHEMA_VERSION,
+            total_functions: 1,
+            functions: vec![FunctionDocument::new(&function, &[])],
+            endpoints_discovery: vec![],
+            total_endpoints: 0,
+            types: BTreeMap::new(),
+            schema_digest: None,
+            api_version: Some("1.0.0".to_string()),
+            conflicts: vec![],
+        };
+        let json = serde_json::to_string(&document).unwrap();
+        let read = MetadataDocument::from_json(&json).unwrap();
+        assert_eq!(read.functions[0].function_name, "plant_tree");
+        assert_eq!(read.functions[0].method, Some(HttpMethod::Post));
+        assert_eq!(read.functions[0].output_schema_json.as_deref(), Some(r#"{"kind":"Unit"}"#));
+
+        // Documents from before versioning, with only the fields they had
+        let legacy = r#"{ "functions": [{ "function_name": "health", "output_type_name": "String" }] }"#;
+        assert_eq!(MetadataDocument::from_json(legacy).unwrap().schema_version, 0);
+
+        let newer = json.replacen(
+            &format!("\"schema_version\":{}", METADATA_SCHEMA_VERSION),
+            &format!("\"schema_version\":{}", METADATA_SCHEMA_VERSION + 1),
+            1,
+        );
+        let error = MetadataDocument::from_json(&newer).unwrap_err();
+        assert!(error.to_string().contains("upgrade laz"));
+    }
+}
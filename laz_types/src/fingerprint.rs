@@ -0,0 +1,159 @@
+//! Stable hashes of schemas and metadata.
+//!
+//! Fingerprints are computed over a canonical JSON rendering (object keys sorted, no
+//! whitespace) so they don't depend on field declaration order in serde_json maps or on
+//! inventory registration order. They are meant for cheap change detection, not security.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{find_type_schema, get_public_function_metadata, get_type_schema_map, FunctionMetadata, TypeSchema};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl TypeSchema {
+    /// Stable 16-character hex hash of this schema
+    pub fn fingerprint(&self) -> String {
+        hex(fnv1a(FNV_OFFSET, canonical_json(self).as_bytes()))
+    }
+}
+
+impl FunctionMetadata {
+    /// Stable 16-character hex hash of the function's wire format: its parameters, return
+    /// type and the registered schemas of its input and output. Descriptions and other
+    /// documentation don't change it.
+    pub fn schema_fingerprint(&self) -> String {
+        let input = self.input_type_name.as_deref().and_then(find_type_schema);
+        let output = find_type_schema(&self.output_type_name);
+        let signature = (&self.params, &self.return_type, input, output);
+        hex(fnv1a(FNV_OFFSET, canonical_json(&signature).as_bytes()))
+    }
+}
+
+/// Digest over every public registered function and type schema.
+///
+/// Two servers exposing the same API produce the same digest, so a client can compare it
+/// against the digest it was generated from to tell whether its code is stale.
+pub fn metadata_digest() -> String {
+    let mut functions: Vec<&FunctionMetadata> = get_public_function_metadata();
+    functions.sort_by(|a, b| a.function_name.cmp(&b.function_name));
+
+    let schemas: Vec<String> = get_type_schema_map()
+        .into_iter()
+        .map(|(name, schema)| format!("{}={}", name, canonical_json(schema)))
+        .collect();
+
+    let mut hash = FNV_OFFSET;
+    for function in functions {
+        hash = fnv1a(hash, canonical_json(function).as_bytes());
+        hash = fnv1a(hash, b"\n");
+    }
+    for schema in schemas {
+        hash = fnv1a(hash, schema.as_bytes());
+        hash = fnv1a(hash, b"\n");
+    }
+    hex(hash)
+}
+
+/// Stable 16-character hex hash of a JSON document, independent of its key order
+pub fn json_fingerprint(value: &Value) -> String {
+    hex(fnv1a(FNV_OFFSET, canonical_json(value).as_bytes()))
+}
+
+/// Stable 16-character hex hash of a text document
+pub fn text_fingerprint(text: &str) -> String {
+    hex(fnv1a(FNV_OFFSET, text.as_bytes()))
+}
+
+fn canonical_json<T: Serialize + ?Sized>(value: &T) -> String {
+    let mut out = String::new();
+    match serde_json::to_value(value) {
+        Ok(value) => write_canonical(&value, &mut out),
+        Err(_) => out.push_str("null"),
+    }
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+fn hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_json_sorts_keys() {
+        let value = serde_json::json!({ "b": [1, { "d": true, "c": null }], "a": "x" });
+        let mut out = String::new();
+        write_canonical(&value, &mut out);
+        assert_eq!(out, r#"{"a":"x","b":[1,{"c":null,"d":true}]}"#);
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_schema_changes() {
+        let a = TypeSchema::Primitive("String".to_string());
+        let b = TypeSchema::Primitive("i64".to_string());
+        assert_eq!(a.fingerprint(), a.clone().fingerprint());
+        assert_ne!(a.fingerprint(), b.fingerprint());
+        assert_eq!(a.fingerprint().len(), 16);
+    }
+
+    #[test]
+    fn test_schema_fingerprint_ignores_documentation() {
+        let function = crate::make_function_metadata(
+            "plant_tree".to_string(),
+            Vec::new(),
+            TypeSchema::Primitive("String".to_string()),
+            None,
+            "String".to_string(),
+            true,
+            true,
+        );
+        let mut documented = function.clone();
+        documented.description = Some("Plant a tree".to_string());
+        assert_eq!(function.schema_fingerprint(), documented.schema_fingerprint());
+        let mut changed = function.clone();
+        changed.return_type = TypeSchema::Primitive("u64".to_string());
+        assert_ne!(function.schema_fingerprint(), changed.schema_fingerprint());
+    }
+}
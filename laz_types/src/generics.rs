@@ -0,0 +1,236 @@
+//! Schemas for generic types.
+//!
+//! Deriving `LazSchema` on a generic struct registers a template in which each type
+//! parameter appears as `TypeSchema::Ref("T")`. Every concrete use such as `Paginated<User>`
+//! registers a monomorphized instance (`Paginated_User`) that is built from the template by
+//! substituting the parameters, so clients only ever see concrete types.
+
+use crate::{EnumSchema, FieldSchema, StructSchema, TypeSchema, VariantSchema};
+
+/// Inventory entry for the schema template of a generic type
+pub struct GenericSchemaEntry {
+    pub type_name: &'static str,
+    /// Type parameter names in declaration order
+    pub params: &'static [&'static str],
+    pub getter: fn() -> &'static TypeSchema,
+}
+
+inventory::collect!(GenericSchemaEntry);
+
+/// Build the schema of a generic type instantiated with `args`, named `instance_name`.
+///
+/// Falls back to `TypeSchema::Opaque` when no template is registered for `type_name`.
+pub fn instantiate_generic(type_name: &str, instance_name: &str, args: Vec<TypeSchema>) -> TypeSchema {
+    let Some(entry) = inventory::iter::<GenericSchemaEntry>
+        .into_iter()
+        .find(|entry| entry.type_name == type_name)
+    else {
+        return TypeSchema::Opaque(instance_name.to_string());
+    };
+
+    let bindings: Vec<(&str, &TypeSchema)> = entry.params.iter().copied().zip(args.iter()).collect();
+    match (entry.getter)().substitute(&bindings) {
+        TypeSchema::Struct(s) => TypeSchema::Struct(StructSchema {
+            type_name: instance_name.to_string(),
+            ..s
+        }),
+        TypeSchema::Enum(e) => TypeSchema::Enum(EnumSchema {
+            type_name: instance_name.to_string(),
+            ..e
+        }),
+        other => other,
+    }
+}
+
+/// Concrete type name for a generic instantiation, from its token string.
+///
+/// Path prefixes are dropped and the remaining identifiers are joined in PascalCase, each
+/// type argument preceded by one underscore per level of nesting: `Paginated < models ::
+/// User >` becomes `Paginated_User` and `Page<Vec<User>, i64>` becomes `Page_Vec__User_I64`,
+/// so distinct instantiations get distinct names. Smart pointers are transparent, so
+/// `Paginated<Box<User>>` names the same instance.
+pub fn generic_instance_name(type_str: &str) -> String {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut name = String::new();
+    let mut rest = type_str;
+    let mut after_quote = false;
+    // Whether each open bracket nests its arguments a level deeper; smart pointers' don't
+    let mut brackets: Vec<bool> = Vec::new();
+    let mut pointer_next = false;
+    while let Some(c) = rest.chars().next() {
+        if !is_ident_char(c) {
+            after_quote = c == '\'';
+            match c {
+                '<' | '(' | '[' => brackets.push(!std::mem::take(&mut pointer_next)),
+                '>' | ')' | ']' => {
+                    brackets.pop();
+                }
+                _ => {}
+            }
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let end = rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+        let (ident, tail) = rest.split_at(end);
+        rest = tail;
+
+        // Module path segments, lifetimes and smart pointers don't contribute to the name
+        let is_pointer = is_smart_pointer_name(ident) && tail.trim_start().starts_with('<');
+        if after_quote || is_pointer || tail.trim_start().starts_with("::") {
+            after_quote = false;
+            pointer_next = is_pointer;
+            continue;
+        }
+        let depth = brackets.iter().filter(|nests| **nests).count();
+        name.extend(std::iter::repeat_n('_', depth));
+        let mut chars = ident.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+    name
+}
+
+/// Whether a type name is a pointer that serializes exactly like its pointee
+/// (`Box<T>`, `Arc<T>`, `Rc<T>`, `Cow<T>`)
+pub fn is_smart_pointer_name(name: &str) -> bool {
+    matches!(name, "Box" | "Arc" | "Rc" | "Cow")
+}
+
+/// Whether a type name denotes a language primitive (or `serde_json::Value`)
+/// rather than a user-defined type
+pub fn is_primitive_type_name(name: &str) -> bool {
+    matches!(
+        name,
+        "bool"
+            | "char"
+            | "str"
+            | "String"
+            | "i8"
+            | "i16"
+            | "i32"
+            | "i64"
+            | "i128"
+            | "isize"
+            | "u8"
+            | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "usize"
+            | "f32"
+            | "f64"
+            | "Value"
+    )
+}
+
+impl TypeSchema {
+    /// Replace `Ref`s to bound type parameters with their arguments
+    fn substitute(&self, bindings: &[(&str, &TypeSchema)]) -> TypeSchema {
+        let sub = |schema: &TypeSchema| Box::new(schema.substitute(bindings));
+        match self {
+            TypeSchema::Ref(name) => bindings
+                .iter()
+                .find(|(param, _)| param == name)
+                .map(|(_, arg)| (*arg).clone())
+                .unwrap_or_else(|| self.clone()),
+            TypeSchema::Struct(s) => TypeSchema::Struct(StructSchema {
+                type_name: s.type_name.clone(),
+                fields: substitute_fields(&s.fields, bindings),
+            }),
+            TypeSchema::Enum(e) => TypeSchema::Enum(EnumSchema {
+                type_name: e.type_name.clone(),
+                variants: e
+                    .variants
+                    .iter()
+                    .map(|v| VariantSchema {
+                        inner_schema: v.inner_schema.as_deref().map(sub),
+                        fields: substitute_fields(&v.fields, bindings),
+                        ..v.clone()
+                    })
+                    .collect(),
+                tagging: e.tagging.clone(),
+            }),
+            TypeSchema::Container {
+                container_type,
+                type_params,
+            } => TypeSchema::Container {
+                container_type: container_type.clone(),
+                type_params: type_params.iter().map(|p| p.substitute(bindings)).collect(),
+            },
+            TypeSchema::Map {
+                key_type,
+                value_type,
+            } => TypeSchema::Map {
+                key_type: sub(key_type),
+                value_type: sub(value_type),
+            },
+            TypeSchema::Array { inner, len } => TypeSchema::Array {
+                inner: sub(inner),
+                len: *len,
+            },
+            TypeSchema::Tuple(items) => TypeSchema::Tuple(items.iter().map(|i| sub(i)).collect()),
+            TypeSchema::Newtype(inner) => TypeSchema::Newtype(sub(inner)),
+            TypeSchema::Primitive(_)
+            | TypeSchema::DateTime { .. }
+            | TypeSchema::Decimal(_)
+            | TypeSchema::Int128(_)
+            | TypeSchema::NonZero(_)
+            | TypeSchema::Bytes(_)
+            | TypeSchema::Any
+            | TypeSchema::Unit
+            | TypeSchema::Opaque(_) => self.clone(),
+        }
+    }
+}
+
+fn substitute_fields(fields: &[FieldSchema], bindings: &[(&str, &TypeSchema)]) -> Vec<FieldSchema> {
+    fields
+        .iter()
+        .map(|f| FieldSchema {
+            field_type: Box::new(f.field_type.substitute(bindings)),
+            ..f.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generic_instance_name_drops_paths() {
+        assert_eq!(generic_instance_name("Paginated < User >"), "Paginated_User");
+        assert_eq!(
+            generic_instance_name("crate :: Paginated < Vec < models :: User > >"),
+            "Paginated_Vec__User"
+        );
+        assert_eq!(generic_instance_name("Page<i64>"), "Page_I64");
+        assert_eq!(generic_instance_name("Borrowed<'static, str>"), "Borrowed_Str");
+        assert_eq!(generic_instance_name("Paginated<std::sync::Arc<User>>"), "Paginated_User");
+    }
+
+    #[test]
+    fn test_generic_instance_names_dont_collide() {
+        let distinct = |a: &str, b: &str| assert_ne!(generic_instance_name(a), generic_instance_name(b), "{} and {}", a, b);
+        distinct("Page<FixtureAudit>", "PageFixture<Audit>");
+        distinct("Pair<A, B<C>>", "Pair<A<B>, C>");
+        distinct("Pair<A, B<C>>", "Pair<A<B, C>>");
+        distinct("Pair<A, B, C>", "Pair<A, B<C>>");
+        distinct("Page<(A, B)>", "Page<A, B>");
+    }
+
+    #[test]
+    fn test_substitute_replaces_bound_params() {
+        let template = TypeSchema::Container {
+            container_type: "Vec".to_string(),
+            type_params: vec![TypeSchema::Ref("T".to_string())],
+        };
+        let user = TypeSchema::Ref("User".to_string());
+        let TypeSchema::Container { type_params, .. } = template.substitute(&[("T", &user)]) else {
+            panic!("substitution changed the schema kind");
+        };
+        assert!(matches!(&type_params[0], TypeSchema::Ref(name) if name == "User"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Serde helper encoding 128-bit integers as decimal strings.
+//!
+//! JSON numbers lose precision past 2^53 in most parsers, so `i128`/`u128` values travel as
+//! strings. Use it with `#[serde(with = "laz_types::int128")]`, or on optional fields with
+//! `#[serde(default, with = "laz_types::int128::option")]`. Deserialization also accepts
+//! plain numbers, so payloads from peers that don't use the helper still decode.
+
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::{de, Deserializer, Serializer};
+
+pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(IntVisitor(PhantomData))
+}
+
+/// The same encoding for optional integers
+pub mod option {
+    use super::*;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+
+    struct OptionVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> de::Visitor<'de> for OptionVisitor<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        type Value = Option<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an integer, an integer string or null")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+struct IntVisitor<T>(PhantomData<T>);
+
+impl<T> IntVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    fn parse<E: de::Error>(digits: &str) -> Result<T, E> {
+        digits.parse().map_err(E::custom)
+    }
+}
+
+impl<T> de::Visitor<'_> for IntVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an integer or an integer string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        Self::parse(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Ledger {
+        #[serde(with = "super")]
+        balance: i128,
+        #[serde(default, with = "super::option")]
+        limit: Option<u128>,
+    }
+
+    #[test]
+    fn test_round_trip_as_strings() {
+        let ledger = Ledger { balance: -(1 << 100), limit: Some(u128::MAX) };
+        let json = serde_json::to_value(&ledger).unwrap();
+        assert_eq!(json["balance"], "-1267650600228229401496703205376");
+        assert_eq!(json["limit"], u128::MAX.to_string());
+        assert_eq!(serde_json::from_value::<Ledger>(json).unwrap(), ledger);
+
+        let plain: Ledger = serde_json::from_str(r#"{"balance": 42, "limit": null}"#).unwrap();
+        assert_eq!(plain, Ledger { balance: 42, limit: None });
+        assert!(serde_json::from_str::<Ledger>(r#"{"balance": "4x"}"#).is_err());
+    }
+}
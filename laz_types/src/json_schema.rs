@@ -0,0 +1,417 @@
+//! Export of [`TypeSchema`]s as JSON Schema (draft 2020-12) documents.
+//!
+//! The output describes the serde wire format: wire names for fields and variants,
+//! enums in their serde tagging style, and `null` for `Option`s.
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    find_type_schema, BytesEncoding, DateTimeEncoding, EnumSchema, EnumTagging, FieldConstraints,
+    FieldDefault, FieldSchema, TypeSchema, VariantSchema,
+};
+
+/// Dialect URI written to the `$schema` keyword
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Convert a schema into a standalone JSON Schema document.
+///
+/// Named types referenced through [`TypeSchema::Ref`] are resolved from the registry and
+/// placed under `$defs`; references that can't be resolved accept any value.
+pub fn to_json_schema(schema: &TypeSchema) -> Value {
+    let mut document = match schema_node(schema, "#/$defs/") {
+        Value::Object(map) => map,
+        other => {
+            let mut map = Map::new();
+            map.insert("allOf".to_string(), json!([other]));
+            map
+        }
+    };
+    document.insert("$schema".to_string(), json!(JSON_SCHEMA_DIALECT));
+
+    let defs = referenced_definitions(schema, "#/$defs/");
+    if !defs.is_empty() {
+        document.insert("$defs".to_string(), Value::Object(defs));
+    }
+    Value::Object(document)
+}
+
+/// Schemas of all named types reachable from `schema`, keyed by name
+pub(crate) fn referenced_definitions(schema: &TypeSchema, ref_prefix: &str) -> Map<String, Value> {
+    let mut defs = Map::new();
+    let mut pending: Vec<String> = schema.referenced_types().into_iter().map(String::from).collect();
+    while let Some(name) = pending.pop() {
+        if defs.contains_key(&name) {
+            continue;
+        }
+        let node = match find_type_schema(&name) {
+            Some(def) => {
+                pending.extend(def.referenced_types().into_iter().map(String::from));
+                schema_node(def, ref_prefix)
+            }
+            None => json!({}),
+        };
+        defs.insert(name, node);
+    }
+    defs
+}
+
+/// JSON Schema for a single type, with references pointing at `ref_prefix` + name
+pub(crate) fn schema_node(schema: &TypeSchema, ref_prefix: &str) -> Value {
+    match schema {
+        TypeSchema::Primitive(name) => primitive_node(name),
+        TypeSchema::Struct(s) => {
+            let mut node = object_node(&s.fields, ref_prefix);
+            node["title"] = json!(s.type_name);
+            node
+        }
+        TypeSchema::Enum(e) => {
+            let mut node = enum_node(e, ref_prefix);
+            node["title"] = json!(e.type_name);
+            node
+        }
+        TypeSchema::Container {
+            container_type,
+            type_params,
+        } => {
+            let params: Vec<Value> = type_params.iter().map(|p| schema_node(p, ref_prefix)).collect();
+            match (container_type.as_str(), params.as_slice()) {
+                ("Vec", [inner]) => json!({ "type": "array", "items": inner }),
+                ("HashSet" | "BTreeSet", [inner]) => {
+                    json!({ "type": "array", "items": inner, "uniqueItems": true })
+                }
+                ("Option", [inner]) => json!({ "anyOf": [inner, { "type": "null" }] }),
+                ("Result", [ok, err]) => json!({
+                    "oneOf": [
+                        tagged_node("Ok", ok.clone()),
+                        tagged_node("Err", err.clone()),
+                    ]
+                }),
+                _ => json!({}),
+            }
+        }
+        TypeSchema::Map { value_type, .. } => json!({
+            "type": "object",
+            "additionalProperties": schema_node(value_type, ref_prefix),
+        }),
+        TypeSchema::Array { inner, len } => json!({
+            "type": "array",
+            "items": schema_node(inner, ref_prefix),
+            "minItems": len,
+            "maxItems": len,
+        }),
+        TypeSchema::DateTime { encoding, .. } => match encoding {
+            DateTimeEncoding::Rfc3339 => json!({ "type": "string", "format": "date-time" }),
+            DateTimeEncoding::Date => json!({ "type": "string", "format": "date" }),
+            // Offset-less values don't satisfy the RFC 3339 based formats
+            DateTimeEncoding::NaiveDateTime | DateTimeEncoding::Time => json!({ "type": "string" }),
+            DateTimeEncoding::Unix => json!({ "type": "integer" }),
+        },
+        TypeSchema::Decimal(_) => json!({ "type": "string", "format": "decimal" }),
+        TypeSchema::Int128(name) => int128_node(name, false),
+        TypeSchema::NonZero(name) if is_int128(name) => int128_node(name, true),
+        TypeSchema::NonZero(name) => {
+            let mut node = primitive_node(name);
+            if node.get("minimum").is_some() {
+                node["minimum"] = json!(1);
+            } else {
+                node["not"] = json!({ "const": 0 });
+            }
+            node
+        }
+        TypeSchema::Bytes(BytesEncoding::Base64) => {
+            json!({ "type": "string", "contentEncoding": "base64" })
+        }
+        TypeSchema::Bytes(BytesEncoding::Array) => json!({
+            "type": "array",
+            "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+        }),
+        TypeSchema::Tuple(items) => tuple_node(items.iter().map(|i| schema_node(i, ref_prefix))),
+        TypeSchema::Newtype(inner) => schema_node(inner, ref_prefix),
+        TypeSchema::Ref(name) => json!({ "$ref": format!("{}{}", ref_prefix, name) }),
+        TypeSchema::Unit => json!({ "type": "null" }),
+        TypeSchema::Any | TypeSchema::Opaque(_) => json!({}),
+    }
+}
+
+fn primitive_node(name: &str) -> Value {
+    match name {
+        "String" | "str" => json!({ "type": "string" }),
+        "char" => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        "bool" => json!({ "type": "boolean" }),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => json!({ "type": "integer" }),
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+            json!({ "type": "integer", "minimum": 0 })
+        }
+        "f32" | "f64" => json!({ "type": "number" }),
+        "Uuid" => json!({ "type": "string", "format": "uuid" }),
+        _ => json!({}),
+    }
+}
+
+fn is_int128(name: &str) -> bool {
+    name == "i128" || name == "u128"
+}
+
+/// Decimal digit string, optionally signed and optionally excluding zero
+fn int128_node(name: &str, non_zero: bool) -> Value {
+    let sign = if name == "i128" { "-?" } else { "" };
+    let digits = if non_zero { "[1-9][0-9]*" } else { "(0|[1-9][0-9]*)" };
+    json!({ "type": "string", "format": name, "pattern": format!("^{}{}$", sign, digits) })
+}
+
+fn object_node(fields: &[FieldSchema], ref_prefix: &str) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for field in fields {
+        let mut node = schema_node(&field.field_type, ref_prefix);
+        apply_constraints(&mut node, &field.constraints);
+        if let Some(description) = &field.description {
+            node["description"] = json!(description);
+        }
+        if field.deprecated.is_some() {
+            node["deprecated"] = json!(true);
+        }
+        if let Some(default) = field.default.as_ref().and_then(FieldDefault::value) {
+            node["default"] = default.clone();
+        }
+        if let Some(example) = &field.example {
+            node["examples"] = json!([example]);
+        }
+        if !field.optional {
+            required.push(json!(field.wire_name()));
+        }
+        properties.insert(field.wire_name().to_string(), node);
+    }
+
+    let mut node = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        node["required"] = Value::Array(required);
+    }
+    node
+}
+
+/// Externally tagged enum: unit variants are strings, the rest single-key objects
+fn enum_node(schema: &EnumSchema, ref_prefix: &str) -> Value {
+    let variants = &schema.variants;
+    let payload = |variant: &VariantSchema| {
+        if !variant.fields.is_empty() {
+            Some(object_node(&variant.fields, ref_prefix))
+        } else {
+            variant.inner_schema.as_ref().map(|inner| schema_node(inner, ref_prefix))
+        }
+    };
+
+    let one_of: Vec<Value> = match &schema.tagging {
+        EnumTagging::External => {
+            let unit_names: Vec<&str> = variants
+                .iter()
+                .filter(|v| v.inner_schema.is_none() && v.fields.is_empty())
+                .map(|v| v.wire_name())
+                .collect();
+            if unit_names.len() == variants.len() {
+                return json!({ "type": "string", "enum": unit_names });
+            }
+            let mut one_of = Vec::new();
+            if !unit_names.is_empty() {
+                one_of.push(json!({ "type": "string", "enum": unit_names }));
+            }
+            one_of.extend(variants.iter().filter_map(|variant| {
+                payload(variant).map(|payload| tagged_node(variant.wire_name(), payload))
+            }));
+            one_of
+        }
+        // The tag sits beside the variant's own fields (or a newtype payload's fields)
+        EnumTagging::Internal { tag } => variants
+            .iter()
+            .map(|variant| {
+                let tag_node = json!({
+                    "type": "object",
+                    "properties": { tag.as_str(): { "const": variant.wire_name() } },
+                    "required": [tag],
+                });
+                match payload(variant) {
+                    Some(payload) => json!({ "allOf": [tag_node, payload] }),
+                    None => tag_node,
+                }
+            })
+            .collect(),
+        EnumTagging::Adjacent { tag, content } => variants
+            .iter()
+            .map(|variant| {
+                let mut properties = Map::new();
+                properties.insert(tag.clone(), json!({ "const": variant.wire_name() }));
+                let mut required = vec![json!(tag)];
+                if let Some(payload) = payload(variant) {
+                    properties.insert(content.clone(), payload);
+                    required.push(json!(content));
+                }
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                    "additionalProperties": false,
+                })
+            })
+            .collect(),
+        EnumTagging::Untagged => variants
+            .iter()
+            .map(|variant| payload(variant).unwrap_or_else(|| json!({ "type": "null" })))
+            .collect(),
+    };
+    json!({ "oneOf": one_of })
+}
+
+fn tagged_node(tag: &str, payload: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": { tag: payload },
+        "required": [tag],
+        "additionalProperties": false,
+    })
+}
+
+fn tuple_node(items: impl Iterator<Item = Value>) -> Value {
+    let items: Vec<Value> = items.collect();
+    json!({
+        "type": "array",
+        "prefixItems": items,
+        "items": false,
+        "minItems": items.len(),
+        "maxItems": items.len(),
+    })
+}
+
+fn apply_constraints(node: &mut Value, constraints: &FieldConstraints) {
+    if constraints.is_empty() {
+        return;
+    }
+    let is_array = node["type"] == "array";
+    if let Some(min) = constraints.min {
+        node["minimum"] = json!(min);
+    }
+    if let Some(max) = constraints.max {
+        node["maximum"] = json!(max);
+    }
+    if let Some(min_length) = constraints.min_length {
+        node[if is_array { "minItems" } else { "minLength" }] = json!(min_length);
+    }
+    if let Some(max_length) = constraints.max_length {
+        node[if is_array { "maxItems" } else { "maxLength" }] = json!(max_length);
+    }
+    if let Some(pattern) = &constraints.pattern {
+        node["pattern"] = json!(pattern);
+    }
+    if !constraints.one_of.is_empty() {
+        node["enum"] = json!(constraints.one_of);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StructSchema;
+
+    fn field(name: &str, field_type: TypeSchema, optional: bool) -> FieldSchema {
+        FieldSchema {
+            field_name: name.to_string(),
+            field_type: Box::new(field_type),
+            optional,
+            nullable: false,
+            rename: None,
+            description: None,
+            deprecated: None,
+            constraints: FieldConstraints::default(),
+            default: None,
+            example: None,
+        }
+    }
+
+    #[test]
+    fn test_struct_becomes_object_with_required_fields() {
+        let mut name = field("name", TypeSchema::Primitive("String".to_string()), false);
+        name.constraints.max_length = Some(32);
+        let schema = TypeSchema::Struct(StructSchema {
+            type_name: "User".to_string(),
+            fields: vec![
+                name,
+                field(
+                    "nickname",
+                    TypeSchema::Container {
+                        container_type: "Option".to_string(),
+                        type_params: vec![TypeSchema::Primitive("String".to_string())],
+                    },
+                    true,
+                ),
+            ],
+        });
+
+        let document = to_json_schema(&schema);
+        assert_eq!(document["$schema"], JSON_SCHEMA_DIALECT);
+        assert_eq!(document["type"], "object");
+        assert_eq!(document["required"], json!(["name"]));
+        assert_eq!(document["properties"]["name"]["maxLength"], 32);
+        assert_eq!(
+            document["properties"]["nickname"]["anyOf"][1],
+            json!({ "type": "null" })
+        );
+    }
+
+    #[test]
+    fn test_unit_enum_becomes_string_enum() {
+        let schema = TypeSchema::Enum(crate::EnumSchema {
+            type_name: "Role".to_string(),
+            variants: ["Admin", "Member"]
+                .into_iter()
+                .map(|name| VariantSchema {
+                    variant_name: name.to_string(),
+                    inner_schema: None,
+                    rename: None,
+                    fields: Vec::new(),
+                })
+                .collect(),
+            tagging: EnumTagging::External,
+        });
+        let node = schema_node(&schema, "#/$defs/");
+        assert_eq!(node["enum"], json!(["Admin", "Member"]));
+    }
+
+    #[test]
+    fn test_internally_tagged_enum_carries_tag_property() {
+        let schema = TypeSchema::Enum(crate::EnumSchema {
+            type_name: "Event".to_string(),
+            variants: vec![
+                VariantSchema {
+                    variant_name: "Opened".to_string(),
+                    inner_schema: None,
+                    rename: None,
+                    fields: Vec::new(),
+                },
+                VariantSchema {
+                    variant_name: "Moved".to_string(),
+                    inner_schema: None,
+                    rename: None,
+                    fields: vec![field("x", TypeSchema::Primitive("i32".to_string()), false)],
+                },
+            ],
+            tagging: EnumTagging::Internal { tag: "type".to_string() },
+        });
+        let node = schema_node(&schema, "#/$defs/");
+        assert_eq!(node["oneOf"][0]["properties"]["type"], json!({ "const": "Opened" }));
+        assert_eq!(node["oneOf"][1]["allOf"][0]["required"], json!(["type"]));
+        assert_eq!(node["oneOf"][1]["allOf"][1]["required"], json!(["x"]));
+    }
+
+    #[test]
+    fn test_wide_and_non_zero_integers() {
+        let node = schema_node(&TypeSchema::Int128("u128".to_string()), "");
+        assert_eq!(node["type"], "string");
+        assert_eq!(node["pattern"], "^(0|[1-9][0-9]*)$");
+        let node = schema_node(&TypeSchema::NonZero("i128".to_string()), "");
+        assert_eq!(node["pattern"], "^-?[1-9][0-9]*$");
+
+        let node = schema_node(&TypeSchema::NonZero("u32".to_string()), "");
+        assert_eq!(node, json!({ "type": "integer", "minimum": 1 }));
+        let node = schema_node(&TypeSchema::NonZero("i64".to_string()), "");
+        assert_eq!(node, json!({ "type": "integer", "not": { "const": 0 } }));
+    }
+}
@@ -6,6 +6,32 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod arbitrary;
+pub mod base64;
+mod diff;
+mod document;
+mod fingerprint;
+mod generics;
+pub mod int128;
+mod json_schema;
+pub mod openapi;
+mod pagination;
+pub mod signature;
+pub mod typescript;
+mod validate;
+pub mod visit;
+
+pub use diff::{diff, diff_with_types, ChangeKind, SchemaChange, SchemaDiff};
+pub use document::{FunctionDocument, MetadataDocument, METADATA_SCHEMA_VERSION};
+pub use fingerprint::{json_fingerprint, metadata_digest, text_fingerprint};
+pub use json_schema::{to_json_schema, JSON_SCHEMA_DIALECT};
+pub use pagination::{Pagination, PaginationStyle};
+pub use validate::{validate, ValidationError};
+pub use generics::{
+    generic_instance_name, instantiate_generic, is_primitive_type_name, is_smart_pointer_name,
+    GenericSchemaEntry,
+};
+
 /// Schema for any Rust type (struct, enum, primitive)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", content = "value")]
@@ -16,17 +42,73 @@ pub enum TypeSchema {
     Struct(StructSchema),
     /// Enums with variants
     Enum(EnumSchema),
-    /// Generic container like Vec<T>, Option<T>
+    /// Generic container like Vec<T>, Option<T>, Result<T, E>
     Container {
         container_type: String,
-        inner_type: Box<TypeSchema>,
+        /// Type parameters in declaration order (e.g. `[T, E]` for `Result<T, E>`)
+        type_params: Vec<TypeSchema>,
+    },
+    /// Key/value maps like HashMap<K, V>, BTreeMap<K, V>
+    Map {
+        key_type: Box<TypeSchema>,
+        value_type: Box<TypeSchema>,
     },
-    /// Tuple types
+    /// Fixed-size arrays like [T; N]
+    Array { inner: Box<TypeSchema>, len: usize },
+    /// Date/time values such as chrono::DateTime<Utc> or time::OffsetDateTime
+    DateTime {
+        type_name: String,
+        encoding: DateTimeEncoding,
+    },
+    /// Arbitrary-precision decimals (rust_decimal, bigdecimal), string-encoded on the wire
+    Decimal(String),
+    /// 128-bit integers (`i128`, `u128`), string-encoded on the wire via the [`int128`] helper
+    Int128(String),
+    /// Integers that are never zero (`NonZeroU32`, `NonZero<i64>`), named by their primitive
+    NonZero(String),
+    /// Binary data such as `Vec<u8>` or `bytes::Bytes`
+    Bytes(BytesEncoding),
+    /// Arbitrary JSON (`serde_json::Value`)
+    Any,
+    /// `()`, unit structs and `PhantomData`, all serialized as `null`
+    Unit,
+    /// Tuple types, and tuple structs with more than one field
     Tuple(Vec<Box<TypeSchema>>),
-    /// Self-referencing or unresolvable types
+    /// Single-field tuple structs like `UserId(i64)`, serialized as their inner value
+    Newtype(Box<TypeSchema>),
+    /// Reference by name to an entry in the metadata type table.
+    /// Used for user-defined types so recursive and shared types are described once.
+    Ref(String),
+    /// Types that cannot be described, e.g. trait objects
     Opaque(String),
 }
 
+/// Wire encoding of a date/time value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateTimeEncoding {
+    /// RFC 3339 timestamp with offset, e.g. `2024-01-01T12:00:00Z`
+    Rfc3339,
+    /// Timestamp without offset, e.g. `2024-01-01T12:00:00`
+    NaiveDateTime,
+    /// Calendar date, e.g. `2024-01-01`
+    Date,
+    /// Time of day, e.g. `12:00:00`
+    Time,
+    /// Integer seconds since the Unix epoch
+    Unix,
+}
+
+/// Wire encoding of binary data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BytesEncoding {
+    /// Base64 string, via the [`base64`] serde helper
+    Base64,
+    /// Array of integers, serde's default for byte buffers in JSON
+    Array,
+}
+
 /// Schema for a struct
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StructSchema {
@@ -39,7 +121,97 @@ pub struct StructSchema {
 pub struct FieldSchema {
     pub field_name: String,
     pub field_type: Box<TypeSchema>,
+    /// The field may be absent from the JSON
     pub optional: bool,
+    /// The field may be `null` (an `Option`), which is independent of being absent
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nullable: bool,
+    /// Name used on the wire when it differs from `field_name` (serde rename/rename_all)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rename: Option<String>,
+    /// Documentation taken from the field's doc comments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Deprecation note; an empty string marks the field deprecated without a note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Validation constraints declared with `#[laz(...)]`
+    #[serde(default, skip_serializing_if = "FieldConstraints::is_empty")]
+    pub constraints: FieldConstraints,
+    /// Value used when the field is missing, from `#[laz(default = ...)]` or `#[serde(default)]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<FieldDefault>,
+    /// Sample value from `#[laz(example = ...)]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<serde_json::Value>,
+}
+
+/// Value of an attribute like `example = "..."`: JSON when it parses as such, a plain
+/// string otherwise
+pub fn json_or_string(value: &str) -> serde_json::Value {
+    serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string()))
+}
+
+/// Default of a field that may be omitted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldDefault {
+    /// The type's `Default` or a serde default function; the value isn't described
+    Implicit,
+    /// A known default value
+    Value(serde_json::Value),
+}
+
+impl FieldDefault {
+    /// Default from an attribute string, see [`json_or_string`]
+    pub fn parse(value: &str) -> Self {
+        FieldDefault::Value(json_or_string(value))
+    }
+
+    /// The default value, when known
+    pub fn value(&self) -> Option<&serde_json::Value> {
+        match self {
+            FieldDefault::Implicit => None,
+            FieldDefault::Value(value) => Some(value),
+        }
+    }
+}
+
+/// Validation constraints on a field value, e.g. `#[laz(min = 1, max = 100)]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldConstraints {
+    /// Inclusive lower bound for numbers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Inclusive upper bound for numbers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Minimum length of strings and collections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// Maximum length of strings and collections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Regular expression strings must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Allowed string values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub one_of: Vec<String>,
+}
+
+impl FieldConstraints {
+    /// Whether no constraint is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl FieldSchema {
+    /// Name of the field as it appears in serialized JSON
+    pub fn wire_name(&self) -> &str {
+        self.rename.as_deref().unwrap_or(&self.field_name)
+    }
 }
 
 /// Schema for an enum
@@ -47,19 +219,69 @@ pub struct FieldSchema {
 pub struct EnumSchema {
     pub type_name: String,
     pub variants: Vec<VariantSchema>,
+    /// JSON layout of the variants, from the serde `tag`/`content`/`untagged` attributes
+    #[serde(default, skip_serializing_if = "EnumTagging::is_external")]
+    pub tagging: EnumTagging,
+}
+
+/// How enum variants are represented in JSON, mirroring serde's enum representations
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "style", rename_all = "snake_case")]
+pub enum EnumTagging {
+    /// `{"Variant": payload}`, or `"Variant"` for unit variants (serde's default)
+    #[default]
+    External,
+    /// `{"<tag>": "Variant", ...fields}`, from `#[serde(tag = "...")]`
+    Internal { tag: String },
+    /// `{"<tag>": "Variant", "<content>": payload}`, from `#[serde(tag = "...", content = "...")]`
+    Adjacent { tag: String, content: String },
+    /// The payload alone, from `#[serde(untagged)]`
+    Untagged,
+}
+
+impl EnumTagging {
+    pub fn is_external(&self) -> bool {
+        *self == EnumTagging::External
+    }
 }
 
 /// Enum variant
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VariantSchema {
     pub variant_name: String,
+    /// Payload of a tuple variant; a `Tuple` schema when it has more than one field
     pub inner_schema: Option<Box<TypeSchema>>,
+    /// Name used on the wire when it differs from `variant_name` (serde rename/rename_all)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rename: Option<String>,
+    /// Named fields of a struct variant like `Created { id: i64 }`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldSchema>,
+}
+
+impl VariantSchema {
+    /// Name of the variant as it appears in serialized JSON
+    pub fn wire_name(&self) -> &str {
+        self.rename.as_deref().unwrap_or(&self.variant_name)
+    }
 }
 
 /// Metadata for RPC functions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionMetadata {
+    /// Public name of the function; the Rust name unless overridden with `name = "..."`
     pub function_name: String,
+    /// Name of the Rust handler, when `function_name` was overridden
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rust_name: Option<String>,
+    /// Module declaring the handler, e.g. `app::controllers::auth`; not part of the API,
+    /// so left out of serialized metadata and its digest
+    #[serde(skip)]
+    pub module_path: Option<String>,
+    /// Type whose impl block declares the handler, e.g. `TreeController`, or
+    /// `<TreeController as Resource>` for a trait impl; left out like `module_path`
+    #[serde(skip)]
+    pub self_type: Option<String>,
     pub params: Vec<ParamInfo>,
     pub return_type: TypeSchema,
     /// Optional declared primary input type name (e.g., payload), if any
@@ -68,6 +290,396 @@ pub struct FunctionMetadata {
     pub output_type_name: String,
     pub is_async: bool,
     pub is_mutation: bool,
+    /// Declared with `rpc_subscription`: the handler streams `output_type_name` values as
+    /// server-sent events instead of returning one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_subscription: bool,
+    /// Declared with `rpc_ws`: the handler upgrades to a WebSocket, where clients send
+    /// `input_type_name` messages and receive `output_type_name` ones
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_websocket: bool,
+    /// Takes a `Multipart` extractor and reads a `multipart/form-data` body instead of JSON
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_multipart: bool,
+    /// How the request body is encoded, from the handler's `Json`, `Form` or `Multipart`
+    /// extractor
+    #[serde(default)]
+    pub body_encoding: BodyEncoding,
+    /// Declared with `stream_body`: the response is a large body clients should read as a
+    /// stream of bytes rather than buffer and parse as JSON
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream_body: bool,
+    /// Declared with `idempotent`: the mutation accepts an `Idempotency-Key` header and is
+    /// safe for clients to retry with the same key
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub idempotent: bool,
+    /// Pagination convention of a query declared with `paginated`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+    /// API version that introduced the function, declared with `since = "..."`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// API version that drops the function, declared with `removed = "..."`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed: Option<String>,
+    /// Documentation taken from the handler's doc comments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Deprecation note; an empty string marks the function deprecated without a note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Declared application error type, sent as the `details` of an [`RpcErrorBody`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_type_name: Option<String>,
+    /// Sample input, from `example = ...` in the RPC attribute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<serde_json::Value>,
+    /// Route path declared with `path = "..."`; takes precedence over matching the
+    /// function name against discovered routes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// HTTP verb declared with `method = ...`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<HttpMethod>,
+    /// Credentials the function requires, declared with `auth = ...`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthScheme>,
+    /// Group the function belongs to, declared with `namespace = "..."`; generated clients
+    /// put each namespace's methods on a sub-client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Categories declared with `tags = [...]`, used to group operations in specs and to
+    /// generate clients for a subset of functions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Default client-side timeout of a call, declared with `timeout_ms = ...`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Seconds a query's responses may be cached, declared with `cache = "60s"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_max_age: Option<u64>,
+    /// Broadcast topic a subscription streams, declared with `topic = "..."` and also served
+    /// by `LazEndpoint` at `/_laz/topics/{topic}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// Success status declared with `status = 201`, which clients accept besides `2xx`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Error statuses declared with `error_status = [404, 409]`, which clients report as
+    /// errors carrying the status
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error_statuses: Vec<u16>,
+    /// Request budget declared with `rate_limit = "..."`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    /// Declared with `internal`: registered for server-side tooling but left out of the
+    /// public metadata, OpenAPI document and generated clients
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub internal: bool,
+}
+
+/// How an RPC function authenticates callers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "scheme", rename_all = "snake_case")]
+pub enum AuthScheme {
+    /// `Authorization: Bearer <token>`
+    Bearer,
+    /// `Authorization: Basic <credentials>`
+    Basic,
+    /// A key sent in the named header
+    ApiKey { header: String },
+}
+
+impl AuthScheme {
+    pub const DEFAULT_API_KEY_HEADER: &'static str = "X-Api-Key";
+}
+
+impl std::str::FromStr for AuthScheme {
+    type Err = String;
+
+    /// Parse `bearer`, `basic`, `api_key` or `api_key("Header-Name")`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        match s.as_str() {
+            "bearer" => Ok(AuthScheme::Bearer),
+            "basic" => Ok(AuthScheme::Basic),
+            "api_key" => Ok(AuthScheme::ApiKey {
+                header: Self::DEFAULT_API_KEY_HEADER.to_string(),
+            }),
+            _ => s
+                .strip_prefix("api_key(\"")
+                .and_then(|rest| rest.strip_suffix("\")"))
+                .filter(|header| !header.is_empty())
+                .map(|header| AuthScheme::ApiKey {
+                    header: header.to_string(),
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "unsupported auth scheme `{}`; expected bearer, basic, api_key or api_key(\"Header\")",
+                        s
+                    )
+                }),
+        }
+    }
+}
+
+/// Requests a function accepts per period, declared with `rate_limit = "10/min"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period_secs: u64,
+}
+
+impl RateLimit {
+    pub fn period(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.period_secs)
+    }
+}
+
+impl std::str::FromStr for RateLimit {
+    type Err = String;
+
+    /// Parse `<requests>/<period>`, where the period is a [`parse_duration`] duration whose
+    /// count may be left out, as in `10/min` or `100/15min`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid rate limit `{}`; expected e.g. `10/min` or `100/15min`", s);
+        let (requests, period) = s.split_once('/').ok_or_else(invalid)?;
+        let requests: u32 = requests.trim().parse().map_err(|_| invalid())?;
+        let period = period.trim();
+        let period = if period.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_duration(period)
+        } else {
+            parse_duration(&format!("1{}", period))
+        };
+        match period {
+            Ok(period) if requests > 0 && period.as_secs() > 0 => Ok(Self {
+                requests,
+                period_secs: period.as_secs(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Parse a whole number of seconds, minutes, hours or days such as `60s`, `15min`, `1h` or
+/// `2days`; a bare number counts seconds
+pub fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    let invalid = || format!("invalid duration `{}`; expected e.g. `60s`, `5min` or `1h`", s);
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let count: u64 = s[..unit_start].parse().map_err(|_| invalid())?;
+    let unit_secs = match s[unit_start..].trim() {
+        "" | "s" | "sec" | "second" | "seconds" => 1,
+        "m" | "min" | "minute" | "minutes" => 60,
+        "h" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86_400,
+        _ => return Err(invalid()),
+    };
+    count
+        .checked_mul(unit_secs)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+impl std::fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.period_secs {
+            1 => write!(f, "{}/s", self.requests),
+            60 => write!(f, "{}/min", self.requests),
+            3600 => write!(f, "{}/hour", self.requests),
+            86_400 => write!(f, "{}/day", self.requests),
+            secs => write!(f, "{}/{}s", self.requests, secs),
+        }
+    }
+}
+
+impl FunctionMetadata {
+    /// The declared HTTP verb, else GET for queries and POST for mutations
+    pub fn http_method(&self) -> HttpMethod {
+        self.method.unwrap_or(HttpMethod::default_for(self.is_mutation))
+    }
+
+    /// Whether the function reads its input from the query string rather than the body
+    pub fn input_in_query(&self) -> bool {
+        let has_extractor = |name: &str| self.params.iter().any(|p| p.extractor == name);
+        !self.is_mutation || (has_extractor("Query") && !has_extractor("Json") && !has_extractor("Form"))
+    }
+
+    /// Name of the Rust handler, which routes are usually named after
+    pub fn handler_name(&self) -> &str {
+        self.rust_name.as_deref().unwrap_or(&self.function_name)
+    }
+
+    /// Path of the Rust handler, e.g. `app::controllers::TreeController::list`
+    pub fn handler_path(&self) -> String {
+        let mut path = String::new();
+        for prefix in [&self.module_path, &self.self_type].into_iter().flatten() {
+            path.push_str(prefix);
+            path.push_str("::");
+        }
+        path + self.handler_name()
+    }
+
+    /// The discovered route serving the function: the one at its declared `path`, else the
+    /// one whose last URI segment (or, failing that, any part of its URI) names the handler
+    pub fn endpoint<'a>(&self, endpoints: &'a [EndpointDiscovery]) -> Option<&'a EndpointDiscovery> {
+        if let Some(path) = &self.path {
+            return endpoints.iter().find(|e| &e.uri == path);
+        }
+        let name = self.handler_name();
+        let kebab = name.replace('_', "-");
+        endpoints
+            .iter()
+            .find(|e| {
+                let segment = e.uri.rsplit('/').next().unwrap_or_default();
+                segment == name || segment == kebab
+            })
+            .or_else(|| endpoints.iter().find(|e| e.uri.contains(name) || e.uri.contains(&kebab)))
+    }
+
+    /// Route template and HTTP verb serving the function, resolved against the discovered
+    /// routes: the declared `path` and `method` when set, else the route that names the
+    /// handler, with the default verb when that route accepts it or the first one it does
+    pub fn route(&self, endpoints: &[EndpointDiscovery]) -> Option<(String, HttpMethod)> {
+        let endpoint = self.endpoint(endpoints);
+        let path = self.path.clone().or_else(|| endpoint.map(|e| e.uri.clone()))?;
+        let method = self.method.unwrap_or_else(|| {
+            let preferred = HttpMethod::default_for(self.is_mutation);
+            let accepted: Vec<HttpMethod> = endpoint
+                .into_iter()
+                .flat_map(|e| &e.methods)
+                .filter_map(|m| m.parse().ok())
+                .collect();
+            if accepted.is_empty() || accepted.contains(&preferred) {
+                return preferred;
+            }
+            [HttpMethod::Get, HttpMethod::Post, HttpMethod::Put, HttpMethod::Patch, HttpMethod::Delete]
+                .into_iter()
+                .find(|method| accepted.contains(method))
+                .unwrap_or(preferred)
+        });
+        Some((path, method))
+    }
+
+    /// Whether the function exists in API `version`: at or after `since`, before `removed`
+    pub fn available_in(&self, version: &str) -> bool {
+        version_in_range(version, self.since.as_deref(), self.removed.as_deref())
+    }
+}
+
+/// Compare dotted version numbers such as `1.2` and `v1.10.0`; missing components count
+/// as zero and anything after a `-` or `+` is ignored
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    fn components(version: &str) -> Vec<u64> {
+        let version = version.trim().trim_start_matches('v');
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        core.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    }
+    let (a, b) = (components(a), components(b));
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Whether `version` is at or after `since` and before `removed`
+pub fn version_in_range(version: &str, since: Option<&str>, removed: Option<&str>) -> bool {
+    since.is_none_or(|since| compare_versions(version, since).is_ge())
+        && removed.is_none_or(|removed| compare_versions(version, removed).is_lt())
+}
+
+/// HTTP verb an RPC function is served with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl HttpMethod {
+    pub fn default_for(is_mutation: bool) -> Self {
+        if is_mutation {
+            HttpMethod::Post
+        } else {
+            HttpMethod::Get
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DELETE",
+        }
+    }
+
+    /// Whether parameters travel in a JSON body rather than the query string
+    pub fn has_body(&self) -> bool {
+        !matches!(self, HttpMethod::Get)
+    }
+}
+
+impl std::fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for HttpMethod {
+    type Err = String;
+
+    /// Parse a verb case-insensitively
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "GET" => Ok(HttpMethod::Get),
+            "POST" => Ok(HttpMethod::Post),
+            "PUT" => Ok(HttpMethod::Put),
+            "PATCH" => Ok(HttpMethod::Patch),
+            "DELETE" => Ok(HttpMethod::Delete),
+            _ => Err(format!("unsupported HTTP method `{}`", s)),
+        }
+    }
+}
+
+/// Encoding of a function's request body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyEncoding {
+    /// `application/json`, read with `Json<T>`
+    #[default]
+    Json,
+    /// `application/x-www-form-urlencoded`, read with `Form<T>`
+    Form,
+    /// `multipart/form-data`, read with `Multipart`
+    Multipart,
+}
+
+impl BodyEncoding {
+    /// Encoding of the body read by a handler with the given extractors
+    pub fn of_extractors<'a>(extractors: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut encoding = BodyEncoding::Json;
+        for extractor in extractors {
+            match extractor {
+                "Multipart" => return BodyEncoding::Multipart,
+                "Form" => encoding = BodyEncoding::Form,
+                _ => {}
+            }
+        }
+        encoding
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            BodyEncoding::Json => "application/json",
+            BodyEncoding::Form => "application/x-www-form-urlencoded",
+            BodyEncoding::Multipart => "multipart/form-data",
+        }
+    }
 }
 
 /// Parameter information
@@ -76,7 +688,110 @@ pub struct ParamInfo {
     pub name: String,
     pub full_type: String,
     pub extractor: String,
+    /// Part of the request the extractor reads
+    #[serde(default)]
+    pub role: ParamRole,
+    /// Name of the extracted type, e.g. `Filters` for `Query<Filters>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
     pub inner_type_schema: TypeSchema,
+    /// URL path segments bound by a `Path<T>` extractor
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_params: Vec<PathParam>,
+    /// Header a `TypedHeader<T>` extractor requires, e.g. `User-Agent`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+}
+
+/// Part of the request a handler parameter is extracted from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamRole {
+    /// URL path segments, `Path<T>`
+    Path,
+    /// The query string, `Query<T>`
+    Query,
+    /// A JSON or form-encoded body, `Json<T>` or `Form<T>`
+    Body,
+    /// A `multipart/form-data` body
+    Multipart,
+    /// Request headers, `TypedHeader<T>` or `HeaderMap`
+    Header,
+    /// Server-side state such as `State<T>` or `Extension<T>`, never sent by clients
+    Server,
+    #[default]
+    Other,
+}
+
+impl ParamRole {
+    /// Role of an extractor, by the last segment of its type name
+    pub fn of_extractor(extractor: &str) -> Self {
+        match extractor {
+            "Path" => Self::Path,
+            "Query" => Self::Query,
+            "Json" | "Form" => Self::Body,
+            "Multipart" => Self::Multipart,
+            "TypedHeader" | "HeaderMap" => Self::Header,
+            // The upgrade is the handshake itself, not something clients pass
+            "State" | "Extension" | "ConnectInfo" | "WebSocketUpgrade" => Self::Server,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether clients supply the parameter; server-only params are left out of client
+    /// signatures and the public metadata
+    pub fn is_client_visible(&self) -> bool {
+        *self != Self::Server
+    }
+}
+
+/// A URL path segment bound by a `Path<T>` extractor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathParam {
+    /// Binding name, or the field's wire name for a struct extracted by name
+    pub name: String,
+    /// Index among the extractor's segments, in declaration order
+    pub position: usize,
+    pub schema: TypeSchema,
+}
+
+/// Path params of a `Path<T>` extractor: one per tuple element, one per field of a
+/// struct, otherwise a single segment. `bindings` are the names bound by the pattern,
+/// e.g. `["org", "id"]` for `Path((org, id))`.
+pub fn path_params(bindings: &[&str], schema: TypeSchema) -> Vec<PathParam> {
+    let binding = |position: usize| {
+        bindings
+            .get(position)
+            .filter(|name| **name != "_")
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("arg{}", position))
+    };
+    match schema {
+        TypeSchema::Tuple(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(position, item)| PathParam {
+                name: binding(position),
+                position,
+                schema: *item,
+            })
+            .collect(),
+        TypeSchema::Struct(s) => s
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(position, field)| PathParam {
+                name: field.wire_name().to_string(),
+                position,
+                schema: (*field.field_type).clone(),
+            })
+            .collect(),
+        schema => vec![PathParam {
+            name: binding(0),
+            position: 0,
+            schema,
+        }],
+    }
 }
 
 /// Error types for laz RPC operations
@@ -96,6 +811,62 @@ pub enum LazError {
     TypeGenerationError(String),
 }
 
+impl LazError {
+    /// Machine-readable code used for this error in an [`RpcErrorBody`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            LazError::RequestError(_) => "request_error",
+            LazError::JsonError(_) => "invalid_json",
+            LazError::FunctionNotFound(_) => "function_not_found",
+            LazError::InvalidParameter(_) => "invalid_parameter",
+            LazError::ServerError(_) => "server_error",
+            LazError::TypeGenerationError(_) => "type_generation_error",
+        }
+    }
+}
+
+/// Error envelope returned by the server when an RPC call fails
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcErrorBody {
+    /// Machine-readable error code, e.g. `invalid_parameter`
+    pub code: String,
+    /// Human-readable description
+    pub message: String,
+    /// Additional structured context, such as the fields that failed validation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl RpcErrorBody {
+    /// Code of errors whose `details` hold a function's declared application error
+    pub const APPLICATION_ERROR: &'static str = "application_error";
+
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl std::fmt::Display for RpcErrorBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl From<&LazError> for RpcErrorBody {
+    fn from(error: &LazError) -> Self {
+        RpcErrorBody::new(error.code(), error.to_string())
+    }
+}
+
 /// Helper to construct FunctionMetadata to avoid missing-field errors in macro sites
 pub fn make_function_metadata(
     function_name: String,
@@ -108,15 +879,54 @@ pub fn make_function_metadata(
 ) -> FunctionMetadata {
     FunctionMetadata {
         function_name,
+        rust_name: None,
+        module_path: None,
+        self_type: None,
         params,
         return_type,
         input_type_name,
         output_type_name,
         is_async,
         is_mutation,
+        is_subscription: false,
+        is_websocket: false,
+        is_multipart: false,
+        body_encoding: BodyEncoding::Json,
+        stream_body: false,
+        idempotent: false,
+        pagination: None,
+        since: None,
+        removed: None,
+        description: None,
+        deprecated: None,
+        error_type_name: None,
+        example: None,
+        path: None,
+        method: None,
+        auth: None,
+        namespace: None,
+        tags: Vec::new(),
+        timeout_ms: None,
+        cache_max_age: None,
+        topic: None,
+        status: None,
+        error_statuses: Vec::new(),
+        rate_limit: None,
+        internal: false,
     }
 }
 
+/// Marker for types described by `#[derive(LazSchema)]`.
+///
+/// The RPC macros assert that user types named as inputs, outputs and errors implement it,
+/// so a missing derive fails the build instead of surfacing as an opaque type in metadata.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no schema",
+    label = "used by an RPC function",
+    note = "add `#[derive(LazSchema)]` to `{Self}`"
+)]
+pub trait LazSchema {}
+
 /// Inventory entry that lazily constructs and exposes a type schema
 pub struct TypeSchemaEntry {
     pub type_name: &'static str,
@@ -129,15 +939,46 @@ pub struct FunctionMetadataEntry {
     pub getter: fn() -> &'static FunctionMetadata,
 }
 
+/// Schemas of the JSON an RPC function reads and answers with, resolved from its
+/// signature, so servers can check requests before the handler runs
+#[derive(Debug, Clone)]
+pub struct FunctionSchemas {
+    /// Schema of the `Json<T>` body, when the handler reads one
+    pub request: Option<TypeSchema>,
+    /// Schema of the `Query<T>` parameters, when the handler reads them
+    pub query: Option<TypeSchema>,
+    /// Schema of the response body
+    pub response: TypeSchema,
+    /// Schemas of the messages exchanged over the socket of an `rpc_ws` handler
+    pub messages: Option<MessageSchemas>,
+}
+
+/// Schemas of the JSON messages exchanged over a WebSocket session
+#[derive(Debug, Clone)]
+pub struct MessageSchemas {
+    /// Messages clients send
+    pub client: TypeSchema,
+    /// Messages the server sends
+    pub server: TypeSchema,
+}
+
+/// Inventory entry that lazily constructs and exposes a function's [`FunctionSchemas`]
+pub struct FunctionSchemasEntry {
+    pub function_name: &'static str,
+    pub getter: fn() -> &'static FunctionSchemas,
+}
+
 // Registry for schemas and metadata using inventory pattern
 inventory::collect!(TypeSchema);
 inventory::collect!(TypeSchemaEntry);
 inventory::collect!(FunctionMetadata);
 inventory::collect!(FunctionMetadataEntry);
+inventory::collect!(FunctionSchemasEntry);
 
 /// Global registry for function metadata
 use std::sync::{RwLock, OnceLock};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 static FUNCTION_METADATA_REGISTRY: OnceLock<RwLock<HashMap<String, FunctionMetadata>>> =
     OnceLock::new();
@@ -170,6 +1011,24 @@ pub fn get_all_type_schemas() -> Vec<&'static TypeSchema> {
     schemas
 }
 
+/// All collected type schemas keyed by type name, deduplicated and sorted.
+///
+/// Schemas registered through a [`TypeSchemaEntry`] are keyed by the entry's name and win
+/// over directly submitted schemas of the same name; unnamed direct submissions are skipped.
+pub fn get_type_schema_map() -> BTreeMap<String, &'static TypeSchema> {
+    let mut map = BTreeMap::new();
+    for entry in inventory::iter::<TypeSchemaEntry> {
+        map.entry(entry.type_name.to_string())
+            .or_insert_with(|| (entry.getter)());
+    }
+    for schema in inventory::iter::<TypeSchema> {
+        if let Some(name) = schema.type_name() {
+            map.entry(name.to_string()).or_insert(schema);
+        }
+    }
+    map
+}
+
 /// Get all collected function metadata
 pub fn get_all_function_metadata() -> Vec<&'static FunctionMetadata> {
     let mut metadata: Vec<&'static FunctionMetadata> = inventory::iter::<FunctionMetadataEntry>
@@ -180,8 +1039,127 @@ pub fn get_all_function_metadata() -> Vec<&'static FunctionMetadata> {
     metadata
 }
 
+/// Collected function metadata without `internal` functions; what clients get to see
+pub fn get_public_function_metadata() -> Vec<&'static FunctionMetadata> {
+    get_all_function_metadata().into_iter().filter(|m| !m.internal).collect()
+}
+
+/// Public name shared by more than one handler, which clients can't tell apart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionConflict {
+    pub function_name: String,
+    /// Namespace the handlers share, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Paths of the conflicting handlers, e.g. `app::controllers::auth::login`
+    pub handlers: Vec<String>,
+}
+
+/// Names registered by more than one handler in the same namespace. Handlers in different
+/// namespaces may share a name, as generated clients put them on separate sub-clients.
+pub fn function_conflicts<'a>(functions: impl IntoIterator<Item = &'a FunctionMetadata>) -> Vec<FunctionConflict> {
+    let mut by_name: BTreeMap<(Option<&str>, &str), Vec<String>> = BTreeMap::new();
+    for function in functions {
+        by_name
+            .entry((function.namespace.as_deref(), &function.function_name))
+            .or_default()
+            .push(function.handler_path());
+    }
+    by_name
+        .into_iter()
+        .filter(|(_, handlers)| handlers.len() > 1)
+        .map(|((namespace, function_name), mut handlers)| {
+            handlers.sort();
+            FunctionConflict {
+                function_name: function_name.to_string(),
+                namespace: namespace.map(String::from),
+                handlers,
+            }
+        })
+        .collect()
+}
+
+/// Iterate over every known function: inventory registrations first, then those added
+/// at runtime through [`register_function_metadata`].
+///
+/// Inventory metadata is borrowed; runtime registrations are cloned out of the registry.
+pub fn iter_function_metadata() -> impl Iterator<Item = Cow<'static, FunctionMetadata>> {
+    inventory::iter::<FunctionMetadataEntry>
+        .into_iter()
+        .map(|entry| (entry.getter)())
+        .chain(inventory::iter::<FunctionMetadata>)
+        .map(Cow::Borrowed)
+        .chain(get_all_registered_functions().into_iter().map(Cow::Owned))
+}
+
+/// Find a function's metadata by name
+pub fn find_function_metadata(function_name: &str) -> Option<Cow<'static, FunctionMetadata>> {
+    iter_function_metadata().find(|m| m.function_name == function_name)
+}
+
+/// Request and response schemas the RPC macros registered for `function_name`
+pub fn find_function_schemas(function_name: &str) -> Option<&'static FunctionSchemas> {
+    inventory::iter::<FunctionSchemasEntry>
+        .into_iter()
+        .find(|entry| entry.function_name == function_name)
+        .map(|entry| (entry.getter)())
+}
+
+/// All mutations (`true`) or all queries (`false`)
+pub fn functions_by_mutation(is_mutation: bool) -> Vec<Cow<'static, FunctionMetadata>> {
+    iter_function_metadata()
+        .filter(|m| m.is_mutation == is_mutation)
+        .collect()
+}
+
+/// Functions whose input, output or error type is `type_name` or refers to it, directly
+/// or through other registered types
+pub fn functions_for_type(type_name: &str) -> Vec<Cow<'static, FunctionMetadata>> {
+    iter_function_metadata()
+        .filter(|m| function_uses_type(m, type_name))
+        .collect()
+}
+
+fn function_uses_type(metadata: &FunctionMetadata, type_name: &str) -> bool {
+    let mut pending: Vec<&str> = metadata
+        .input_type_name
+        .iter()
+        .chain(metadata.error_type_name.iter())
+        .chain(std::iter::once(&metadata.output_type_name))
+        .map(String::as_str)
+        .collect();
+    let mut seen = HashSet::new();
+    while let Some(name) = pending.pop() {
+        if name == type_name {
+            return true;
+        }
+        if !seen.insert(name) {
+            continue;
+        }
+        if let Some(schema) = find_type_schema(name) {
+            pending.extend(schema.referenced_types());
+        }
+    }
+    false
+}
+
+/// Find a derived type schema by its registered type name.
+///
+/// Unlike [`find_type_schema`] this only builds the matching schema, so it is safe to call
+/// while another schema is being constructed (e.g. to inline `#[serde(flatten)]` fields).
+pub fn lookup_type_schema(type_name: &str) -> Option<&'static TypeSchema> {
+    inventory::iter::<TypeSchemaEntry>
+        .into_iter()
+        .find(|entry| entry.type_name == type_name)
+        .map(|entry| (entry.getter)())
+}
+
 /// Find a type schema by name
 pub fn find_type_schema(type_name: &str) -> Option<&'static TypeSchema> {
+    if let Some(schema) = lookup_type_schema(type_name) {
+        return Some(schema);
+    }
+
     for entry in inventory::iter::<TypeSchemaEntry> {
         let schema = (entry.getter)();
         if entry.type_name == type_name {
@@ -240,8 +1218,96 @@ pub fn find_type_schema(type_name: &str) -> Option<&'static TypeSchema> {
     None
 }
 
+impl TypeSchema {
+    /// Name the schema is registered under, for schemas that carry one
+    pub fn type_name(&self) -> Option<&str> {
+        match self {
+            TypeSchema::Primitive(name) | TypeSchema::Opaque(name) => Some(name),
+            TypeSchema::Struct(s) => Some(&s.type_name),
+            TypeSchema::Enum(e) => Some(&e.type_name),
+            _ => None,
+        }
+    }
+
+    /// Names of all types this schema refers to via [`TypeSchema::Ref`], in first-seen order
+    pub fn referenced_types(&self) -> Vec<&str> {
+        struct RefCollector<'a>(Vec<&'a str>);
+
+        impl<'a> visit::Visitor<'a> for RefCollector<'a> {
+            fn visit_ref(&mut self, name: &'a str) {
+                if !self.0.contains(&name) {
+                    self.0.push(name);
+                }
+            }
+        }
+
+        let mut collector = RefCollector(Vec::new());
+        visit::Visitor::visit_schema(&mut collector, self);
+        collector.0
+    }
+}
+
+/// Build the metadata type table: every named type reachable from the public registered
+/// functions' input, output and query types, following [`TypeSchema::Ref`]s transitively.
+///
+/// Referenced names without a registered schema are left out; clients treat them as opaque.
+pub fn type_table() -> BTreeMap<String, &'static TypeSchema> {
+    type_table_for(get_public_function_metadata())
+}
+
+/// [`type_table`] restricted to the types reachable from `functions`
+pub fn type_table_for<'a>(functions: impl IntoIterator<Item = &'a FunctionMetadata>) -> BTreeMap<String, &'static TypeSchema> {
+    let mut pending: Vec<String> = functions
+        .into_iter()
+        .flat_map(|m| {
+            m.input_type_name
+                .iter()
+                .chain(m.error_type_name.iter())
+                .chain(std::iter::once(&m.output_type_name))
+                .chain(m.params.iter().filter(|p| p.role == ParamRole::Query).flat_map(|p| &p.type_name))
+        })
+        .cloned()
+        .collect();
+    let mut table = BTreeMap::new();
+
+    while let Some(name) = pending.pop() {
+        if table.contains_key(&name) {
+            continue;
+        }
+        let Some(schema) = find_type_schema(&name) else {
+            continue;
+        };
+        if !matches!(
+            schema,
+            TypeSchema::Struct(_)
+                | TypeSchema::Enum(_)
+                | TypeSchema::Tuple(_)
+                | TypeSchema::Newtype(_)
+                | TypeSchema::Unit
+        ) {
+            continue;
+        }
+        pending.extend(schema.referenced_types().into_iter().map(String::from));
+        table.insert(name, schema);
+    }
+    table
+}
+
+/// Served at `/_laz/version`, so clients can check they're talking to a compatible laz
+/// server without downloading the metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerVersion {
+    /// Version of the `laz_server` crate
+    pub laz_version: String,
+    /// Version declared with `LazEndpoint::with_version`
+    pub api_version: Option<String>,
+    /// See [`metadata_digest`]
+    pub schema_digest: String,
+    pub total_functions: usize,
+}
+
 /// Endpoint discovery information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EndpointDiscovery {
     pub uri: String,
     pub methods: Vec<String>,
@@ -278,4 +1344,159 @@ mod tests {
         let schema = TypeSchema::Primitive("String".to_string());
         assert!(matches!(schema, TypeSchema::Primitive(_)));
     }
+
+    #[test]
+    fn test_container_schema_keeps_all_type_params() {
+        let schema = TypeSchema::Container {
+            container_type: "Result".to_string(),
+            type_params: vec![
+                TypeSchema::Primitive("User".to_string()),
+                TypeSchema::Primitive("ApiError".to_string()),
+            ],
+        };
+        let json = serde_json::to_string(&schema).unwrap();
+        let decoded: TypeSchema = serde_json::from_str(&json).unwrap();
+        match decoded {
+            TypeSchema::Container { container_type, type_params } => {
+                assert_eq!(container_type, "Result");
+                assert_eq!(type_params.len(), 2);
+            }
+            other => panic!("unexpected schema: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_field_wire_name_prefers_rename() {
+        let field = FieldSchema {
+            field_name: "user_id".to_string(),
+            field_type: Box::new(TypeSchema::Primitive("i64".to_string())),
+            optional: false,
+            nullable: false,
+            rename: Some("userId".to_string()),
+            description: None,
+            deprecated: None,
+            constraints: FieldConstraints::default(),
+            default: None,
+            example: None,
+        };
+        assert_eq!(field.wire_name(), "userId");
+
+        let json = serde_json::json!({
+            "field_name": "id",
+            "field_type": { "kind": "Primitive", "value": "i64" },
+            "optional": false
+        });
+        let field: FieldSchema = serde_json::from_value(json).unwrap();
+        assert_eq!(field.wire_name(), "id");
+    }
+
+    #[test]
+    fn test_map_schema_serialization() {
+        let schema = TypeSchema::Map {
+            key_type: Box::new(TypeSchema::Primitive("String".to_string())),
+            value_type: Box::new(TypeSchema::Primitive("i32".to_string())),
+        };
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["kind"], "Map");
+        assert_eq!(json["value"]["key_type"]["value"], "String");
+        assert_eq!(json["value"]["value_type"]["value"], "i32");
+    }
+
+    #[test]
+    fn test_rate_limit_parsing() {
+        let limit: RateLimit = "10/min".parse().unwrap();
+        assert_eq!(limit, RateLimit { requests: 10, period_secs: 60 });
+        assert_eq!(limit.to_string(), "10/min");
+        let limit: RateLimit = "100 / 15min".parse().unwrap();
+        assert_eq!(limit.period_secs, 900);
+        assert_eq!(limit.to_string(), "100/900s");
+        assert!("10".parse::<RateLimit>().is_err());
+        assert!("0/s".parse::<RateLimit>().is_err());
+        assert!("10/fortnight".parse::<RateLimit>().is_err());
+
+        assert_eq!(parse_duration("60s"), Ok(std::time::Duration::from_secs(60)));
+        assert_eq!(parse_duration("2 hours").map(|d| d.as_secs()), Ok(7200));
+        assert_eq!(parse_duration("90").map(|d| d.as_secs()), Ok(90));
+        assert!(parse_duration("soon").is_err());
+    }
+
+    #[test]
+    fn test_unset_hints_are_left_out_of_serialized_metadata() {
+        let metadata = make_function_metadata("ping".to_string(), vec![], TypeSchema::Unit, None, "()".to_string(), true, false);
+        let json = serde_json::to_value(&metadata).unwrap();
+        for hint in ["tags", "timeout_ms", "cache_max_age", "status", "error_statuses", "rate_limit", "internal"] {
+            assert!(json.get(hint).is_none(), "{} is serialized", hint);
+        }
+        // Metadata written before the hints existed still reads
+        let read: FunctionMetadata = serde_json::from_value(json).unwrap();
+        assert!(!read.internal);
+        assert!(read.tags.is_empty());
+        assert_eq!(read.timeout_ms, None);
+        assert_eq!(read.cache_max_age, None);
+        assert_eq!(read.status, None);
+        assert!(read.error_statuses.is_empty());
+        assert_eq!(read.rate_limit, None);
+    }
+
+    #[test]
+    fn test_function_conflicts_group_handlers_by_name() {
+        let function = |name: &str, module: &str, namespace: Option<&str>| {
+            let mut metadata =
+                make_function_metadata(name.to_string(), vec![], TypeSchema::Unit, None, "()".to_string(), true, true);
+            metadata.module_path = Some(module.to_string());
+            metadata.namespace = namespace.map(String::from);
+            metadata
+        };
+        let mut method = function("login", "app::controllers", None);
+        method.self_type = Some("SessionController".to_string());
+        let functions = [
+            function("login", "app::users", None),
+            function("login", "app::admin", None),
+            function("login", "app::partners", Some("partners")),
+            function("logout", "app::users", None),
+            method,
+        ];
+        assert_eq!(
+            function_conflicts(&functions),
+            [FunctionConflict {
+                function_name: "login".to_string(),
+                namespace: None,
+                handlers: vec![
+                    "app::admin::login".to_string(),
+                    "app::controllers::SessionController::login".to_string(),
+                    "app::users::login".to_string(),
+                ],
+            }]
+        );
+        assert!(function_conflicts(&functions[2..]).is_empty());
+    }
+
+    #[test]
+    fn test_routes_resolve_against_discovered_endpoints() {
+        let function = |name: &str, is_mutation: bool| {
+            make_function_metadata(name.to_string(), vec![], TypeSchema::Unit, None, "()".to_string(), true, is_mutation)
+        };
+        let endpoint = |uri: &str, methods: &[&str]| EndpointDiscovery {
+            uri: uri.to_string(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+        };
+        let endpoints = [endpoint("/api/trees/plant-tree", &["PUT", "DELETE"]), endpoint("/api/trees", &["get", "post"])];
+
+        let plant = function("plant_tree", true);
+        assert_eq!(plant.route(&endpoints), Some(("/api/trees/plant-tree".to_string(), HttpMethod::Put)));
+        let mut trees = function("list", false);
+        trees.path = Some("/api/trees".to_string());
+        assert_eq!(trees.route(&endpoints), Some(("/api/trees".to_string(), HttpMethod::Get)));
+        trees.method = Some(HttpMethod::Patch);
+        assert_eq!(trees.route(&endpoints), Some(("/api/trees".to_string(), HttpMethod::Patch)));
+        assert_eq!(function("fell_forest", true).route(&endpoints), None);
+    }
+
+    #[test]
+    fn test_body_encoding_of_extractors() {
+        assert_eq!(BodyEncoding::of_extractors(["Path", "Json"]), BodyEncoding::Json);
+        assert_eq!(BodyEncoding::of_extractors(["State", "Form"]), BodyEncoding::Form);
+        assert_eq!(BodyEncoding::of_extractors(["Form", "Multipart"]), BodyEncoding::Multipart);
+        assert_eq!(serde_json::to_value(BodyEncoding::Form).unwrap(), "form");
+    }
 }
@@ -0,0 +1,402 @@
+//! OpenAPI 3.1 document generation from registered RPC metadata.
+//!
+//! Queries are described as `GET` operations taking their input's fields as query
+//! parameters, mutations as `POST` operations with a JSON body, mirroring how
+//! `laz_client` calls them. Named types are emitted once under `components.schemas`.
+
+use serde_json::{json, Map, Value};
+
+use crate::json_schema::{referenced_definitions, schema_node};
+use crate::{
+    find_type_schema, get_public_function_metadata, AuthScheme, EndpointDiscovery, FunctionMetadata,
+    ParamRole, TypeSchema, JSON_SCHEMA_DIALECT,
+};
+
+/// OpenAPI version written to the `openapi` field
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+const COMPONENTS_PREFIX: &str = "#/components/schemas/";
+
+/// Build an OpenAPI document for every public registered RPC function.
+///
+/// `endpoints` is the route discovery data; a function is mapped to its declared `path`,
+/// else to the route whose URI names it, and falls back to `/{function_name}` when no
+/// route matches.
+pub fn openapi_document(title: &str, version: &str, endpoints: &[EndpointDiscovery]) -> Value {
+    let mut functions = get_public_function_metadata();
+    functions.sort_by(|a, b| a.function_name.cmp(&b.function_name));
+    build_document(&functions, title, version, endpoints)
+}
+
+fn build_document(
+    functions: &[&FunctionMetadata],
+    title: &str,
+    version: &str,
+    endpoints: &[EndpointDiscovery],
+) -> Value {
+    let mut paths = Map::new();
+    let mut components = Map::new();
+    let mut security_schemes = Map::new();
+    let mut tags = std::collections::BTreeSet::new();
+
+    for function in functions {
+        let (path, method) = route(function, endpoints);
+        let mut operation = operation(function, &mut components);
+        if let Some(auth) = &function.auth {
+            let (name, scheme) = security_scheme(auth);
+            operation["security"] = json!([{ name.clone(): [] }]);
+            security_schemes.insert(name, scheme);
+        }
+        tags.extend(function.tags.iter().cloned());
+        let item = paths.entry(path).or_insert_with(|| json!({}));
+        item[method] = operation;
+    }
+
+    let tags: Vec<Value> = tags.into_iter().map(|name| json!({ "name": name })).collect();
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": { "title": title, "version": version },
+        "jsonSchemaDialect": JSON_SCHEMA_DIALECT,
+        "tags": tags,
+        "paths": paths,
+        "components": { "schemas": components, "securitySchemes": security_schemes },
+    })
+}
+
+/// Path and lowercase method a function is served at; functions no route names are
+/// assumed to be served at `/{handler}`
+pub(crate) fn route(function: &FunctionMetadata, endpoints: &[EndpointDiscovery]) -> (String, String) {
+    let (path, method) = function
+        .route(endpoints)
+        .unwrap_or_else(|| (format!("/{}", function.handler_name()), function.http_method()));
+    (path, method.as_str().to_ascii_lowercase())
+}
+
+/// Name and OpenAPI security scheme object for an auth requirement
+fn security_scheme(auth: &AuthScheme) -> (String, Value) {
+    match auth {
+        AuthScheme::Bearer => ("bearerAuth".to_string(), json!({ "type": "http", "scheme": "bearer" })),
+        AuthScheme::Basic => ("basicAuth".to_string(), json!({ "type": "http", "scheme": "basic" })),
+        AuthScheme::ApiKey { header } => (
+            format!("apiKey{}", header.replace('-', "")),
+            json!({ "type": "apiKey", "in": "header", "name": header }),
+        ),
+    }
+}
+
+fn operation(function: &FunctionMetadata, components: &mut Map<String, Value>) -> Value {
+    let mut operation = json!({ "operationId": function.function_name });
+    if let Some(description) = &function.description {
+        let summary = description.lines().next().unwrap_or_default();
+        operation["summary"] = json!(summary);
+        operation["description"] = json!(description);
+    }
+    if function.deprecated.is_some() {
+        operation["deprecated"] = json!(true);
+    }
+    if !function.tags.is_empty() {
+        operation["tags"] = json!(function.tags);
+    }
+
+    if function.is_websocket {
+        // The input is the messages clients send once connected, not the request
+        let message = |name: Option<&str>, components: &mut Map<String, Value>| match name {
+            Some(name) if !name.is_empty() && name != "()" => type_reference(name, components),
+            _ => json!({ "type": "null" }),
+        };
+        operation["x-laz-websocket"] = json!({
+            "client_message": message(function.input_type_name.as_deref(), components),
+            "server_message": message(Some(function.output_type_name.trim()), components),
+        });
+    } else if function.is_multipart {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": { "multipart/form-data": { "schema": { "type": "object" } } },
+        });
+    } else if let Some(input) = function.input_type_name.as_deref().filter(|n| !n.is_empty()) {
+        if !function.input_in_query() {
+            let mut content = json!({ "schema": type_reference(input, components) });
+            if let Some(example) = &function.example {
+                content["example"] = example.clone();
+            }
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { function.body_encoding.content_type(): content },
+            });
+        } else {
+            let mut parameters = query_parameters(input, components);
+            // Query examples are given per parameter
+            if let Some(Value::Object(example)) = &function.example {
+                for parameter in &mut parameters {
+                    if let Some(value) = parameter["name"].as_str().and_then(|name| example.get(name)) {
+                        parameter["example"] = value.clone();
+                    }
+                }
+            }
+            operation["parameters"] = Value::Array(parameters);
+        }
+    }
+    // A query read alongside the body adds its own parameters
+    let extra_queries = function
+        .params
+        .iter()
+        .filter(|p| p.role == ParamRole::Query)
+        .filter_map(|p| p.type_name.as_deref())
+        .filter(|name| Some(*name) != function.input_type_name.as_deref());
+    for type_name in extra_queries {
+        let mut parameters = query_parameters(type_name, components);
+        match operation["parameters"].as_array_mut() {
+            Some(existing) => existing.append(&mut parameters),
+            None => operation["parameters"] = Value::Array(parameters),
+        }
+    }
+
+    if let Some(max_age) = function.cache_max_age {
+        operation["x-laz-cache-max-age"] = json!(max_age);
+    }
+    if let Some(timeout_ms) = function.timeout_ms {
+        operation["x-laz-timeout-ms"] = json!(timeout_ms);
+    }
+    if let Some(pagination) = &function.pagination {
+        operation["x-laz-pagination"] = json!(pagination);
+    }
+    let mut headers: Vec<Value> = function
+        .params
+        .iter()
+        .filter_map(|p| p.header.as_deref())
+        // `Authorization` is described by the security requirement instead
+        .filter(|header| !header.eq_ignore_ascii_case("Authorization"))
+        .map(|header| json!({ "name": header, "in": "header", "required": true, "schema": { "type": "string" } }))
+        .collect();
+    if function.idempotent {
+        headers.push(json!({
+            "name": "Idempotency-Key",
+            "in": "header",
+            "required": false,
+            "description": "Retries with the same key are applied once",
+            "schema": { "type": "string" },
+        }));
+    }
+    if !headers.is_empty() {
+        match operation["parameters"].as_array_mut() {
+            Some(parameters) => parameters.append(&mut headers),
+            None => operation["parameters"] = Value::Array(headers),
+        }
+    }
+    // Every variable of the path template has to be declared, so path parameters lead
+    let mut parameters: Vec<Value> = function
+        .params
+        .iter()
+        .flat_map(|p| &p.path_params)
+        .map(|param| {
+            json!({
+                "name": param.name,
+                "in": "path",
+                "required": true,
+                "schema": schema_node(&param.schema, COMPONENTS_PREFIX),
+            })
+        })
+        .collect();
+    if !parameters.is_empty() {
+        if let Some(existing) = operation["parameters"].as_array_mut() {
+            parameters.append(existing);
+        }
+        operation["parameters"] = Value::Array(parameters);
+    }
+
+    let output = function.output_type_name.trim();
+    operation["responses"] = if function.is_websocket {
+        json!({ "101": { "description": "Switching to a WebSocket session" } })
+    } else if function.stream_body {
+        json!({
+            "200": {
+                "description": "Streamed body",
+                "content": { "application/octet-stream": { "schema": { "type": "string", "format": "binary" } } },
+            }
+        })
+    } else if output.is_empty() || output == "()" {
+        json!({ "200": { "description": "Success" } })
+    } else if function.is_subscription {
+        // Each server-sent event carries one JSON-encoded output value
+        json!({
+            "200": {
+                "description": "Stream of events",
+                "content": { "text/event-stream": { "schema": type_reference(output, components) } },
+            }
+        })
+    } else {
+        json!({
+            "200": {
+                "description": "Success",
+                "content": { "application/json": { "schema": type_reference(output, components) } },
+            }
+        })
+    };
+    // A declared success status replaces `200`, and declared errors are listed beside it
+    if let Some(status) = function.status.filter(|status| *status != 200) {
+        if let Some(responses) = operation["responses"].as_object_mut() {
+            if let Some(success) = responses.remove("200") {
+                responses.insert(status.to_string(), success);
+            }
+        }
+    }
+    for status in &function.error_statuses {
+        operation["responses"][status.to_string()] = json!({ "description": "Error" });
+    }
+    if let Some(rate_limit) = &function.rate_limit {
+        operation["x-laz-rate-limit"] = json!(rate_limit.to_string());
+        operation["responses"]["429"] = json!({
+            "description": "Rate limit exceeded",
+            "headers": { "Retry-After": { "schema": { "type": "integer" } } },
+        });
+    }
+    operation
+}
+
+/// Schema for a named type: a `$ref` into components for registered types, inline otherwise
+fn type_reference(type_name: &str, components: &mut Map<String, Value>) -> Value {
+    let schema = find_type_schema(type_name)
+        .cloned()
+        .unwrap_or_else(|| TypeSchema::Primitive(type_name.to_string()));
+    if !matches!(
+        schema,
+        TypeSchema::Struct(_) | TypeSchema::Enum(_) | TypeSchema::Tuple(_) | TypeSchema::Newtype(_)
+    ) {
+        return schema_node(&schema, COMPONENTS_PREFIX);
+    }
+
+    let reference = TypeSchema::Ref(type_name.to_string());
+    components
+        .entry(type_name.to_string())
+        .or_insert_with(|| schema_node(&schema, COMPONENTS_PREFIX));
+    components.extend(referenced_definitions(&schema, COMPONENTS_PREFIX));
+    schema_node(&reference, COMPONENTS_PREFIX)
+}
+
+/// Query parameters for a query's input struct; other inputs travel as a single `params` value
+fn query_parameters(type_name: &str, components: &mut Map<String, Value>) -> Vec<Value> {
+    let Some(TypeSchema::Struct(input)) = find_type_schema(type_name) else {
+        return vec![json!({
+            "name": "params",
+            "in": "query",
+            "required": true,
+            "schema": type_reference(type_name, components),
+        })];
+    };
+
+    components.extend(referenced_definitions(
+        &TypeSchema::Struct(input.clone()),
+        COMPONENTS_PREFIX,
+    ));
+    input
+        .fields
+        .iter()
+        .map(|field| {
+            let mut parameter = json!({
+                "name": field.wire_name(),
+                "in": "query",
+                "required": !field.optional,
+                "schema": schema_node(&field.field_type, COMPONENTS_PREFIX),
+            });
+            if let Some(description) = &field.description {
+                parameter["description"] = json!(description);
+            }
+            if field.deprecated.is_some() {
+                parameter["deprecated"] = json!(true);
+            }
+            if let Some(example) = &field.example {
+                parameter["example"] = example.clone();
+            }
+            parameter
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::make_function_metadata;
+
+    fn metadata(name: &str, output: &str, is_mutation: bool) -> FunctionMetadata {
+        make_function_metadata(
+            name.to_string(),
+            Vec::new(),
+            TypeSchema::Primitive(output.to_string()),
+            None,
+            output.to_string(),
+            true,
+            is_mutation,
+        )
+    }
+
+    #[test]
+    fn test_functions_map_to_discovered_routes() {
+        let mut create = metadata("create_user", "String", true);
+        create.input_type_name = Some("NewUser".to_string());
+        create.example = Some(json!({ "name": "Ada" }));
+        let mut rename = metadata("rename_user", "String", true);
+        rename.input_type_name = Some("NewName".to_string());
+        rename.body_encoding = crate::BodyEncoding::Form;
+        let mut ping = metadata("ping", "bool", false);
+        ping.deprecated = Some(String::new());
+        let mut health = metadata("health", "bool", false);
+        health.path = Some("/api/status".to_string());
+        health.method = Some(crate::HttpMethod::Put);
+        health.auth = Some(AuthScheme::Bearer);
+        health.tags = vec!["ops".to_string()];
+        health.status = Some(202);
+        health.error_statuses = vec![409];
+        create.tags = vec!["users".to_string(), "admin".to_string()];
+        let endpoints = vec![EndpointDiscovery {
+            uri: "/api/users/create-user".to_string(),
+            methods: vec!["POST".to_string()],
+        }];
+
+        let document = build_document(&[&create, &rename, &ping, &health], "Test API", "1.0.0", &endpoints);
+        assert_eq!(document["openapi"], OPENAPI_VERSION);
+        let create_op = &document["paths"]["/api/users/create-user"]["post"];
+        assert_eq!(create_op["operationId"], "create_user");
+        assert_eq!(
+            create_op["requestBody"]["content"]["application/json"]["example"],
+            json!({ "name": "Ada" })
+        );
+        assert_eq!(
+            create_op["responses"]["200"]["content"]["application/json"]["schema"],
+            json!({ "type": "string" })
+        );
+        let rename_body = &document["paths"]["/rename_user"]["post"]["requestBody"]["content"];
+        assert!(rename_body["application/x-www-form-urlencoded"]["schema"].is_object());
+        assert_eq!(document["paths"]["/ping"]["get"]["deprecated"], true);
+        let health_op = &document["paths"]["/api/status"]["put"];
+        assert_eq!(health_op["operationId"], "health");
+        assert_eq!(health_op["security"], json!([{ "bearerAuth": [] }]));
+        assert_eq!(document["components"]["securitySchemes"]["bearerAuth"]["scheme"], "bearer");
+        assert_eq!(health_op["tags"], json!(["ops"]));
+        assert_eq!(health_op["responses"]["202"]["content"]["application/json"]["schema"], json!({ "type": "boolean" }));
+        assert!(health_op["responses"].get("200").is_none());
+        assert_eq!(health_op["responses"]["409"]["description"], "Error");
+        assert_eq!(document["tags"], json!([{ "name": "admin" }, { "name": "ops" }, { "name": "users" }]));
+    }
+
+    #[test]
+    fn test_path_extractors_declare_path_parameters() {
+        let mut get = metadata("get_tree", "String", false);
+        get.path = Some("/trees/{id}".to_string());
+        get.params = vec![crate::ParamInfo {
+            name: "id".to_string(),
+            full_type: "Path<i64>".to_string(),
+            extractor: "Path".to_string(),
+            role: ParamRole::Path,
+            type_name: Some("i64".to_string()),
+            inner_type_schema: TypeSchema::Primitive("i64".to_string()),
+            path_params: crate::path_params(&["id"], TypeSchema::Primitive("i64".to_string())),
+            header: None,
+        }];
+
+        let document = build_document(&[&get], "Test API", "1.0.0", &[]);
+        assert_eq!(
+            document["paths"]["/trees/{id}"]["get"]["parameters"],
+            json!([{ "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }])
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Pagination conventions of `paginated` queries.
+//!
+//! The convention is recognised from the field names of the query's input and output
+//! structs, so clients can walk the pages without per-endpoint code.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{find_type_schema, FieldSchema, TypeSchema};
+
+/// How a paginated query selects its pages. Field names are as sent on the wire.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "style", rename_all = "snake_case")]
+pub enum Pagination {
+    /// Numbered pages: the input's `page_field` counts up from the requested page
+    Page {
+        page_field: String,
+        /// Input field bounding the page size; a shorter page is the last one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit_field: Option<String>,
+        /// Output field holding the page's items; an empty page is the last one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        items_field: Option<String>,
+    },
+    /// Opaque cursors: the output's `next_cursor_field` is sent back as the input's
+    /// `cursor_field` until it's `null`
+    Cursor {
+        cursor_field: String,
+        next_cursor_field: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        items_field: Option<String>,
+    },
+}
+
+/// Pagination style forced with `paginated = page` or `paginated = cursor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaginationStyle {
+    Page,
+    Cursor,
+}
+
+impl std::str::FromStr for PaginationStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "page" => Ok(Self::Page),
+            "cursor" => Ok(Self::Cursor),
+            other => Err(format!(
+                "unknown pagination style `{}`; expected `true`, `page` or `cursor`",
+                other
+            )),
+        }
+    }
+}
+
+const PAGE_FIELDS: &[&str] = &["page", "pagenumber", "pageno"];
+const LIMIT_FIELDS: &[&str] = &["limit", "perpage", "pagesize", "size"];
+const CURSOR_FIELDS: &[&str] = &["cursor", "after", "pagetoken", "startingafter"];
+const NEXT_CURSOR_FIELDS: &[&str] = &["nextcursor", "nextpagetoken", "next", "cursor", "endcursor"];
+const ITEMS_FIELDS: &[&str] = &["items", "data", "results", "records", "entries", "nodes"];
+
+impl Pagination {
+    /// Recognise the pagination convention of a query from its registered input and output
+    /// schemas, optionally restricted to one `style`
+    pub fn infer(input_type: &str, output_type: &str, style: Option<PaginationStyle>) -> Option<Self> {
+        let input = struct_fields(input_type)?;
+        let output = struct_fields(output_type).unwrap_or_default();
+        let items_field = find_field(&output, ITEMS_FIELDS)
+            .or_else(|| output.iter().copied().find(|f| is_list(&f.field_type)))
+            .map(|f| f.wire_name().to_string());
+
+        let cursor = || {
+            Some(Pagination::Cursor {
+                cursor_field: find_field(&input, CURSOR_FIELDS)?.wire_name().to_string(),
+                next_cursor_field: find_field(&output, NEXT_CURSOR_FIELDS)?.wire_name().to_string(),
+                items_field: items_field.clone(),
+            })
+        };
+        let page = || {
+            Some(Pagination::Page {
+                page_field: find_field(&input, PAGE_FIELDS)?.wire_name().to_string(),
+                limit_field: find_field(&input, LIMIT_FIELDS).map(|f| f.wire_name().to_string()),
+                items_field: items_field.clone(),
+            })
+        };
+        match style {
+            Some(PaginationStyle::Cursor) => cursor(),
+            Some(PaginationStyle::Page) => page(),
+            None => cursor().or_else(page),
+        }
+    }
+}
+
+fn struct_fields(type_name: &str) -> Option<Vec<&'static FieldSchema>> {
+    match find_type_schema(type_name)? {
+        TypeSchema::Struct(schema) => Some(schema.fields.iter().collect()),
+        _ => None,
+    }
+}
+
+/// First field whose name, ignoring case and separators, is one of `names`
+fn find_field<'a>(fields: &[&'a FieldSchema], names: &[&str]) -> Option<&'a FieldSchema> {
+    let normalize = |name: &str| name.replace(['_', '-'], "").to_ascii_lowercase();
+    names
+        .iter()
+        .find_map(|name| fields.iter().copied().find(|f| normalize(f.wire_name()) == *name))
+}
+
+fn is_list(schema: &TypeSchema) -> bool {
+    matches!(schema, TypeSchema::Container { container_type, .. } if container_type == "Vec")
+}
@@ -0,0 +1,447 @@
+//! TypeScript (`.d.ts`) declarations for [`TypeSchema`]s.
+//!
+//! Structs become interfaces and everything else a type alias, following the serde wire
+//! format: wire names for fields and variants, enums in their serde tagging style, and
+//! `null` for `Option`s. [`client_module`] adds a fetch-based client on top.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    find_type_schema, get_public_function_metadata, openapi, type_table, BodyEncoding, BytesEncoding,
+    DateTimeEncoding, EndpointDiscovery, EnumSchema, EnumTagging, FieldDefault, FieldSchema,
+    FunctionMetadata, TypeSchema,
+};
+
+/// Runtime shared by the methods of the client from [`client_module`]
+const CLIENT_RUNTIME: &str = r#"
+export interface ClientOptions {
+  baseUrl: string;
+  headers?: Record<string, string>;
+  fetch?: typeof fetch;
+}
+
+export class RpcError extends Error {
+  readonly status: number;
+  readonly body: unknown;
+
+  constructor(status: number, body: unknown) {
+    super(`RPC call failed with HTTP ${status}`);
+    this.status = status;
+    this.body = body;
+  }
+}
+
+function fillPath(template: string, params: Record<string, string | number>): string {
+  return template.replace(/\{\*?(\w+)\}/g, (_, name) => encodeURIComponent(String(params[name])));
+}
+
+function encode(input: object): URLSearchParams {
+  const params = new URLSearchParams();
+  for (const [key, value] of Object.entries(input)) {
+    if (value !== undefined && value !== null) params.append(key, String(value));
+  }
+  return params;
+}
+
+async function call(
+  options: ClientOptions,
+  method: string,
+  path: string,
+  input: unknown,
+  encoding: "query" | "json" | "form",
+): Promise<unknown> {
+  let url = options.baseUrl.replace(/\/$/, "") + path;
+  const init: RequestInit = { method, headers: { ...options.headers } };
+  if (input !== undefined && encoding === "query") {
+    const query = encode(input as object).toString();
+    if (query) url += "?" + query;
+  } else if (input !== undefined && encoding === "form") {
+    init.body = encode(input as object);
+  } else if (input !== undefined) {
+    init.body = JSON.stringify(input);
+    (init.headers as Record<string, string>)["Content-Type"] = "application/json";
+  }
+  const response = await (options.fetch ?? fetch)(url, init);
+  const text = await response.text();
+  const body = text ? JSON.parse(text) : null;
+  if (!response.ok) throw new RpcError(response.status, body);
+  return body;
+}
+"#;
+
+/// Declarations for every type in the metadata [`type_table`], as a `.d.ts` file
+pub fn type_definitions() -> String {
+    let mut out = String::from("// Generated by laz. Do not edit.\n");
+    for (name, schema) in type_table() {
+        out.push('\n');
+        out.push_str(&declaration(&name, schema));
+    }
+    out
+}
+
+/// [`type_definitions`] followed by `createClient`, a thin fetch-based client with one method
+/// per public registered function. Functions without a plain JSON request and response
+/// (subscriptions, WebSockets, uploads and streamed bodies) are left out.
+///
+/// `endpoints` is the route discovery data, used to find each function's route like
+/// [`openapi::openapi_document`] does.
+pub fn client_module(endpoints: &[EndpointDiscovery]) -> String {
+    let mut functions = get_public_function_metadata();
+    functions.sort_by(|a, b| a.function_name.cmp(&b.function_name));
+
+    let table = type_table();
+    let mut out = type_definitions();
+    out.push_str(CLIENT_RUNTIME);
+    out.push_str("\nexport function createClient(options: ClientOptions) {\n  return {\n");
+    for function in functions {
+        if function.is_subscription || function.is_websocket || function.is_multipart || function.stream_body {
+            continue;
+        }
+        out.push_str(&client_method(function, endpoints, &table));
+    }
+    out.push_str("  };\n}\n");
+    out
+}
+
+fn client_method(
+    function: &FunctionMetadata,
+    endpoints: &[EndpointDiscovery],
+    table: &BTreeMap<String, &TypeSchema>,
+) -> String {
+    let (path, method) = openapi::route(function, endpoints);
+    let path_params: Vec<&str> = path
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| name.trim_start_matches('*'))
+        .collect();
+    let input = function.input_type_name.as_deref().filter(|name| !name.is_empty() && *name != "()");
+
+    let mut params = Vec::new();
+    if !path_params.is_empty() {
+        let fields: Vec<String> = path_params.iter().map(|name| format!("{}: string | number", property_name(name))).collect();
+        params.push(format!("path: {{ {} }}", fields.join("; ")));
+    }
+    if let Some(input) = input {
+        params.push(format!("input: {}", named_type(input, table)));
+    }
+    let path_expr = if path_params.is_empty() { format!("{:?}", path) } else { format!("fillPath({:?}, path)", path) };
+    let encoding = if function.input_in_query() {
+        "query"
+    } else if function.body_encoding == BodyEncoding::Form {
+        "form"
+    } else {
+        "json"
+    };
+    let output = named_type(&function.output_type_name, table);
+
+    let mut out = String::new();
+    if let Some(description) = &function.description {
+        out.push_str("    /**\n");
+        for line in description.lines() {
+            out.push_str(format!("     * {}", line).trim_end());
+            out.push('\n');
+        }
+        out.push_str("     */\n");
+    }
+    out.push_str(&format!(
+        "    {}: ({}): Promise<{}> =>\n      call(options, {:?}, {}, {}, {:?}) as Promise<{}>,\n",
+        property_name(&function.function_name),
+        params.join(", "),
+        output,
+        method.to_ascii_uppercase(),
+        path_expr,
+        if input.is_some() { "input" } else { "undefined" },
+        encoding,
+        output,
+    ));
+    out
+}
+
+/// TypeScript for a type named in function metadata: its declaration's name when
+/// [`type_definitions`] has one, else the type written out
+fn named_type(name: &str, table: &BTreeMap<String, &TypeSchema>) -> String {
+    let name = name.trim();
+    if name.is_empty() || name == "()" {
+        return "null".to_string();
+    }
+    match find_type_schema(name) {
+        Some(_) if table.contains_key(name) => name.to_string(),
+        Some(schema) => type_expr(schema),
+        // Primitives aren't registered; anything else unregistered comes out `unknown`
+        None => primitive_type(name).to_string(),
+    }
+}
+
+/// Exported declaration of a named type: an interface for structs, a type alias otherwise
+pub fn declaration(name: &str, schema: &TypeSchema) -> String {
+    match schema {
+        TypeSchema::Struct(s) => {
+            format!("export interface {} {}\n", name, object_type(&s.fields, ""))
+        }
+        other => format!("export type {} = {};\n", name, type_expr(other)),
+    }
+}
+
+/// Inline TypeScript type for a schema
+pub fn type_expr(schema: &TypeSchema) -> String {
+    match schema {
+        TypeSchema::Primitive(name) => primitive_type(name).to_string(),
+        TypeSchema::Struct(s) => object_type(&s.fields, ""),
+        TypeSchema::Enum(e) => enum_type(e),
+        TypeSchema::Container {
+            container_type,
+            type_params,
+        } => match (container_type.as_str(), type_params.as_slice()) {
+            ("Vec" | "HashSet" | "BTreeSet", [inner]) => array_type(inner),
+            ("Option", [inner]) => format!("{} | null", type_expr(inner)),
+            ("Result", [ok, err]) => {
+                format!("{{ Ok: {} }} | {{ Err: {} }}", type_expr(ok), type_expr(err))
+            }
+            _ => "unknown".to_string(),
+        },
+        TypeSchema::Map { value_type, .. } => format!("Record<string, {}>", type_expr(value_type)),
+        TypeSchema::Array { inner, .. } => array_type(inner),
+        TypeSchema::DateTime { encoding, .. } => match encoding {
+            DateTimeEncoding::Unix => "number".to_string(),
+            _ => "string".to_string(),
+        },
+        TypeSchema::Decimal(_) | TypeSchema::Int128(_) => "string".to_string(),
+        TypeSchema::NonZero(name) if name == "i128" || name == "u128" => "string".to_string(),
+        TypeSchema::NonZero(name) => primitive_type(name).to_string(),
+        TypeSchema::Bytes(BytesEncoding::Base64) => "string".to_string(),
+        TypeSchema::Bytes(BytesEncoding::Array) => "number[]".to_string(),
+        TypeSchema::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(|i| type_expr(i)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        TypeSchema::Newtype(inner) => type_expr(inner),
+        TypeSchema::Ref(name) => name.clone(),
+        TypeSchema::Unit => "null".to_string(),
+        TypeSchema::Any | TypeSchema::Opaque(_) => "unknown".to_string(),
+    }
+}
+
+fn primitive_type(name: &str) -> &'static str {
+    match name {
+        "String" | "str" | "char" | "Uuid" => "string",
+        "bool" => "boolean",
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+        | "u128" | "usize" | "f32" | "f64" => "number",
+        _ => "unknown",
+    }
+}
+
+fn array_type(inner: &TypeSchema) -> String {
+    let inner = type_expr(inner);
+    // Unions need parentheses: `(A | B)[]`
+    if inner.contains(" | ") {
+        format!("({})[]", inner)
+    } else {
+        format!("{}[]", inner)
+    }
+}
+
+fn object_type(fields: &[FieldSchema], indent: &str) -> String {
+    if fields.is_empty() {
+        return "{}".to_string();
+    }
+    let inner_indent = format!("{}  ", indent);
+    let mut out = String::from("{\n");
+    for field in fields {
+        let mut doc = Vec::new();
+        if let Some(description) = &field.description {
+            doc.extend(description.lines().map(String::from));
+        }
+        if let Some(default) = field.default.as_ref().and_then(FieldDefault::value) {
+            doc.push(format!("@default {}", default));
+        }
+        if let Some(example) = &field.example {
+            doc.push(format!("@example {}", example));
+        }
+        if let Some(note) = &field.deprecated {
+            doc.push(format!("@deprecated {}", note).trim_end().to_string());
+        }
+        if !doc.is_empty() {
+            out.push_str(&format!("{}/**\n", inner_indent));
+            for line in doc {
+                out.push_str(format!("{} * {}", inner_indent, line).trim_end());
+                out.push('\n');
+            }
+            out.push_str(&format!("{} */\n", inner_indent));
+        }
+        let optional = if field.optional { "?" } else { "" };
+        let field_type = match field.field_type.as_ref() {
+            TypeSchema::Struct(s) => object_type(&s.fields, &inner_indent),
+            other => type_expr(other),
+        };
+        out.push_str(&format!(
+            "{}{}{}: {};\n",
+            inner_indent,
+            property_name(field.wire_name()),
+            optional,
+            field_type
+        ));
+    }
+    out.push_str(indent);
+    out.push('}');
+    out
+}
+
+fn enum_type(schema: &EnumSchema) -> String {
+    if schema.variants.is_empty() {
+        return "never".to_string();
+    }
+    let members: Vec<String> = schema
+        .variants
+        .iter()
+        .map(|variant| {
+            let name = variant.wire_name();
+            let payload = if !variant.fields.is_empty() {
+                Some(object_type(&variant.fields, "  "))
+            } else {
+                variant.inner_schema.as_deref().map(type_expr)
+            };
+            match (&schema.tagging, payload) {
+                (EnumTagging::External, Some(payload)) => {
+                    format!("{{ {}: {} }}", property_name(name), payload)
+                }
+                (EnumTagging::External, None) => format!("{:?}", name),
+                (EnumTagging::Internal { tag }, Some(payload)) => {
+                    format!("({{ {}: {:?} }} & {})", property_name(tag), name, payload)
+                }
+                (EnumTagging::Internal { tag }, None) => {
+                    format!("{{ {}: {:?} }}", property_name(tag), name)
+                }
+                (EnumTagging::Adjacent { tag, content }, Some(payload)) => format!(
+                    "{{ {}: {:?}; {}: {} }}",
+                    property_name(tag),
+                    name,
+                    property_name(content),
+                    payload
+                ),
+                (EnumTagging::Adjacent { tag, .. }, None) => {
+                    format!("{{ {}: {:?} }}", property_name(tag), name)
+                }
+                (EnumTagging::Untagged, Some(payload)) => payload,
+                (EnumTagging::Untagged, None) => "null".to_string(),
+            }
+        })
+        .collect();
+    members.join(" | ")
+}
+
+/// Quote property names that aren't valid identifiers
+fn property_name(name: &str) -> String {
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{make_function_metadata, FieldConstraints, StructSchema, VariantSchema};
+
+    #[test]
+    fn test_struct_becomes_interface() {
+        let schema = TypeSchema::Struct(StructSchema {
+            type_name: "User".to_string(),
+            fields: vec![
+                FieldSchema {
+                    field_name: "user_id".to_string(),
+                    field_type: Box::new(TypeSchema::Primitive("i64".to_string())),
+                    optional: false,
+                    nullable: false,
+                    rename: Some("user-id".to_string()),
+                    description: Some("Primary key".to_string()),
+                    deprecated: None,
+                    constraints: FieldConstraints::default(),
+                    default: None,
+                    example: None,
+                },
+                FieldSchema {
+                    field_name: "tags".to_string(),
+                    field_type: Box::new(TypeSchema::Container {
+                        container_type: "Option".to_string(),
+                        type_params: vec![TypeSchema::Container {
+                            container_type: "Vec".to_string(),
+                            type_params: vec![TypeSchema::Ref("Tag".to_string())],
+                        }],
+                    }),
+                    optional: true,
+                    nullable: true,
+                    rename: None,
+                    description: None,
+                    deprecated: None,
+                    constraints: FieldConstraints::default(),
+                    default: None,
+                    example: None,
+                },
+            ],
+        });
+        assert_eq!(
+            declaration("User", &schema),
+            "export interface User {\n  /**\n   * Primary key\n   */\n  \"user-id\": number;\n  tags?: Tag[] | null;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_enum_becomes_tagged_union() {
+        let schema = TypeSchema::Enum(EnumSchema {
+            type_name: "Shape".to_string(),
+            variants: vec![
+                VariantSchema {
+                    variant_name: "Empty".to_string(),
+                    inner_schema: None,
+                    rename: None,
+                    fields: Vec::new(),
+                },
+                VariantSchema {
+                    variant_name: "Circle".to_string(),
+                    inner_schema: Some(Box::new(TypeSchema::Primitive("f64".to_string()))),
+                    rename: None,
+                    fields: Vec::new(),
+                },
+            ],
+            tagging: EnumTagging::External,
+        });
+        assert_eq!(
+            declaration("Shape", &schema),
+            "export type Shape = \"Empty\" | { Circle: number };\n"
+        );
+
+        let TypeSchema::Enum(mut adjacent) = schema else { unreachable!() };
+        adjacent.tagging = EnumTagging::Adjacent { tag: "t".to_string(), content: "c".to_string() };
+        assert_eq!(
+            declaration("Shape", &TypeSchema::Enum(adjacent)),
+            "export type Shape = { t: \"Empty\" } | { t: \"Circle\"; c: number };\n"
+        );
+    }
+
+    #[test]
+    fn test_client_method_fills_path_and_sends_body() {
+        let mut function = make_function_metadata(
+            "rename_tree".to_string(),
+            Vec::new(),
+            TypeSchema::Primitive("String".to_string()),
+            None,
+            "String".to_string(),
+            true,
+            true,
+        );
+        function.input_type_name = Some("u32".to_string());
+        function.path = Some("/trees/{id}/name".to_string());
+        function.description = Some("Rename a tree".to_string());
+        assert_eq!(
+            client_method(&function, &[], &BTreeMap::new()),
+            "    /**\n     * Rename a tree\n     */\n    rename_tree: (path: { id: string | number }, input: number): Promise<string> =>\n      call(options, \"POST\", fillPath(\"/trees/{id}/name\", path), input, \"json\") as Promise<string>,\n"
+        );
+    }
+}
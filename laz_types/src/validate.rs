@@ -0,0 +1,487 @@
+//! Checking JSON values against a [`TypeSchema`] before they are sent or dispatched.
+//!
+//! Validation follows the serde wire format described by the schema. Every mismatch is
+//! reported with the path to the offending value, e.g. `$.items[2].email`, so a request can
+//! be rejected with all of its problems at once. Named types are resolved through the
+//! registry; names without a registered schema, `Any` and `Opaque` accept any value.
+//! `pattern` constraints are not checked, since laz_types has no regex engine.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    base64, find_type_schema, BytesEncoding, DateTimeEncoding, EnumSchema, EnumTagging,
+    FieldConstraints, FieldSchema, TypeSchema, VariantSchema,
+};
+
+/// A value that doesn't match its schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationError {
+    /// Location of the value, `$` being the root
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check `value` against `schema`, collecting every mismatch
+pub fn validate(value: &Value, schema: &TypeSchema) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    check(value, schema, "$", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn error(errors: &mut Vec<ValidationError>, path: &str, message: impl Into<String>) {
+    errors.push(ValidationError {
+        path: path.to_string(),
+        message: message.into(),
+    });
+}
+
+fn expected(errors: &mut Vec<ValidationError>, path: &str, what: &str, value: &Value) {
+    error(errors, path, format!("expected {}, found {}", what, json_kind(value)));
+}
+
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn member_path(path: &str, name: &str) -> String {
+    format!("{}.{}", path, name)
+}
+
+fn index_path(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
+
+fn check(value: &Value, schema: &TypeSchema, path: &str, errors: &mut Vec<ValidationError>) {
+    match schema {
+        TypeSchema::Primitive(name) => check_primitive(value, name, path, errors),
+        TypeSchema::Struct(s) => match value {
+            Value::Object(object) => check_fields(object, &s.fields, path, errors),
+            other => expected(errors, path, "object", other),
+        },
+        TypeSchema::Enum(e) => check_enum(value, e, path, errors),
+        TypeSchema::Container {
+            container_type,
+            type_params,
+        } => match (container_type.as_str(), type_params.as_slice()) {
+            ("Option", [_]) if value.is_null() => {}
+            ("Option", [inner]) => check(value, inner, path, errors),
+            ("Vec" | "HashSet" | "BTreeSet", [inner]) => match value {
+                Value::Array(items) => check_items(items, inner, path, errors),
+                other => expected(errors, path, "array", other),
+            },
+            ("Result", [ok, err]) => match single_entry(value) {
+                Some(("Ok", inner)) => check(inner, ok, &member_path(path, "Ok"), errors),
+                Some(("Err", inner)) => check(inner, err, &member_path(path, "Err"), errors),
+                _ => error(errors, path, "expected an object with a single `Ok` or `Err` key"),
+            },
+            _ => {}
+        },
+        TypeSchema::Map { value_type, .. } => match value {
+            Value::Object(object) => {
+                for (key, entry) in object {
+                    check(entry, value_type, &member_path(path, key), errors);
+                }
+            }
+            other => expected(errors, path, "object", other),
+        },
+        TypeSchema::Array { inner, len } => match value {
+            Value::Array(items) if items.len() != *len => error(
+                errors,
+                path,
+                format!("expected {} items, found {}", len, items.len()),
+            ),
+            Value::Array(items) => check_items(items, inner, path, errors),
+            other => expected(errors, path, "array", other),
+        },
+        TypeSchema::DateTime { encoding, .. } => match (encoding, value) {
+            (DateTimeEncoding::Unix, Value::Number(n)) if n.is_i64() || n.is_u64() => {}
+            (DateTimeEncoding::Unix, other) => expected(errors, path, "integer timestamp", other),
+            (_, Value::String(_)) => {}
+            (_, other) => expected(errors, path, "date/time string", other),
+        },
+        TypeSchema::Decimal(_) => match value {
+            Value::String(s) if s.parse::<f64>().is_ok() => {}
+            Value::String(_) => error(errors, path, "expected a decimal number string"),
+            other => expected(errors, path, "decimal string", other),
+        },
+        TypeSchema::Int128(name) => check_int128(value, name, false, path, errors),
+        TypeSchema::NonZero(name) if name == "i128" || name == "u128" => {
+            check_int128(value, name, true, path, errors)
+        }
+        TypeSchema::NonZero(name) => {
+            check_primitive(value, name, path, errors);
+            if value.as_f64() == Some(0.0) {
+                error(errors, path, "expected a non-zero integer");
+            }
+        }
+        TypeSchema::Bytes(BytesEncoding::Base64) => match value {
+            Value::String(s) => {
+                if let Err(message) = base64::decode(s) {
+                    error(errors, path, message);
+                }
+            }
+            other => expected(errors, path, "base64 string", other),
+        },
+        TypeSchema::Bytes(BytesEncoding::Array) => match value {
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    check_primitive(item, "u8", &index_path(path, i), errors);
+                }
+            }
+            other => expected(errors, path, "array of bytes", other),
+        },
+        TypeSchema::Tuple(items) => match value {
+            Value::Array(values) if values.len() != items.len() => error(
+                errors,
+                path,
+                format!("expected {} items, found {}", items.len(), values.len()),
+            ),
+            Value::Array(values) => {
+                for (i, (value, item)) in values.iter().zip(items).enumerate() {
+                    check(value, item, &index_path(path, i), errors);
+                }
+            }
+            other => expected(errors, path, "array", other),
+        },
+        TypeSchema::Newtype(inner) => check(value, inner, path, errors),
+        TypeSchema::Ref(name) => {
+            if let Some(resolved) = find_type_schema(name) {
+                check(value, resolved, path, errors);
+            }
+        }
+        TypeSchema::Unit => {
+            if !value.is_null() {
+                expected(errors, path, "null", value);
+            }
+        }
+        TypeSchema::Any | TypeSchema::Opaque(_) => {}
+    }
+}
+
+fn check_items(items: &[Value], schema: &TypeSchema, path: &str, errors: &mut Vec<ValidationError>) {
+    for (i, item) in items.iter().enumerate() {
+        check(item, schema, &index_path(path, i), errors);
+    }
+}
+
+fn check_primitive(value: &Value, name: &str, path: &str, errors: &mut Vec<ValidationError>) {
+    let integer_range = |min: i128, max: i128| {
+        let n = value.as_i64().map(i128::from).or(value.as_u64().map(i128::from));
+        n.is_some_and(|n| (min..=max).contains(&n))
+    };
+    let (valid, what) = match name {
+        "String" | "str" => (value.is_string(), "string"),
+        "char" => (
+            value.as_str().is_some_and(|s| s.chars().count() == 1),
+            "single-character string",
+        ),
+        "bool" => (value.is_boolean(), "boolean"),
+        "i8" => (integer_range(i8::MIN.into(), i8::MAX.into()), "i8 integer"),
+        "i16" => (integer_range(i16::MIN.into(), i16::MAX.into()), "i16 integer"),
+        "i32" => (integer_range(i32::MIN.into(), i32::MAX.into()), "i32 integer"),
+        "i64" | "isize" | "i128" => (value.is_i64(), "integer"),
+        "u8" => (integer_range(0, u8::MAX.into()), "u8 integer"),
+        "u16" => (integer_range(0, u16::MAX.into()), "u16 integer"),
+        "u32" => (integer_range(0, u32::MAX.into()), "u32 integer"),
+        "u64" | "usize" | "u128" => (value.is_u64(), "non-negative integer"),
+        "f32" | "f64" => (value.is_number(), "number"),
+        "Uuid" => (value.as_str().is_some_and(is_uuid), "UUID string"),
+        _ => (true, ""),
+    };
+    if !valid {
+        expected(errors, path, what, value);
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// 128-bit integers travel as digit strings, but plain numbers are accepted too
+fn check_int128(value: &Value, name: &str, non_zero: bool, path: &str, errors: &mut Vec<ValidationError>) {
+    let digits = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) if n.is_i64() || n.is_u64() => n.to_string(),
+        other => return expected(errors, path, "integer string", other),
+    };
+    let parsed = if name == "u128" {
+        digits.parse::<u128>().map(|n| n == 0)
+    } else {
+        digits.parse::<i128>().map(|n| n == 0)
+    };
+    match parsed {
+        Ok(true) if non_zero => error(errors, path, "expected a non-zero integer"),
+        Ok(_) => {}
+        Err(_) => error(errors, path, format!("expected a {} integer string", name)),
+    }
+}
+
+fn check_fields(
+    object: &Map<String, Value>,
+    fields: &[FieldSchema],
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    for field in fields {
+        let field_path = member_path(path, field.wire_name());
+        match object.get(field.wire_name()) {
+            None if field.optional || field.default.is_some() => {}
+            None => error(errors, &field_path, "missing required field"),
+            Some(Value::Null) if field.nullable => {}
+            Some(value) => {
+                check(value, &field.field_type, &field_path, errors);
+                check_constraints(value, &field.constraints, &field_path, errors);
+            }
+        }
+    }
+}
+
+fn check_constraints(
+    value: &Value,
+    constraints: &FieldConstraints,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = constraints.min.filter(|min| n < *min) {
+            error(errors, path, format!("{} is less than the minimum {}", n, min));
+        }
+        if let Some(max) = constraints.max.filter(|max| n > *max) {
+            error(errors, path, format!("{} is greater than the maximum {}", n, max));
+        }
+    }
+    let len = match value {
+        Value::String(s) => Some(s.chars().count()),
+        Value::Array(items) => Some(items.len()),
+        _ => None,
+    };
+    if let Some(len) = len {
+        if let Some(min_length) = constraints.min_length.filter(|min| len < *min) {
+            error(errors, path, format!("length {} is less than the minimum {}", len, min_length));
+        }
+        if let Some(max_length) = constraints.max_length.filter(|max| len > *max) {
+            error(errors, path, format!("length {} is greater than the maximum {}", len, max_length));
+        }
+    }
+    if let Some(s) = value.as_str() {
+        if !constraints.one_of.is_empty() && !constraints.one_of.iter().any(|allowed| allowed == s) {
+            error(errors, path, format!("{:?} is not one of {:?}", s, constraints.one_of));
+        }
+    }
+}
+
+/// The only key of a single-entry object, with its value
+fn single_entry(value: &Value) -> Option<(&str, &Value)> {
+    match value.as_object() {
+        Some(object) if object.len() == 1 => object.iter().next().map(|(k, v)| (k.as_str(), v)),
+        _ => None,
+    }
+}
+
+fn has_payload(variant: &VariantSchema) -> bool {
+    variant.inner_schema.is_some() || !variant.fields.is_empty()
+}
+
+fn check_payload(value: &Value, variant: &VariantSchema, path: &str, errors: &mut Vec<ValidationError>) {
+    if let Some(inner) = &variant.inner_schema {
+        check(value, inner, path, errors);
+    } else if !variant.fields.is_empty() {
+        match value {
+            Value::Object(object) => check_fields(object, &variant.fields, path, errors),
+            other => expected(errors, path, "object", other),
+        }
+    }
+}
+
+fn find_variant<'a>(schema: &'a EnumSchema, wire_name: &str) -> Option<&'a VariantSchema> {
+    schema.variants.iter().find(|v| v.wire_name() == wire_name)
+}
+
+fn unknown_variant(errors: &mut Vec<ValidationError>, path: &str, schema: &EnumSchema, name: &str) {
+    error(errors, path, format!("unknown variant {:?} of {}", name, schema.type_name));
+}
+
+fn check_enum(value: &Value, schema: &EnumSchema, path: &str, errors: &mut Vec<ValidationError>) {
+    match &schema.tagging {
+        EnumTagging::External => match value {
+            Value::String(name) => match find_variant(schema, name) {
+                Some(variant) if has_payload(variant) => {
+                    error(errors, path, format!("variant {:?} requires a payload", name))
+                }
+                Some(_) => {}
+                None => unknown_variant(errors, path, schema, name),
+            },
+            _ => match single_entry(value) {
+                Some((name, payload)) => match find_variant(schema, name) {
+                    Some(variant) => check_payload(payload, variant, &member_path(path, name), errors),
+                    None => unknown_variant(errors, path, schema, name),
+                },
+                None => error(
+                    errors,
+                    path,
+                    format!("expected a variant of {}, found {}", schema.type_name, json_kind(value)),
+                ),
+            },
+        },
+        EnumTagging::Internal { tag } => {
+            let Some(name) = value.get(tag).and_then(Value::as_str) else {
+                return error(errors, &member_path(path, tag), "missing variant tag");
+            };
+            match find_variant(schema, name) {
+                // The payload's fields sit beside the tag
+                Some(variant) => check_payload(value, variant, path, errors),
+                None => unknown_variant(errors, path, schema, name),
+            }
+        }
+        EnumTagging::Adjacent { tag, content } => {
+            let Some(name) = value.get(tag).and_then(Value::as_str) else {
+                return error(errors, &member_path(path, tag), "missing variant tag");
+            };
+            let content_path = member_path(path, content);
+            match (find_variant(schema, name), value.get(content)) {
+                (Some(variant), Some(payload)) => check_payload(payload, variant, &content_path, errors),
+                (Some(variant), None) if has_payload(variant) => {
+                    error(errors, &content_path, "missing variant content")
+                }
+                (Some(_), None) => {}
+                (None, _) => unknown_variant(errors, path, schema, name),
+            }
+        }
+        EnumTagging::Untagged => {
+            let matches = |variant: &VariantSchema| {
+                let mut variant_errors = Vec::new();
+                if has_payload(variant) {
+                    check_payload(value, variant, path, &mut variant_errors);
+                } else if !value.is_null() {
+                    expected(&mut variant_errors, path, "null", value);
+                }
+                variant_errors.is_empty()
+            };
+            if !schema.variants.iter().any(matches) {
+                error(errors, path, format!("value matches no variant of {}", schema.type_name));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::StructSchema;
+
+    fn field(name: &str, field_type: TypeSchema) -> FieldSchema {
+        FieldSchema {
+            field_name: name.to_string(),
+            field_type: Box::new(field_type),
+            optional: false,
+            nullable: false,
+            rename: None,
+            description: None,
+            deprecated: None,
+            constraints: FieldConstraints::default(),
+            default: None,
+            example: None,
+        }
+    }
+
+    fn primitive(name: &str) -> TypeSchema {
+        TypeSchema::Primitive(name.to_string())
+    }
+
+    #[test]
+    fn test_reports_every_mismatch_with_its_path() {
+        let mut email = field("email", primitive("String"));
+        email.constraints.min_length = Some(3);
+        let schema = TypeSchema::Struct(StructSchema {
+            type_name: "Signup".to_string(),
+            fields: vec![
+                field("age", primitive("u8")),
+                field(
+                    "contacts",
+                    TypeSchema::Container {
+                        container_type: "Vec".to_string(),
+                        type_params: vec![TypeSchema::Struct(StructSchema {
+                            type_name: "Contact".to_string(),
+                            fields: vec![email],
+                        })],
+                    },
+                ),
+                field("name", primitive("String")),
+            ],
+        });
+
+        let value = json!({ "age": 300, "contacts": [{ "email": "ada@example.com" }, { "email": "a" }] });
+        let errors = validate(&value, &schema).unwrap_err();
+        let paths: Vec<_> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["$.age", "$.contacts[1].email", "$.name"]);
+        assert_eq!(errors[0].to_string(), "$.age: expected u8 integer, found number");
+        assert_eq!(errors[2].message, "missing required field");
+
+        let value = json!({ "age": 36, "contacts": [], "name": "Ada" });
+        assert!(validate(&value, &schema).is_ok());
+    }
+
+    #[test]
+    fn test_enums_follow_their_tagging() {
+        let schema = EnumSchema {
+            type_name: "Shape".to_string(),
+            variants: vec![
+                VariantSchema {
+                    variant_name: "Empty".to_string(),
+                    inner_schema: None,
+                    rename: None,
+                    fields: Vec::new(),
+                },
+                VariantSchema {
+                    variant_name: "Circle".to_string(),
+                    inner_schema: None,
+                    rename: None,
+                    fields: vec![field("radius", primitive("f64"))],
+                },
+            ],
+            tagging: EnumTagging::External,
+        };
+        let external = TypeSchema::Enum(schema.clone());
+        assert!(validate(&json!("Empty"), &external).is_ok());
+        assert!(validate(&json!({ "Circle": { "radius": 1.5 } }), &external).is_ok());
+        let errors = validate(&json!({ "Circle": { "radius": "big" } }), &external).unwrap_err();
+        assert_eq!(errors[0].path, "$.Circle.radius");
+        assert!(validate(&json!("Square"), &external).is_err());
+
+        let internal = TypeSchema::Enum(EnumSchema {
+            tagging: EnumTagging::Internal { tag: "type".to_string() },
+            ..schema
+        });
+        assert!(validate(&json!({ "type": "Circle", "radius": 2 }), &internal).is_ok());
+        let errors = validate(&json!({ "radius": 2 }), &internal).unwrap_err();
+        assert_eq!(errors[0].path, "$.type");
+    }
+}
@@ -0,0 +1,183 @@
+//! Traversal of [`TypeSchema`] trees.
+//!
+//! Implement [`Visitor`], overriding only the hooks of interest; each default
+//! implementation recurses through the matching `walk_*` function, so an override that
+//! still wants to descend calls it explicitly. References are reported through
+//! [`Visitor::visit_ref`] but not followed; [`walk_resolved`] follows them, visiting each
+//! referenced type once so recursive types terminate.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{FieldSchema, TypeSchema, VariantSchema};
+
+pub trait Visitor<'a> {
+    fn visit_schema(&mut self, schema: &'a TypeSchema) {
+        walk_schema(self, schema)
+    }
+
+    fn visit_field(&mut self, field: &'a FieldSchema) {
+        walk_field(self, field)
+    }
+
+    fn visit_variant(&mut self, variant: &'a VariantSchema) {
+        walk_variant(self, variant)
+    }
+
+    /// A [`TypeSchema::Ref`] to a named type
+    fn visit_ref(&mut self, name: &'a str) {
+        let _ = name;
+    }
+}
+
+/// Visit the direct children of `schema`
+pub fn walk_schema<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, schema: &'a TypeSchema) {
+    match schema {
+        TypeSchema::Ref(name) => visitor.visit_ref(name),
+        TypeSchema::Struct(s) => {
+            for field in &s.fields {
+                visitor.visit_field(field);
+            }
+        }
+        TypeSchema::Enum(e) => {
+            for variant in &e.variants {
+                visitor.visit_variant(variant);
+            }
+        }
+        TypeSchema::Container { type_params, .. } => {
+            for param in type_params {
+                visitor.visit_schema(param);
+            }
+        }
+        TypeSchema::Map {
+            key_type,
+            value_type,
+        } => {
+            visitor.visit_schema(key_type);
+            visitor.visit_schema(value_type);
+        }
+        TypeSchema::Array { inner, .. } | TypeSchema::Newtype(inner) => visitor.visit_schema(inner),
+        TypeSchema::Tuple(items) => {
+            for item in items {
+                visitor.visit_schema(item);
+            }
+        }
+        TypeSchema::Primitive(_)
+        | TypeSchema::DateTime { .. }
+        | TypeSchema::Decimal(_)
+        | TypeSchema::Int128(_)
+        | TypeSchema::NonZero(_)
+        | TypeSchema::Bytes(_)
+        | TypeSchema::Any
+        | TypeSchema::Unit
+        | TypeSchema::Opaque(_) => {}
+    }
+}
+
+pub fn walk_field<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, field: &'a FieldSchema) {
+    visitor.visit_schema(&field.field_type);
+}
+
+pub fn walk_variant<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, variant: &'a VariantSchema) {
+    if let Some(inner) = &variant.inner_schema {
+        visitor.visit_schema(inner);
+    }
+    for field in &variant.fields {
+        visitor.visit_field(field);
+    }
+}
+
+/// Visit `schema`, then every type it references directly or transitively, each once and
+/// in first-seen order. `resolve` looks referenced names up, e.g. in [`crate::type_table`];
+/// names it can't resolve are still reported to [`Visitor::visit_ref`] but not descended into.
+pub fn walk_resolved<'a, V, F>(visitor: &mut V, schema: &'a TypeSchema, resolve: F)
+where
+    V: Visitor<'a> + ?Sized,
+    F: Fn(&str) -> Option<&'a TypeSchema>,
+{
+    visitor.visit_schema(schema);
+    let mut pending: VecDeque<&str> = schema.referenced_types().into();
+    // A named root that refers back to itself isn't visited twice
+    let mut seen: HashSet<&str> = schema.type_name().into_iter().collect();
+    while let Some(name) = pending.pop_front() {
+        if !seen.insert(name) {
+            continue;
+        }
+        if let Some(resolved) = resolve(name) {
+            visitor.visit_schema(resolved);
+            pending.extend(resolved.referenced_types());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{FieldConstraints, StructSchema};
+
+    fn node(name: &str, child: &str) -> TypeSchema {
+        TypeSchema::Struct(StructSchema {
+            type_name: name.to_string(),
+            fields: vec![FieldSchema {
+                field_name: "next".to_string(),
+                field_type: Box::new(TypeSchema::Container {
+                    container_type: "Option".to_string(),
+                    type_params: vec![TypeSchema::Ref(child.to_string())],
+                }),
+                optional: true,
+                nullable: true,
+                rename: None,
+                description: None,
+                deprecated: None,
+                constraints: FieldConstraints::default(),
+                default: None,
+                example: None,
+            }],
+        })
+    }
+
+    #[derive(Default)]
+    struct Recorder<'a> {
+        structs: Vec<&'a str>,
+        refs: Vec<&'a str>,
+        fields: usize,
+    }
+
+    impl<'a> Visitor<'a> for Recorder<'a> {
+        fn visit_schema(&mut self, schema: &'a TypeSchema) {
+            if let TypeSchema::Struct(s) = schema {
+                self.structs.push(&s.type_name);
+            }
+            walk_schema(self, schema);
+        }
+
+        fn visit_field(&mut self, field: &'a FieldSchema) {
+            self.fields += 1;
+            walk_field(self, field);
+        }
+
+        fn visit_ref(&mut self, name: &'a str) {
+            self.refs.push(name);
+        }
+    }
+
+    #[test]
+    fn test_walk_resolved_terminates_on_cycles() {
+        let types = BTreeMap::from([
+            ("A".to_string(), node("A", "B")),
+            ("B".to_string(), node("B", "A")),
+        ]);
+        let mut recorder = Recorder::default();
+        walk_resolved(&mut recorder, &types["A"], |name| types.get(name));
+        assert_eq!(recorder.structs, ["A", "B"]);
+        assert_eq!(recorder.refs, ["B", "A"]);
+        assert_eq!(recorder.fields, 2);
+
+        // Without resolution references are reported but not followed
+        let mut recorder = Recorder::default();
+        recorder.visit_schema(&types["A"]);
+        assert_eq!(recorder.structs, ["A"]);
+        assert_eq!(recorder.refs, ["B"]);
+    }
+}
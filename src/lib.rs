@@ -8,6 +8,9 @@
 //! - **server**: Server-side RPC functionality with Loco.rs integration
 //! - **client**: Client-side RPC functionality with automatic code generation  
 //! - **schema**: Schema derivation macros
+//! - **chrono**: chrono date/time types in the generated client
+//! - **uuid**: uuid::Uuid types in the generated client
+//! - **decimal**: rust_decimal::Decimal types in the generated client
 //! - **full**: All features enabled
 //! 
 //! ## Usage
@@ -41,7 +44,7 @@ mod client_gen;
 #[cfg(feature = "server")]
 pub mod server {
     pub use laz_server::*;
-    pub use laz_server_macros::{rpc_query, rpc_mutation};
+    pub use laz_server_macros::{rpc_query, rpc_mutation, rpc_subscription, rpc_ws, rpc_impl};
 }
 
 // Client functionality
@@ -80,7 +83,7 @@ pub use laz_client::reqwest;
 #[cfg(feature = "server")]
 pub mod prelude {
     pub use crate::server::*;
-    pub use crate::server::{rpc_query, rpc_mutation};
+    pub use crate::server::{rpc_query, rpc_mutation, rpc_subscription, rpc_ws, rpc_impl};
     pub use crate::LazSchema;
 }
 